@@ -0,0 +1 @@
+DROP TABLE broker_statements
\ No newline at end of file
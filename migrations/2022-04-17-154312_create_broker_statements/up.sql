@@ -0,0 +1,9 @@
+CREATE TABLE broker_statements (
+  path TEXT NOT NULL,
+  size BIGINT NOT NULL,
+  modify_time DATETIME NOT NULL,
+  hash TEXT NOT NULL,
+  context TEXT NOT NULL,
+  statement TEXT NOT NULL,
+  PRIMARY KEY (path)
+) WITHOUT ROWID
\ No newline at end of file
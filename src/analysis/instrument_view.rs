@@ -1,11 +1,8 @@
-use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap};
 
 use itertools::Itertools;
-use num_traits::Zero;
 
-use crate::core::{GenericResult, EmptyResult};
-use crate::formatting;
+use crate::core::EmptyResult;
 use crate::time::{Date, DateOptTime};
 use crate::types::Decimal;
 
@@ -64,7 +61,7 @@ impl InstrumentDepositView {
             let current = open_position.get_or_insert_with(||
                 OpenPosition::new(date));
 
-            if current.trade(date, trades)? {
+            if current.trade(trades) {
                 continue;
             }
 
@@ -111,24 +108,17 @@ impl<'a> OpenPosition<'a> {
         OpenPosition { open_date, symbols: HashMap::new() }
     }
 
-    fn trade(&mut self, date: Date, trades: &'a HashMap<String, Decimal>) -> GenericResult<bool> {
+    // Negative balance means a short position
+    fn trade(&mut self, trades: &'a HashMap<String, Decimal>) -> bool {
         for (symbol, quantity) in trades {
             let current = self.symbols.entry(symbol).or_default();
             *current += quantity;
 
-            match (*current).cmp(&Decimal::zero()) {
-                Ordering::Greater => {},
-                Ordering::Equal => {
-                    self.symbols.remove(symbol.as_str());
-                },
-                Ordering::Less => {
-                    return Err!(
-                        "Error while processing {} sell operations: Got a negative balance on {}",
-                        symbol, formatting::format_date(date));
-                }
-            };
+            if current.is_zero() {
+                self.symbols.remove(symbol.as_str());
+            }
         }
 
-        Ok(!self.symbols.is_empty())
+        !self.symbols.is_empty()
     }
 }
\ No newline at end of file
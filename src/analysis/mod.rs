@@ -10,18 +10,23 @@ use std::collections::BTreeMap;
 use std::rc::Rc;
 
 use easy_logging::GlobalContext;
+use itertools::Itertools;
 use log::warn;
+use rayon::prelude::*;
 
 use crate::brokers::Broker;
-use crate::broker_statement::{BrokerStatement, ReadingStrictness, StockSellType};
+use crate::broker_statement::{BrokerStatement, ReadingStrictness, StockSource, StockSellType};
 use crate::commissions::CommissionCalc;
 use crate::config::{Config, PortfolioConfig};
 use crate::core::{GenericResult, EmptyResult};
 use crate::currency::converter::{CurrencyConverter, CurrencyConverterRc};
 use crate::db;
 use crate::localities::Country;
+use crate::logging::ThreadContext;
 use crate::quotes::Quotes;
-use crate::taxes::{IncomeType, LtoDeductionCalculator, LtoDeduction, NetLtoDeduction};
+use crate::tax_statement;
+use crate::taxes::{
+    IncomeType, LossCarryforward, LtoDeductionCalculator, LtoDeduction, NetLtoDeduction, ProgressiveTax};
 use crate::telemetry::TelemetryRecordBuilder;
 use crate::types::Decimal;
 
@@ -33,6 +38,8 @@ pub struct PortfolioStatistics {
     country: Country,
     pub currencies: Vec<PortfolioCurrencyStatistics>,
     pub lto: Option<LtoStatistics>,
+    pub loss_carryforward: Option<LossCarryforward>,
+    pub progressive_tax: Option<ProgressiveTax>,
 }
 
 pub struct LtoStatistics {
@@ -58,6 +65,8 @@ impl PortfolioStatistics {
                 }
             )).collect(),
             lto: None,
+            loss_carryforward: None,
+            progressive_tax: None,
         }
     }
 
@@ -84,6 +93,14 @@ impl PortfolioStatistics {
         if !lto.projected.deduction.is_zero() {
             lto.projected.print("Projected LTO deduction")
         }
+
+        if let Some(ref loss_carryforward) = self.loss_carryforward {
+            loss_carryforward.print("Tax loss carryforward");
+        }
+
+        if let Some(ref progressive_tax) = self.progressive_tax {
+            progressive_tax.print("Progressive tax surcharge");
+        }
     }
 
     fn process<F>(&mut self, mut handler: F) -> EmptyResult
@@ -129,6 +146,7 @@ pub fn analyse(
     let country = config.get_tax_country();
     let (converter, quotes) = load_tools(config)?;
     let mut lto_calc = LtoDeductionCalculator::new();
+    let mut projected_profit = BTreeMap::new();
     let mut statistics = PortfolioStatistics::new(country.clone());
 
     for (_, statement) in &mut portfolios {
@@ -152,13 +170,26 @@ pub fn analyse(
             Ok(statistics.add_assets(broker, "Cash", cash_assets))
         })?;
 
+        if !statement.derivative_positions.is_empty() {
+            let symbols = statement.derivative_positions.keys().sorted().join(", ");
+            warn!(concat!(
+                "The portfolio has open derivative positions ({}) which can't be valuated, ",
+                "so only their realized result is taken into account."
+            ), symbols);
+        }
+
         let net_value = statement.net_value(&converter, &quotes, portfolio.currency()?)?;
         let mut commission_calc = CommissionCalc::new(
             converter.clone(), statement.broker.commission_spec.clone(), net_value)?;
 
         for (symbol, quantity) in statement.open_positions.clone() {
             let price = quotes.get(statement.get_quote_query(&symbol))?;
-            statement.emulate_sell(&symbol, quantity, price, &mut commission_calc)?;
+
+            if quantity.is_sign_negative() {
+                statement.emulate_buy(&symbol, -quantity, price, &mut commission_calc)?;
+            } else {
+                statement.emulate_sell(&symbol, quantity, price, &mut commission_calc)?;
+            }
         }
 
         let additional_commissions = statement.emulate_commissions(commission_calc)?;
@@ -172,17 +203,33 @@ pub fn analyse(
 
         statement.process_trades(None)?;
 
-        for trade in statement.stock_sells.iter().rev() {
-            if !trade.emulation {
-                break;
-            }
+        for trade in statement.stock_buys.iter().filter(|trade| trade.emulation) {
+            let (volume, commission) = match trade.type_ {
+                StockSource::Trade {volume, commission, ..} => (volume, commission),
+                _ => unreachable!(),
+            };
+
+            statistics.process(|statistics| {
+                let currency = &statistics.currency;
+
+                let volume = converter.real_time_convert_to(volume, currency)?;
+                let commission = converter.real_time_convert_to(commission, currency)?;
 
+                statistics.add_assets(broker, &trade.symbol, -volume);
+                statistics.projected_commissions += commission;
+
+                Ok(())
+            })?;
+        }
+
+        // Short positions are closed by emulated buys, so their sells are projected too
+        for trade in statement.stock_sells.iter().filter(|trade| trade.is_projected()) {
             let (volume, commission) = match trade.type_ {
                 StockSellType::Trade {volume, commission, ..} => (volume, commission),
                 _ => unreachable!(),
             };
 
-            let (tax_year, _) = portfolio.tax_payment_day().get(trade.execution_date, true);
+            let (tax_year, _) = portfolio.tax_payment_day().get(trade.realization_date(), true);
             let details = trade.calculate(&country, tax_year, &portfolio.tax_exemptions, &converter)?;
 
             let mut taxable_local_profit = details.taxable_local_profit;
@@ -202,17 +249,22 @@ pub fn analyse(
             let tax_deduction = tax_without_deduction - tax_to_pay;
             assert!(!tax_deduction.is_negative());
 
+            *projected_profit.entry(tax_year).or_insert_with(Decimal::default) += taxable_local_profit.amount;
+
             statistics.process(|statistics| {
                 let currency = &statistics.currency;
 
-                let volume = converter.real_time_convert_to(volume, currency)?;
-                let commission = converter.real_time_convert_to(commission, currency)?;
-
                 let tax_to_pay = converter.real_time_convert_to(tax_to_pay, currency)?;
                 let tax_deduction = converter.real_time_convert_to(tax_deduction, currency)?;
 
-                statistics.add_assets(broker, &trade.symbol, volume);
-                statistics.projected_commissions += commission;
+                if trade.emulation {
+                    let volume = converter.real_time_convert_to(volume, currency)?;
+                    let commission = converter.real_time_convert_to(commission, currency)?;
+
+                    statistics.add_assets(broker, &trade.symbol, volume);
+                    statistics.projected_commissions += commission;
+                }
+
                 statistics.projected_taxes += tax_to_pay;
                 statistics.projected_tax_deductions += tax_deduction;
 
@@ -221,6 +273,60 @@ pub fn analyse(
         }
     }
 
+    let other_statements = tax_statement::read_other_portfolios(
+        config, &portfolios.iter().map(|(portfolio, _)| *portfolio).collect::<Vec<_>>())?;
+
+    let statements = portfolios.iter()
+        .chain(other_statements.iter())
+        .map(|(portfolio, statement)| (*portfolio, statement))
+        .collect::<Vec<_>>();
+
+    let loss_carryforward = tax_statement::calculate_loss_carryforward(config, &statements, &converter)?;
+
+    if let Some(ref loss_carryforward) = loss_carryforward {
+        for (tax_year, applied) in project_loss_carryforward(loss_carryforward, &projected_profit) {
+            *projected_profit.get_mut(&tax_year).unwrap() -= applied;
+
+            let tax_deduction = country.tax_to_pay(
+                IncomeType::Trading, tax_year, country.cash(applied), None);
+
+            statistics.process(|statistics| {
+                let tax_deduction = converter.real_time_convert_to(tax_deduction, &statistics.currency)?;
+                statistics.projected_taxes -= tax_deduction;
+                statistics.projected_tax_deductions += tax_deduction;
+                Ok(())
+            })?;
+        }
+    }
+
+    if let Some(mut calculator) = tax_statement::calculate_progressive_tax(
+        config, &statements, loss_carryforward.as_ref(), &converter,
+    )? {
+        // Projected profit may move the year's income above the progressive tax threshold
+        let realized = calculator.clone().calculate();
+        for (&tax_year, &profit) in &projected_profit {
+            calculator.add_income(tax_year, IncomeType::Trading, profit);
+        }
+        let projected = calculator.calculate();
+
+        for &tax_year in projected_profit.keys() {
+            let surcharge = projected.surcharge(tax_year) - realized.surcharge(tax_year);
+            if surcharge <= dec!(0) {
+                continue;
+            }
+
+            statistics.process(|statistics| {
+                statistics.projected_taxes += converter.real_time_convert_to(
+                    country.cash(surcharge), &statistics.currency)?;
+                Ok(())
+            })?;
+        }
+
+        statistics.progressive_tax = Some(projected);
+    }
+
+    statistics.loss_carryforward = loss_carryforward;
+
     let mut applied_lto = None;
 
     statistics.process(|statistics| {
@@ -269,37 +375,55 @@ pub fn simulate_sell(
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
 }
 
+// Previous years' losses which are left after realized profit reduce the projected profit. Returns the losses which are applied to each tax year.
+fn project_loss_carryforward(
+    loss_carryforward: &LossCarryforward, projected_profit: &BTreeMap<i32, Decimal>,
+) -> BTreeMap<i32, Decimal> {
+    let mut applied_losses = BTreeMap::new();
+    let mut total_applied = dec!(0);
+
+    for (&tax_year, &profit) in projected_profit {
+        let applied = std::cmp::min(profit, loss_carryforward.available(tax_year) - total_applied);
+        if applied <= dec!(0) {
+            continue;
+        }
+
+        applied_losses.insert(tax_year, applied);
+        total_applied += applied;
+    }
+
+    applied_losses
+}
+
 fn load_portfolios<'a>(config: &'a Config, name: Option<&str>) -> GenericResult<Vec<(&'a PortfolioConfig, BrokerStatement)>> {
-    let mut portfolios = Vec::new();
-    let reading_strictness = ReadingStrictness::REPO_TRADES;
+    let reading_strictness = ReadingStrictness::empty();
 
     if let Some(name) = name {
         let portfolio = config.get_portfolio(name)?;
         let statement = load_portfolio(config, portfolio, reading_strictness)?;
-        portfolios.push((portfolio, statement));
-    } else {
-        if config.portfolios.is_empty() {
-            return Err!("There is no any portfolio defined in the configuration file")
-        }
-
-        let multiple = config.portfolios.len() > 1;
+        return Ok(vec![(portfolio, statement)]);
+    }
 
-        for portfolio in &config.portfolios {
-            let _logging_context = multiple.then(|| GlobalContext::new(&portfolio.name));
-            let statement = load_portfolio(config, portfolio, reading_strictness)?;
-            portfolios.push((portfolio, statement));
-        }
+    if config.portfolios.is_empty() {
+        return Err!("There is no any portfolio defined in the configuration file")
     }
 
-    Ok(portfolios)
+    // Prepare the database before concurrent access to it
+    db::connect(&config.db_path)?;
+
+    let multiple = config.portfolios.len() > 1;
+
+    // The portfolios are read in parallel, so global logging context can't be used here
+    config.portfolios.par_iter().map(|portfolio| -> GenericResult<(&PortfolioConfig, BrokerStatement)> {
+        let _logging_context = multiple.then(|| ThreadContext::new(&portfolio.name));
+        let statement = load_portfolio(config, portfolio, reading_strictness).map_err(|e| format!(
+            "Failed to read {:?} portfolio: {}", portfolio.name, e))?;
+        Ok((portfolio, statement))
+    }).collect()
 }
 
 fn load_portfolio(config: &Config, portfolio: &PortfolioConfig, strictness: ReadingStrictness) -> GenericResult<BrokerStatement> {
-    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
-    BrokerStatement::read(
-        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
-        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.corporate_actions,
-        strictness)
+    BrokerStatement::read_portfolio(config, portfolio, strictness)
 }
 
 fn load_tools(config: &Config) -> GenericResult<(CurrencyConverterRc, Rc<Quotes>)> {
@@ -307,4 +431,37 @@ fn load_tools(config: &Config) -> GenericResult<(CurrencyConverterRc, Rc<Quotes>
     let quotes = Rc::new(Quotes::new(config, database.clone())?);
     let converter = CurrencyConverter::new(database, Some(quotes.clone()), false);
     Ok((converter, quotes))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::taxes::{LossCarryforwardCalculator, LossCarryforwardConfig};
+    use super::*;
+
+    #[test]
+    fn loss_carryforward_projection() {
+        let config = LossCarryforwardConfig {
+            losses: btreemap!{2020 => dec!(100)},
+            ..Default::default()
+        };
+
+        let mut calculator = LossCarryforwardCalculator::new("RUB", &config);
+        calculator.add_profit(2021, "portfolio", dec!(30));
+        let loss_carryforward = calculator.calculate();
+
+        // The losses which are left after realized profit are applied to the projected profit only once
+        assert_eq!(project_loss_carryforward(&loss_carryforward, &btreemap!{
+            2021 => dec!(50),
+            2022 => dec!(-10),
+            2023 => dec!(50),
+        }), btreemap!{
+            2021 => dec!(50),
+            2023 => dec!(20),
+        });
+
+        // 2020 year loss expires in 2031
+        assert_eq!(project_loss_carryforward(&loss_carryforward, &btreemap!{
+            2031 => dec!(50),
+        }), btreemap!{});
+    }
+}
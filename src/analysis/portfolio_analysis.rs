@@ -31,24 +31,32 @@ pub struct IncomeStructure {
 
     pub dividends: Decimal,
     pub interest: Decimal,
+    pub coupons: Decimal,
+    pub repo: Decimal,
+    pub derivatives: Decimal,
 
     pub trading_taxes: Decimal,
     pub dividend_taxes: Decimal,
     pub interest_taxes: Decimal,
+    pub coupon_taxes: Decimal,
+    pub repo_taxes: Decimal,
+    pub derivative_taxes: Decimal,
 
     pub trading_tax_deductions: Decimal,
     pub additional_tax_deductions: Decimal,
 
     pub commissions: Decimal,
+    pub margin_expenses: Decimal, // Margin interest and stock borrow fees
 }
 
 impl IncomeStructure {
     pub fn profit(&self) -> Decimal {
-        self.net_profit + self.taxes() + self.commissions
+        self.net_profit + self.taxes() + self.commissions + self.margin_expenses
     }
 
     pub fn net_trading_income(&self) -> Decimal {
-        self.net_profit - self.net_dividend_income() - self.net_interest_income() - self.tax_deductions()
+        self.net_profit - self.net_dividend_income() - self.net_interest_income() - self.net_coupon_income()
+            - self.net_repo_income() - self.net_derivative_income() - self.tax_deductions()
     }
 
     pub fn net_dividend_income(&self) -> Decimal {
@@ -59,8 +67,21 @@ impl IncomeStructure {
         self.interest - self.interest_taxes
     }
 
+    pub fn net_coupon_income(&self) -> Decimal {
+        self.coupons - self.coupon_taxes
+    }
+
+    pub fn net_repo_income(&self) -> Decimal {
+        self.repo - self.repo_taxes
+    }
+
+    pub fn net_derivative_income(&self) -> Decimal {
+        self.derivatives - self.derivative_taxes
+    }
+
     pub fn taxes(&self) -> Decimal {
-        self.trading_taxes + self.dividend_taxes + self.interest_taxes
+        self.trading_taxes + self.dividend_taxes + self.interest_taxes + self.coupon_taxes +
+            self.repo_taxes + self.derivative_taxes
     }
 
     pub fn tax_deductions(&self) -> Decimal {
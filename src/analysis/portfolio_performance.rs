@@ -3,7 +3,9 @@ use std::collections::{HashMap, BTreeMap};
 use itertools::Itertools;
 use log::{self, log_enabled, trace};
 
-use crate::broker_statement::{BrokerStatement, StockSource, StockSellType};
+use crate::broker_statement::{
+    BrokerStatement, FeeType, StockSource, StockSellType, calculate_bond_income_taxes,
+    calculate_derivative_taxes, calculate_repo_income_taxes};
 use crate::config::PortfolioConfig;
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
@@ -81,6 +83,9 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
         self.process_positions(statement, portfolio)?;
         self.process_dividends(statement, portfolio)?;
         self.process_interest(statement, portfolio)?;
+        self.process_bonds(statement, portfolio)?;
+        self.process_repo(statement, portfolio)?;
+        self.process_derivatives(statement, portfolio)?;
         self.process_fees(statement)?;
         self.process_tax_agent_withholdings(statement)?;
         self.process_tax_deductions(portfolio)?;
@@ -251,8 +256,12 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
                     deposit_view.trade(&portfolio.name, &trade.symbol, trade.conclusion_time, quantity);
                     deposit_view.transaction(trade.conclusion_time, volume);
                     deposit_view.transaction(trade.conclusion_time, commission);
+
+                    if trade.emulation {
+                        deposit_view.closed = false;
+                    }
                 },
-                StockSource::CorporateAction => {
+                StockSource::CorporateAction | StockSource::Transfer => {
                     self.get_deposit_view(&trade.symbol).trade(
                         &portfolio.name, &trade.symbol, trade.conclusion_time, quantity);
                 },
@@ -280,19 +289,22 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
                         deposit_view.transaction(trade.conclusion_time, -volume);
                         deposit_view.transaction(trade.conclusion_time, commission);
 
-                        if trade.emulation {
+                        if trade.is_projected() {
                             deposit_view.closed = false;
                         }
                     }
 
-                    let (tax_year, _) = portfolio.tax_payment_day().get(trade.execution_date, true);
+                    let projected = trade.is_projected();
+                    let realization_date = trade.realization_date();
+
+                    let (tax_year, _) = portfolio.tax_payment_day().get(realization_date, true);
                     let details = trade.calculate(self.country, tax_year, &portfolio.tax_exemptions, self.converter)?;
 
                     let mut lto_deductibles = Vec::new();
 
                     for fifo in &details.fifo {
                         if let Some(lto) = fifo.long_term_ownership_deductible {
-                            self.net_lto_calc.add_profit(tax_year, lto.profit, lto.years, trade.emulation);
+                            self.net_lto_calc.add_profit(tax_year, lto.profit, lto.years, projected);
                             lto_deductibles.push(lto);
                         }
                     }
@@ -301,14 +313,14 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
                         .or_insert_with(|| NetTaxCalculator::new(
                             self.country.clone(), portfolio.tax_payment_day()))
                         .add_profit(
-                            trade.execution_date, details.local_profit, details.taxable_local_profit,
-                            &lto_deductibles, trade.emulation);
+                            realization_date, details.local_profit, details.taxable_local_profit,
+                            &lto_deductibles, projected);
 
                     taxes.add_profit(
-                        trade.execution_date, details.local_profit, details.taxable_local_profit,
-                        &lto_deductibles, trade.emulation);
+                        realization_date, details.local_profit, details.taxable_local_profit,
+                        &lto_deductibles, projected);
                 },
-                StockSellType::CorporateAction => {
+                StockSellType::CorporateAction | StockSellType::Transfer => {
                     self.get_deposit_view(&trade.symbol).trade(
                         &portfolio.name, &trade.symbol, trade.conclusion_time, -quantity);
                 },
@@ -395,10 +407,95 @@ impl <'a> PortfolioPerformanceAnalyser<'a> {
         Ok(())
     }
 
+    fn process_bonds(&mut self, statement: &BrokerStatement, portfolio: &PortfolioConfig) -> EmptyResult {
+        for income in &statement.bond_income {
+            let amount = self.converter.convert_to(income.date, income.amount, self.currency)?;
+            self.get_deposit_view(&income.symbol).transaction(income.date.into(), -amount);
+            self.income_structure.coupons += amount;
+        }
+
+        for amortization in &statement.bond_amortizations {
+            let amount = self.converter.convert_to(amortization.date, amortization.amount, self.currency)?;
+            self.get_deposit_view(&amortization.symbol).transaction(amortization.date.into(), -amount);
+        }
+
+        // Paid accrued interest reduces taxable coupon income of the year, so taxes can't be
+        // attributed to a particular bond.
+        let taxes = calculate_bond_income_taxes(self.country, &statement.bond_income, self.converter)?;
+
+        for (year, tax) in taxes {
+            let tax_payment_date = portfolio.tax_payment_day().get_for(year, false);
+
+            if let Some(amount) = self.map_tax_to_deposit_amount(tax_payment_date, tax.tax_to_pay)? {
+                trace!("* {} bond income {} tax: {}",
+                       year, formatting::format_date(tax_payment_date), amount);
+
+                self.transaction(tax_payment_date, amount);
+                self.income_structure.coupon_taxes += amount;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_repo(&mut self, statement: &BrokerStatement, portfolio: &PortfolioConfig) -> EmptyResult {
+        for repo in &statement.repo_income {
+            let profit = self.converter.convert_to(repo.date, repo.profit()?, self.currency)?;
+            self.get_deposit_view(&repo.symbol).transaction(repo.date.into(), -profit);
+            self.income_structure.repo += profit;
+        }
+
+        let taxes = calculate_repo_income_taxes(self.country, &statement.repo_income, self.converter)?;
+
+        for (year, tax) in taxes {
+            let tax_payment_date = portfolio.tax_payment_day().get_for(year, true);
+
+            if let Some(amount) = self.map_tax_to_deposit_amount(tax_payment_date, tax.tax_to_pay)? {
+                trace!("* {} repo income ({}) {} tax: {}",
+                       year, tax.income, formatting::format_date(tax_payment_date), amount);
+
+                self.transaction(tax_payment_date, amount);
+                self.income_structure.repo_taxes += amount;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_derivatives(&mut self, statement: &BrokerStatement, portfolio: &PortfolioConfig) -> EmptyResult {
+        // Derivatives have no investments in common sense (futures are settled via variation margin
+        // and option premiums may be received as well as paid), so they aren't analysed as separate
+        // instruments: their result is accounted in portfolio cash assets and income structure.
+        for result in &statement.derivative_results {
+            self.income_structure.derivatives += self.converter.convert_to(
+                result.date, result.profit, self.currency)?;
+        }
+
+        let taxes = calculate_derivative_taxes(self.country, &statement.derivative_results, self.converter)?;
+
+        for (year, tax) in taxes {
+            let tax_payment_date = portfolio.tax_payment_day().get_for(year, true);
+
+            if let Some(amount) = self.map_tax_to_deposit_amount(tax_payment_date, tax.tax_to_pay)? {
+                trace!("* {} derivatives income ({}) {} tax: {}",
+                       year, tax.income, formatting::format_date(tax_payment_date), amount);
+
+                self.transaction(tax_payment_date, amount);
+                self.income_structure.derivative_taxes += amount;
+            }
+        }
+
+        Ok(())
+    }
+
     fn process_fees(&mut self, statement: &BrokerStatement) -> EmptyResult {
         for fee in &statement.fees {
-            self.income_structure.commissions += self.converter.convert_to(
-                fee.date, fee.amount, self.currency)?;
+            let amount = self.converter.convert_to(fee.date, fee.amount, self.currency)?;
+
+            match fee.type_ {
+                FeeType::Commission => self.income_structure.commissions += amount,
+                FeeType::MarginInterest | FeeType::BorrowFee => self.income_structure.margin_expenses += amount,
+            }
         }
 
         Ok(())
@@ -21,14 +21,19 @@ pub fn simulate_sell(
     mut positions: Vec<(String, Option<Decimal>)>, base_currency: Option<&str>,
 ) -> EmptyResult {
     if positions.is_empty() {
-        positions = statement.open_positions.keys()
-            .map(|symbol| (symbol.to_owned(), None))
+        positions = statement.open_positions.iter()
+            .filter(|(_, quantity)| quantity.is_sign_positive())
+            .map(|(symbol, _)| (symbol.to_owned(), None))
             .collect();
         positions.sort();
     } else {
         for (symbol, _) in &positions {
-            if statement.open_positions.get(symbol).is_none() {
-                return Err!("The portfolio has no open {:?} positions", symbol);
+            match statement.open_positions.get(symbol) {
+                Some(quantity) if quantity.is_sign_negative() => return Err!(
+                    "{:?} is a short position. Sell simulation isn't supported for short positions",
+                    symbol),
+                Some(_) => {},
+                None => return Err!("The portfolio has no open {:?} positions", symbol),
             }
         }
     }
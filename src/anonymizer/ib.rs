@@ -0,0 +1,175 @@
+use csv::{StringRecord, WriterBuilder};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::core::EmptyResult;
+
+use super::{Anonymizer, PersonalData, is_scalable};
+
+// Interactive Brokers activity statements consist of sections with `Section,Header,Field...` header
+// records and `Section,Data,Value...` data records. Trade confirmation reports are plain CSV tables.
+pub fn anonymize(anonymizer: &mut Anonymizer, input: &str, output: &str) -> EmptyResult {
+    let records = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(input)?
+        .into_records()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tables = Tables::new(&records);
+
+    for (index, record) in records.iter().enumerate() {
+        if let Some((section, titles, offset)) = tables.get(index) {
+            collect(anonymizer, section, titles, record, offset);
+        }
+    }
+
+    let mut writer = WriterBuilder::new().flexible(true).from_path(output)?;
+
+    for (index, record) in records.iter().enumerate() {
+        let record = match tables.get(index) {
+            Some((_, titles, offset)) => rewrite(anonymizer, titles, record, offset),
+            None => record.iter().map(|value| anonymizer.replace(value)).collect(),
+        };
+        writer.write_record(&record)?;
+    }
+
+    Ok(writer.flush()?)
+}
+
+struct Tables<'a> {
+    // Section name, column titles and offset of the first value for each data record
+    records: Vec<Option<(&'a str, Vec<&'a str>, usize)>>,
+}
+
+impl<'a> Tables<'a> {
+    fn new(records: &'a [StringRecord]) -> Tables<'a> {
+        let mut tables = Tables {records: Vec::with_capacity(records.len())};
+
+        if records.first().and_then(|record| record.get(0)) == Some("ClientAccountID") {
+            let titles: Vec<&str> = records[0].iter().collect();
+            tables.records.push(None);
+            tables.records.extend(records[1..].iter().map(|_| Some(("", titles.clone(), 0))));
+            return tables;
+        }
+
+        let mut current: Option<(&str, Vec<&str>)> = None;
+
+        for record in records {
+            let section = record.get(0).unwrap_or_default();
+
+            if record.get(1) == Some("Header") {
+                current.replace((section, record.iter().skip(2).collect()));
+                tables.records.push(None);
+                continue;
+            }
+
+            tables.records.push(match current {
+                Some((name, ref titles)) if name == section => Some((name, titles.clone(), 2)),
+                _ => None,
+            });
+        }
+
+        tables
+    }
+
+    fn get(&self, index: usize) -> Option<(&'a str, &[&'a str], usize)> {
+        self.records[index].as_ref().map(|(section, titles, offset)| (*section, titles.as_slice(), *offset))
+    }
+}
+
+fn collect(anonymizer: &mut Anonymizer, section: &str, titles: &[&str], record: &StringRecord, offset: usize) {
+    let get = |title: &str| {
+        titles.iter().position(|&other| other == title).and_then(|index| record.get(offset + index))
+    };
+
+    if section == "Account Information" {
+        if let (Some(name), Some(value)) = (get("Field Name"), get("Field Value")) {
+            if let Some(kind) = match name {
+                "Account" | "Account Alias" => Some(PersonalData::Account),
+                "Name" | "Master Name" => Some(PersonalData::Name),
+                "Address" => Some(PersonalData::Address),
+                _ => None,
+            } {
+                anonymizer.register(kind, value);
+            }
+        }
+        return;
+    }
+
+    for title in ["Account", "Account Alias", "ClientAccountID", "AccountAlias"] {
+        if let Some(value) = get(title) {
+            anonymizer.register(PersonalData::Account, value);
+        }
+    }
+}
+
+fn rewrite(anonymizer: &Anonymizer, titles: &[&str], record: &StringRecord, offset: usize) -> StringRecord {
+    lazy_static! {
+        static ref THOUSANDS_SEPARATOR_REGEX: Regex = Regex::new(r"^-?\d{1,3}(?:,\d{3})+(?:\.\d+)?$").unwrap();
+    }
+
+    record.iter().enumerate().map(|(index, value)| {
+        if index < offset {
+            return value.to_owned();
+        }
+
+        let scalable = titles.get(index - offset).map(|title| is_scalable(title)).unwrap_or_default();
+        if scalable {
+            let number = if THOUSANDS_SEPARATOR_REGEX.is_match(value) {
+                value.replace(',', "")
+            } else {
+                value.to_owned()
+            };
+
+            if let Some(scaled) = anonymizer.scale_number(&number, false) {
+                return scaled;
+            }
+        }
+
+        anonymizer.replace(value)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use tempfile::TempDir;
+    use super::*;
+
+    #[test]
+    fn anonymization() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.csv");
+        let output = dir.path().join("output.csv");
+
+        std::fs::write(&input, indoc!(r#"
+            Statement,Header,Field Name,Field Value
+            Statement,Data,Period,"January 1, 2020 - December 31, 2020"
+            Account Information,Header,Field Name,Field Value
+            Account Information,Data,Name,Ivan Petrov
+            Account Information,Data,Account,U1234567
+            Account Information,Data,Base Currency,USD
+            Trades,Header,DataDiscriminator,Asset Category,Currency,Symbol,Date/Time,Quantity,T. Price,Proceeds,Comm/Fee
+            Trades,Data,Order,Stocks,USD,VTI,"2020-02-03, 11:01:12","1,000",150.05,-150050,-1
+            Deposits & Withdrawals,Header,Currency,Settle Date,Description,Amount
+            Deposits & Withdrawals,Data,USD,2020-01-02,Electronic Fund Transfer U1234567,1000.5
+        "#)).unwrap();
+
+        let mut anonymizer = Anonymizer::new(Some(2)).unwrap();
+        anonymize(&mut anonymizer, input.to_str().unwrap(), output.to_str().unwrap()).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), indoc!(r#"
+            Statement,Header,Field Name,Field Value
+            Statement,Data,Period,"January 1, 2020 - December 31, 2020"
+            Account Information,Header,Field Name,Field Value
+            Account Information,Data,Name,John Doe
+            Account Information,Data,Account,U0000001
+            Account Information,Data,Base Currency,USD
+            Trades,Header,DataDiscriminator,Asset Category,Currency,Symbol,Date/Time,Quantity,T. Price,Proceeds,Comm/Fee
+            Trades,Data,Order,Stocks,USD,VTI,"2020-02-03, 11:01:12",2000,150.05,-300100,-2
+            Deposits & Withdrawals,Header,Currency,Settle Date,Description,Amount
+            Deposits & Withdrawals,Data,USD,2020-01-02,Electronic Fund Transfer U0000001,2001.0
+        "#));
+    }
+}
@@ -0,0 +1,307 @@
+// Rewrites broker statements replacing personal data (account numbers, names and addresses) and
+// optionally scaling all amounts, so the statements can be shared in bug reports.
+//
+// Scaling multiplies all amounts and quantities by an integer factor while keeping prices, rates
+// and identifiers as is: this way `quantity * price = volume` and all totals remain consistent
+// without any rounding, and the anonymized statement parses to the equivalent broker statement.
+
+mod ib;
+mod ofx;
+mod open;
+mod spreadsheet;
+
+use std::path::Path;
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use num_traits::{FromPrimitive, ToPrimitive};
+use regex::Regex;
+
+use crate::brokers::Broker;
+use crate::core::GenericResult;
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::types::Decimal;
+
+pub fn anonymize(input: &str, output: &str, scale: Option<u32>) -> GenericResult<TelemetryRecordBuilder> {
+    let format = Format::detect(input)?;
+
+    let extension = format.output_extension();
+    if !output.ends_with(extension) {
+        return Err!("The output file must have {} extension", extension);
+    } else if Path::new(input) == Path::new(output) {
+        return Err!("The output file must differ from the input one");
+    }
+
+    let mut anonymizer = Anonymizer::new(scale)?;
+
+    match format {
+        Format::IbCsv => ib::anonymize(&mut anonymizer, input, output),
+        Format::FirstradeOfx => ofx::anonymize(&mut anonymizer, input, output),
+        Format::OpenXml => open::anonymize(&mut anonymizer, input, output),
+        Format::Spreadsheet(_) => spreadsheet::anonymize(&mut anonymizer, input, output),
+    }.map_err(|e| format!("Failed to anonymize {:?}: {}", input, e))?;
+
+    Ok(TelemetryRecordBuilder::new_with_broker(format.broker()))
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    IbCsv,
+    FirstradeOfx,
+    OpenXml,
+    Spreadsheet(Broker),
+}
+
+impl Format {
+    fn detect(path: &str) -> GenericResult<Format> {
+        Ok(if path.ends_with(".csv") {
+            Format::IbCsv
+        } else if path.ends_with(".ofx") {
+            Format::FirstradeOfx
+        } else if path.ends_with(".xml") {
+            Format::OpenXml
+        } else if path.ends_with(".xlsx") {
+            Format::Spreadsheet(Broker::Tinkoff)
+        } else if path.ends_with(".xls") {
+            Format::Spreadsheet(Broker::Bcs)
+        } else {
+            return Err!("Unsupported broker statement format: {:?}", path);
+        })
+    }
+
+    fn broker(self) -> Broker {
+        match self {
+            Format::IbCsv => Broker::InteractiveBrokers,
+            Format::FirstradeOfx => Broker::Firstrade,
+            Format::OpenXml => Broker::Open,
+            Format::Spreadsheet(broker) => broker,
+        }
+    }
+
+    fn output_extension(self) -> &'static str {
+        match self {
+            Format::IbCsv => ".csv",
+            Format::FirstradeOfx => ".ofx",
+            Format::OpenXml => ".xml",
+            // There is no XLS writer, so all spreadsheets are written in XLSX format
+            Format::Spreadsheet(_) => ".xlsx",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum PersonalData {
+    Account,
+    Name,
+    Address,
+}
+
+pub struct Anonymizer {
+    scale: Option<Decimal>,
+    // Sorted by original value length in descending order to not replace a part of a longer value
+    replacements: Vec<(String, String)>,
+    counters: [usize; 3],
+}
+
+impl Anonymizer {
+    fn new(scale: Option<u32>) -> GenericResult<Anonymizer> {
+        if scale == Some(0) {
+            return Err!("Invalid scale factor: 0");
+        }
+
+        Ok(Anonymizer {
+            scale: scale.map(Decimal::from),
+            replacements: Vec::new(),
+            counters: Default::default(),
+        })
+    }
+
+    pub fn register(&mut self, kind: PersonalData, value: &str) {
+        let value = value.trim();
+        if value.is_empty() || self.replacements.iter().any(|(original, _)| original == value) {
+            return;
+        }
+
+        let counter = &mut self.counters[kind as usize];
+        *counter += 1;
+
+        let replacement = match kind {
+            PersonalData::Account => fake_account(value, *counter),
+            PersonalData::Name => numbered(
+                if is_cyrillic(value) {"Иванов Иван Иванович"} else {"John Doe"}, *counter),
+            PersonalData::Address => numbered(
+                if is_cyrillic(value) {"г. Москва, ул. Тверская, д. 1"} else {"1 Main St, New York, NY 10001"},
+                *counter),
+        };
+
+        let position = self.replacements.iter()
+            .position(|(original, _)| original.len() < value.len())
+            .unwrap_or(self.replacements.len());
+        self.replacements.insert(position, (value.to_owned(), replacement));
+    }
+
+    // Replaces all registered personal data in the specified text. Numbers are replaced only if
+    // they match the registered value exactly to not corrupt amounts.
+    pub fn replace(&self, text: &str) -> String {
+        if parse_number(text, false).is_some() {
+            return match self.replacements.iter().find(|(original, _)| original == text) {
+                Some((_, replacement)) => replacement.clone(),
+                None => text.to_owned(),
+            };
+        }
+
+        let mut text = text.to_owned();
+
+        for (original, replacement) in &self.replacements {
+            if text.contains(original.as_str()) {
+                text = text.replace(original.as_str(), replacement);
+            }
+        }
+
+        text
+    }
+
+    pub fn is_scaling(&self) -> bool {
+        self.scale.is_some()
+    }
+
+    // Scales the number returning None if the value is not a number or scaling is disabled
+    pub fn scale_number(&self, value: &str, decimal_comma: bool) -> Option<String> {
+        let scale = self.scale?;
+        let number = parse_number(value, decimal_comma)?;
+
+        let scaled = (number * scale).to_string();
+        Some(if decimal_comma && value.contains(',') {
+            scaled.replace('.', ",")
+        } else {
+            scaled
+        })
+    }
+
+    pub fn scale_float(&self, value: f64) -> Option<f64> {
+        let scale = self.scale?;
+        (Decimal::from_f64(value)? * scale).to_f64()
+    }
+
+    pub fn scale_int(&self, value: i64) -> Option<i64> {
+        let scale = self.scale?;
+        value.checked_mul(scale.to_i64()?)
+    }
+}
+
+// Checks whether values of the column with the specified title should be scaled: prices, rates,
+// ratios and identifiers are kept as is.
+pub fn is_scalable(title: &str) -> bool {
+    let title = title.to_lowercase();
+
+    for keyword in ["цена", "цены", "курс", "номинал", "ставка", "номер", "код", "коэфф", "№", "%"] {
+        if title.contains(keyword) {
+            return false;
+        }
+    }
+
+    !title.split(|c: char| !c.is_alphanumeric()).any(|word| {
+        matches!(word, "price" | "rate" | "mult" | "multiplier" | "conid" | "id" | "strike" | "code" | "ratio" | "factor") ||
+            word.ends_with("price") || (word.len() > 4 && word.ends_with("id"))
+    })
+}
+
+fn parse_number(value: &str, decimal_comma: bool) -> Option<Decimal> {
+    lazy_static! {
+        static ref NUMBER_REGEX: Regex = Regex::new(r"^-?\d+(?:\.\d+)?$").unwrap();
+    }
+
+    let value = if decimal_comma {
+        value.replace(',', ".")
+    } else {
+        value.to_owned()
+    };
+
+    if !NUMBER_REGEX.is_match(&value) {
+        return None;
+    }
+
+    Decimal::from_str(&value).ok()
+}
+
+fn fake_account(value: &str, counter: usize) -> String {
+    let digits = value.chars().filter(char::is_ascii_digit).count();
+    if digits == 0 {
+        return format!("Account {}", counter);
+    }
+
+    // The number is truncated if there are too few digits to keep the value format
+    let number = format!("{:0width$}", counter, width = digits);
+    let mut fake_digits = number[number.len() - digits..].chars();
+
+    value.chars().map(|c| {
+        if c.is_ascii_digit() {
+            fake_digits.next().unwrap()
+        } else {
+            c
+        }
+    }).collect()
+}
+
+fn numbered(value: &str, counter: usize) -> String {
+    if counter == 1 {
+        value.to_owned()
+    } else {
+        format!("{} {}", value, counter)
+    }
+}
+
+fn is_cyrillic(value: &str) -> bool {
+    value.chars().any(|c| matches!(c, 'а'..='я' | 'А'..='Я' | 'ё' | 'Ё'))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[test]
+    fn replacement() {
+        let mut anonymizer = Anonymizer::new(None).unwrap();
+        anonymizer.register(PersonalData::Account, "U1234567");
+        anonymizer.register(PersonalData::Account, "123");
+        anonymizer.register(PersonalData::Account, "123456");
+        anonymizer.register(PersonalData::Name, "Иванов Петр Сергеевич");
+        anonymizer.register(PersonalData::Name, "Ivan Petrov");
+
+        assert_eq!(anonymizer.replace("Account U1234567 (Ivan Petrov)"), "Account U0000001 (John Doe 2)");
+        assert_eq!(anonymizer.replace("Выплата дохода клиент 123456 дивиденды"), "Выплата дохода клиент 000003 дивиденды");
+        assert_eq!(anonymizer.replace("Инвестор: Иванов Петр Сергеевич"), "Инвестор: Иванов Иван Иванович");
+        assert_eq!(anonymizer.replace("123"), "002");
+        assert_eq!(anonymizer.replace("1234.5"), "1234.5");
+    }
+
+    #[test]
+    fn scaling() {
+        let anonymizer = Anonymizer::new(Some(3)).unwrap();
+        assert_eq!(anonymizer.scale_number("-10.05", false).unwrap(), "-30.15");
+        assert_eq!(anonymizer.scale_number("10,05", true).unwrap(), "30,15");
+        assert_eq!(anonymizer.scale_number("2020-01-01", false), None);
+        assert_eq!(anonymizer.scale_int(7), Some(21));
+
+        assert_eq!(Anonymizer::new(None).unwrap().scale_number("10", false), None);
+    }
+
+    #[rstest(title, expected,
+        case("Quantity", true),
+        case("Proceeds", true),
+        case("Comm/Fee", true),
+        case("T. Price", false),
+        case("Mult", false),
+        case("Security ID", false),
+        case("Tax Paid", true),
+        case("OrigTradePrice", false),
+        case("TradeID", false),
+        case("Сумма сделки", true),
+        case("Цена за единицу", false),
+        case("Номер сделки", false),
+    )]
+    fn scalable_columns(title: &str, expected: bool) {
+        assert_eq!(is_scalable(title), expected);
+    }
+}
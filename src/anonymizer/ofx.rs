@@ -0,0 +1,48 @@
+use std::fs;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::core::EmptyResult;
+
+use super::{Anonymizer, PersonalData};
+
+const ACCOUNT_ELEMENTS: &[&str] = &["ACCTID", "USERID"];
+
+const SCALABLE_ELEMENTS: &[&str] = &[
+    "UNITS", "TOTAL", "COMMISSION", "FEES", "TAXES", "TRNAMT", "MKTVAL",
+    "AVAILCASH", "MARGINBALANCE", "SHORTBALANCE", "BUYPOWER", "VALUE",
+];
+
+pub fn anonymize(anonymizer: &mut Anonymizer, input: &str, output: &str) -> EmptyResult {
+    lazy_static! {
+        static ref ELEMENT_REGEX: Regex = Regex::new(r"<([A-Z0-9.]+)>([^<]*)").unwrap();
+    }
+
+    let data = fs::read_to_string(input)?;
+
+    for captures in ELEMENT_REGEX.captures_iter(&data) {
+        if ACCOUNT_ELEMENTS.contains(&&captures[1]) {
+            anonymizer.register(PersonalData::Account, &captures[2]);
+        }
+    }
+
+    // Values may be followed by a closing tag or by whitespace (SGML-style OFX)
+    let data = ELEMENT_REGEX.replace_all(&data, |captures: &Captures| {
+        let name = &captures[1];
+        let value = &captures[2];
+
+        let trimmed = value.trim_end();
+        let whitespace = &value[trimmed.len()..];
+
+        let new_value = if SCALABLE_ELEMENTS.contains(&name) {
+            anonymizer.scale_number(trimmed, false).unwrap_or_else(|| trimmed.to_owned())
+        } else {
+            anonymizer.replace(trimmed)
+        };
+
+        format!("<{}>{}{}", name, new_value, whitespace)
+    });
+
+    Ok(fs::write(output, data.as_bytes())?)
+}
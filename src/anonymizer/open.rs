@@ -0,0 +1,103 @@
+use std::borrow::Cow;
+use std::fs;
+
+use encoding_rs::Encoding;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::core::EmptyResult;
+
+use super::{Anonymizer, PersonalData};
+
+// Attributes which are read by the statement parser and hold amounts or quantities
+const SCALABLE_ATTRIBUTES: &[&str] = &[
+    // MOEX reports
+    "value", "opening_position_plan", "closing_position_plan", "buy_qnty", "sell_qnty", "volume",
+    "nkd", "broker_commission", "amount", "quantity", "sum_1", "sum_2", "brokers_fee", "broker_comm",
+
+    // SPB reports
+    "paymentamount", "brokerage", "amountin", "amountplaneout", "onaccountbegin", "plannedbalance",
+];
+
+pub fn anonymize(anonymizer: &mut Anonymizer, input: &str, output: &str) -> EmptyResult {
+    lazy_static! {
+        static ref ENCODING_REGEX: Regex = Regex::new(r#"^<\?xml [^>]*encoding="([^"]+)""#).unwrap();
+        static ref ATTRIBUTE_REGEX: Regex = Regex::new(r#"([\w:]+)="([^"]*)""#).unwrap();
+        static ref PERSONAL_ATTRIBUTE_REGEX: Regex = Regex::new(
+            r"^(?:client|investor|agreement|account)(?:_|$)").unwrap();
+        static ref CLIENT_REGEX: Regex = Regex::new(r"клиент <?([^ >]+)>?").unwrap();
+    }
+
+    let data = fs::read(input)?;
+
+    let header = String::from_utf8_lossy(&data[..std::cmp::min(data.len(), 100)]).into_owned();
+    let encoding_name = ENCODING_REGEX.captures(&header)
+        .map(|captures| captures[1].to_owned())
+        .unwrap_or_else(|| s!("UTF-8"));
+
+    let encoding = Encoding::for_label(encoding_name.as_bytes()).ok_or_else(|| format!(
+        "Unsupported document encoding: {:?}", encoding_name))?;
+
+    let (data, _, errors) = encoding.decode(&data);
+    if errors {
+        return Err!("Got an invalid {} encoded data", encoding_name);
+    }
+
+    for captures in ATTRIBUTE_REGEX.captures_iter(&data) {
+        let name = &captures[1];
+        let value = &captures[2];
+
+        if PERSONAL_ATTRIBUTE_REGEX.is_match(name) {
+            let kind = if name.contains("name") || name.contains("fio") {
+                PersonalData::Name
+            } else if name.contains("address") {
+                PersonalData::Address
+            } else {
+                PersonalData::Account
+            };
+            anonymizer.register(kind, value);
+        } else if name == "comment" {
+            if let Some(captures) = CLIENT_REGEX.captures(value) {
+                anonymizer.register(PersonalData::Account, &captures[1]);
+            }
+        }
+    }
+
+    let data = ATTRIBUTE_REGEX.replace_all(&data, |captures: &Captures| {
+        let name = &captures[1];
+        let value = &captures[2];
+
+        let new_value = if SCALABLE_ATTRIBUTES.contains(&name) {
+            anonymizer.scale_number(value, false).unwrap_or_else(|| value.to_owned())
+        } else if name == "comment" {
+            anonymizer.replace(&scale_comment(anonymizer, value))
+        } else {
+            anonymizer.replace(value)
+        };
+
+        format!(r#"{}="{}""#, name, new_value)
+    });
+
+    let (data, _, errors) = encoding.encode(&data);
+    if errors {
+        return Err!("Unable to encode the statement to {}", encoding_name);
+    }
+
+    Ok(fs::write(output, data)?)
+}
+
+// Dividend descriptions of SPB reports contain quantity and tax amount which are parsed
+fn scale_comment<'a>(anonymizer: &Anonymizer, comment: &'a str) -> Cow<'a, str> {
+    lazy_static! {
+        static ref AMOUNT_REGEX: Regex = Regex::new(
+            r"(количество |удержан налог эмитентом )(\d+(?:\.\d+)?)").unwrap();
+    }
+
+    if !anonymizer.is_scaling() {
+        return Cow::Borrowed(comment);
+    }
+
+    AMOUNT_REGEX.replace_all(comment, |captures: &Captures| {
+        format!("{}{}", &captures[1], anonymizer.scale_number(&captures[2], false).unwrap())
+    })
+}
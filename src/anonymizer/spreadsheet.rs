@@ -0,0 +1,292 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+
+use calamine::{Range, Reader, open_workbook_auto};
+use zip::{ZipWriter, write::FileOptions};
+
+use crate::core::EmptyResult;
+use crate::xls::Cell;
+
+use super::{Anonymizer, PersonalData, is_scalable};
+
+// Labels of personal data in statement headers: the data is either in the same cell after the
+// label or in the next non-empty cell of the row.
+const LABELS: &[(&str, PersonalData)] = &[
+    ("инвестор", PersonalData::Name),
+    ("клиент", PersonalData::Name),
+    ("фио", PersonalData::Name),
+    ("ф.и.о.", PersonalData::Name),
+    ("владелец", PersonalData::Name),
+    ("генеральное соглашение", PersonalData::Account),
+    ("номер договора", PersonalData::Account),
+    ("договор", PersonalData::Account),
+    ("код клиента", PersonalData::Account),
+    ("торговый код", PersonalData::Account),
+    ("субсчет", PersonalData::Account),
+    ("адрес", PersonalData::Address),
+];
+
+const SEPARATORS: &[char] = &[':', ' ', '№'];
+
+pub fn anonymize(anonymizer: &mut Anonymizer, input: &str, output: &str) -> EmptyResult {
+    let mut workbook = open_workbook_auto(input)?;
+    let mut sheets = Vec::new();
+
+    for name in workbook.sheet_names().to_vec() {
+        if let Some(sheet) = workbook.worksheet_range(&name).transpose()? {
+            sheets.push((name, sheet));
+        }
+    }
+
+    for (_, sheet) in &sheets {
+        for row in sheet.rows() {
+            collect(anonymizer, row);
+        }
+    }
+
+    let sheets: Vec<_> = sheets.iter().map(|(name, sheet)| {
+        (name.as_str(), rewrite(anonymizer, sheet))
+    }).collect();
+
+    write_xlsx(output, &sheets)
+}
+
+fn collect(anonymizer: &mut Anonymizer, row: &[Cell]) {
+    let mut expecting: Option<PersonalData> = None;
+
+    for cell in row {
+        let value = match cell {
+            Cell::Empty => continue,
+            Cell::String(value) => value.trim(),
+            _ => {
+                expecting = None;
+                continue;
+            },
+        };
+
+        if let Some(kind) = expecting.take() {
+            anonymizer.register(kind, value);
+            continue;
+        }
+
+        let lowercase = value.to_lowercase();
+
+        for &(label, kind) in LABELS {
+            let rest = match lowercase.strip_prefix(label) {
+                Some(rest) if rest.is_empty() || rest.starts_with(SEPARATORS) => rest,
+                _ => continue,
+            };
+
+            // Plain text like column titles
+            if !rest.trim_start().starts_with(&[':', '№'][..]) {
+                break;
+            }
+
+            let data_start = value.char_indices().nth(label.chars().count())
+                .map(|(index, _)| index).unwrap_or(value.len());
+            let data = value[data_start..].trim_start_matches(SEPARATORS).trim();
+
+            if data.is_empty() {
+                expecting.replace(kind);
+            } else {
+                anonymizer.register(kind, data);
+            }
+
+            break;
+        }
+    }
+}
+
+fn rewrite(anonymizer: &Anonymizer, sheet: &Range<Cell>) -> Vec<(u32, u32, Cell)> {
+    let (start_row, start_column) = sheet.start().unwrap_or_default();
+
+    // Column titles are tracked as the last non-numeric string in each column
+    let mut titles: Vec<Option<&str>> = vec![None; sheet.width()];
+    let mut cells = Vec::new();
+
+    for (row_id, row) in sheet.rows().enumerate() {
+        for (column_id, cell) in row.iter().enumerate() {
+            let scalable = titles[column_id].map(is_scalable).unwrap_or(true);
+
+            let cell = match cell {
+                Cell::Empty => continue,
+
+                Cell::String(value) => {
+                    match anonymizer.scale_number(value, true) {
+                        Some(scaled) if scalable => Cell::String(scaled),
+                        Some(_) => Cell::String(anonymizer.replace(value)),
+                        None => {
+                            titles[column_id].replace(value.as_str());
+                            Cell::String(anonymizer.replace(value))
+                        },
+                    }
+                },
+
+                Cell::Float(value) if scalable => Cell::Float(
+                    anonymizer.scale_float(*value).unwrap_or(*value)),
+
+                Cell::Int(value) if scalable => Cell::Int(
+                    anonymizer.scale_int(*value).unwrap_or(*value)),
+
+                _ => cell.clone(),
+            };
+
+            cells.push((start_row + row_id as u32, start_column + column_id as u32, cell));
+        }
+    }
+
+    cells
+}
+
+fn write_xlsx(path: &str, sheets: &[(&str, Vec<(u32, u32, Cell)>)]) -> EmptyResult {
+    const CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml";
+    const RELATIONSHIPS_NAMESPACE: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+    const RELATIONSHIP_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+    const SPREADSHEET_NAMESPACE: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+    const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+
+    let mut content_types = format!(concat!(
+        r#"{}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+        r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+        r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+        r#"<Override PartName="/xl/workbook.xml" ContentType="{base}.sheet.main+xml"/>"#,
+        r#"<Override PartName="/xl/styles.xml" ContentType="{base}.styles+xml"/>"#,
+    ), XML_HEADER, base=CONTENT_TYPE);
+
+    let mut workbook = format!(
+        r#"{}<workbook xmlns="{}" xmlns:r="{}"><sheets>"#,
+        XML_HEADER, SPREADSHEET_NAMESPACE, RELATIONSHIP_TYPE);
+
+    let mut relationships = format!(r#"{}<Relationships xmlns="{}">"#, XML_HEADER, RELATIONSHIPS_NAMESPACE);
+
+    for (index, (name, _)) in sheets.iter().enumerate() {
+        let id = index + 1;
+
+        write!(content_types,
+            r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="{}.worksheet+xml"/>"#,
+            id, CONTENT_TYPE).unwrap();
+
+        write!(workbook, r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, escape(name), id, id).unwrap();
+
+        write!(relationships,
+            r#"<Relationship Id="rId{}" Type="{}/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+            id, RELATIONSHIP_TYPE, id).unwrap();
+    }
+
+    content_types.push_str("</Types>");
+    workbook.push_str("</sheets></workbook>");
+    write!(relationships,
+        r#"<Relationship Id="rId{}" Type="{}/styles" Target="styles.xml"/></Relationships>"#,
+        sheets.len() + 1, RELATIONSHIP_TYPE).unwrap();
+
+    // The second cell format is used for dates
+    let styles = format!(concat!(
+        r#"{}<styleSheet xmlns="{}">"#,
+        r#"<fonts count="1"><font/></fonts><fills count="1"><fill/></fills><borders count="1"><border/></borders>"#,
+        r#"<cellStyleXfs count="1"><xf/></cellStyleXfs>"#,
+        r#"<cellXfs count="2"><xf/><xf numFmtId="22" applyNumberFormat="1"/></cellXfs>"#,
+        r#"</styleSheet>"#,
+    ), XML_HEADER, SPREADSHEET_NAMESPACE);
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let mut add_file = |name: &str, data: &str| -> EmptyResult {
+        zip.start_file(name, FileOptions::default())?;
+        Ok(zip.write_all(data.as_bytes())?)
+    };
+
+    add_file("[Content_Types].xml", &content_types)?;
+    add_file("_rels/.rels", &format!(
+        r#"{}<Relationships xmlns="{}"><Relationship Id="rId1" Type="{}/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+        XML_HEADER, RELATIONSHIPS_NAMESPACE, RELATIONSHIP_TYPE))?;
+    add_file("xl/workbook.xml", &workbook)?;
+    add_file("xl/_rels/workbook.xml.rels", &relationships)?;
+    add_file("xl/styles.xml", &styles)?;
+
+    for (index, (_, cells)) in sheets.iter().enumerate() {
+        add_file(&format!("xl/worksheets/sheet{}.xml", index + 1), &format_sheet(cells))?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn format_sheet(cells: &[(u32, u32, Cell)]) -> String {
+    let mut sheet = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><worksheet xmlns="{}"><sheetData>"#,
+        "http://schemas.openxmlformats.org/spreadsheetml/2006/main");
+
+    let mut current_row = None;
+
+    for (row, column, cell) in cells {
+        if current_row != Some(*row) {
+            if current_row.is_some() {
+                sheet.push_str("</row>");
+            }
+            write!(sheet, r#"<row r="{}">"#, row + 1).unwrap();
+            current_row.replace(*row);
+        }
+
+        let reference = format!("{}{}", column_name(*column), row + 1);
+
+        match cell {
+            Cell::String(value) => write!(
+                sheet, r#"<c r="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                reference, escape(value)),
+            Cell::Float(value) => write!(sheet, r#"<c r="{}"><v>{}</v></c>"#, reference, value),
+            Cell::Int(value) => write!(sheet, r#"<c r="{}"><v>{}</v></c>"#, reference, value),
+            Cell::DateTime(value) => write!(sheet, r#"<c r="{}" s="1"><v>{}</v></c>"#, reference, value),
+            Cell::Bool(value) => write!(sheet, r#"<c r="{}" t="b"><v>{}</v></c>"#, reference, *value as u8),
+            Cell::Error(_) | Cell::Empty => Ok(()),
+        }.unwrap();
+    }
+
+    if current_row.is_some() {
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+
+    sheet
+}
+
+fn column_name(mut index: u32) -> String {
+    let mut name = Vec::new();
+
+    loop {
+        name.push((b'A' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+
+    name.iter().rev().collect()
+}
+
+fn escape(value: &str) -> String {
+    value.chars().filter(|&c| c >= ' ' || matches!(c, '\t' | '\n' | '\r')).fold(String::new(), |mut escaped, c| {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+        escaped
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_names() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+}
@@ -41,10 +41,27 @@ pub enum Action {
         year: Option<i32>,
         tax_statement_path: Option<String>,
     },
+    TaxSummary {
+        year: i32,
+        tax_statement_path: Option<String>,
+    },
     CashFlow {
         name: String,
         year: Option<i32>,
     },
+    Export {
+        name: String,
+        path: Option<String>,
+    },
+    Check,
+    Identify {
+        path: String,
+    },
+    Anonymize {
+        input: String,
+        output: String,
+        scale: Option<u32>,
+    },
 
     Deposits {
         date: Date,
@@ -14,11 +14,16 @@ use std::time::Duration;
 use log::error;
 
 use investments::analysis;
+use investments::anonymizer;
 use investments::cash_flow;
+use investments::check;
 use investments::config::Config;
 use investments::core::EmptyResult;
 use investments::db;
 use investments::deposits;
+use investments::export;
+use investments::identify;
+use investments::logging;
 use investments::metrics;
 use investments::portfolio;
 use investments::tax_statement;
@@ -35,7 +40,7 @@ fn main() {
         process::exit(1);
     });
 
-    if let Err(e) = easy_logging::init(module_path!().split("::").next().unwrap(), global.log_level) {
+    if let Err(e) = logging::init(module_path!().split("::").next().unwrap(), global.log_level) {
         let _ = writeln!(io::stderr(), "Failed to initialize the logging: {}.", e);
         process::exit(1);
     }
@@ -97,8 +102,15 @@ fn run(config: Config, command: &str, action: Action) -> EmptyResult {
         Action::TaxStatement {name, year, tax_statement_path} =>
             tax_statement::generate_tax_statement(
                 &config, &name, year, tax_statement_path.as_deref())?,
+        Action::TaxSummary {year, tax_statement_path} =>
+            tax_statement::generate_tax_summary(&config, year, tax_statement_path.as_deref())?,
         Action::CashFlow {name, year} =>
             cash_flow::generate_cash_flow_report(&config, &name, year)?,
+        Action::Export {name, path} => export::export(&config, &name, path.as_deref())?,
+        Action::Check => check::check(&config)?,
+        Action::Identify {path} => identify::identify(&path)?,
+        Action::Anonymize {input, output, scale} =>
+            anonymizer::anonymize(&input, &output, scale)?,
 
         Action::Deposits {date, cron_mode} => {
             deposits::list(
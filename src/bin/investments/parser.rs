@@ -62,6 +62,9 @@ impl Parser {
                     .short('e').long("cache-expire-time")
                     .value_name("DURATION"),
 
+                cli::new_arg("reread_statements", "Ignore broker statement cache and reread all statements")
+                    .short('r').long("reread-statements"),
+
                 cli::new_arg("verbose", "Set verbosity level")
                     .short('v').long("verbose")
                     .multiple_occurrences(true)
@@ -146,12 +149,29 @@ impl Parser {
                     named Декларация) by adding all required information about income from stock \
                     selling, paid dividends and idle cash interest.\n\
                     \n\
+                    If *.xml path is specified instead, generates 3-NDFL in FNS XML format which \
+                    can be uploaded to the taxpayer's personal account.\n\
+                    \n\
                     If tax statement file is not specified only outputs the data which is going to \
                     be declared.")
                 .args([
                     portfolio::arg(),
                     cli::new_arg("YEAR", "Year to generate the statement for"),
-                    cli::new_arg("TAX_STATEMENT", "Path to tax statement *.dcX file"),
+                    cli::new_arg("TAX_STATEMENT", "Path to tax statement *.dcX or *.xml file"),
+                ]))
+
+            .subcommand(cli::new_subcommand(
+                "tax-summary", "Summarize taxes of all portfolios for the specified year")
+                .long_about("\
+                    Processes income of all portfolios for the specified tax year and outputs \
+                    consolidated taxes by income types and brokers: tax withheld by tax agents, tax \
+                    to pay, applied long-term ownership tax deductions and previous years' losses.\n\
+                    \n\
+                    If tax statement *.dcX or *.xml file is specified, declares income of all \
+                    portfolios in it.")
+                .args([
+                    cli::new_arg("YEAR", "Tax year").required(true),
+                    cli::new_arg("TAX_STATEMENT", "Path to tax statement *.dcX or *.xml file"),
                 ]))
 
             .subcommand(cli::new_subcommand(
@@ -162,6 +182,49 @@ impl Parser {
                     cli::new_arg("YEAR", "Year to generate the report for"),
                 ]))
 
+            .subcommand(cli::new_subcommand(
+                "export", "Export broker statement")
+                .long_about("\
+                    Reads broker statements and exports the resulting normalized broker statement \
+                    in JSON format (see docs/export.md for format description).")
+                .args([
+                    portfolio::arg(),
+                    cli::new_arg("PATH", "Path to write the statement to (stdout if not specified)"),
+                ]))
+
+            .subcommand(cli::new_subcommand(
+                "check", "Check broker statements of all portfolios")
+                .long_about("\
+                    Reads broker statements of all portfolios and reports statement periods, gaps \
+                    between them, cash assets reconciliation results and all found problems. Exits \
+                    with non-zero code if any problem is found."))
+
+            .subcommand(cli::new_subcommand(
+                "identify", "Identify broker statements")
+                .long_about("\
+                    Detects broker, format, account and period of the specified broker statement or \
+                    of all statements in the specified directory or archive.")
+                .arg(cli::new_arg("PATH", "Broker statement, statements directory or archive").required(true)))
+
+            .subcommand(cli::new_subcommand(
+                "anonymize", "Anonymize broker statement")
+                .long_about("\
+                    Rewrites broker statement replacing account numbers, names and addresses, so it \
+                    can be attached to a bug report. Optionally scales all amounts and quantities by \
+                    the specified factor keeping prices and all totals consistent.\n\
+                    \n\
+                    Supports Interactive Brokers (*.csv), Firstrade (*.ofx), Открытие (*.xml), \
+                    Тинькофф (*.xlsx) and БКС (*.xls) statements. Spreadsheets are always written in \
+                    XLSX format.")
+                .args([
+                    cli::new_arg("scale", "Scale all amounts by the specified factor")
+                        .short('s').long("scale")
+                        .value_name("FACTOR"),
+
+                    cli::new_arg("INPUT", "Broker statement to anonymize").required(true),
+                    cli::new_arg("OUTPUT", "Path to write the anonymized statement to").required(true),
+                ]))
+
             .subcommand(cli::new_subcommand(
                 "deposits", "List deposits")
                 .args([
@@ -224,6 +287,7 @@ impl Parser {
             config.cache_expire_time = time::parse_duration(expire_time).map_err(|_| format!(
                 "Invalid cache expire time: {:?}", expire_time))?;
         };
+        config.reread_statements = matches.is_present("reread_statements");
 
         let (command, matches) = matches.subcommand().unwrap();
         let action = self.parse_command(command, matches)?;
@@ -286,6 +350,11 @@ impl Parser {
                 }
             },
 
+            "tax-summary" => Action::TaxSummary {
+                year: get_year(matches)?.unwrap(),
+                tax_statement_path: matches.value_of("TAX_STATEMENT").map(ToOwned::to_owned),
+            },
+
             "cash-flow" => {
                 Action::CashFlow {
                     name: portfolio::get(matches),
@@ -293,6 +362,26 @@ impl Parser {
                 }
             },
 
+            "export" => Action::Export {
+                name: portfolio::get(matches),
+                path: matches.value_of("PATH").map(ToOwned::to_owned),
+            },
+
+            "check" => Action::Check,
+
+            "identify" => Action::Identify {
+                path: matches.value_of("PATH").unwrap().to_owned(),
+            },
+
+            "anonymize" => Action::Anonymize {
+                input: matches.value_of("INPUT").unwrap().to_owned(),
+                output: matches.value_of("OUTPUT").unwrap().to_owned(),
+                scale: matches.value_of("scale").map(|scale| {
+                    scale.parse::<u32>().ok().filter(|&scale| scale > 0).ok_or_else(|| format!(
+                        "Invalid scale factor: {}", scale))
+                }).transpose()?,
+            },
+
             "deposits" => {
                 let date = match matches.value_of("date") {
                     Some(date) => time::parse_user_date(date)?,
@@ -14,7 +14,7 @@ use crate::exchanges::Exchange;
 #[cfg(test)] use crate::taxes::TaxRemapping;
 use crate::xls::{XlsStatementParser, Section, SheetParser};
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
 use super::{BrokerStatementReader, PartialBrokerStatement};
 
 use assets::AssetsParser;
@@ -34,10 +34,11 @@ impl StatementReader {
 
 impl BrokerStatementReader for StatementReader {
     fn check(&mut self, path: &str) -> GenericResult<bool> {
-        Ok(path.ends_with(".xls"))
+        // XLSX statements are produced by the anonymizer
+        Ok(path.ends_with(".xls") || path.ends_with(".xlsx"))
     }
 
-    fn read(&mut self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
         let parser = Box::new(StatementSheetParser{});
         let statement = PartialBrokerStatement::new_rc(&[Exchange::Moex, Exchange::Spb], true);
 
@@ -96,9 +97,9 @@ mod tests {
         let corporate_actions = &config.get_portfolio(portfolio_name).unwrap().corporate_actions;
 
         let statement = BrokerStatement::read(
-            broker, &format!("testdata/bcs/{}", name),
+            broker, &format!("testdata/bcs/{}", name), None,
             &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(),
-            corporate_actions, ReadingStrictness::all()).unwrap();
+            corporate_actions, StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
 
         assert!(!statement.cash_assets.is_empty());
         assert!(!statement.deposits_and_withdrawals.is_empty());
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting;
+use crate::instruments::InstrumentId;
+use crate::localities::Country;
+use crate::taxes::IncomeType;
+use crate::time::Date;
+use crate::types::Decimal;
+
+use super::BrokerStatement;
+use super::trades::StockSell;
+
+// Bond payments are reported by some brokers with issuer name or broker-specific internal ID
+// instead of symbol, so they are resolved to symbols only on statements merging stage.
+#[derive(Serialize, Deserialize)]
+pub struct BondPayment {
+    pub date: Date,
+    pub issuer: InstrumentId,
+    pub type_: BondPaymentType,
+    pub amount: Cash,
+}
+
+impl BondPayment {
+    pub fn new(date: Date, issuer: InstrumentId, type_: BondPaymentType, amount: Cash) -> BondPayment {
+        BondPayment {date, issuer, type_, amount}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum BondPaymentType {
+    Coupon,
+
+    // Accrued coupon income (НКД) which is paid to seller on top of the bond price on purchase and
+    // received from buyer on sell. Negative when paid.
+    AccruedInterest,
+
+    // Partial redemption of the bond face value
+    Amortization,
+
+    // Redemption of the whole position at maturity. Brokers report only the paid amount, so
+    // quantity is calculated using the bond face value.
+    Redemption,
+}
+
+pub struct BondIncome {
+    pub date: Date,
+    pub symbol: String,
+    pub original_symbol: String,
+
+    pub type_: BondIncomeType,
+    pub amount: Cash, // May be negative
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BondIncomeType {
+    Coupon,
+    AccruedInterest,
+}
+
+impl BondIncome {
+    pub fn new(date: Date, symbol: &str, type_: BondIncomeType, amount: Cash) -> BondIncome {
+        BondIncome {
+            date,
+            symbol: symbol.to_owned(),
+            original_symbol: symbol.to_owned(),
+            type_, amount,
+        }
+    }
+
+    pub fn local_description(&self) -> &'static str {
+        match self.type_ {
+            BondIncomeType::Coupon => "Купон",
+            BondIncomeType::AccruedInterest => if self.amount.is_negative() {
+                "НКД уплаченный"
+            } else {
+                "НКД полученный"
+            },
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let type_ = match self.type_ {
+            BondIncomeType::Coupon => "coupon",
+            BondIncomeType::AccruedInterest => "accrued interest",
+        };
+        format!("{} {} from {}", self.original_symbol, type_, formatting::format_date(self.date))
+    }
+}
+
+// Amortization is a partial redemption of the bond face value. It doesn't change the position
+// quantity, but returns a part of the invested money, so we treat it as a reduction of the purchase
+// cost of all open lots for the bond (profit is taxed later on sell or redemption).
+pub struct BondAmortization {
+    pub date: Date,
+    pub symbol: String,
+    pub original_symbol: String,
+    pub amount: Cash,
+    processed: bool,
+}
+
+impl BondAmortization {
+    pub fn new(date: Date, symbol: &str, amount: Cash) -> BondAmortization {
+        BondAmortization {
+            date,
+            symbol: symbol.to_owned(),
+            original_symbol: symbol.to_owned(),
+            amount,
+            processed: false,
+        }
+    }
+
+    pub fn is_processed(&self) -> bool {
+        self.processed
+    }
+
+    pub fn process(&mut self) {
+        assert!(!self.processed);
+        self.processed = true;
+    }
+
+    pub fn description(&self) -> String {
+        format!("{} amortization from {}", self.original_symbol, formatting::format_date(self.date))
+    }
+}
+
+// Redemption at maturity is processed as an ordinary sell of the whole position at its face value
+fn new_redemption(symbol: &str, quantity: Decimal, amount: Cash, date: Date) -> GenericResult<StockSell> {
+    if quantity <= dec!(0) {
+        return Err!("Invalid {} redemption quantity: {}", symbol, quantity);
+    }
+
+    let price = amount / quantity;
+    let commission = Cash::zero(amount.currency);
+
+    Ok(StockSell::new_trade(symbol, quantity, price, amount, commission, date.into(), date, false))
+}
+
+pub struct BondIncomeTax {
+    pub income: Cash,
+    pub tax_to_pay: Cash,
+}
+
+// Accrued interest paid on bond purchase reduces taxable coupon income, so tax is calculated for the
+// whole year instead of each payment separately.
+pub fn calculate_bond_income_taxes<'a, I>(
+    country: &Country, income: I, converter: &CurrencyConverter,
+) -> GenericResult<BTreeMap<i32, BondIncomeTax>>
+    where I: IntoIterator<Item=&'a BondIncome>
+{
+    let mut years = BTreeMap::new();
+
+    for income in income {
+        let amount = converter.convert_to_cash_rounding(income.date, income.amount, country.currency)?;
+        *years.entry(income.date.year()).or_insert_with(|| Cash::zero(country.currency)) += amount;
+    }
+
+    Ok(years.into_iter().map(|(year, income)| {
+        let tax_to_pay = country.tax_to_pay(IncomeType::Interest, year, income, None);
+        (year, BondIncomeTax {income, tax_to_pay})
+    }).collect())
+}
+
+pub fn process_bond_payments(statement: &mut BrokerStatement, payments: Vec<BondPayment>) -> EmptyResult {
+    for payment in payments {
+        let instrument = statement.instrument_info.get_or_add_by_id(&payment.issuer).map_err(|e| format!(
+            "Failed to process {} bond payment from {}: {}",
+            payment.issuer, formatting::format_date(payment.date), e))?;
+
+        let symbol = instrument.symbol.clone();
+        let face_value = instrument.face_value;
+
+        match payment.type_ {
+            BondPaymentType::Coupon => {
+                statement.bond_income.push(BondIncome::new(
+                    payment.date, &symbol, BondIncomeType::Coupon, payment.amount));
+            },
+            BondPaymentType::AccruedInterest => {
+                statement.bond_income.push(BondIncome::new(
+                    payment.date, &symbol, BondIncomeType::AccruedInterest, payment.amount));
+            },
+            BondPaymentType::Amortization => {
+                statement.bond_amortizations.push(BondAmortization::new(
+                    payment.date, &symbol, payment.amount));
+            },
+            BondPaymentType::Redemption => {
+                let face_value = face_value.ok_or_else(|| format!(
+                    "Unable to process {} redemption: face value of the bond is unknown", symbol))?;
+
+                let quantity = payment.amount.div(face_value).map_err(|e| format!(
+                    "Unable to process {} redemption: {}", symbol, e))?.normalize();
+
+                if !quantity.fract().is_zero() {
+                    return Err!(
+                        "Unable to process {} redemption: {} redemption amount doesn't match {} face value",
+                        symbol, payment.amount, face_value);
+                }
+
+                statement.stock_sells.push(new_redemption(&symbol, quantity, payment.amount, payment.date)?);
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::localities;
+
+    use super::*;
+
+    #[test]
+    fn bond_income_taxes() {
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &BTreeMap::new());
+
+        let income = [
+            (date!(2021, 1, 13), BondIncomeType::AccruedInterest, dec!(-60.5)),
+            (date!(2021, 2, 3), BondIncomeType::Coupon, dec!(406.9)),
+            (date!(2021, 8, 3), BondIncomeType::Coupon, dec!(406.9)),
+            (date!(2021, 9, 10), BondIncomeType::AccruedInterest, dec!(25.4)),
+
+            // Paid accrued interest exceeds the coupon
+            (date!(2022, 1, 20), BondIncomeType::AccruedInterest, dec!(-300)),
+            (date!(2022, 2, 2), BondIncomeType::Coupon, dec!(200)),
+        ].iter().map(|&(date, type_, amount)| {
+            BondIncome::new(date, "SU46020RMFS2", type_, Cash::new("RUB", amount))
+        }).collect::<Vec<_>>();
+
+        let taxes = calculate_bond_income_taxes(&country, &income, &CurrencyConverter::mock()).unwrap()
+            .into_iter()
+            .map(|(year, tax)| (year, tax.income.amount, tax.tax_to_pay.amount))
+            .collect::<Vec<_>>();
+
+        assert_eq!(taxes, vec![
+            (2021, dec!(778.7), dec!(101)),
+            (2022, dec!(-100), dec!(0)),
+        ]);
+    }
+}
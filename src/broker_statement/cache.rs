@@ -0,0 +1,166 @@
+use std::fs;
+
+use chrono::Utc;
+use diesel::{self, prelude::*};
+use log::debug;
+#[cfg(test)] use tempfile::NamedTempFile;
+
+use crate::config::Config;
+use crate::core::{EmptyResult, GenericResult};
+use crate::db::{self, schema::broker_statements, models};
+use crate::types::DateTime;
+
+use super::files::StatementFile;
+use super::partial::PartialBrokerStatement;
+
+// Parsing of broker statements is quite expensive (especially for *.xls(x) statements), so parsed
+// statements are cached in the database and are reread only when the statement file or the reader
+// context changes.
+pub struct StatementCache {
+    db: Option<db::Connection>,
+    reread: bool,
+}
+
+impl StatementCache {
+    pub fn new(config: &Config) -> GenericResult<StatementCache> {
+        Ok(StatementCache {
+            db: Some(db::connect(&config.db_path)?),
+            reread: config.reread_statements,
+        })
+    }
+
+    pub fn new_disabled() -> StatementCache {
+        StatementCache {
+            db: None,
+            reread: false,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn new_temporary() -> (NamedTempFile, StatementCache) {
+        let (database, connection) = db::new_temporary();
+        (database, StatementCache {
+            db: Some(connection),
+            reread: false,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.db.is_some()
+    }
+
+    pub fn get(&self, key: &StatementKey) -> GenericResult<Option<PartialBrokerStatement>> {
+        let db = match self.db {
+            Some(ref db) if !self.reread => db,
+            _ => return Ok(None),
+        };
+
+        let statement = broker_statements::table
+            .select(broker_statements::statement)
+            .filter(broker_statements::path.eq(&key.path))
+            .filter(broker_statements::size.eq(key.size))
+            .filter(broker_statements::modify_time.eq(key.modify_time))
+            .filter(broker_statements::hash.eq(&key.hash))
+            .filter(broker_statements::context.eq(&key.context))
+            .get_result::<String>(&**db).optional()?;
+
+        let statement = match statement {
+            Some(statement) => statement,
+            None => return Ok(None),
+        };
+
+        // The data may be written by another version of the program
+        Ok(match serde_json::from_str(&statement) {
+            Ok(statement) => Some(statement),
+            Err(e) => {
+                debug!("Ignoring invalid cached {:?} broker statement: {}.", key.path, e);
+                None
+            },
+        })
+    }
+
+    pub fn save(&self, key: &StatementKey, statement: &PartialBrokerStatement) -> EmptyResult {
+        let db = match self.db {
+            Some(ref db) => db,
+            None => return Ok(()),
+        };
+
+        diesel::replace_into(broker_statements::table)
+            .values(models::NewBrokerStatement {
+                path: &key.path,
+                size: key.size,
+                modify_time: key.modify_time,
+                hash: &key.hash,
+                context: &key.context,
+                statement: serde_json::to_string(statement)?,
+            })
+            .execute(&**db)?;
+
+        Ok(())
+    }
+}
+
+pub struct StatementKey {
+    path: String,
+    size: i64,
+    modify_time: DateTime,
+    hash: String,
+    context: String,
+}
+
+impl StatementKey {
+    // Context must contain all data except the statement file itself which affects the parsing results
+    pub fn new(file: &StatementFile, context: &str) -> GenericResult<StatementKey> {
+        let data = fs::read(&file.path)?;
+        let modify_time: chrono::DateTime<Utc> = fs::metadata(file.origin_path())?.modified()?.into();
+
+        Ok(StatementKey {
+            path: file.id(),
+            size: data.len() as i64,
+            modify_time: modify_time.naive_utc(),
+            hash: hash(&data),
+            context: hash(context.as_bytes()),
+        })
+    }
+}
+
+fn hash(data: &[u8]) -> String {
+    blake2b_simd::blake2b(data).to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn cache() {
+        let (_database, mut cache) = StatementCache::new_temporary();
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"statement").unwrap();
+        let path = file.path().to_str().unwrap().to_owned();
+        let statement_file = StatementFile::new("statement", &path);
+
+        let key = StatementKey::new(&statement_file, "context").unwrap();
+        let other_context_key = StatementKey::new(&statement_file, "other context").unwrap();
+        assert!(cache.get(&key).unwrap().is_none());
+
+        let mut statement = PartialBrokerStatement::new(&[], true);
+        statement.add_open_position("VTI", dec!(10)).unwrap();
+        cache.save(&key, &statement).unwrap();
+
+        let cached = cache.get(&key).unwrap().unwrap();
+        assert_eq!(cached.open_positions, hashmap!{s!("VTI") => dec!(10)});
+        assert!(cache.get(&other_context_key).unwrap().is_none());
+
+        cache.reread = true;
+        assert!(cache.get(&key).unwrap().is_none());
+        cache.reread = false;
+
+        file.write_all(b" update").unwrap();
+        let changed_key = StatementKey::new(&statement_file, "context").unwrap();
+        assert!(cache.get(&changed_key).unwrap().is_none());
+    }
+}
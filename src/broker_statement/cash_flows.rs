@@ -1,18 +1,27 @@
+use serde::{Deserialize, Serialize};
+
 use crate::currency::Cash;
 use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
 
 // Represents actual cash flows on account including reversal operations. Used to be able to
 // calculate cash balance for specific point of time.
+#[derive(Serialize, Deserialize)]
 pub struct CashFlow {
     pub date: DateOptTime,
     pub amount: Cash,
     pub type_: CashFlowType,
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum CashFlowType {
     Dividend {date: Date, issuer: String},
     Tax {date: Date, issuer: String},
-    Repo {symbol: String, commission: Cash},
+    Repo {symbol: String, quantity: Decimal, commission: Cash},
+
+    // Daily settlement of open futures positions. Futures profit is taxed on position closing, so
+    // variation margin affects only cash balance.
+    VariationMargin,
 }
 
 impl CashFlow {
@@ -25,6 +34,7 @@ impl CashFlow {
             CashFlowType::Dividend {issuer, ..} => issuer,
             CashFlowType::Tax {issuer, ..} => issuer,
             CashFlowType::Repo {symbol, ..} => symbol,
+            CashFlowType::VariationMargin => return None,
         })
     }
 
@@ -33,6 +43,7 @@ impl CashFlow {
             CashFlowType::Dividend {issuer, ..} => issuer,
             CashFlowType::Tax {issuer, ..} => issuer,
             CashFlowType::Repo {symbol, ..} => symbol,
+            CashFlowType::VariationMargin => return None,
         })
     }
 
@@ -40,7 +51,7 @@ impl CashFlow {
         (self.date, self.symbol(), match self.type_ {
             CashFlowType::Dividend {date, ..} => Some(date),
             CashFlowType::Tax {date, ..} => Some(date),
-            CashFlowType::Repo {..} => None,
+            CashFlowType::Repo {..} | CashFlowType::VariationMargin => None,
         })
     }
 }
\ No newline at end of file
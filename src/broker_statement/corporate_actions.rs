@@ -6,15 +6,16 @@ use lazy_static::lazy_static;
 use log::debug;
 use num_traits::{ToPrimitive, Zero};
 use regex::{self, Regex};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use serde::de::{Deserializer, Error};
 
 use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
 use crate::formatting::format_date;
 use crate::localities::Jurisdiction;
 use crate::time::{Date, DateTime, DateOptTime, deserialize_date_opt_time};
 use crate::types::Decimal;
-use crate::util;
+use crate::util::{self, DecimalRestrictions};
 
 use super::BrokerStatement;
 use super::trades::{StockBuy, StockSell, StockSellSource, PurchaseTotalCost};
@@ -60,9 +61,13 @@ pub enum CorporateActionType {
 
     // There are two types of stock dividend (see https://github.com/KonishchevDmitry/investments/issues/27#issuecomment-802212517)
     // At this time we support only one of them.
-    #[serde(skip)]
     StockDividend {
         quantity: Decimal,
+
+        // Share of the position cost basis which is moved to the received stocks. If not
+        // specified, the stocks are received with zero cost.
+        #[serde(default)]
+        cost_basis_ratio: Option<Decimal>,
     },
 
     // See https://github.com/KonishchevDmitry/investments/issues/29 for details
@@ -71,12 +76,37 @@ pub enum CorporateActionType {
     },
 
     // See https://github.com/KonishchevDmitry/investments/issues/20 for details
-    #[serde(skip)]
     Spinoff {
+        #[serde(rename = "new_symbol")]
         symbol: String,
         quantity: Decimal,
         currency: String,
-    }
+
+        // Share of the parent position cost basis which is moved to the spun-off company stocks.
+        // If not specified, the stocks are received with zero cost.
+        #[serde(default)]
+        cost_basis_ratio: Option<Decimal>,
+    },
+
+    // The whole position is bought out for cash at the specified price per share
+    CashMerger {
+        price: Decimal,
+        currency: String,
+    },
+
+    // The whole position is converted to stocks of another company: each stock is exchanged for
+    // the specified number of new ones. The cost basis is moved to the new stocks.
+    StockMerger {
+        new_symbol: String,
+        ratio: Decimal,
+    },
+
+    // Fractional shares which remain after a corporate action are sold for cash
+    CashInLieu {
+        quantity: Decimal,
+        price: Decimal,
+        currency: String,
+    },
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -111,6 +141,95 @@ impl<'de> Deserialize<'de> for StockSplitRatio {
     }
 }
 
+impl Serialize for StockSplitRatio {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.collect_str(&format_args!("{}:{}", self.to, self.from))
+    }
+}
+
+// The derived Deserialize implements the configuration file format, so broker statement cache uses
+// its own format which preserves all the fields.
+pub mod cache_format {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::time::{Date, DateOptTime};
+    use crate::types::Decimal;
+
+    use super::{CorporateAction, CorporateActionType, StockSplitRatio};
+
+    pub fn serialize<S>(actions: &[CorporateAction], serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        struct Wrapper<'a>(&'a CorporateAction);
+
+        impl Serialize for Wrapper<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+                CorporateActionDef::serialize(self.0, serializer)
+            }
+        }
+
+        serializer.collect_seq(actions.iter().map(Wrapper))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<CorporateAction>, D::Error>
+        where D: Deserializer<'de>
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "CorporateActionDef")] CorporateAction);
+
+        let actions: Vec<Wrapper> = Deserialize::deserialize(deserializer)?;
+        Ok(actions.into_iter().map(|Wrapper(action)| action).collect())
+    }
+
+    #[allow(dead_code)]
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "CorporateAction")]
+    struct CorporateActionDef {
+        time: DateOptTime,
+        report_date: Option<Date>,
+        symbol: String,
+        #[serde(with = "CorporateActionTypeDef")]
+        action: CorporateActionType,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "CorporateActionType")]
+    enum CorporateActionTypeDef {
+        StockSplit {
+            ratio: StockSplitRatio,
+            from_change: Option<Decimal>,
+            to_change: Option<Decimal>,
+        },
+        StockDividend {
+            quantity: Decimal,
+            cost_basis_ratio: Option<Decimal>,
+        },
+        Rename {
+            new_symbol: String,
+        },
+        Spinoff {
+            symbol: String,
+            quantity: Decimal,
+            currency: String,
+            cost_basis_ratio: Option<Decimal>,
+        },
+        CashMerger {
+            price: Decimal,
+            currency: String,
+        },
+        StockMerger {
+            new_symbol: String,
+            ratio: Decimal,
+        },
+        CashInLieu {
+            quantity: Decimal,
+            price: Decimal,
+            currency: String,
+        },
+    }
+}
+
 #[derive(Default)]
 pub struct StockSplitController {
     symbols: HashMap<String, BTreeMap<DateTime, u32>>
@@ -209,20 +328,24 @@ fn process_corporate_action(statement: &mut BrokerStatement, action: CorporateAc
             ))?;
         },
 
-        CorporateActionType::StockDividend {quantity} => {
-            statement.stock_buys.push(StockBuy::new_corporate_action(
-                &action.symbol, quantity, PurchaseTotalCost::new(),
-                action.time, action.execution_date(),
-            ));
-            statement.sort_and_validate_stock_buys()?;
+        CorporateActionType::StockDividend {quantity, cost_basis_ratio} => {
+            process_stock_receiving(
+                statement, action.time, action.execution_date(), &action.symbol, &action.symbol,
+                quantity, cost_basis_ratio,
+            ).map_err(|e| format!(
+                "Failed to process {} stock dividend from {}: {}",
+                action.symbol, format_date(action.time), e,
+            ))?;
         },
 
-        CorporateActionType::Spinoff {ref symbol, quantity, ..} => {
-            statement.stock_buys.push(StockBuy::new_corporate_action(
-                symbol, quantity, PurchaseTotalCost::new(),
-                action.time, action.execution_date(),
-            ));
-            statement.sort_and_validate_stock_buys()?;
+        CorporateActionType::Spinoff {ref symbol, quantity, cost_basis_ratio, ..} => {
+            process_stock_receiving(
+                statement, action.time, action.execution_date(), &action.symbol, symbol,
+                quantity, cost_basis_ratio,
+            ).map_err(|e| format!(
+                "Failed to process {} -> {} spinoff from {}: {}",
+                action.symbol, symbol, format_date(action.time), e,
+            ))?;
         },
 
         CorporateActionType::Rename {ref new_symbol} => {
@@ -230,12 +353,160 @@ fn process_corporate_action(statement: &mut BrokerStatement, action: CorporateAc
                 "Failed to process {} -> {} rename corporate action: {}",
                 action.symbol, new_symbol, e))?;
         },
+
+        CorporateActionType::CashMerger {price, ref currency} => {
+            get_open_position(statement, &action.symbol, action.time).and_then(|quantity| process_cash_out(
+                statement, action.time, action.execution_date(), &action.symbol,
+                quantity, Cash::new(currency, price),
+            )).map_err(|e| format!(
+                "Failed to process {} cash merger from {}: {}",
+                action.symbol, format_date(action.time), e,
+            ))?;
+        },
+
+        CorporateActionType::StockMerger {ref new_symbol, ratio} => {
+            process_stock_merger(statement, action.time, &action.symbol, new_symbol, ratio).map_err(|e| format!(
+                "Failed to process {} -> {} stock merger from {}: {}",
+                action.symbol, new_symbol, format_date(action.time), e,
+            ))?;
+        },
+
+        CorporateActionType::CashInLieu {quantity, price, ref currency} => {
+            process_cash_out(
+                statement, action.time, action.execution_date(), &action.symbol,
+                quantity, Cash::new(currency, price),
+            ).map_err(|e| format!(
+                "Failed to process {} cash in lieu from {}: {}",
+                action.symbol, format_date(action.time), e,
+            ))?;
+        },
     };
 
     statement.corporate_actions.push(action);
     Ok(())
 }
 
+// Processes stock dividends and spinoffs: the stocks are received either with zero cost or with
+// the specified share of the parent position cost basis.
+fn process_stock_receiving(
+    statement: &mut BrokerStatement, time: DateOptTime, execution_date: Date,
+    parent_symbol: &str, symbol: &str, quantity: Decimal, cost_basis_ratio: Option<Decimal>,
+) -> EmptyResult {
+    let quantity = util::validate_named_decimal("quantity", quantity, DecimalRestrictions::StrictlyPositive)?;
+    let mut cost = PurchaseTotalCost::new();
+
+    if let Some(ratio) = cost_basis_ratio {
+        if ratio <= dec!(0) || ratio >= dec!(1) {
+            return Err!("Invalid cost basis ratio: {}. It must be between 0 and 1", ratio);
+        }
+
+        statement.process_trades(Some(time))?;
+        let mut found = false;
+
+        for stock_buy in &mut statement.stock_buys {
+            if stock_buy.symbol == parent_symbol && !stock_buy.is_sold() && stock_buy.conclusion_time < time {
+                cost.add(&stock_buy.split_off_cost(ratio));
+                found = true;
+            }
+        }
+
+        if !found {
+            return Err!("The portfolio has no open {} position at {}", parent_symbol, format_date(time));
+        }
+    }
+
+    statement.stock_buys.push(StockBuy::new_corporate_action(
+        symbol, quantity, cost, time, execution_date));
+    statement.sort_and_validate_stock_buys()
+}
+
+// Processes cash mergers and cash in lieu: the stocks are sold for cash as in an ordinary trade
+fn process_cash_out(
+    statement: &mut BrokerStatement, time: DateOptTime, execution_date: Date,
+    symbol: &str, quantity: Decimal, price: Cash,
+) -> EmptyResult {
+    let quantity = util::validate_named_decimal("quantity", quantity, DecimalRestrictions::StrictlyPositive)?;
+    let price = util::validate_named_cash("price", price.currency, price.amount, DecimalRestrictions::StrictlyPositive)?;
+    let volume = (price * quantity).round();
+    let commission = Cash::zero(price.currency);
+
+    statement.stock_sells.push(StockSell::new_trade(
+        symbol, quantity, price, volume, commission, time, execution_date, false));
+    statement.sort_and_validate_stock_sells()
+}
+
+fn process_stock_merger(
+    statement: &mut BrokerStatement, time: DateOptTime, symbol: &str, new_symbol: &str, ratio: Decimal,
+) -> EmptyResult {
+    let ratio = util::validate_named_decimal("ratio", ratio, DecimalRestrictions::StrictlyPositive)?;
+
+    let (quantity, sell_sources) = close_position(statement, symbol, time)?;
+    let new_quantity = (quantity * ratio).normalize();
+
+    debug!("{} -> {} stock merger from {}: {} -> {}.",
+        symbol, new_symbol, format_date(time.date), quantity, new_quantity);
+
+    let (sell, buy) = convert_stocks(symbol, new_symbol, quantity, new_quantity, time, sell_sources);
+
+    statement.stock_sells.insert(0, sell);
+    statement.sort_and_validate_stock_sells()?;
+
+    statement.stock_buys.insert(0, buy);
+    statement.sort_and_validate_stock_buys()?;
+
+    Ok(())
+}
+
+fn get_open_position(statement: &mut BrokerStatement, symbol: &str, time: DateOptTime) -> GenericResult<Decimal> {
+    statement.process_trades(Some(time))?;
+
+    let mut quantity = dec!(0);
+
+    for stock_buy in &statement.stock_buys {
+        if stock_buy.symbol != symbol || stock_buy.is_sold() || stock_buy.conclusion_time >= time {
+            continue;
+        }
+
+        let multiplier = statement.stock_splits.get_multiplier(symbol, stock_buy.conclusion_time, time);
+        quantity += stock_buy.get_unsold() * multiplier;
+    }
+
+    if quantity.is_zero() {
+        return Err!("The portfolio has no open {} position at {}", symbol, format_date(time));
+    }
+
+    Ok(quantity)
+}
+
+// Closes all open lots of the position which have been acquired before the specified time
+fn close_position(
+    statement: &mut BrokerStatement, symbol: &str, time: DateOptTime,
+) -> GenericResult<(Decimal, Vec<StockSellSource>)> {
+    statement.process_trades(Some(time))?;
+
+    let mut quantity = dec!(0);
+    let mut sell_sources = Vec::new();
+
+    for stock_buy in &mut statement.stock_buys {
+        if stock_buy.symbol != symbol || stock_buy.is_sold() || stock_buy.conclusion_time >= time {
+            continue;
+        }
+
+        let multiplier = statement.stock_splits.get_multiplier(
+            symbol, stock_buy.conclusion_time, time);
+
+        let sell_source = stock_buy.sell(stock_buy.get_unsold(), multiplier);
+        quantity += sell_source.quantity * sell_source.multiplier;
+        sell_sources.push(sell_source);
+    }
+
+    if sell_sources.is_empty() {
+        return Err!("The portfolio has no open {} position at {}", symbol, format_date(time));
+    }
+
+    Ok((quantity, sell_sources))
+}
+
 fn process_stock_split(
     statement: &mut BrokerStatement,
     split_time: DateOptTime, symbol: &str, ratio: StockSplitRatio,
@@ -251,7 +522,7 @@ fn process_stock_split(
     // first one and reset FIFO and LTO by this fact. See docs/brokers.md#stock-splits-in-russian-brokers
     // for details.
 
-    if ratio.from == 1 && statement.broker.type_.jurisdiction() != Jurisdiction::Russia {
+    if ratio.from == 1 && statement.broker.jurisdiction != Jurisdiction::Russia {
         if !statement.stock_buys.iter().any(|trade| {
             trade.symbol == symbol && !trade.is_sold() && trade.conclusion_time < split_time
         }) {
@@ -272,27 +543,7 @@ fn process_complex_stock_split(
     split_time: DateOptTime, symbol: &str, ratio: StockSplitRatio,
     from_change: Option<Decimal>, to_change: Option<Decimal>,
 ) -> EmptyResult {
-    statement.process_trades(Some(split_time))?;
-
-    let mut quantity = dec!(0);
-    let mut sell_sources = Vec::new();
-
-    for stock_buy in &mut statement.stock_buys {
-        if stock_buy.symbol != symbol || stock_buy.is_sold() || stock_buy.conclusion_time >= split_time {
-            continue;
-        }
-
-        let multiplier = statement.stock_splits.get_multiplier(
-            symbol, stock_buy.conclusion_time, split_time);
-
-        let sell_source = stock_buy.sell(stock_buy.get_unsold(), multiplier);
-        quantity += sell_source.quantity * sell_source.multiplier;
-        sell_sources.push(sell_source);
-    }
-
-    if sell_sources.is_empty() {
-        return Err!("The portfolio has no open {} position at {}", symbol, format_date(split_time));
-    }
+    let (quantity, sell_sources) = close_position(statement, symbol, split_time)?;
 
     let new_quantity = calculate_stock_split(quantity, ratio, from_change, to_change)?;
     debug!("{} stock split from {}: {} -> {}.",
@@ -302,7 +553,7 @@ fn process_complex_stock_split(
     // beginning of the list to be sure that they will be placed before any corporate action related
     // trades issued by broker after list sorting.
 
-    let (sell, buy) = convert_stocks(symbol, quantity, new_quantity, split_time, sell_sources);
+    let (sell, buy) = convert_stocks(symbol, symbol, quantity, new_quantity, split_time, sell_sources);
 
     statement.stock_sells.insert(0, sell);
     statement.sort_and_validate_stock_sells()?;
@@ -366,7 +617,7 @@ fn get_stock_split_lots(quantity: Decimal, ratio: StockSplitRatio) -> Option<u32
 }
 
 fn convert_stocks(
-    symbol: &str, old_quantity: Decimal, new_quantity: Decimal,
+    symbol: &str, new_symbol: &str, old_quantity: Decimal, new_quantity: Decimal,
     conclusion_time: DateOptTime, sell_sources: Vec<StockSellSource>,
 ) -> (StockSell, StockBuy) {
     let mut cost = PurchaseTotalCost::new();
@@ -379,7 +630,58 @@ fn convert_stocks(
     sell.process(sell_sources);
 
     let buy = StockBuy::new_corporate_action(
-        symbol, new_quantity, cost, conclusion_time, conclusion_time.date);
+        new_symbol, new_quantity, cost, conclusion_time, conclusion_time.date);
 
     (sell, buy)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::broker_statement::{ReadingStrictness, StatementCache, StockTransfers};
+    use crate::brokers::Broker;
+    use crate::config::Config;
+    use crate::taxes::TaxRemapping;
+
+    use super::*;
+
+    #[test]
+    fn declarative_corporate_actions() {
+        let corporate_actions: Vec<CorporateAction> = serde_yaml::from_str(indoc!("
+            - {date: 2021.03.01, symbol: PRNT, type: spinoff, new_symbol: CHLD, quantity: 25, currency: USD, cost_basis_ratio: 0.2}
+            - {date: 2021.03.10, symbol: OLD, type: stock-merger, new_symbol: NEW, ratio: 1.55}
+            - {date: 2021.03.11, symbol: NEW, type: cash-in-lieu, quantity: 0.5, price: 20, currency: USD}
+            - {date: 2021.04.01, symbol: CASH, type: cash-merger, price: 12, currency: USD}
+            - {date: 2021.05.01, symbol: PRNT, type: stock-dividend, quantity: 5}
+        ")).unwrap();
+
+        let broker = Broker::Ledger.get_info(&Config::mock(), None).unwrap();
+        let statement = BrokerStatement::read(
+            broker, "testdata/corporate-actions", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(),
+            &corporate_actions, StockTransfers::new(), &StatementCache::new_disabled(),
+            ReadingStrictness::all()).unwrap();
+
+        let sells = statement.stock_sells.iter().map(|trade| {
+            let cost: Decimal = trade.sources().iter()
+                .flat_map(|source| source.cost.transactions())
+                .map(|(_, _, cost)| cost.amount)
+                .sum();
+            (trade.symbol.as_str(), trade.quantity, util::round(cost, 2))
+        }).collect::<Vec<_>>();
+
+        assert_eq!(sells, vec![
+            ("OLD", dec!(10), dec!(300)),
+            ("NEW", dec!(0.5), dec!(9.68)),
+            ("CASH", dec!(20), dec!(200)),
+            ("CHLD", dec!(25), dec!(1000)),
+            ("PRNT", dec!(100), dec!(4000)),
+        ]);
+
+        assert_eq!(statement.open_positions, hashmap!{
+            s!("PRNT") => dec!(5),
+            s!("NEW") => dec!(15),
+        });
+    }
+}
@@ -0,0 +1,538 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use chrono::Datelike;
+use num_traits::Signed;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting;
+use crate::localities::Country;
+use crate::taxes::IncomeType;
+use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
+
+use super::BrokerStatement;
+use super::trades::{StockSellType, StockSource};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum DerivativeType {
+    Call,
+    Put,
+    Future,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DerivativeContract {
+    pub type_: DerivativeType,
+    pub underlying: String,
+    pub multiplier: Decimal,
+    pub expiration: Date,
+    pub strike: Option<Decimal>, // Specified only for options
+}
+
+impl DerivativeContract {
+    pub fn new_option(
+        type_: DerivativeType, underlying: &str, multiplier: Decimal, expiration: Date, strike: Decimal,
+    ) -> DerivativeContract {
+        assert_ne!(type_, DerivativeType::Future);
+        DerivativeContract {
+            type_,
+            underlying: underlying.to_owned(),
+            multiplier, expiration,
+            strike: Some(strike),
+        }
+    }
+
+    pub fn new_future(underlying: &str, multiplier: Decimal, expiration: Date) -> DerivativeContract {
+        DerivativeContract {
+            type_: DerivativeType::Future,
+            underlying: underlying.to_owned(),
+            multiplier, expiration,
+            strike: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DerivativeTrade {
+    pub symbol: String,
+    pub conclusion_time: DateOptTime,
+
+    pub quantity: Decimal, // Positive for buys and negative for sells
+    pub price: Cash,
+
+    // Cash flow caused by the trade: paid or received premium for options. Futures are settled via
+    // variation margin, so it's always zero for them.
+    pub volume: Cash,
+    pub commission: Cash,
+
+    // Set for position closings without a trade
+    pub closing: Option<DerivativeClosingType>,
+}
+
+impl DerivativeTrade {
+    pub fn new(
+        symbol: &str, conclusion_time: DateOptTime, quantity: Decimal, price: Cash, volume: Cash,
+        commission: Cash,
+    ) -> DerivativeTrade {
+        DerivativeTrade {
+            symbol: symbol.to_owned(),
+            conclusion_time, quantity, price, volume, commission,
+            closing: None,
+        }
+    }
+
+    fn new_closing(closing: &DerivativeClosing) -> DerivativeTrade {
+        let zero = Cash::zero(&closing.currency);
+        DerivativeTrade {
+            closing: Some(closing.type_),
+            ..DerivativeTrade::new(&closing.symbol, closing.date.into(), closing.quantity, zero, zero, zero)
+        }
+    }
+
+    fn is_delivery(&self) -> bool {
+        matches!(self.closing, Some(DerivativeClosingType::Exercise | DerivativeClosingType::Assignment))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum DerivativeClosingType {
+    Expiration,
+    Exercise,
+    Assignment,
+}
+
+// Position closing without a trade. The position is closed at zero price. On exercise or assignment
+// the underlying is bought or sold at the strike price by a separate stock trade from the statement
+// and the option premium is moved to the cost of this trade.
+#[derive(Serialize, Deserialize)]
+pub struct DerivativeClosing {
+    pub date: Date,
+    pub symbol: String,
+    pub type_: DerivativeClosingType,
+    pub quantity: Decimal, // Position change: negative for long positions and positive for short ones
+    pub currency: String,
+}
+
+impl DerivativeClosing {
+    pub fn new(
+        date: Date, symbol: &str, type_: DerivativeClosingType, quantity: Decimal, currency: &str,
+    ) -> DerivativeClosing {
+        DerivativeClosing {
+            date,
+            symbol: symbol.to_owned(),
+            type_, quantity,
+            currency: currency.to_owned(),
+        }
+    }
+
+    fn description(&self) -> String {
+        let type_ = match self.type_ {
+            DerivativeClosingType::Expiration => "expiration",
+            DerivativeClosingType::Exercise => "exercise",
+            DerivativeClosingType::Assignment => "assignment",
+        };
+        format!("{} {} from {}", self.symbol, type_, formatting::format_date(self.date))
+    }
+}
+
+// Realized result of derivative position closing. Both options and futures are taxed on position
+// closing, so premiums and price differences are accounted here and variation margin is taken into
+// account only as a cash flow.
+pub struct DerivativeResult {
+    pub date: Date,
+    pub symbol: String,
+    pub quantity: Decimal,
+    pub profit: Cash, // Includes commissions of both opening and closing trades
+}
+
+impl DerivativeResult {
+    pub fn description(&self) -> String {
+        format!("{} position closing from {}", self.symbol, formatting::format_date(self.date))
+    }
+}
+
+// Option position closing by exercise or assignment. It doesn't produce any result by itself: the
+// premium becomes a part of the underlying trade cost.
+pub struct DerivativeDelivery {
+    pub date: Date,
+    pub symbol: String,
+    pub quantity: Decimal, // Position change: negative for long positions and positive for short ones
+
+    // Paid (positive) or received (negative) premium of the closed lots including their commissions
+    pub premium: Vec<(Date, Cash)>,
+}
+
+impl DerivativeDelivery {
+    fn description(&self) -> String {
+        format!("{} delivery from {}", self.symbol, formatting::format_date(self.date))
+    }
+}
+
+pub struct DerivativeTax {
+    pub income: Cash,
+    pub tax_to_pay: Cash,
+}
+
+// Russian tax rules allow to offset derivative losses against derivative profit of the same year, so
+// tax is calculated for the whole year instead of each position closing separately.
+pub fn calculate_derivative_taxes<'a, I>(
+    country: &Country, results: I, converter: &CurrencyConverter,
+) -> GenericResult<BTreeMap<i32, DerivativeTax>>
+    where I: IntoIterator<Item=&'a DerivativeResult>
+{
+    let mut years = BTreeMap::new();
+
+    for result in results {
+        let profit = converter.convert_to_cash_rounding(result.date, result.profit, country.currency)?;
+        *years.entry(result.date.year()).or_insert_with(|| Cash::zero(country.currency)) += profit;
+    }
+
+    Ok(years.into_iter().map(|(year, income)| {
+        let tax_to_pay = country.tax_to_pay(IncomeType::Derivatives, year, income, None);
+        (year, DerivativeTax {income, tax_to_pay})
+    }).collect())
+}
+
+pub fn process_derivative_closings(statement: &mut BrokerStatement, closings: Vec<DerivativeClosing>) -> EmptyResult {
+    for closing in closings {
+        process_derivative_closing(statement, &closing).map_err(|e| format!(
+            "Failed to process {}: {}", closing.description(), e))?;
+    }
+    Ok(())
+}
+
+fn process_derivative_closing(statement: &mut BrokerStatement, closing: &DerivativeClosing) -> EmptyResult {
+    if closing.type_ != DerivativeClosingType::Expiration {
+        let contract = statement.derivative_contracts.get(&closing.symbol).ok_or(
+            "Contract specification is missing")?;
+
+        if contract.type_ == DerivativeType::Future {
+            return Err!("Physical delivery of futures is not supported yet");
+        }
+    }
+
+    statement.derivative_trades.push(DerivativeTrade::new_closing(closing));
+    Ok(())
+}
+
+// Finds the underlying stock trades of option exercises and assignments and moves option premium to
+// their cost
+pub fn process_derivative_deliveries(statement: &mut BrokerStatement, deliveries: Vec<DerivativeDelivery>) -> EmptyResult {
+    let mut processed_buys = HashSet::new();
+    let mut processed_sells = HashSet::new();
+
+    for delivery in deliveries {
+        process_derivative_delivery(statement, &delivery, &mut processed_buys, &mut processed_sells)
+            .map_err(|e| format!("Failed to process {}: {}", delivery.description(), e))?;
+    }
+
+    Ok(())
+}
+
+fn process_derivative_delivery(
+    statement: &mut BrokerStatement, delivery: &DerivativeDelivery,
+    processed_buys: &mut HashSet<usize>, processed_sells: &mut HashSet<usize>,
+) -> EmptyResult {
+    let contract = statement.derivative_contracts.get(&delivery.symbol).ok_or(
+        "Contract specification is missing")?;
+
+    let (buy, strike) = match contract.type_ {
+        DerivativeType::Call => (delivery.quantity.is_sign_negative(), contract.strike.unwrap()),
+        DerivativeType::Put => (delivery.quantity.is_sign_positive(), contract.strike.unwrap()),
+        DerivativeType::Future => return Err!("Physical delivery of futures is not supported yet"),
+    };
+
+    let symbol = &contract.underlying;
+    let date = delivery.date;
+    let quantity = (delivery.quantity.abs() * contract.multiplier).normalize();
+
+    let is_delivery_trade = |trade_symbol: &str, trade_date: Date, trade_quantity: Decimal, price: Cash| {
+        trade_symbol == symbol && trade_date == date && trade_quantity == quantity && price.amount == strike
+    };
+
+    if buy {
+        let (index, trade) = statement.stock_buys.iter_mut().enumerate().find(|(index, trade)| {
+            !processed_buys.contains(index) && match trade.type_ {
+                StockSource::Trade {price, ..} => is_delivery_trade(
+                    &trade.symbol, trade.conclusion_time.date, trade.quantity, price),
+                _ => false,
+            }
+        }).ok_or_else(|| format!(
+            "Unable to find the corresponding buy trade of {} {} at {}", quantity, symbol, strike))?;
+
+        for &(date, premium) in &delivery.premium {
+            trade.add_option_premium(date, premium);
+        }
+        processed_buys.insert(index);
+    } else {
+        let (index, trade) = statement.stock_sells.iter_mut().enumerate().find(|(index, trade)| {
+            !processed_sells.contains(index) && match trade.type_ {
+                StockSellType::Trade {price, ..} => is_delivery_trade(
+                    &trade.symbol, trade.conclusion_time.date, trade.quantity, price),
+                _ => false,
+            }
+        }).ok_or_else(|| format!(
+            "Unable to find the corresponding sell trade of {} {} at {}", quantity, symbol, strike))?;
+
+        for &(date, premium) in &delivery.premium {
+            trade.add_option_premium(date, premium);
+        }
+        processed_sells.insert(index);
+    }
+
+    Ok(())
+}
+
+struct OpenLot {
+    date: Date,
+    quantity: Decimal, // Negative for short positions
+    price: Cash,
+    commission: Cash,
+}
+
+pub struct ProcessedDerivativeTrades {
+    pub results: Vec<DerivativeResult>,
+    pub deliveries: Vec<DerivativeDelivery>,
+    pub open_positions: HashMap<String, Decimal>,
+}
+
+// Matches derivative trades using FIFO and returns realized results and deliveries along with the
+// remaining open positions
+pub fn process_derivative_trades(
+    trades: &[DerivativeTrade], contracts: &HashMap<String, DerivativeContract>,
+) -> GenericResult<ProcessedDerivativeTrades> {
+    let mut results = Vec::new();
+    let mut deliveries = Vec::new();
+    let mut open_lots: HashMap<&str, VecDeque<OpenLot>> = HashMap::new();
+
+    for trade in trades {
+        let contract = contracts.get(&trade.symbol).ok_or_else(|| format!(
+            "Unable to find contract specification for {}", trade.symbol))?;
+
+        let lots = open_lots.entry(&trade.symbol).or_default();
+        let mut remaining = trade.quantity;
+        let mut closed = dec!(0);
+        let mut profit = Cash::zero(trade.price.currency);
+        let mut premium = Vec::new();
+
+        while let Some(lot) = lots.front_mut() {
+            if remaining.is_zero() || lot.quantity.is_sign_positive() == remaining.is_sign_positive() {
+                break;
+            }
+
+            let quantity = std::cmp::min(lot.quantity.abs(), remaining.abs()) * lot.quantity.signum();
+            let commission = lot.commission * quantity / lot.quantity;
+
+            if trade.is_delivery() {
+                premium.push((lot.date, lot.price * quantity * contract.multiplier + commission));
+            }
+
+            profit.add_assign(trade.price.sub(lot.price)? * quantity * contract.multiplier)?;
+            profit.sub_assign(commission)?;
+
+            lot.commission -= commission;
+            lot.quantity -= quantity;
+            remaining += quantity;
+            closed += quantity.abs();
+
+            if lot.quantity.is_zero() {
+                lots.pop_front();
+            }
+        }
+
+        let closing_commission = trade.commission * closed / trade.quantity.abs();
+
+        if !closed.is_zero() && trade.is_delivery() {
+            deliveries.push(DerivativeDelivery {
+                date: trade.conclusion_time.date,
+                symbol: trade.symbol.clone(),
+                quantity: trade.quantity - remaining,
+                premium,
+            });
+        } else if !closed.is_zero() {
+            profit.sub_assign(closing_commission)?;
+
+            results.push(DerivativeResult {
+                date: trade.conclusion_time.date,
+                symbol: trade.symbol.clone(),
+                quantity: closed.normalize(),
+                profit,
+            });
+        }
+
+        if !remaining.is_zero() {
+            if trade.closing.is_some() {
+                return Err!(
+                    "Got {} {} position closing without an open position",
+                    remaining.abs(), trade.symbol);
+            }
+
+            lots.push_back(OpenLot {
+                date: trade.conclusion_time.date,
+                quantity: remaining,
+                price: trade.price,
+                commission: trade.commission - closing_commission,
+            });
+        }
+    }
+
+    let open_positions = open_lots.into_iter().filter_map(|(symbol, lots)| {
+        let quantity: Decimal = lots.iter().map(|lot| lot.quantity).sum();
+        if quantity.is_zero() {
+            None
+        } else {
+            Some((symbol.to_owned(), quantity.normalize()))
+        }
+    }).collect();
+
+    Ok(ProcessedDerivativeTrades {results, deliveries, open_positions})
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::brokers::Broker;
+    use crate::config::Config;
+    use crate::exchanges::Exchange;
+    use crate::localities;
+    use crate::time::Period;
+
+    use super::super::partial::PartialBrokerStatement;
+    use super::super::trades::{StockBuy, StockSell};
+    use super::*;
+
+    #[test]
+    fn fifo() {
+        let symbol = "AAPL 210219C00150000";
+        let contracts = contracts(symbol, DerivativeType::Call, dec!(150));
+
+        let trade = |date, quantity, price, commission| DerivativeTrade::new(
+            symbol, date, quantity, Cash::new("USD", price), Cash::new("USD", -quantity * price * dec!(100)),
+            Cash::new("USD", commission));
+
+        let trades = [
+            trade(date!(2021, 1, 4).into(), dec!(2), dec!(1.5), dec!(2)),
+            trade(date!(2021, 1, 5).into(), dec!(1), dec!(2), dec!(1)),
+            trade(date!(2021, 1, 6).into(), dec!(-2), dec!(3), dec!(2)),
+            trade(date!(2021, 1, 7).into(), dec!(-2), dec!(1), dec!(2)), // Closes the rest and opens a short position
+            trade(date!(2021, 1, 8).into(), dec!(1), dec!(0.5), dec!(1)),
+            trade(date!(2021, 1, 11).into(), dec!(-3), dec!(2), dec!(3)),
+        ];
+
+        let ProcessedDerivativeTrades {results, deliveries, open_positions} = process_derivative_trades(
+            &trades, &contracts).unwrap();
+        assert!(deliveries.is_empty());
+
+        assert_eq!(results.iter().map(|result| (result.date, result.quantity, result.profit.amount)).collect::<Vec<_>>(), vec![
+            (date!(2021, 1, 6), dec!(2), dec!(296)),
+            (date!(2021, 1, 7), dec!(1), dec!(-102)),
+            (date!(2021, 1, 8), dec!(1), dec!(48)),
+        ]);
+
+        assert_eq!(open_positions, hashmap!{symbol.to_owned() => dec!(-3)});
+    }
+
+    #[test]
+    fn closings() {
+        let symbol = "AAPL 210219C00150000";
+        let contracts = contracts(symbol, DerivativeType::Call, dec!(150));
+
+        let trade = |date, quantity, price, commission| DerivativeTrade::new(
+            symbol, date, quantity, Cash::new("USD", price), Cash::new("USD", -quantity * price * dec!(100)),
+            Cash::new("USD", commission));
+
+        let closing = |date, type_, quantity| DerivativeTrade::new_closing(
+            &DerivativeClosing::new(date, symbol, type_, quantity, "USD"));
+
+        let trades = [
+            trade(date!(2021, 1, 4).into(), dec!(1), dec!(2), dec!(1)),
+            trade(date!(2021, 1, 5).into(), dec!(-2), dec!(3), dec!(2)),
+            closing(date!(2021, 2, 19), DerivativeClosingType::Expiration, dec!(1)),
+            trade(date!(2021, 3, 1).into(), dec!(2), dec!(1), dec!(2)),
+            closing(date!(2021, 3, 19), DerivativeClosingType::Exercise, dec!(-2)),
+        ];
+
+        let ProcessedDerivativeTrades {results, deliveries, open_positions} = process_derivative_trades(
+            &trades, &contracts).unwrap();
+        assert!(open_positions.is_empty());
+
+        // The long position is closed by the short sell and the rest of the short position expires
+        assert_eq!(results.iter().map(|result| (result.date, result.quantity, result.profit.amount)).collect::<Vec<_>>(), vec![
+            (date!(2021, 1, 5), dec!(1), dec!(98)),
+            (date!(2021, 2, 19), dec!(1), dec!(299)),
+        ]);
+
+        // The exercised position doesn't produce any result: its premium goes to the underlying
+        assert_eq!(deliveries.len(), 1);
+        let delivery = &deliveries[0];
+        assert_eq!((delivery.date, delivery.quantity), (date!(2021, 3, 19), dec!(-2)));
+        assert_eq!(delivery.premium, vec![(date!(2021, 3, 1), Cash::new("USD", dec!(202)))]);
+
+        let error = process_derivative_trades(&trades[4..], &contracts).err().unwrap().to_string();
+        assert_eq!(error, "Got 2 AAPL 210219C00150000 position closing without an open position");
+    }
+
+    #[test]
+    fn deliveries() {
+        let call = "AAPL 210219C00150000";
+        let put = "AAPL 210219P00100000";
+
+        let mut partial = PartialBrokerStatement::new(&[Exchange::Us], true);
+        partial.set_period(Period::new(date!(2021, 1, 1), date!(2021, 12, 31)).unwrap()).unwrap();
+        partial.set_has_starting_assets(false).unwrap();
+
+        let broker = Broker::InteractiveBrokers.get_info(&Config::mock(), None).unwrap();
+        let mut statement = BrokerStatement::new_empty_from(broker, &partial).unwrap();
+        statement.derivative_contracts.extend(contracts(call, DerivativeType::Call, dec!(150)));
+        statement.derivative_contracts.extend(contracts(put, DerivativeType::Put, dec!(100)));
+
+        let cash = |amount| Cash::new("RUB", amount);
+
+        // Short put assignment
+        statement.stock_buys.push(StockBuy::new_trade(
+            "AAPL", dec!(100), cash(dec!(100)), cash(dec!(10000)), cash(dec!(1)),
+            date!(2021, 2, 19).into(), date!(2021, 2, 23)));
+
+        // Short call assignment
+        statement.stock_sells.push(StockSell::new_trade(
+            "AAPL", dec!(100), cash(dec!(150)), cash(dec!(15000)), cash(dec!(1)),
+            date!(2021, 2, 19).into(), date!(2021, 2, 23), false));
+
+        let delivery = |symbol: &str, quantity, premium| DerivativeDelivery {
+            date: date!(2021, 2, 19),
+            symbol: symbol.to_owned(),
+            quantity,
+            premium: vec![(date!(2021, 1, 4), cash(premium))],
+        };
+
+        process_derivative_deliveries(&mut statement, vec![
+            delivery(put, dec!(1), dec!(-299)),
+            delivery(call, dec!(1), dec!(-199)),
+        ]).unwrap();
+
+        let error = process_derivative_deliveries(&mut statement, vec![
+            delivery(put, dec!(2), dec!(-598)),
+        ]).err().unwrap().to_string();
+        assert!(error.starts_with("Failed to process AAPL 210219P00100000 delivery from "));
+        assert!(error.ends_with(": Unable to find the corresponding buy trade of 200 AAPL at 100"));
+
+        statement.process_trades(None).unwrap();
+
+        let converter = CurrencyConverter::mock();
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &BTreeMap::new());
+        let details = statement.stock_sells[0].calculate(&country, 2021, &[], &converter).unwrap();
+
+        assert_eq!(details.fifo[0].total_cost("RUB", &converter).unwrap(), cash(dec!(10000) + dec!(1) - dec!(299)));
+        assert_eq!(details.profit, cash(dec!(15000) - dec!(9702) - dec!(1) + dec!(199)));
+    }
+
+    fn contracts(symbol: &str, type_: DerivativeType, strike: Decimal) -> HashMap<String, DerivativeContract> {
+        hashmap!{
+            symbol.to_owned() => DerivativeContract::new_option(
+                type_, "AAPL", dec!(100), date!(2021, 12, 17), strike),
+        }
+    }
+}
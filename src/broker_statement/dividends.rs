@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use chrono::Datelike;
+use serde::{Deserialize, Serialize};
 
 use crate::core::GenericResult;
 use crate::currency::Cash;
@@ -62,7 +63,7 @@ impl Dividend {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 pub struct DividendId {
     pub date: Date,
     pub issuer: InstrumentId,
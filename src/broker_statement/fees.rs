@@ -1,25 +1,48 @@
+use serde::{Deserialize, Serialize};
+
 use crate::currency::Cash;
 use crate::time::Date;
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum FeeType {
+    Commission,
+
+    // Interest paid for borrowed money on margin account
+    MarginInterest,
+
+    // Fee paid for borrowed stocks of short positions
+    BorrowFee,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Fee {
     pub date: Date,
+    pub type_: FeeType,
     pub amount: Cash, // The amount is positive for commission and negative for refund
     pub description: Option<String>,
 }
 
 impl Fee {
     pub fn new(date: Date, amount: Cash, description: Option<String>) -> Fee {
-        Fee {date, amount, description}
+        Fee::new_with_type(date, FeeType::Commission, amount, description)
+    }
+
+    pub fn new_with_type(date: Date, type_: FeeType, amount: Cash, description: Option<String>) -> Fee {
+        Fee {date, type_, amount, description}
     }
 
     pub fn local_description(&self) -> &str {
         match self.description.as_ref() {
             Some(description) => description,
-            None => if self.amount.is_negative() {
-                "Возврат излишне удержанной комиссии"
-            } else {
-                "Комиссия брокера"
+            None => match self.type_ {
+                FeeType::Commission => if self.amount.is_negative() {
+                    "Возврат излишне удержанной комиссии"
+                } else {
+                    "Комиссия брокера"
+                },
+                FeeType::MarginInterest => "Проценты по маржинальному кредиту",
+                FeeType::BorrowFee => "Комиссия за заем ценных бумаг",
             },
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,271 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use tempfile::TempDir;
+
+use crate::core::{EmptyResult, GenericResult};
+
+// Broker statement files. Statements may be stored in archives: their members are extracted to a
+// temporary directory which lives as long as the files list.
+pub struct StatementFiles {
+    files: Vec<StatementFile>,
+    temp_dir: Option<TempDir>,
+}
+
+pub struct StatementFile {
+    // The name which defines the statements order
+    pub name: String,
+
+    // Path to read the statement from
+    pub path: String,
+
+    archive: Option<ArchiveMember>,
+}
+
+struct ArchiveMember {
+    archive: String,
+    member: String,
+}
+
+impl StatementFile {
+    pub fn new(name: &str, path: &str) -> StatementFile {
+        StatementFile {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            archive: None,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self.archive {
+            Some(ArchiveMember {ref archive, ref member}) => format!("{:?} from {:?} archive", member, archive),
+            None => format!("{:?}", self.path),
+        }
+    }
+
+    // Archive members are extracted to a new location on each read, so they are identified by the
+    // archive path and member name
+    pub fn id(&self) -> String {
+        match self.archive {
+            Some(ArchiveMember {ref archive, ref member}) => format!("{}:{}", archive, member),
+            None => self.path.clone(),
+        }
+    }
+
+    // The file which modification time reflects the statement modification time
+    pub fn origin_path(&self) -> &str {
+        match self.archive {
+            Some(ArchiveMember {ref archive, ..}) => archive,
+            None => &self.path,
+        }
+    }
+}
+
+impl StatementFiles {
+    // The path may point to a statements directory or to a statements archive
+    pub fn list(path: &str) -> GenericResult<StatementFiles> {
+        StatementFiles::list_impl(path, false)
+    }
+
+    // Same as list(), but the path may also point to a single statement file
+    pub fn open(path: &str) -> GenericResult<StatementFiles> {
+        StatementFiles::list_impl(path, true)
+    }
+
+    fn list_impl(path: &str, allow_file: bool) -> GenericResult<StatementFiles> {
+        let mut files = StatementFiles {
+            files: Vec::new(),
+            temp_dir: None,
+        };
+
+        if fs::metadata(path)?.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let entry = entry?;
+
+                let path = entry.path();
+                let path = path.to_str().ok_or_else(|| format!(
+                    "Got an invalid path: {:?}", path.to_string_lossy()))?;
+
+                let file_name = entry.file_name().into_string().map_err(|file_name| format!(
+                    "Got an invalid file name: {:?}", file_name.to_string_lossy()))?;
+
+                if ArchiveType::detect(&file_name).is_some() {
+                    files.add_archive(path, &file_name)?;
+                } else {
+                    files.files.push(StatementFile::new(&file_name, path));
+                }
+            }
+        } else {
+            let file_name = Path::new(path).file_name().and_then(|file_name| file_name.to_str())
+                .ok_or_else(|| format!("Got an invalid path: {:?}", path))?;
+
+            if ArchiveType::detect(file_name).is_some() {
+                files.add_archive(path, file_name)?;
+            } else if allow_file {
+                files.files.push(StatementFile::new(file_name, path));
+            } else {
+                return Err!("The path must point to a directory or to a supported archive");
+            }
+        }
+
+        files.files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(files)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&StatementFile> {
+        self.files.iter()
+    }
+
+    fn add_archive(&mut self, path: &str, file_name: &str) -> EmptyResult {
+        Ok(self.add_archive_members(path, file_name).map_err(|e| format!(
+            "Error while reading {:?} archive: {}", path, e))?)
+    }
+
+    fn add_archive_members(&mut self, path: &str, file_name: &str) -> EmptyResult {
+        match ArchiveType::detect(file_name).unwrap() {
+            ArchiveType::Zip => {
+                let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+
+                for index in 0..archive.len() {
+                    let mut member = archive.by_index(index)?;
+                    if member.is_dir() {
+                        continue;
+                    }
+
+                    let name = member.enclosed_name().and_then(|name| name.to_str()).ok_or_else(|| format!(
+                        "Got an invalid member name: {:?}", member.name()))?.to_owned();
+
+                    self.extract(path, file_name, &name, &mut member)?;
+                }
+            },
+
+            ArchiveType::TarGz => {
+                let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+
+                for member in archive.entries()? {
+                    let mut member = member?;
+                    if !member.header().entry_type().is_file() {
+                        continue;
+                    }
+
+                    let name = member.path()?;
+                    let name = name.to_str().ok_or_else(|| format!(
+                        "Got an invalid member name: {:?}", name.to_string_lossy()))?.to_owned();
+
+                    self.extract(path, file_name, &name, &mut member)?;
+                }
+            },
+
+            ArchiveType::Gz => {
+                let name = file_name.strip_suffix(".gz").unwrap().to_owned();
+                self.extract(path, file_name, &name, &mut GzDecoder::new(File::open(path)?))?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn extract(&mut self, archive_path: &str, archive_name: &str, member: &str, data: &mut dyn Read) -> EmptyResult {
+        let file_name = Path::new(member).file_name().ok_or_else(|| format!(
+            "Got an invalid member name: {:?}", member))?;
+
+        if self.temp_dir.is_none() {
+            self.temp_dir = Some(tempfile::Builder::new().prefix("investments-").tempdir()?);
+        }
+        let temp_dir = self.temp_dir.as_ref().unwrap();
+
+        // Each member is extracted to its own directory to preserve its file name which may be
+        // significant for the readers and to not get collisions between the members.
+        let member_dir = temp_dir.path().join(self.files.len().to_string());
+        fs::create_dir(&member_dir)?;
+
+        let path = member_dir.join(file_name);
+        io::copy(data, &mut File::create(&path)?).map_err(|e| format!(
+            "Failed to extract {:?}: {}", member, e))?;
+
+        self.files.push(StatementFile {
+            name: format!("{}/{}", archive_name, member),
+            path: path.to_str().unwrap().to_owned(),
+            archive: Some(ArchiveMember {
+                archive: archive_path.to_owned(),
+                member: member.to_owned(),
+            }),
+        });
+
+        Ok(())
+    }
+}
+
+enum ArchiveType {
+    Zip,
+    TarGz,
+    Gz,
+}
+
+impl ArchiveType {
+    fn detect(file_name: &str) -> Option<ArchiveType> {
+        Some(if file_name.ends_with(".zip") {
+            ArchiveType::Zip
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            ArchiveType::TarGz
+        } else if file_name.ends_with(".gz") {
+            ArchiveType::Gz
+        } else {
+            return None;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+
+    #[test]
+    fn archives() {
+        let dir = TempDir::new().unwrap();
+        let dir_path = dir.path().to_str().unwrap();
+
+        fs::write(dir.path().join("2020.csv"), "plain").unwrap();
+
+        let mut gz = GzEncoder::new(File::create(dir.path().join("2021.csv.gz")).unwrap(), Compression::default());
+        gz.write_all(b"gzip").unwrap();
+        gz.finish().unwrap();
+
+        let mut zip = zip::ZipWriter::new(File::create(dir.path().join("2022.zip")).unwrap());
+        zip.start_file("reports/01.csv", Default::default()).unwrap();
+        zip.write_all(b"zip").unwrap();
+        zip.finish().unwrap();
+
+        let tar_gz = GzEncoder::new(File::create(dir.path().join("2023.tar.gz")).unwrap(), Compression::default());
+        let mut tar = tar::Builder::new(tar_gz);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        tar.append_data(&mut header, "01.csv", &b"tar"[..]).unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let files = StatementFiles::list(dir_path).unwrap();
+        let files: Vec<_> = files.iter().map(|file| {
+            (file.name.as_str(), file.origin_path().to_owned(), fs::read_to_string(&file.path).unwrap())
+        }).collect();
+
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_owned();
+        assert_eq!(files, vec![
+            ("2020.csv", path("2020.csv"), s!("plain")),
+            ("2021.csv.gz/2021.csv", path("2021.csv.gz"), s!("gzip")),
+            ("2022.zip/reports/01.csv", path("2022.zip"), s!("zip")),
+            ("2023.tar.gz/01.csv", path("2023.tar.gz"), s!("tar")),
+        ]);
+
+        let files = StatementFiles::list(&path("2022.zip")).unwrap();
+        let file = files.iter().next().unwrap();
+        assert!(file.path.ends_with("/01.csv"));
+        assert_eq!(file.description(), format!("{:?} from {:?} archive", "reports/01.csv", path("2022.zip")));
+        assert_eq!(file.id(), format!("{}:reports/01.csv", path("2022.zip")));
+    }
+}
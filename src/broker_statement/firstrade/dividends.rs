@@ -1,3 +1,5 @@
+use std::sync::atomic::Ordering;
+
 use chrono::Datelike;
 use lazy_static::lazy_static;
 use log::warn;
@@ -21,12 +23,11 @@ pub fn parse_dividend(
         return Err!("Got a dividend from {} in an unexpected currency: {}", issuer, income.currency)
     }
 
-    if parser.reader.warn_on_missing_dividend_details {
+    if parser.reader.warn_on_missing_dividend_details.swap(false, Ordering::Relaxed) {
         warn!(concat!(
             "Firstrade statements don't provide information about real dividend amount, so it ",
             "will be deduced from received amount and expected tax rate.",
         ));
-        parser.reader.warn_on_missing_dividend_details = false;
     }
 
     let mut non_res_tax_withheld = false;
@@ -0,0 +1,297 @@
+// Account history in CSV format. Firstrade allows to download OFX statements only for the last
+// few years, so earlier history is available only in this format.
+//
+// The export doesn't contain balance, open positions and the requested period, so the period is
+// taken from the file name if it's specified there or from the transaction dates otherwise.
+
+use std::path::Path;
+
+use cusip::CUSIP;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serde::de::{Deserializer, Error};
+
+use crate::broker_statement::{StockBuy, StockSell, IdleCashInterest, Fee};
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, CashAssets};
+use crate::instruments::InstrumentId;
+use crate::time::{self, Date, Period};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::StatementParser;
+use super::dividends;
+
+// Firstrade accounts are always in USD
+const CURRENCY: &str = "USD";
+
+pub fn is_account_history(headers: &[&str]) -> bool {
+    headers.starts_with(&["Symbol", "Quantity", "Price", "Action", "Description", "TradeDate"])
+}
+
+pub fn parse(parser: &mut StatementParser, path: &str) -> EmptyResult {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)?;
+
+    let headers = reader.headers()?.clone();
+    if !is_account_history(&headers.iter().collect::<Vec<_>>()) {
+        return Err!("Got an unexpected Firstrade account history CSV header: {:?}", headers);
+    }
+
+    let mut records = Vec::new();
+    for record in reader.deserialize() {
+        let record: HistoryRecord = record?;
+        records.push(record);
+    }
+
+    let period = match parse_period_from_file_name(path)? {
+        Some(period) => period,
+        None => {
+            let first_date = records.iter().map(|record| record.date).min();
+            let last_date = records.iter().map(|record| record.date).max();
+
+            match (first_date, last_date) {
+                (Some(first_date), Some(last_date)) => Period::new(first_date, last_date)?,
+                _ => return Err!(
+                    "Unable to determine the account history period: it has no transactions and the file name doesn't contain it"),
+            }
+        },
+    };
+    parser.statement.set_period(period)?;
+    parser.statement.set_has_starting_assets(false)?;
+
+    let mut ffs_balance = dec!(0);
+
+    for record in records {
+        record.parse(parser, &mut ffs_balance).map_err(|e| format!(
+            "Failed to parse {:?} transaction from {}: {}",
+            record.description, record.date, e))?;
+    }
+
+    if !ffs_balance.is_zero() {
+        return Err!("Got a non-zero FFS balance: {}", ffs_balance);
+    }
+
+    Ok(())
+}
+
+// Example: history-20150101-20181231.csv
+fn parse_period_from_file_name(path: &str) -> GenericResult<Option<Period>> {
+    lazy_static! {
+        static ref PERIOD_REGEX: Regex = Regex::new(
+            r"(?:^|\D)(?P<first>\d{8})[-_](?P<last>\d{8})(?:\D|$)").unwrap();
+    }
+
+    let file_name = Path::new(path).file_name().and_then(|name| name.to_str()).unwrap_or_default();
+
+    Ok(match PERIOD_REGEX.captures(file_name) {
+        Some(captures) => Some(Period::new(
+            time::parse_date(captures.name("first").unwrap().as_str(), "%Y%m%d")?,
+            time::parse_date(captures.name("last").unwrap().as_str(), "%Y%m%d")?,
+        )?),
+        None => None,
+    })
+}
+
+#[derive(Deserialize)]
+struct HistoryRecord {
+    #[serde(rename = "Symbol")]
+    symbol: String,
+    #[serde(rename = "Quantity")]
+    quantity: Option<Decimal>,
+    #[serde(rename = "Price")]
+    price: Option<Decimal>,
+    #[serde(rename = "Action")]
+    action: String,
+    #[serde(rename = "Description")]
+    description: String,
+    #[serde(rename = "TradeDate", deserialize_with = "deserialize_date")]
+    date: Date,
+    #[serde(rename = "SettledDate", deserialize_with = "deserialize_date")]
+    settle_date: Date,
+    #[serde(rename = "Interest")]
+    _interest: Option<Decimal>,
+    #[serde(rename = "Amount")]
+    amount: Decimal,
+    #[serde(rename = "Commission")]
+    commission: Option<Decimal>,
+    #[serde(rename = "Fee")]
+    fee: Option<Decimal>,
+    #[serde(rename = "CUSIP")]
+    cusip: String,
+    #[serde(rename = "RecordType")]
+    record_type: String,
+}
+
+impl HistoryRecord {
+    fn parse(&self, parser: &mut StatementParser, ffs_balance: &mut Decimal) -> EmptyResult {
+        let description = util::fold_spaces(self.description.trim()).to_string();
+
+        match (self.record_type.as_str(), self.action.as_str()) {
+            ("Trade", "BUY") => self.parse_trade(parser, true),
+            ("Trade", "SELL") => self.parse_trade(parser, false),
+
+            ("Financial", "Dividend") => {
+                let amount = util::validate_named_cash(
+                    "dividend amount", CURRENCY, self.amount,
+                    DecimalRestrictions::StrictlyPositive)?;
+
+                dividends::parse_dividend(parser, self.date, self.get_symbol()?, amount, &description)
+            },
+
+            ("Financial", "Interest") => {
+                if !self.symbol.is_empty() {
+                    return Err!("Got an unsupported interest from {}", self.symbol);
+                }
+
+                let amount = util::validate_named_cash(
+                    "idle cash interest amount", CURRENCY, self.amount,
+                    DecimalRestrictions::NonZero)?;
+
+                parser.statement.idle_cash_interest.push(IdleCashInterest::new(self.date, amount));
+                Ok(())
+            },
+
+            ("Financial", "Other") => self.parse_other(parser, ffs_balance, &description),
+
+            _ => Err!("Got an unsupported transaction type: {} / {}", self.record_type, self.action),
+        }
+    }
+
+    fn parse_trade(&self, parser: &mut StatementParser, buy: bool) -> EmptyResult {
+        let symbol = self.get_symbol()?;
+
+        let quantity = util::validate_named_decimal(
+            "trade quantity", self.quantity.unwrap_or_default(), if buy {
+                DecimalRestrictions::StrictlyPositive
+            } else {
+                DecimalRestrictions::StrictlyNegative
+            })?.abs().normalize();
+
+        let price = util::validate_named_cash(
+            "price", CURRENCY, self.price.unwrap_or_default().normalize(),
+            DecimalRestrictions::StrictlyPositive)?;
+
+        let commission = util::validate_named_decimal(
+            "commission", self.commission.unwrap_or_default(), DecimalRestrictions::PositiveOrZero
+        ).and_then(|commission| {
+            let fee = util::validate_named_decimal(
+                "fee", self.fee.unwrap_or_default(), DecimalRestrictions::PositiveOrZero)?;
+            Ok(commission + fee)
+        }).map(|commission| Cash::new(CURRENCY, commission))?;
+
+        // Amount is the resulting cash flow, so it includes commission
+        let volume = util::validate_named_decimal(
+            "trade amount", self.amount, if buy {
+                DecimalRestrictions::StrictlyNegative
+            } else {
+                DecimalRestrictions::StrictlyPositive
+            })
+            .map(|mut volume| {
+                volume = volume.abs();
+
+                if buy {
+                    volume -= commission.amount;
+                } else {
+                    volume += commission.amount
+                }
+
+                Cash::new(CURRENCY, volume)
+            })?;
+        debug_assert_eq!(volume, (price * quantity).round());
+
+        if !self.cusip.is_empty() {
+            let cusip: CUSIP = self.cusip.parse().map_err(|_| format!(
+                "Invalid CUSIP: {:?}", self.cusip))?;
+            parser.statement.instrument_info.get_or_add(symbol).add_cusip(cusip);
+        }
+
+        if buy {
+            parser.statement.stock_buys.push(StockBuy::new_trade(
+                symbol, quantity, price, volume, commission,
+                self.date.into(), self.settle_date));
+        } else {
+            parser.statement.stock_sells.push(StockSell::new_trade(
+                symbol, quantity, price, volume, commission,
+                self.date.into(), self.settle_date, false));
+        }
+
+        Ok(())
+    }
+
+    fn parse_other(&self, parser: &mut StatementParser, ffs_balance: &mut Decimal, description: &str) -> EmptyResult {
+        let date = self.date;
+
+        // These are some service transactions related to Securities Lending Income Program.
+        // They shouldn't affect account balance and always compensate each other.
+        if description.starts_with("XFER CASH FROM FFS") || description.starts_with("XFER FFS TO CASH") {
+            *ffs_balance += self.amount;
+            return Ok(());
+        }
+
+        if !self.symbol.is_empty() {
+            return match dividends::parse_tax_reversal_description(&format!(" {} ", description)) {
+                Some(tax_date) => {
+                    let amount = util::validate_named_cash(
+                        "tax reversal amount", CURRENCY, self.amount,
+                        DecimalRestrictions::StrictlyPositive)?;
+
+                    let issuer_id = InstrumentId::Symbol(self.symbol.clone());
+                    parser.statement.tax_accruals(tax_date, issuer_id, false).reverse(date, amount);
+                    Ok(())
+                },
+                None => Err!("Got an unsupported operation with {}", self.symbol),
+            };
+        }
+
+        if description.starts_with("Wire Funds Received") {
+            let amount = util::validate_named_cash(
+                "deposit amount", CURRENCY, self.amount, DecimalRestrictions::StrictlyPositive)?;
+            parser.statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(date, amount));
+        } else if description.starts_with("WIRE TRANSFER") {
+            let amount = util::validate_named_cash(
+                "withdrawal amount", CURRENCY, self.amount, DecimalRestrictions::StrictlyNegative)?;
+            parser.statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(date, amount));
+        } else if description.starts_with("INTL WIRE FEE") {
+            let amount = util::validate_named_cash(
+                "fee amount", CURRENCY, self.amount, DecimalRestrictions::StrictlyNegative)?;
+            parser.statement.fees.push(Fee::new(date, -amount, None));
+        } else {
+            return Err!("Got an unsupported operation");
+        }
+
+        Ok(())
+    }
+
+    fn get_symbol(&self) -> GenericResult<&str> {
+        if self.symbol.is_empty() {
+            return Err!("The transaction has no symbol");
+        }
+        Ok(&self.symbol)
+    }
+}
+
+fn deserialize_date<'de, D>(deserializer: D) -> Result<Date, D::Error> where D: Deserializer<'de> {
+    let date: String = Deserialize::deserialize(deserializer)?;
+    time::parse_date(&date, "%Y-%m-%d").map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(path, period,
+        case("history-20150101-20181231.csv", Some((date!(2015, 1, 1), date!(2018, 12, 31)))),
+        case("/statements/FT_20150101_20181231.csv", Some((date!(2015, 1, 1), date!(2018, 12, 31)))),
+        case("history.csv", None),
+    )]
+    fn period_from_file_name(path: &str, period: Option<(Date, Date)>) {
+        assert_eq!(
+            parse_period_from_file_name(path).unwrap(),
+            period.map(|(first, last)| Period::new(first, last).unwrap()),
+        );
+    }
+}
@@ -1,6 +1,7 @@
 mod balance;
 mod common;
 mod dividends;
+mod history;
 mod open_positions;
 mod parser;
 mod security_info;
@@ -9,37 +10,63 @@ mod transactions;
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::{Read, BufReader, BufRead, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(test)] use crate::brokers::Broker;
 #[cfg(test)] use crate::config::Config;
 use crate::core::GenericResult;
 #[cfg(test)] use crate::taxes::TaxRemapping;
+#[cfg(test)] use crate::time::Period;
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
 use super::{BrokerStatementReader, PartialBrokerStatement};
 
+pub use self::history::is_account_history;
 use self::parser::{StatementParser, Ofx};
 
 pub struct StatementReader {
-    warn_on_missing_dividend_details: bool,
+    warn_on_missing_dividend_details: AtomicBool,
 }
 
 impl StatementReader {
     pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
         Ok(Box::new(StatementReader{
-            warn_on_missing_dividend_details: true,
+            warn_on_missing_dividend_details: AtomicBool::new(true),
         }))
     }
 }
 
 impl BrokerStatementReader for StatementReader {
     fn check(&mut self, path: &str) -> GenericResult<bool> {
-        Ok(path.ends_with(".ofx"))
+        Ok(path.ends_with(".ofx") || path.ends_with(".csv"))
     }
 
-    fn read(&mut self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement> {
+    fn read(&self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        if path.ends_with(".csv") {
+            return self.read_account_history(path, is_last);
+        }
         StatementParser::parse(self, read_statement(path)?, is_last)
     }
+
+    fn has_warnings(&self) -> bool {
+        !self.warn_on_missing_dividend_details.load(Ordering::Relaxed)
+    }
+}
+
+impl StatementReader {
+    fn read_account_history(&self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        // Account history doesn't contain balance and open positions, so it may be used only to
+        // cover the periods preceding the OFX statements.
+        if is_last {
+            return Err!(concat!(
+                "Firstrade account history can't be the last statement because it has no information ",
+                "about current assets. Please add an OFX statement for the latest period"));
+        }
+
+        let mut parser = StatementParser::new(self, is_last);
+        history::parse(&mut parser, path)?;
+        parser.statement.validate()
+    }
 }
 
 fn read_statement(path: &str) -> GenericResult<Ofx> {
@@ -85,9 +112,9 @@ mod tests {
         let broker = Broker::Firstrade.get_info(&Config::mock(), None).unwrap();
 
         let statement = BrokerStatement::read(
-            broker, "testdata/firstrade/my",
+            broker, "testdata/firstrade/my", None,
             &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(),
-            &[], ReadingStrictness::all()).unwrap();
+            &[], StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
 
         assert!(!statement.cash_assets.is_empty());
         assert!(!statement.deposits_and_withdrawals.is_empty());
@@ -104,4 +131,27 @@ mod tests {
         assert!(statement.open_positions.is_empty());
         assert!(!statement.instrument_info.is_empty());
     }
+
+    #[test]
+    fn parse_account_history() {
+        let reader = StatementReader::new().unwrap();
+        let path = "testdata/firstrade-history/history-20190101-20191231.csv";
+
+        let statement = reader.read(path, false).unwrap();
+        assert_eq!(statement.get_period().unwrap(), Period::new(date!(2019, 1, 1), date!(2019, 12, 31)).unwrap());
+
+        assert_eq!(statement.deposits_and_withdrawals.len(), 2);
+        assert_eq!(statement.fees.len(), 1);
+        assert_eq!(statement.idle_cash_interest.len(), 1);
+
+        assert_eq!(statement.stock_buys.len(), 2);
+        assert_eq!(statement.stock_sells.len(), 1);
+        assert_eq!(statement.dividend_accruals.len(), 1);
+        assert_eq!(statement.tax_accruals.len(), 1);
+
+        assert!(statement.assets.cash.is_none());
+        assert!(statement.open_positions.is_empty());
+
+        assert!(reader.read(path, true).is_err());
+    }
 }
\ No newline at end of file
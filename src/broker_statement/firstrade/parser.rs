@@ -13,18 +13,22 @@ use super::security_info::SecurityInfoSection;
 use super::transactions::Transactions;
 
 pub struct StatementParser<'a> {
-    pub reader: &'a mut StatementReader,
+    pub reader: &'a StatementReader,
     pub statement: PartialBrokerStatement,
     is_last: bool,
 }
 
 impl<'a> StatementParser<'a> {
-    pub fn parse(reader: &mut StatementReader, statement: Ofx, is_last: bool) -> GenericResult<PartialBrokerStatement> {
-        let mut parser = StatementParser {
+    pub fn new(reader: &StatementReader, is_last: bool) -> StatementParser {
+        StatementParser {
             reader,
             statement: PartialBrokerStatement::new(&[Exchange::Us], false),
             is_last,
-        };
+        }
+    }
+
+    pub fn parse(reader: &StatementReader, statement: Ofx, is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        let mut parser = StatementParser::new(reader, is_last);
         statement.parse(&mut parser)?;
         parser.statement.validate()
     }
@@ -0,0 +1,64 @@
+mod report;
+
+use std::fs;
+
+#[cfg(test)] use crate::brokers::Broker;
+#[cfg(test)] use crate::config::Config;
+use crate::core::GenericResult;
+#[cfg(test)] use crate::taxes::TaxRemapping;
+
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
+use super::{BrokerStatementReader, PartialBrokerStatement};
+
+use self::report::BrokerReport;
+pub use self::report::AccountInfo;
+
+pub struct StatementReader {
+}
+
+impl StatementReader {
+    pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
+        Ok(Box::new(StatementReader{}))
+    }
+}
+
+impl BrokerStatementReader for StatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        Ok(path.ends_with(".json"))
+    }
+
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        let report: BrokerReport = serde_json::from_slice(&fs::read(path)?)?;
+        report.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let broker = Broker::FreedomFinance.get_info(&Config::mock(), None).unwrap();
+
+        let statement = BrokerStatement::read(
+            broker, "testdata/freedom-finance", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(), &[],
+            StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
+
+        assert!(!statement.cash_assets.is_empty());
+        assert!(!statement.deposits_and_withdrawals.is_empty());
+
+        assert!(!statement.fees.is_empty());
+        assert!(statement.idle_cash_interest.is_empty());
+        assert!(statement.tax_agent_withholdings.is_empty());
+
+        assert!(statement.forex_trades.is_empty());
+        assert!(!statement.stock_buys.is_empty());
+        assert!(!statement.stock_sells.is_empty());
+        assert!(!statement.dividends.is_empty());
+
+        assert!(!statement.open_positions.is_empty());
+        assert!(!statement.instrument_info.is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+use serde::Deserialize;
+
+use crate::broker_statement::fees::Fee;
+use crate::broker_statement::partial::PartialBrokerStatement;
+use crate::broker_statement::trades::{StockBuy, StockSell};
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::CashAssets;
+use crate::exchanges::Exchange;
+use crate::instruments::{InstrumentId, parse_isin};
+use crate::time::{self, Date, DateTime, Period};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+// Freedom Finance (Tradernet) broker report in JSON format. The report contains a lot of other
+// information, so only the used fields are described here.
+#[derive(Deserialize)]
+pub struct BrokerReport {
+    date_start: String,
+    date_end: String,
+
+    account_at_start: AccountState,
+    account_at_end: AccountState,
+
+    #[serde(default)]
+    cash_in_outs: Vec<CashFlowInfo>,
+    #[serde(default)]
+    commissions: Details<CommissionInfo>,
+    #[serde(default)]
+    trades: Details<TradeInfo>,
+    #[serde(default)]
+    corporate_actions: Details<CorporateActionInfo>,
+}
+
+impl BrokerReport {
+    pub fn parse(self) -> GenericResult<PartialBrokerStatement> {
+        let mut statement = PartialBrokerStatement::new(&[Exchange::Us], true);
+
+        statement.set_period(Period::new(
+            parse_date_time(&self.date_start)?.date(),
+            parse_date_time(&self.date_end)?.date(),
+        )?)?;
+        statement.set_has_starting_assets(!self.account_at_start.is_empty())?;
+
+        for cash_flow in self.cash_in_outs {
+            cash_flow.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {} cash flow: {}", cash_flow.datetime, e))?;
+        }
+
+        for commission in self.commissions.detailed {
+            commission.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {} commission: {}", commission.datetime, e))?;
+        }
+
+        for trade in self.trades.detailed {
+            trade.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse #{} trade: {}", trade.trade_id, e))?;
+        }
+
+        for action in self.corporate_actions.detailed {
+            action.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {} {} corporate action: {}", action.date, action.ticker, e))?;
+        }
+
+        self.account_at_end.parse(&mut statement)?;
+
+        statement.validate()
+    }
+}
+
+// Used only for statement identification
+#[derive(Deserialize)]
+pub struct AccountInfo {
+    pub client_code: String,
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+struct Details<T> {
+    #[serde(default)]
+    detailed: Vec<T>,
+}
+
+impl<T> Default for Details<T> {
+    fn default() -> Details<T> {
+        Details {detailed: Vec::new()}
+    }
+}
+
+#[derive(Deserialize)]
+struct AccountState {
+    #[serde(default)]
+    cash: Vec<CashInfo>,
+    #[serde(default)]
+    positions: Vec<PositionInfo>,
+}
+
+impl AccountState {
+    fn is_empty(&self) -> bool {
+        self.cash.iter().all(|cash| cash.amount.is_zero()) && self.positions.is_empty()
+    }
+
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        for cash in self.cash {
+            let amount = util::validate_named_cash(
+                "cash amount", &cash.currency, cash.amount, DecimalRestrictions::No)?;
+
+            if amount.is_negative() {
+                return Err!("Leverage is not supported yet");
+            } else if !amount.is_zero() {
+                statement.assets.cash.as_mut().unwrap().deposit(amount);
+            }
+        }
+
+        for position in self.positions {
+            let symbol = parse_ticker(&position.ticker)?;
+            let quantity = util::validate_named_decimal(
+                "position quantity", position.quantity, DecimalRestrictions::StrictlyPositive)?;
+
+            add_instrument(statement, symbol, &position.isin, position.name.as_deref())?;
+            statement.add_open_position(symbol, quantity)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct CashInfo {
+    currency: String,
+    amount: Decimal,
+}
+
+#[derive(Deserialize)]
+struct PositionInfo {
+    ticker: String,
+    isin: String,
+    name: Option<String>,
+    #[serde(rename = "q")]
+    quantity: Decimal,
+}
+
+#[derive(Deserialize)]
+struct CashFlowInfo {
+    datetime: String,
+    amount: Decimal,
+    currency: String,
+}
+
+impl CashFlowInfo {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let date = parse_date_time(&self.datetime)?.date();
+        let amount = util::validate_named_cash(
+            "deposit or withdrawal amount", &self.currency, self.amount, DecimalRestrictions::NonZero)?;
+
+        statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(date, amount));
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct CommissionInfo {
+    datetime: String,
+    sum: Decimal,
+    currency: String,
+    comment: Option<String>,
+}
+
+impl CommissionInfo {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let date = parse_date_time(&self.datetime)?.date();
+
+        // Fees are negative and refunds are positive
+        let amount = -util::validate_named_cash(
+            "commission amount", &self.currency, self.sum, DecimalRestrictions::NonZero)?;
+
+        statement.fees.push(Fee::new(date, amount, self.comment.clone()));
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct TradeInfo {
+    trade_id: u64,
+    date: String,
+    pay_d: String,
+
+    instr_nm: String,
+    isin: String,
+    operation: String,
+
+    #[serde(rename = "q")]
+    quantity: Decimal,
+    #[serde(rename = "p")]
+    price: Decimal,
+    #[serde(rename = "summ")]
+    volume: Decimal,
+    curr_c: String,
+
+    commission: Decimal,
+    commission_currency: String,
+}
+
+impl TradeInfo {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let symbol = parse_ticker(&self.instr_nm)?;
+        add_instrument(statement, symbol, &self.isin, None)?;
+
+        let conclusion_time = parse_date_time(&self.date)?;
+        let execution_date = parse_date_time(&self.pay_d)?.date();
+
+        let quantity = util::validate_named_decimal(
+            "trade quantity", self.quantity, DecimalRestrictions::StrictlyPositive)?;
+        let price = util::validate_named_cash(
+            "price", &self.curr_c, self.price, DecimalRestrictions::StrictlyPositive)?;
+        let volume = util::validate_named_cash(
+            "trade volume", &self.curr_c, self.volume, DecimalRestrictions::StrictlyPositive)?;
+        debug_assert_eq!(volume, (price * quantity).round());
+
+        if self.commission_currency != self.curr_c {
+            return Err!("Commissions in currency other than trade currency are not supported yet");
+        }
+        let commission = util::validate_named_cash(
+            "commission", &self.commission_currency, self.commission, DecimalRestrictions::PositiveOrZero)?;
+
+        match self.operation.as_str() {
+            "buy" => statement.stock_buys.push(StockBuy::new_trade(
+                symbol, quantity, price, volume, commission, conclusion_time.into(), execution_date)),
+            "sell" => statement.stock_sells.push(StockSell::new_trade(
+                symbol, quantity, price, volume, commission, conclusion_time.into(), execution_date,
+                false)),
+            _ => return Err!("Unsupported trade operation: {:?}", self.operation),
+        };
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct CorporateActionInfo {
+    date: String,
+    type_id: String,
+
+    ticker: String,
+    isin: String,
+
+    amount: Decimal,
+    currency: String,
+
+    tax_amount: Option<Decimal>,
+    tax_currency: Option<String>,
+}
+
+impl CorporateActionInfo {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let date = parse_date(&self.date)?;
+        let symbol = parse_ticker(&self.ticker)?;
+        add_instrument(statement, symbol, &self.isin, None)?;
+
+        match self.type_id.as_str() {
+            "dividend" => {
+                let issuer = InstrumentId::Symbol(symbol.to_owned());
+
+                let amount = util::validate_named_cash(
+                    "dividend amount", &self.currency, self.amount, DecimalRestrictions::StrictlyPositive)?;
+                statement.dividend_accruals(date, issuer.clone(), true).add(date, amount);
+
+                // Tax is withheld at source by the issuer's country and is specified as negative amount
+                if let Some(tax) = self.tax_amount.filter(|tax| !tax.is_zero()) {
+                    let currency = self.tax_currency.as_ref().unwrap_or(&self.currency);
+                    let tax = -util::validate_named_cash(
+                        "dividend tax", currency, tax, DecimalRestrictions::StrictlyNegative)?;
+                    statement.tax_accruals(date, issuer, true).add(date, tax);
+                }
+            },
+            _ => return Err!("Unsupported corporate action type: {:?}", self.type_id),
+        }
+
+        Ok(())
+    }
+}
+
+fn add_instrument(
+    statement: &mut PartialBrokerStatement, symbol: &str, isin: &str, name: Option<&str>,
+) -> EmptyResult {
+    let instrument = statement.instrument_info.get_or_add(symbol);
+
+    instrument.add_isin(parse_isin(isin)?);
+    if let Some(name) = name {
+        instrument.set_name(name);
+    }
+
+    Ok(())
+}
+
+// Tickers have exchange suffix: AAPL.US
+fn parse_ticker(ticker: &str) -> GenericResult<&str> {
+    match ticker.rsplit_once('.') {
+        Some((symbol, "US")) if !symbol.is_empty() => Ok(symbol),
+        _ => Err!("Unsupported instrument: {:?}", ticker),
+    }
+}
+
+fn parse_date(date: &str) -> GenericResult<Date> {
+    time::parse_date(date, "%Y-%m-%d")
+}
+
+fn parse_date_time(date_time: &str) -> GenericResult<DateTime> {
+    time::parse_date_time(date_time, "%Y-%m-%d %H:%M:%S")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(ticker, symbol,
+        case("AAPL.US", Some("AAPL")),
+        case("BRK.B.US", Some("BRK.B")),
+        case("KAP.KZ", None),
+        case("AAPL", None),
+    )]
+    fn ticker_parsing(ticker: &str, symbol: Option<&str>) {
+        assert_eq!(parse_ticker(ticker).ok(), symbol);
+    }
+}
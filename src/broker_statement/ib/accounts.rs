@@ -0,0 +1,81 @@
+use crate::core::GenericResult;
+
+// Returns accounts of the activity statement. Consolidated statements of several accounts list
+// each account as a separate record with "Account" column instead of "Field Name/Field Value"
+// records of single account statements.
+pub fn read_accounts(path: &str) -> GenericResult<Vec<String>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)?;
+
+    let mut accounts = Vec::new();
+    let mut account_column = None;
+
+    for record in reader.records() {
+        let record = record?;
+        if record.get(0) != Some("Account Information") {
+            continue;
+        }
+
+        let values: Vec<&str> = record.iter().skip(2).collect();
+
+        match record.get(1) {
+            Some("Header") => {
+                account_column = if values.contains(&"Field Name") {
+                    None
+                } else {
+                    values.iter().position(|&field| field == "Account")
+                };
+            },
+            Some("Data") => {
+                let account = match account_column {
+                    Some(index) => values.get(index).copied(),
+                    None if values.first() == Some(&"Account") => values.get(1).copied(),
+                    None => None,
+                };
+
+                if let Some(account) = account.map(str::trim).filter(|account| !account.is_empty()) {
+                    if !accounts.iter().any(|other| other == account) {
+                        accounts.push(account.to_owned());
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use tempfile::NamedTempFile;
+    use super::*;
+
+    #[test]
+    fn accounts() {
+        let file = NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+
+        std::fs::write(path, indoc!("
+            Statement,Header,Field Name,Field Value
+            Statement,Data,Period,\"January 1, 2021 - December 31, 2021\"
+            Account Information,Header,Field Name,Field Value
+            Account Information,Data,Name,John Doe
+            Account Information,Data,Account,U1234567
+            Account Information,Data,Base Currency,USD
+        ")).unwrap();
+        assert_eq!(read_accounts(path).unwrap(), vec![s!("U1234567")]);
+
+        std::fs::write(path, indoc!("
+            Statement,Header,Field Name,Field Value
+            Statement,Data,Period,\"January 1, 2021 - December 31, 2021\"
+            Account Information,Header,Account,Name,Account Capabilities,Base Currency
+            Account Information,Data,U1234567,John Doe,Cash,USD
+            Account Information,Data,U7654321,Jane Doe,Margin,USD
+        ")).unwrap();
+        assert_eq!(read_accounts(path).unwrap(), vec![s!("U1234567"), s!("U7654321")]);
+    }
+}
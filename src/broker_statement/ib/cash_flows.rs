@@ -1,5 +1,6 @@
 use std::collections::{HashMap, hash_map::Entry};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use log::{debug, warn, error};
 
@@ -11,13 +12,13 @@ use crate::time::Date;
 
 // Operation dates in broker statement sometimes differ from actual dates of cash flow operations on
 // broker account. This helper provides the actual dates of cash flow operations.
-pub struct CashFlows {
+pub struct CashFlows<'a> {
     cash_flows: HashMap<CashFlowId, CashFlowRecords>,
-    enable_warnings: bool,
+    enable_warnings: &'a AtomicBool,
 }
 
-impl CashFlows {
-    pub fn new(enable_warnings: bool) -> CashFlows {
+impl<'a> CashFlows<'a> {
+    pub fn new(enable_warnings: &'a AtomicBool) -> CashFlows<'a> {
         CashFlows {
             cash_flows: HashMap::new(),
             enable_warnings,
@@ -32,14 +33,13 @@ impl CashFlows {
 
     pub fn map(&mut self, statement: &PartialBrokerStatement, id: CashFlowId, mut fallback: Date) -> GenericResult<Date> {
         if self.cash_flows.is_empty() {
-            if self.enable_warnings {
+            if self.enable_warnings.swap(false, Ordering::Relaxed) {
                 // https://github.com/KonishchevDmitry/investments/blob/master/docs/brokers.md#ib-cash-flow-info
                 let url = "http://bit.ly/investments-ib-cash-flow-info";
                 warn!(concat!(
                     "The broker statement misses account cash flow info (see {}). ",
                     "Operation dates may not be correct enough for account cash flow calculations.",
                 ), url);
-                self.enable_warnings = false;
             }
         } else {
             if let Entry::Occupied(mut entry) = self.cash_flows.entry(id.clone()) {
@@ -68,7 +68,7 @@ impl CashFlows {
         Ok(fallback)
     }
 
-    pub fn commit(mut self) -> GenericResult<bool> {
+    pub fn commit(mut self) -> EmptyResult {
         let cash_flows = std::mem::take(&mut self.cash_flows);
 
         for (id, records) in cash_flows {
@@ -77,7 +77,7 @@ impl CashFlows {
             }
         }
 
-        Ok(self.enable_warnings)
+        Ok(())
     }
 
     fn on_mapping_error(&mut self, id: &CashFlowId) -> EmptyResult {
@@ -90,9 +90,8 @@ impl CashFlows {
             return Err(error.into());
         }
 
-        if self.enable_warnings {
+        if self.enable_warnings.swap(false, Ordering::Relaxed) {
             error!("{}.", error);
-            self.enable_warnings = false;
         }
 
         Ok(())
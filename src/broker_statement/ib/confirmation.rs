@@ -16,7 +16,7 @@ pub struct OrderId {
     pub date: Date,
 }
 
-pub fn try_parse(path: &str, execution_dates: &mut TradeExecutionDates) -> GenericResult<bool> {
+pub fn try_parse(path: &str, account: Option<&str>, execution_dates: &mut TradeExecutionDates) -> GenericResult<bool> {
     let mut reader = csv::ReaderBuilder::new()
         .has_headers(false)
         .from_path(path)?;
@@ -36,11 +36,20 @@ pub fn try_parse(path: &str, execution_dates: &mut TradeExecutionDates) -> Gener
     }
 
     let record_spec = RecordSpec::new("Trade confirmation", headers, 0);
+    let filter_account = account.filter(|_| record_spec.has_field("ClientAccountID"));
 
     for record in records {
         let record = record?;
-        parse_record(&Record::new(&record_spec, &record), execution_dates).map_err(|e| format!(
-            "Failed to parse {} record: {}", format_error_record(&record), e
+        let record = Record::new(&record_spec, &record);
+
+        if let Some(account) = filter_account {
+            if record.get_value("ClientAccountID")? != account {
+                continue;
+            }
+        }
+
+        parse_record(&record, execution_dates).map_err(|e| format!(
+            "Failed to parse {} record: {}", format_error_record(record.values), e
         ))?;
     }
 
@@ -81,7 +90,7 @@ mod tests {
         let mut execution_dates = TradeExecutionDates::new();
         let path = Path::new(file!()).parent().unwrap().join(
             "testdata/empty-trade-confirmation.csv");
-        assert!(try_parse(path.to_str().unwrap(), &mut execution_dates).unwrap());
+        assert!(try_parse(path.to_str().unwrap(), None, &mut execution_dates).unwrap());
         assert!(execution_dates.is_empty());
     }
 
@@ -98,7 +107,7 @@ mod tests {
                 continue
             }
 
-            if try_parse(path, &mut execution_dates).unwrap() {
+            if try_parse(path, None, &mut execution_dates).unwrap() {
                 count += 1;
             }
         }
@@ -3,9 +3,12 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::broker_statement::corporate_actions::{CorporateAction, CorporateActionType, StockSplitRatio};
+use crate::broker_statement::partial::PartialBrokerStatement;
 use crate::core::{EmptyResult, GenericResult};
 use crate::formatting::format_date;
-#[cfg(test)] use crate::types::{Date, DateTime, Decimal};
+use crate::time::DateOptTime;
+#[cfg(test)] use crate::types::DateTime;
+use crate::types::{Date, Decimal};
 use crate::util::{self, DecimalRestrictions};
 
 use super::StatementParser;
@@ -35,35 +38,41 @@ impl CorporateActionsParser {
     }
 
     pub fn commit(self, parser: &mut StatementParser) -> EmptyResult {
-        // Here we postprocess parsed corporate actions:
-        // * Complex stock splits are represented by two records, so we join them here
-
-        let mut stock_splits = Vec::<CorporateAction>::new();
-
-        for action in self.corporate_actions {
-            match action.action {
-                CorporateActionType::StockSplit {..} => {
-                    if let Some(last) = stock_splits.last() {
-                        if action.time == last.time && action.symbol == last.symbol {
-                            stock_splits.push(action);
-                        } else {
-                            parser.statement.corporate_actions.push(join_stock_splits(stock_splits)?);
-                            stock_splits = vec![action];
-                        }
-                    } else {
+        postprocess_corporate_actions(&mut parser.statement, self.corporate_actions)
+    }
+}
+
+pub fn postprocess_corporate_actions(
+    statement: &mut PartialBrokerStatement, corporate_actions: Vec<CorporateAction>,
+) -> EmptyResult {
+    // Here we postprocess parsed corporate actions:
+    // * Complex stock splits are represented by two records, so we join them here
+
+    let mut stock_splits = Vec::<CorporateAction>::new();
+
+    for action in corporate_actions {
+        match action.action {
+            CorporateActionType::StockSplit {..} => {
+                if let Some(last) = stock_splits.last() {
+                    if action.time == last.time && action.symbol == last.symbol {
                         stock_splits.push(action);
+                    } else {
+                        statement.corporate_actions.push(join_stock_splits(stock_splits)?);
+                        stock_splits = vec![action];
                     }
-                },
-                _ => parser.statement.corporate_actions.push(action),
-            }
-        }
-
-        if !stock_splits.is_empty() {
-            parser.statement.corporate_actions.push(join_stock_splits(stock_splits)?);
+                } else {
+                    stock_splits.push(action);
+                }
+            },
+            _ => statement.corporate_actions.push(action),
         }
+    }
 
-        Ok(())
+    if !stock_splits.is_empty() {
+        statement.corporate_actions.push(join_stock_splits(stock_splits)?);
     }
+
+    Ok(())
 }
 
 fn parse(record: &Record) -> GenericResult<CorporateAction> {
@@ -73,9 +82,19 @@ fn parse(record: &Record) -> GenericResult<CorporateAction> {
     }
 
     let time = record.parse_date_time("Date/Time")?;
-    let report_date = Some(record.parse_date("Report Date")?);
+    let report_date = record.parse_date("Report Date")?;
+    let description = record.get_value("Description")?;
+    let quantity = record.parse_quantity("Quantity", DecimalRestrictions::No)?;
+    let currency = record.get_value("Currency")?;
+
+    parse_corporate_action(time.into(), report_date, description, quantity, currency)
+}
 
-    let description = util::fold_spaces(record.get_value("Description")?);
+pub fn parse_corporate_action(
+    time: DateOptTime, report_date: Date, description: &str, quantity: Decimal, currency: &str,
+) -> GenericResult<CorporateAction> {
+    let report_date = Some(report_date);
+    let description = util::fold_spaces(description);
     let description = description.as_ref();
 
     lazy_static! {
@@ -106,7 +125,7 @@ fn parse(record: &Record) -> GenericResult<CorporateAction> {
             let to: u32 = captures.name("to").unwrap().as_str().parse()?;
             let ratio = StockSplitRatio::new(from, to);
 
-            let change = record.parse_quantity("Quantity", DecimalRestrictions::NonZero)?;
+            let change = util::validate_named_decimal("quantity", quantity, DecimalRestrictions::NonZero)?;
             let (from_change, to_change) = if change.is_sign_positive() {
                 (None, Some(change))
             } else {
@@ -121,24 +140,26 @@ fn parse(record: &Record) -> GenericResult<CorporateAction> {
                 return error();
             }
 
-            let quantity = record.parse_quantity("Quantity", DecimalRestrictions::StrictlyPositive)?;
-            CorporateActionType::StockDividend {quantity}
+            let quantity = util::validate_named_decimal(
+                "quantity", quantity, DecimalRestrictions::StrictlyPositive)?;
+            CorporateActionType::StockDividend {quantity, cost_basis_ratio: None}
         },
 
         "Spinoff" => {
-            let quantity = record.parse_quantity("Quantity", DecimalRestrictions::StrictlyPositive)?;
-            let currency = record.get_value("Currency")?.to_owned();
-
+            let quantity = util::validate_named_decimal(
+                "quantity", quantity, DecimalRestrictions::StrictlyPositive)?;
             CorporateActionType::Spinoff {
                 symbol: other_symbol,
-                quantity, currency,
+                quantity,
+                currency: currency.to_owned(),
+                cost_basis_ratio: None,
             }
         },
 
         _ => unreachable!(),
     };
 
-    Ok(CorporateAction {time: time.into(), report_date, symbol, action})
+    Ok(CorporateAction {time, report_date, symbol, action})
 }
 
 fn join_stock_splits(mut actions: Vec<CorporateAction>) -> GenericResult<CorporateAction> {
@@ -247,7 +268,7 @@ mod tests {
             report_date: Some(date!(2020, 7, 17)),
 
             symbol: s!("TEF"),
-            action: CorporateActionType::StockDividend {quantity: dec!(1)},
+            action: CorporateActionType::StockDividend {quantity: dec!(1), cost_basis_ratio: None},
         });
     }
 
@@ -266,6 +287,7 @@ mod tests {
                 symbol: s!("VTRS"),
                 quantity: dec!(9.3059),
                 currency: s!("USD"),
+                cost_basis_ratio: None,
             },
         });
     }
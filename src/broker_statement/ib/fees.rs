@@ -1,5 +1,5 @@
 use crate::core::EmptyResult;
-use crate::broker_statement::fees::Fee;
+use crate::broker_statement::fees::{Fee, FeeType};
 use crate::util::DecimalRestrictions;
 
 use super::StatementParser;
@@ -21,4 +21,29 @@ impl RecordParser for FeesParser {
         let amount = record.parse_cash("Amount", currency, DecimalRestrictions::NonZero)?;
         Ok(parser.statement.fees.push(Fee::new(date, -amount, None)))
     }
-}
\ No newline at end of file
+}
+
+// Daily accrued fees for borrowed stocks of short positions
+pub struct BorrowFeesParser {}
+
+impl RecordParser for BorrowFeesParser {
+    fn skip_totals(&self) -> bool {
+        true
+    }
+
+    fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
+        let currency = record.get_value("Currency")?;
+        let date = record.parse_date("Value Date")?;
+        let symbol = record.parse_symbol("Symbol")?;
+
+        let amount = record.parse_cash("Borrow Fee", currency, DecimalRestrictions::No)?;
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        let description = format!("Комиссия за заем {}", symbol);
+        parser.statement.fees.push(Fee::new_with_type(date, FeeType::BorrowFee, -amount, Some(description)));
+
+        Ok(())
+    }
+}
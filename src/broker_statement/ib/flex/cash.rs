@@ -0,0 +1,142 @@
+use serde::Deserialize;
+
+use crate::broker_statement::fees::{Fee, FeeType};
+use crate::broker_statement::interest::IdleCashInterest;
+use crate::broker_statement::partial::PartialBrokerStatement;
+use crate::core::EmptyResult;
+use crate::currency::{Cash, CashAssets};
+use crate::formatting::format_date;
+use crate::instruments::InstrumentId;
+use crate::taxes::TaxRemapping;
+use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::super::common::parse_symbol;
+use super::common::{deserialize_date, deserialize_date_time};
+
+#[derive(Deserialize)]
+pub struct CashReport {
+    #[serde(rename = "CashReportCurrency", default)]
+    currencies: Vec<CashReportCurrency>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CashReportCurrency {
+    currency: String,
+    ending_cash: Decimal,
+}
+
+impl CashReport {
+    pub fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let cash_assets = statement.assets.cash.get_or_insert_with(Default::default);
+
+        for report in self.currencies {
+            // Summary for all currencies converted to base currency
+            if report.currency == "BASE_SUMMARY" {
+                continue;
+            }
+
+            if cash_assets.has_assets(&report.currency) {
+                return Err!("Got duplicated {} assets", report.currency);
+            }
+            cash_assets.deposit(Cash::new(&report.currency, report.ending_cash));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CashTransactions {
+    #[serde(rename = "CashTransaction", default)]
+    transactions: Vec<CashTransaction>,
+}
+
+impl CashTransactions {
+    pub fn parse(self, statement: &mut PartialBrokerStatement, tax_remapping: &TaxRemapping) -> EmptyResult {
+        for transaction in self.transactions {
+            transaction.parse(statement, tax_remapping).map_err(|e| format!(
+                "Failed to parse {:?} cash transaction from {}: {}",
+                transaction.description, format_date(transaction.date_time.date), e))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CashTransaction {
+    #[serde(rename = "type")]
+    type_: String,
+    symbol: String,
+    description: String,
+    currency: String,
+    amount: Decimal,
+    #[serde(deserialize_with = "deserialize_date_time")]
+    date_time: DateOptTime,
+    #[serde(deserialize_with = "deserialize_date")]
+    settle_date: Date,
+    level_of_detail: Option<String>,
+}
+
+impl CashTransaction {
+    fn parse(&self, statement: &mut PartialBrokerStatement, tax_remapping: &TaxRemapping) -> EmptyResult {
+        // Summary records duplicate the detailed ones
+        if matches!(self.level_of_detail.as_deref(), Some(level) if level != "DETAIL") {
+            return Ok(());
+        }
+
+        let date = self.date_time.date;
+        let amount = util::validate_named_cash(
+            "amount", &self.currency, self.amount, DecimalRestrictions::NonZero)?;
+
+        match self.type_.as_str() {
+            "Deposits/Withdrawals" | "Deposits & Withdrawals" => {
+                statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(self.settle_date, amount));
+            },
+
+            "Dividends" | "Payment In Lieu Of Dividends" => {
+                let issuer = InstrumentId::Symbol(parse_symbol(&self.symbol)?);
+                let accruals = statement.dividend_accruals(date, issuer, true);
+
+                if amount.is_negative() {
+                    accruals.reverse(self.settle_date, -amount);
+                } else {
+                    accruals.add(self.settle_date, amount);
+                }
+            },
+
+            // See the comment for WithholdingTaxParser
+            "Withholding Tax" => {
+                let issuer = InstrumentId::Symbol(parse_symbol(&self.symbol)?);
+                let actual_date = tax_remapping.map(date, &self.description);
+                let accruals = statement.tax_accruals(actual_date, issuer, true);
+
+                if amount.is_positive() {
+                    accruals.reverse(self.settle_date, amount);
+                } else {
+                    accruals.add(self.settle_date, -amount);
+                }
+            },
+
+            "Broker Interest Received" | "Broker Interest Paid" => {
+                // Interest for debit cash balance of margin account
+                if amount.is_negative() {
+                    statement.fees.push(Fee::new_with_type(date, FeeType::MarginInterest, -amount, None));
+                } else {
+                    statement.idle_cash_interest.push(IdleCashInterest::new(date, amount));
+                }
+            },
+
+            "Other Fees" | "Commission Adjustments" => {
+                statement.fees.push(Fee::new(date, -amount, None));
+            },
+
+            _ => return Err!("Unsupported cash transaction type: {:?}", self.type_),
+        }
+
+        Ok(())
+    }
+}
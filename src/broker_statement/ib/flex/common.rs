@@ -0,0 +1,59 @@
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::core::GenericResult;
+use crate::time::{self, Date, DateOptTime};
+
+// Date and time formats are configurable in Flex Query settings, so we support the default ones
+// (yyyyMMdd and HHmmss) and ISO-like ones (yyyy-MM-dd and HH:mm:ss).
+pub fn parse_date(date: &str) -> GenericResult<Date> {
+    time::parse_date(date, "%Y%m%d").or_else(|_| time::parse_date(date, "%Y-%m-%d"))
+}
+
+fn parse_date_time(value: &str) -> GenericResult<DateOptTime> {
+    let (date, time) = match value.find(|c| matches!(c, ';' | ',' | ' ')) {
+        Some(index) => (&value[..index], Some(value[index + 1..].trim())),
+        None => (value, None),
+    };
+
+    let date = parse_date(date)?;
+
+    Ok(match time {
+        Some(time) => {
+            let time = time::parse_time(time, "%H%M%S").or_else(|_| time::parse_time(time, "%H:%M:%S"))?;
+            date.and_time(time).into()
+        },
+        None => date.into(),
+    })
+}
+
+pub fn deserialize_date<'de, D>(deserializer: D) -> Result<Date, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: String = Deserialize::deserialize(deserializer)?;
+    parse_date(&value).map_err(D::Error::custom)
+}
+
+pub fn deserialize_date_time<'de, D>(deserializer: D) -> Result<DateOptTime, D::Error>
+    where D: Deserializer<'de>
+{
+    let value: String = Deserialize::deserialize(deserializer)?;
+    parse_date_time(&value).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("20210115", date!(2021, 1, 15).into()),
+        case("2021-01-15", date!(2021, 1, 15).into()),
+        case("20210115;153012", date_time!(2021, 1, 15, 15, 30, 12).into()),
+        case("2021-01-15;15:30:12", date_time!(2021, 1, 15, 15, 30, 12).into()),
+        case("2021-01-15, 15:30:12", date_time!(2021, 1, 15, 15, 30, 12).into()),
+        case("20210115 153012", date_time!(2021, 1, 15, 15, 30, 12).into()),
+    )]
+    fn date_time_parsing(value: &str, expected: DateOptTime) {
+        assert_eq!(parse_date_time(value).unwrap(), expected);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::hash_map::Entry;
+
+use serde::Deserialize;
+
+use crate::broker_statement::corporate_actions::CorporateAction;
+use crate::broker_statement::derivatives::{DerivativeContract, DerivativeType};
+use crate::broker_statement::partial::PartialBrokerStatement;
+use crate::core::{EmptyResult, GenericResult};
+use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::super::common::{SecurityID, parse_symbol};
+use super::super::corporate_actions::parse_corporate_action;
+use super::common::{deserialize_date, deserialize_date_time, parse_date};
+
+#[derive(Deserialize)]
+pub struct SecuritiesInfo {
+    #[serde(rename = "SecurityInfo", default)]
+    securities: Vec<SecurityInfo>,
+}
+
+impl SecuritiesInfo {
+    pub fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        for security in self.securities {
+            security.parse(statement).map_err(|e| format!(
+                "Failed to parse {} security info: {}", security.symbol, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SecurityInfo {
+    asset_category: String,
+    symbol: String,
+    description: String,
+    #[serde(default)]
+    isin: String,
+    #[serde(default)]
+    cusip: String,
+
+    // Derivatives only
+    #[serde(default)]
+    underlying_symbol: String,
+    #[serde(default)]
+    multiplier: String,
+    #[serde(default)]
+    expiry: String,
+    #[serde(default)]
+    put_call: String,
+    #[serde(default)]
+    strike: String,
+}
+
+impl SecurityInfo {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        match self.asset_category.as_str() {
+            "STK" => {},
+            "OPT" | "FUT" => return self.parse_derivative_contract(statement),
+            _ => return Err!("Unsupported asset category: {}", self.asset_category),
+        }
+
+        let symbol = parse_symbol(&self.symbol)?;
+        let instrument = statement.instrument_info.get_or_add(&symbol);
+        instrument.set_name(&self.description);
+
+        let security_id = if self.isin.is_empty() {
+            &self.cusip
+        } else {
+            &self.isin
+        };
+
+        if !security_id.is_empty() {
+            match security_id.parse::<SecurityID>()? {
+                SecurityID::Isin(isin) => instrument.add_isin(isin),
+                SecurityID::Cusip(cusip) => instrument.add_cusip(cusip),
+                _ => return Err!("Got an unsupported security ID: {:?}", security_id),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_derivative_contract(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let underlying = parse_symbol(&self.underlying_symbol)?;
+        let multiplier = parse_amount("multiplier", &self.multiplier, DecimalRestrictions::StrictlyPositive)?;
+        let expiration = parse_date(&self.expiry)?;
+
+        let contract = if self.asset_category == "OPT" {
+            let type_ = match self.put_call.as_str() {
+                "C" => DerivativeType::Call,
+                "P" => DerivativeType::Put,
+                type_ => return Err!("Got an unsupported option type: {:?}", type_),
+            };
+            let strike = parse_amount("strike", &self.strike, DecimalRestrictions::StrictlyPositive)?;
+            DerivativeContract::new_option(type_, &underlying, multiplier, expiration, strike)
+        } else {
+            DerivativeContract::new_future(&underlying, multiplier, expiration)
+        };
+
+        statement.derivative_contracts.insert(self.symbol.clone(), contract);
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct OpenPositions {
+    #[serde(rename = "OpenPosition", default)]
+    positions: Vec<OpenPosition>,
+}
+
+impl OpenPositions {
+    pub fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        for position in self.positions {
+            position.parse(statement).map_err(|e| format!(
+                "Failed to parse {} open position: {}", position.symbol, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenPosition {
+    asset_category: String,
+    symbol: String,
+    position: Decimal,
+    level_of_detail: String,
+}
+
+impl OpenPosition {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        match self.level_of_detail.as_str() {
+            "SUMMARY" => {},
+            "LOT" => return Ok(()),
+            _ => return Err!("Got an unexpected level of detail: {:?}", self.level_of_detail),
+        }
+
+        let quantity = util::validate_named_decimal(
+            "quantity", self.position, DecimalRestrictions::NonZero)?.normalize();
+
+        match self.asset_category.as_str() {
+            "STK" => statement.add_open_position(&parse_symbol(&self.symbol)?, quantity)?,
+
+            "OPT" | "FUT" => match statement.derivative_positions.entry(self.symbol.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(quantity);
+                },
+                Entry::Occupied(_) => return Err!("Got a duplicated open position"),
+            },
+
+            _ => return Err!("Unsupported asset category: {}", self.asset_category),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CorporateActions {
+    #[serde(rename = "CorporateAction", default)]
+    actions: Vec<CorporateActionInfo>,
+}
+
+impl CorporateActions {
+    pub fn parse(self) -> GenericResult<Vec<CorporateAction>> {
+        let mut corporate_actions = Vec::new();
+
+        for action in self.actions {
+            if action.asset_category != "STK" {
+                return Err!(
+                    "Unsupported asset category of corporate action: {:?}", action.asset_category);
+            }
+
+            corporate_actions.push(parse_corporate_action(
+                action.date_time, action.report_date, &action.description, action.quantity,
+                &action.currency)?);
+        }
+
+        Ok(corporate_actions)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CorporateActionInfo {
+    asset_category: String,
+    #[serde(deserialize_with = "deserialize_date_time")]
+    date_time: DateOptTime,
+    #[serde(deserialize_with = "deserialize_date")]
+    report_date: Date,
+    description: String,
+    quantity: Decimal,
+    currency: String,
+}
+
+fn parse_amount(name: &str, value: &str, restrictions: DecimalRestrictions) -> GenericResult<Decimal> {
+    let amount: Decimal = value.parse().map_err(|_| format!("Invalid {}: {:?}", name, value))?;
+    util::validate_named_decimal(name, amount, restrictions)
+}
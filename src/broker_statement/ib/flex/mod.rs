@@ -0,0 +1,147 @@
+mod cash;
+mod common;
+mod instruments;
+mod trades;
+
+use serde::Deserialize;
+
+use crate::broker_statement::partial::PartialBrokerStatement;
+use crate::core::GenericResult;
+use crate::exchanges::Exchange;
+use crate::taxes::TaxRemapping;
+use crate::time::{Date, Period};
+use crate::types::Decimal;
+
+use super::corporate_actions::postprocess_corporate_actions;
+
+use self::cash::{CashReport, CashTransactions};
+use self::common::deserialize_date;
+use self::instruments::{CorporateActions, OpenPositions, SecuritiesInfo};
+use self::trades::Trades;
+
+// Flex Query statements contain the same information as Activity Statements, but in a structured
+// form and with trade settle dates, so they don't require trade confirmation reports.
+pub fn read_statement(
+    path: &str, account: Option<&str>, tax_remapping: &TaxRemapping,
+) -> GenericResult<PartialBrokerStatement> {
+    let data = std::fs::read_to_string(path)?;
+    let response: FlexQueryResponse<FlexStatement> = serde_xml_rs::from_str(&data)?;
+
+    let mut statements = response.statements.statements;
+
+    let statement = match account {
+        Some(account) => {
+            let index = statements.iter().position(|statement| statement.account_id == account).ok_or_else(|| format!(
+                "The Flex Query statement doesn't contain {} account", account))?;
+            statements.swap_remove(index)
+        },
+        None => match statements.len() {
+            0 => return Err!("The Flex Query statement doesn't contain any account statement"),
+            1 => statements.pop().unwrap(),
+            _ => return Err!(concat!(
+                "Flex Query statements with multiple accounts are supported only with account filter. ",
+                "Please specify the account to read in `account` option of the portfolio config ",
+                "(the statement contains {} accounts)"
+            ), statements.iter().map(|statement| statement.account_id.as_str()).collect::<Vec<_>>().join(", ")),
+        },
+    };
+
+    statement.parse(tax_remapping)
+}
+
+pub fn read_accounts(path: &str) -> GenericResult<Vec<String>> {
+    let data = std::fs::read_to_string(path)?;
+    let response: FlexQueryResponse<FlexStatementAccount> = serde_xml_rs::from_str(&data)?;
+    Ok(response.statements.statements.into_iter().map(|statement| statement.account_id).collect())
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+struct FlexQueryResponse<T> {
+    #[serde(rename = "FlexStatements")]
+    statements: FlexStatements<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+struct FlexStatements<T> {
+    #[serde(rename = "FlexStatement", default)]
+    statements: Vec<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlexStatementAccount {
+    account_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlexStatement {
+    account_id: String,
+
+    #[serde(deserialize_with = "deserialize_date")]
+    from_date: Date,
+    #[serde(deserialize_with = "deserialize_date")]
+    to_date: Date,
+
+    #[serde(rename = "ChangeInNAV")]
+    change_in_nav: ChangeInNav,
+
+    #[serde(rename = "CashReport")]
+    cash_report: CashReport,
+
+    #[serde(rename = "SecuritiesInfo")]
+    securities: Option<SecuritiesInfo>,
+
+    #[serde(rename = "OpenPositions")]
+    open_positions: Option<OpenPositions>,
+
+    #[serde(rename = "Trades")]
+    trades: Option<Trades>,
+
+    #[serde(rename = "CashTransactions")]
+    cash_transactions: Option<CashTransactions>,
+
+    #[serde(rename = "CorporateActions")]
+    corporate_actions: Option<CorporateActions>,
+}
+
+impl FlexStatement {
+    fn parse(self, tax_remapping: &TaxRemapping) -> GenericResult<PartialBrokerStatement> {
+        let mut statement = PartialBrokerStatement::new(&[Exchange::Us], false);
+
+        statement.set_period(Period::new(self.from_date, self.to_date)?)?;
+        statement.set_has_starting_assets(!self.change_in_nav.starting_value.is_zero())?;
+        self.cash_report.parse(&mut statement)?;
+
+        if let Some(securities) = self.securities {
+            securities.parse(&mut statement)?;
+        }
+
+        if let Some(open_positions) = self.open_positions {
+            open_positions.parse(&mut statement)?;
+        }
+
+        if let Some(trades) = self.trades {
+            trades.parse(&mut statement)?;
+        }
+
+        if let Some(cash_transactions) = self.cash_transactions {
+            cash_transactions.parse(&mut statement, tax_remapping)?;
+        }
+
+        if let Some(corporate_actions) = self.corporate_actions {
+            let corporate_actions = corporate_actions.parse()?;
+            postprocess_corporate_actions(&mut statement, corporate_actions)?;
+        }
+
+        statement.validate()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeInNav {
+    starting_value: Decimal,
+}
@@ -0,0 +1,170 @@
+use serde::Deserialize;
+
+use crate::broker_statement::derivatives::{DerivativeClosing, DerivativeTrade};
+use crate::broker_statement::partial::PartialBrokerStatement;
+use crate::broker_statement::trades::{ForexTrade, StockBuy, StockSell};
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::formatting::format_date;
+use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::super::common::parse_symbol;
+use super::super::trades::parse_closing_type;
+use super::common::{deserialize_date, deserialize_date_time};
+
+#[derive(Deserialize)]
+pub struct Trades {
+    #[serde(rename = "$value", default)]
+    items: Vec<TradesItem>,
+}
+
+#[derive(Deserialize)]
+enum TradesItem {
+    Trade(Trade),
+
+    // Orders, closed lots and summaries duplicate trade executions information
+    Order(Ignore),
+    Lot(Ignore),
+    SymbolSummary(Ignore),
+    AssetSummary(Ignore),
+}
+
+#[derive(Deserialize)]
+struct Ignore {
+}
+
+impl Trades {
+    pub fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        for item in self.items {
+            if let TradesItem::Trade(trade) = item {
+                trade.parse(statement).map_err(|e| format!(
+                    "Failed to parse {} trade from {}: {}",
+                    trade.symbol, format_date(trade.date_time.date), e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Trade {
+    asset_category: String,
+    symbol: String,
+    currency: String,
+    #[serde(deserialize_with = "deserialize_date_time")]
+    date_time: DateOptTime,
+    #[serde(deserialize_with = "deserialize_date")]
+    settle_date_target: Date,
+    quantity: Decimal,
+    trade_price: Decimal,
+    proceeds: Decimal,
+    ib_commission: Decimal,
+    ib_commission_currency: String,
+    notes: String,
+    level_of_detail: String,
+}
+
+impl Trade {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        if self.level_of_detail != "EXECUTION" {
+            return Err!("Got an unexpected level of detail: {:?}", self.level_of_detail);
+        }
+
+        match self.asset_category.as_str() {
+            "CASH" => self.parse_forex(statement),
+            "STK" => self.parse_stock(statement),
+            "OPT" | "FUT" => self.parse_derivative(statement),
+            _ => Err!("Unsupported asset category: {}", self.asset_category),
+        }
+    }
+
+    fn parse_forex(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let (base, quote) = self.symbol.split_once('.').ok_or_else(|| format!(
+            "Invalid forex pair: {}", self.symbol))?;
+
+        let volume = util::validate_named_cash(
+            "proceeds", quote, self.proceeds, DecimalRestrictions::NonZero)?;
+        let quantity = util::validate_named_cash(
+            "quantity", base, self.quantity, DecimalRestrictions::NonZero)?;
+
+        let (from, to) = if quantity.is_positive() {
+            (-volume, quantity)
+        } else {
+            (-quantity, volume)
+        };
+        if from.is_negative() || to.is_negative() {
+            return Err!("Unexpected Forex quantity/volume values: {}/{}", quantity, volume);
+        }
+
+        let commission = self.parse_commission()?;
+        statement.forex_trades.push(ForexTrade::new(self.date_time, from, to, commission));
+
+        Ok(())
+    }
+
+    fn parse_stock(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let symbol = parse_symbol(&self.symbol)?;
+        let currency = &self.currency;
+        let price = util::validate_named_cash(
+            "price", currency, self.trade_price, DecimalRestrictions::StrictlyPositive)?;
+        let commission = self.parse_commission()?;
+        let quantity = util::validate_named_decimal(
+            "quantity", self.quantity, DecimalRestrictions::NonZero)?.normalize();
+
+        let volume = util::validate_named_cash(
+            "proceeds", currency, self.proceeds, if quantity.is_sign_positive() {
+                DecimalRestrictions::StrictlyNegative
+            } else {
+                DecimalRestrictions::StrictlyPositive
+            })?;
+
+        if quantity.is_sign_positive() {
+            statement.stock_buys.push(StockBuy::new_trade(
+                &symbol, quantity, price, -volume, commission,
+                self.date_time, self.settle_date_target));
+        } else {
+            statement.stock_sells.push(StockSell::new_trade(
+                &symbol, -quantity, price, volume, commission,
+                self.date_time, self.settle_date_target, false));
+        }
+
+        Ok(())
+    }
+
+    fn parse_derivative(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let currency = &self.currency;
+        let quantity = util::validate_named_decimal(
+            "quantity", self.quantity, DecimalRestrictions::NonZero)?.normalize();
+
+        if let Some(type_) = parse_closing_type(&self.notes)? {
+            statement.derivative_closings.push(DerivativeClosing::new(
+                self.date_time.date, &self.symbol, type_, quantity, currency));
+            return Ok(());
+        }
+
+        let price = util::validate_named_cash(
+            "price", currency, self.trade_price, DecimalRestrictions::PositiveOrZero)?;
+        let commission = self.parse_commission()?;
+
+        // Futures are settled via variation margin, so the trade itself doesn't produce any cash flow
+        let volume = if self.asset_category == "FUT" {
+            Cash::zero(currency)
+        } else {
+            Cash::new(currency, self.proceeds)
+        };
+
+        statement.derivative_trades.push(DerivativeTrade::new(
+            &self.symbol, self.date_time, quantity, price, volume, commission));
+
+        Ok(())
+    }
+
+    fn parse_commission(&self) -> GenericResult<Cash> {
+        Ok(-util::validate_named_cash(
+            "commission", &self.ib_commission_currency, self.ib_commission,
+            DecimalRestrictions::NegativeOrZero)?)
+    }
+}
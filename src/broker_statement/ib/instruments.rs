@@ -1,10 +1,14 @@
+use std::collections::hash_map::Entry;
+
 use chrono::Datelike;
 
+use crate::broker_statement::derivatives::{DerivativeContract, DerivativeType};
 use crate::core::EmptyResult;
 use crate::util::DecimalRestrictions;
 
 use super::StatementParser;
 use super::common::{self, Record, RecordParser, SecurityID, parse_symbol};
+use super::trades::{OPTIONS_ASSET_CATEGORY, FUTURES_ASSET_CATEGORY};
 
 pub struct OpenPositionsParser {}
 
@@ -17,10 +21,15 @@ impl RecordParser for OpenPositionsParser {
         let data_type_field = "DataDiscriminator";
         match record.get_value(data_type_field)? {
             // Default Activity Statement contains only this type
-            "Summary" => record.check_values(&[
-                ("Asset Category", "Stocks"),
-                ("Mult", "1"),
-            ])?,
+            "Summary" => match record.get_value("Asset Category")? {
+                OPTIONS_ASSET_CATEGORY | FUTURES_ASSET_CATEGORY => {
+                    return parse_derivative_position(parser, record);
+                },
+                _ => record.check_values(&[
+                    ("Asset Category", "Stocks"),
+                    ("Mult", "1"),
+                ])?,
+            },
 
             // Custom Activity Statement types:
             // * Lot - open position calculation
@@ -30,16 +39,37 @@ impl RecordParser for OpenPositionsParser {
         };
 
         let symbol = record.parse_symbol("Symbol")?;
-        let quantity = record.parse_quantity("Quantity", DecimalRestrictions::StrictlyPositive)?;
+        let quantity = record.parse_quantity("Quantity", DecimalRestrictions::NonZero)?;
         parser.statement.add_open_position(&symbol, quantity)
     }
 }
 
+fn parse_derivative_position(parser: &mut StatementParser, record: &Record) -> EmptyResult {
+    let symbol = record.get_value("Symbol")?;
+    let quantity = record.parse_quantity("Quantity", DecimalRestrictions::NonZero)?;
+
+    match parser.statement.derivative_positions.entry(symbol.to_owned()) {
+        Entry::Vacant(entry) => entry.insert(quantity),
+        Entry::Occupied(_) => return Err!("Got a duplicated open position for {}", symbol),
+    };
+
+    Ok(())
+}
+
 pub struct FinancialInstrumentInformationParser {
 }
 
 impl RecordParser for FinancialInstrumentInformationParser {
+    // The section has a separate header for each asset category
+    fn allow_multiple(&self) -> bool {
+        true
+    }
+
     fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
+        if matches!(record.get_value("Asset Category")?, OPTIONS_ASSET_CATEGORY | FUTURES_ASSET_CATEGORY) {
+            return parse_derivative_contract(parser, record);
+        }
+
         // If symbol renames save its ISIN the column contains both symbols
         // (see https://github.com/KonishchevDmitry/investments/issues/29)
 
@@ -76,4 +106,26 @@ impl RecordParser for FinancialInstrumentInformationParser {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+fn parse_derivative_contract(parser: &mut StatementParser, record: &Record) -> EmptyResult {
+    let symbol = record.get_value("Symbol")?;
+    let underlying = parse_symbol(record.get_value("Underlying")?)?;
+    let multiplier = record.parse_amount("Multiplier", DecimalRestrictions::StrictlyPositive)?;
+    let expiration = record.parse_date("Expiry")?;
+
+    let contract = match record.get_value("Asset Category")? {
+        OPTIONS_ASSET_CATEGORY => {
+            let type_ = match record.get_value("Type")? {
+                "C" => DerivativeType::Call,
+                "P" => DerivativeType::Put,
+                type_ => return Err!("Got an unsupported option type for {}: {:?}", symbol, type_),
+            };
+            let strike = record.parse_amount("Strike", DecimalRestrictions::StrictlyPositive)?;
+            DerivativeContract::new_option(type_, &underlying, multiplier, expiration, strike)
+        },
+        _ => DerivativeContract::new_future(&underlying, multiplier, expiration),
+    };
+
+    parser.statement.derivative_contracts.insert(symbol.to_owned(), contract);
+    Ok(())
+}
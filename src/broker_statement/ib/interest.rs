@@ -1,4 +1,5 @@
 use crate::core::EmptyResult;
+use crate::broker_statement::fees::{Fee, FeeType};
 use crate::broker_statement::interest::IdleCashInterest;
 use crate::util::DecimalRestrictions;
 
@@ -15,8 +16,16 @@ impl RecordParser for InterestParser {
     fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
         let currency = record.get_value("Currency")?;
         let date = record.parse_date("Date")?;
+        let description = record.get_value("Description")?;
         let amount = record.parse_cash("Amount", currency, DecimalRestrictions::NonZero)?;
-        parser.statement.idle_cash_interest.push(IdleCashInterest::new(date, amount));
+
+        // Interest for debit cash balance of margin account. Example: USD Debit Interest for Jan-2021
+        if amount.is_negative() && description.contains(" Debit Interest ") {
+            parser.statement.fees.push(Fee::new_with_type(date, FeeType::MarginInterest, -amount, None));
+        } else {
+            parser.statement.idle_cash_interest.push(IdleCashInterest::new(date, amount));
+        }
+
         Ok(())
     }
-}
\ No newline at end of file
+}
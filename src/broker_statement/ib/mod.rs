@@ -1,3 +1,4 @@
+mod accounts;
 mod cash;
 mod cash_flows;
 mod common;
@@ -5,6 +6,7 @@ mod confirmation;
 mod corporate_actions;
 mod dividends;
 mod fees;
+mod flex;
 mod interest;
 mod instruments;
 mod sections;
@@ -12,10 +14,13 @@ mod summary;
 mod taxes;
 mod trades;
 
+use std::collections::BTreeSet;
 use std::iter::Iterator;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(test)] use chrono::Datelike;
 use csv::{self, StringRecord};
+use itertools::Itertools;
 use log::{trace, warn};
 
 #[cfg(test)] use crate::brokers::Broker;
@@ -27,64 +32,116 @@ use crate::formatting::format_date;
 use crate::taxes::TaxRemapping;
 use crate::types::Date;
 
-#[cfg(test)] use super::BrokerStatement;
-use super::{BrokerStatementReader, ReadingStrictness, PartialBrokerStatement};
+#[cfg(test)] use super::{BrokerStatement, StatementCache, StockTransfers};
+use super::{BrokerStatementReader, ReadingStrictness, PartialBrokerStatement, StatementCaching};
 
 use self::cash_flows::CashFlows;
 use self::common::{Record, format_record, format_error_record};
 use self::confirmation::{TradeExecutionDates, OrderId};
 
+pub use self::accounts::read_accounts;
+
 pub struct StatementReader {
+    // Consolidated statements and directories with statements of several accounts are read only
+    // with account filter
+    account: Option<String>,
     tax_remapping: TaxRemapping,
     trade_execution_dates: TradeExecutionDates,
 
-    warn_on_margin_account: bool,
-    warn_on_missing_execution_date: bool,
-    warn_on_missing_cash_flow_info: bool,
+    strictness: ReadingStrictness,
+    warn_on_missing_execution_date: AtomicBool,
+    warn_on_missing_cash_flow_info: AtomicBool,
 }
 
 impl StatementReader {
-    pub fn new(tax_remapping: TaxRemapping, strictness: ReadingStrictness) -> GenericResult<Box<dyn BrokerStatementReader>> {
+    pub fn new(
+        account: Option<&str>, tax_remapping: TaxRemapping, strictness: ReadingStrictness,
+    ) -> GenericResult<Box<dyn BrokerStatementReader>> {
         Ok(Box::new(StatementReader {
+            account: account.map(ToOwned::to_owned),
             tax_remapping: tax_remapping,
             trade_execution_dates: TradeExecutionDates::new(),
 
-            warn_on_margin_account: true,
-            warn_on_missing_execution_date: strictness.contains(ReadingStrictness::TRADE_SETTLE_DATE),
-            warn_on_missing_cash_flow_info: strictness.contains(ReadingStrictness::CASH_FLOW_DATES),
+            strictness,
+            warn_on_missing_execution_date: AtomicBool::new(strictness.contains(ReadingStrictness::TRADE_SETTLE_DATE)),
+            warn_on_missing_cash_flow_info: AtomicBool::new(strictness.contains(ReadingStrictness::CASH_FLOW_DATES)),
         }))
     }
 }
 
 impl BrokerStatementReader for StatementReader {
     fn check(&mut self, path: &str) -> GenericResult<bool> {
-        if !path.ends_with(".csv") {
+        if path.ends_with(".xml") {
+            return match self.account {
+                Some(ref account) => Ok(flex::read_accounts(path)?.contains(account)),
+                None => Ok(true),
+            };
+        } else if !path.ends_with(".csv") {
             return Ok(false)
         }
 
-        let is_confirmation_report = confirmation::try_parse(path, &mut self.trade_execution_dates)
-            .map_err(|e| format!("Error while reading {:?}: {}", path, e))?;
+        let is_confirmation_report = confirmation::try_parse(
+            path, self.account.as_deref(), &mut self.trade_execution_dates)?;
+
+        if is_confirmation_report {
+            return Ok(false);
+        }
 
-        Ok(!is_confirmation_report)
+        // Statements of other accounts are skipped to allow several portfolios to share one
+        // statements directory
+        match self.account {
+            Some(ref account) => Ok(read_accounts(path)?.contains(account)),
+            None => Ok(true),
+        }
     }
 
-    fn read(&mut self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+    fn caching(&self) -> StatementCaching {
+        // Tax remapping rules are checked to be matched against the statements being read
+        if !self.tax_remapping.is_empty() {
+            return StatementCaching::Disabled;
+        }
+
+        let mut execution_dates: Vec<_> = self.trade_execution_dates.iter()
+            .map(|(order_id, execution_date)| (&order_id.symbol, order_id.date, *execution_date))
+            .collect();
+        execution_dates.sort_unstable();
+
+        StatementCaching::Independent(format!(
+            "{}:{}:{}", self.strictness.bits(), self.account.as_deref().unwrap_or_default(),
+            serde_json::to_string(&execution_dates).unwrap()))
+    }
+
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        if path.ends_with(".xml") {
+            return flex::read_statement(path, self.account.as_deref(), &self.tax_remapping);
+        }
+
         StatementParser {
             statement: PartialBrokerStatement::new(&[Exchange::Us], false),
 
+            account: self.account.as_deref(),
+            accounts: BTreeSet::new(),
+
             base_currency: None,
             base_currency_summary: None,
-            cash_flows: CashFlows::new(self.warn_on_missing_cash_flow_info),
+            cash_flows: CashFlows::new(&self.warn_on_missing_cash_flow_info),
 
-            tax_remapping: &mut self.tax_remapping,
+            tax_remapping: &self.tax_remapping,
             trade_execution_dates: &self.trade_execution_dates,
 
-            warn_on_margin_account: &mut self.warn_on_margin_account,
-            warn_on_missing_execution_date: &mut self.warn_on_missing_execution_date,
-            warn_on_missing_cash_flow_info: &mut self.warn_on_missing_cash_flow_info,
+            warn_on_missing_execution_date: &self.warn_on_missing_execution_date,
         }.parse(path)
     }
 
+    fn has_warnings(&self) -> bool {
+        let warned = |flag: &AtomicBool, strictness| {
+            flag.load(Ordering::Relaxed) != self.strictness.contains(strictness)
+        };
+
+        warned(&self.warn_on_missing_execution_date, ReadingStrictness::TRADE_SETTLE_DATE) ||
+            warned(&self.warn_on_missing_cash_flow_info, ReadingStrictness::CASH_FLOW_DATES)
+    }
+
     fn close(self: Box<StatementReader>) -> EmptyResult {
         self.tax_remapping.ensure_all_mapped()
     }
@@ -99,16 +156,17 @@ enum State {
 pub struct StatementParser<'a> {
     statement: PartialBrokerStatement,
 
+    account: Option<&'a str>,
+    accounts: BTreeSet<String>,
+
     base_currency: Option<String>,
     base_currency_summary: Option<Cash>,
-    cash_flows: CashFlows,
+    cash_flows: CashFlows<'a>,
 
-    tax_remapping: &'a mut TaxRemapping,
+    tax_remapping: &'a TaxRemapping,
     trade_execution_dates: &'a TradeExecutionDates,
 
-    warn_on_margin_account: &'a mut bool,
-    warn_on_missing_execution_date: &'a mut bool,
-    warn_on_missing_cash_flow_info: &'a mut bool,
+    warn_on_missing_execution_date: &'a AtomicBool,
 }
 
 impl<'a> StatementParser<'a> {
@@ -204,6 +262,14 @@ impl<'a> StatementParser<'a> {
                             continue;
                         }
 
+                        // Each section of consolidated statement contains records of all accounts
+                        // and consolidated totals
+                        if let Some(account) = self.account {
+                            if spec.has_field("Account") && Record::new(&spec, &record).get_value("Account")? != account {
+                                continue;
+                            }
+                        }
+
                         parser.parse(&mut self, &Record::new(&spec, &record)).map_err(|e| format!(
                             "Failed to parse {} record: {}", format_error_record(&record), e
                         ))?;
@@ -214,11 +280,34 @@ impl<'a> StatementParser<'a> {
             }
         }
 
+        if let Some(account) = self.account {
+            if !self.accounts.contains(account) {
+                return Err!("The statement doesn't contain {} account", account);
+            }
+        }
+
         section_parsers.commit(&mut self)?;
-        *self.warn_on_missing_cash_flow_info &= self.cash_flows.commit()?;
+        self.cash_flows.commit()?;
         self.statement.validate()
     }
 
+    fn add_account(&mut self, account: &str) -> EmptyResult {
+        if self.accounts.contains(account) {
+            return Ok(());
+        }
+
+        if self.account.is_none() && !self.accounts.is_empty() {
+            return Err!(concat!(
+                "Consolidated statements of several accounts are supported only with account filter. ",
+                "Please specify the account to read in `account` option of the portfolio config ",
+                "(the statement contains {} and {} accounts)"
+            ), self.accounts.iter().join(", "), account);
+        }
+
+        self.accounts.insert(account.to_owned());
+        Ok(())
+    }
+
     fn base_currency(&self) -> GenericResult<&str> {
         Ok(self.base_currency.as_deref().ok_or("Unable to determine account base currency")?)
     }
@@ -231,7 +320,7 @@ impl<'a> StatementParser<'a> {
             return execution_date;
         }
 
-        if *self.warn_on_missing_execution_date {
+        if self.warn_on_missing_execution_date.swap(false, Ordering::Relaxed) {
             // https://github.com/KonishchevDmitry/investments/blob/master/docs/brokers.md#ib-trade-settle-date
             let url = "http://bit.ly/investments-ib-trade-settle-date";
             warn!(concat!(
@@ -239,7 +328,6 @@ impl<'a> StatementParser<'a> {
                 "First occurred trade - {} at {}. ",
                 "All calculations for such trades will be performed in T+0 mode.",
             ), url, symbol, format_date(conclusion_date));
-            *self.warn_on_missing_execution_date = false;
         }
 
         conclusion_date
@@ -314,6 +402,33 @@ mod tests {
         assert!(!statement.instrument_info.is_empty());
     }
 
+    #[test]
+    fn parse_real_flex() {
+        let statement = parse_full("flex", None);
+
+        assert!(!statement.cash_assets.is_empty());
+        assert!(!statement.deposits_and_withdrawals.is_empty());
+
+        assert!(!statement.fees.is_empty());
+        assert!(!statement.idle_cash_interest.is_empty());
+        assert!(statement.tax_agent_withholdings.is_empty());
+
+        assert!(statement.forex_trades.is_empty());
+        assert!(!statement.stock_buys.is_empty());
+        assert!(statement.stock_sells.is_empty());
+
+        // Flex Query statements provide settle dates without trade confirmation reports
+        for trade in &statement.stock_buys {
+            assert_ne!(trade.execution_date, trade.conclusion_time.date);
+        }
+
+        assert!(!statement.dividends.is_empty());
+        assert!(statement.dividends.iter().any(|dividend| dividend.paid_tax.is_positive()));
+
+        assert!(!statement.open_positions.is_empty());
+        assert!(!statement.instrument_info.is_empty());
+    }
+
     #[rstest(name => [
         "return-of-capital-with-tax",
         "return-of-capital-without-tax",
@@ -334,7 +449,7 @@ mod tests {
     #[rstest(name => ["no-activity", "multi-currency-activity"])]
     fn parse_real_partial(name: &str) {
         let path = format!("testdata/interactive-brokers/partial/{}.csv", name);
-        StatementReader::new(TaxRemapping::new(), ReadingStrictness::all()).unwrap()
+        StatementReader::new(None, TaxRemapping::new(), ReadingStrictness::all()).unwrap()
             .read(&path, true).unwrap();
     }
 
@@ -343,7 +458,7 @@ mod tests {
         let path = format!("testdata/interactive-brokers/{}", name);
         let tax_remapping = tax_remapping.unwrap_or_else(TaxRemapping::new);
         BrokerStatement::read(
-            broker, &path, &Default::default(), &Default::default(), &Default::default(),
-            tax_remapping, &[], ReadingStrictness::all()).unwrap()
+            broker, &path, None, &Default::default(), &Default::default(), &Default::default(),
+            tax_remapping, &[], StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap()
     }
 }
\ No newline at end of file
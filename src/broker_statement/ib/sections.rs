@@ -10,7 +10,7 @@ use super::cash::{CashReportParser, DepositsAndWithdrawalsParser, StatementOfFun
 use super::common::{RecordSpec, RecordParser, UnknownRecordParser, format_record};
 use super::corporate_actions::CorporateActionsParser;
 use super::dividends::DividendsParser;
-use super::fees::FeesParser;
+use super::fees::{FeesParser, BorrowFeesParser};
 use super::instruments::{OpenPositionsParser, FinancialInstrumentInformationParser};
 use super::interest::InterestParser;
 use super::summary::{AccountInformationParser, NavParser, ChangeInNavParser, StatementInfoParser};
@@ -29,6 +29,7 @@ pub struct SectionParsers {
     trades_parser: TradesParser,
     deposits_and_withdrawals_parser: DepositsAndWithdrawalsParser,
     fees_parser: FeesParser,
+    borrow_fees_parser: BorrowFeesParser,
     dividends_parser: DividendsParser,
     withholding_tax_parser: WithholdingTaxParser,
     interest_parser: InterestParser,
@@ -54,6 +55,7 @@ impl SectionParsers {
             trades_parser: TradesParser {},
             deposits_and_withdrawals_parser: DepositsAndWithdrawalsParser {},
             fees_parser: FeesParser {},
+            borrow_fees_parser: BorrowFeesParser {},
             dividends_parser: DividendsParser {},
             withholding_tax_parser: WithholdingTaxParser {},
             interest_parser: InterestParser {},
@@ -80,6 +82,7 @@ impl SectionParsers {
             "Trades" => &mut self.trades_parser,
             "Deposits & Withdrawals" => &mut self.deposits_and_withdrawals_parser,
             "Fees" => &mut self.fees_parser,
+            "Borrow Fee Details" => &mut self.borrow_fees_parser,
             "Dividends" => &mut self.dividends_parser,
             "Withholding Tax" => &mut self.withholding_tax_parser,
             "Interest" => &mut self.interest_parser,
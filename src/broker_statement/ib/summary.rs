@@ -1,7 +1,5 @@
 use std::iter::Iterator;
 
-use log::warn;
-
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
 use crate::time::{self, Date, Period};
@@ -28,25 +26,21 @@ pub struct AccountInformationParser {}
 
 impl RecordParser for AccountInformationParser {
     fn parse(&mut self, parser: &mut StatementParser, record: &Record) -> EmptyResult {
+        // Consolidated statements contain a record per account with fields as columns
+        if !record.spec.has_field("Field Name") {
+            parser.add_account(record.get_value("Account")?)?;
+            check_account_capabilities(record.get_value("Account Capabilities")?)?;
+            parser.base_currency.replace(record.get_value("Base Currency")?.to_owned());
+            return Ok(());
+        }
+
         let name = record.get_value("Field Name")?;
         let value = record.get_value("Field Value")?;
 
-        if name == "Account Capabilities" {
-            match value {
-                "Cash" => {},
-                "Margin" => {
-                    if *parser.warn_on_margin_account {
-                        // https://github.com/KonishchevDmitry/investments/issues/8
-                        let url = "http://bit.ly/investments-margin-accounts";
-                        warn!(concat!(
-                            "The program is not expected to work properly with margin accounts ",
-                            "(see {}), so be critical to its calculation results."
-                        ), url);
-                        *parser.warn_on_margin_account = false;
-                    }
-                },
-                _ => return Err!("Unsupported account type: {}", value),
-            }
+        if name == "Account" {
+            parser.add_account(value)?;
+        } else if name == "Account Capabilities" {
+            check_account_capabilities(value)?;
         } else if name == "Base Currency" {
             parser.base_currency.replace(value.to_owned());
         }
@@ -55,6 +49,13 @@ impl RecordParser for AccountInformationParser {
     }
 }
 
+fn check_account_capabilities(value: &str) -> EmptyResult {
+    match value {
+        "Cash" | "Margin" => Ok(()),
+        _ => Err!("Unsupported account type: {}", value),
+    }
+}
+
 pub struct NavParser {}
 
 impl RecordParser for NavParser {
@@ -74,13 +75,11 @@ impl RecordParser for NavParser {
             // These numbers are informal only and don't decrease `Stock` value.
             "Cash Collateral" | "Securities Lent" => {},
 
-            "Stock" => {
-                if !record.parse_amount("Current Short", DecimalRestrictions::No)?.is_zero() {
-                    return Err!("Short positions aren't supported")
-                }
-
-                let amount = record.parse_amount(
-                    "Current Total", DecimalRestrictions::PositiveOrZero)?;
+            // Options value is negative for short positions. Futures value is always zero, because
+            // they are settled via variation margin.
+            "Stock" | "Options" | "Futures" => {
+                // Negative when short positions value exceeds long positions value
+                let amount = record.parse_amount("Current Total", DecimalRestrictions::No)?;
 
                 parser.statement.assets.other.as_mut().unwrap().amount += amount;
             },
@@ -1,13 +1,18 @@
 use std::ops::Deref;
 
+use crate::broker_statement::derivatives::{DerivativeClosing, DerivativeClosingType, DerivativeTrade};
 use crate::broker_statement::trades::{ForexTrade, StockBuy, StockSell};
-use crate::core::EmptyResult;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
 use crate::time::DateTime;
 use crate::util::DecimalRestrictions;
 
 use super::StatementParser;
 use super::common::{Record, RecordParser, parse_symbol};
 
+pub const OPTIONS_ASSET_CATEGORY: &str = "Equity and Index Options";
+pub const FUTURES_ASSET_CATEGORY: &str = "Futures";
+
 pub struct TradesParser {}
 
 impl RecordParser for TradesParser {
@@ -40,6 +45,8 @@ impl RecordParser for TradesParser {
         match asset_category {
             "Forex" => parse_forex_record(parser, record, symbol, conclusion_time),
             "Stocks" => parse_stock_record(parser, record, symbol, conclusion_time),
+            OPTIONS_ASSET_CATEGORY | FUTURES_ASSET_CATEGORY => parse_derivative_record(
+                parser, record, asset_category, symbol, conclusion_time),
             _ => return Err!("Unsupported asset category: {}", asset_category)
         }
     }
@@ -121,4 +128,51 @@ fn parse_stock_record(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+fn parse_derivative_record(
+    parser: &mut StatementParser, record: &Record, asset_category: &str, symbol: &str,
+    conclusion_time: DateTime,
+) -> EmptyResult {
+    let currency = record.get_value("Currency")?;
+    let quantity = record.parse_quantity("Quantity", DecimalRestrictions::NonZero)?;
+
+    if let Some(type_) = parse_closing_type(record.get_value("Code")?)? {
+        parser.statement.derivative_closings.push(DerivativeClosing::new(
+            conclusion_time.date(), symbol, type_, quantity, currency));
+        return Ok(());
+    }
+
+    let price = record.parse_cash("T. Price", currency, DecimalRestrictions::PositiveOrZero)?;
+    let commission = -record.parse_cash("Comm/Fee", currency, DecimalRestrictions::NegativeOrZero)?;
+
+    // Futures are settled via variation margin, so the trade itself doesn't produce any cash flow
+    let volume = if asset_category == FUTURES_ASSET_CATEGORY {
+        Cash::zero(currency)
+    } else {
+        record.parse_cash("Proceeds", currency, DecimalRestrictions::No)?
+    };
+
+    parser.statement.derivative_trades.push(DerivativeTrade::new(
+        symbol, conclusion_time.into(), quantity, price, volume, commission));
+
+    Ok(())
+}
+
+pub fn parse_closing_type(codes: &str) -> GenericResult<Option<DerivativeClosingType>> {
+    let mut closing_type = None;
+
+    for code in codes.split(';').map(str::trim) {
+        let type_ = match code {
+            "A" => DerivativeClosingType::Assignment,
+            "Ex" => DerivativeClosingType::Exercise,
+            "Ep" => DerivativeClosingType::Expiration,
+            _ => continue,
+        };
+
+        if closing_type.replace(type_).is_some() {
+            return Err!("Got an unexpected trade code: {:?}", codes);
+        }
+    }
+
+    Ok(closing_type)
+}
@@ -0,0 +1,339 @@
+// Detects broker, format and account of statement files by their content to be able to point the
+// user to a misplaced statement instead of failing with a confusing parse error.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use regex::Regex;
+use regex::bytes::Regex as BytesRegex;
+use serde::Deserialize;
+
+use crate::brokers::Broker;
+use crate::core::GenericResult;
+use crate::taxes::TaxRemapping;
+use crate::time::Period;
+use crate::xls::{self, Cell};
+
+use super::files::StatementFiles;
+use super::firstrade;
+use super::freedom;
+use super::ib;
+use super::reader::{self, ReadingStrictness};
+use super::tinkoff::ForeignIncomeStatementReader;
+use super::vtb;
+
+pub struct StatementIdentity {
+    pub broker: Broker,
+    pub format: &'static str,
+    // Consolidated statements may contain several accounts
+    pub accounts: Vec<String>,
+}
+
+impl StatementIdentity {
+    fn new(broker: Broker, format: &'static str, account: Option<String>) -> StatementIdentity {
+        StatementIdentity::new_multi_account(broker, format, account.into_iter().collect())
+    }
+
+    fn new_multi_account(broker: Broker, format: &'static str, accounts: Vec<String>) -> StatementIdentity {
+        StatementIdentity {broker, format, accounts}
+    }
+}
+
+// Returns None if the file doesn't look like a statement of any supported broker
+pub fn identify_statement(path: &str) -> GenericResult<Option<StatementIdentity>> {
+    Ok(if path.ends_with(".csv") {
+        identify_csv(path)?
+    } else if path.ends_with(".xml") {
+        identify_xml(path)?
+    } else if path.ends_with(".ofx") {
+        identify_ofx(path)?
+    } else if path.ends_with(".xls") || path.ends_with(".xlsx") {
+        identify_spreadsheet(path)?
+    } else if path.ends_with(".html") {
+        identify_html(path)?
+    } else if path.ends_with(".yaml") || path.ends_with(".yml") {
+        Some(StatementIdentity::new(Broker::Ledger, "Ledger", None))
+    } else if path.ends_with(".json") {
+        identify_json(path)?
+    } else {
+        None
+    })
+}
+
+// Reads the statement with the broker's reader. Returns None if the reader doesn't accept the file.
+pub fn read_statement_period(broker: Broker, account: Option<&str>, path: &str) -> GenericResult<Option<Period>> {
+    let mut tax_remapping = Some(TaxRemapping::new());
+    let mut statement_reader = reader::new_reader(
+        broker, account, &mut tax_remapping, ReadingStrictness::empty())?;
+
+    if !statement_reader.check(path)? {
+        return Ok(None);
+    }
+
+    let statement = statement_reader.read(path, false)?;
+    Ok(Some(statement.get_period()?))
+}
+
+// Warns if the portfolio's statements directory contains statements of another broker or several
+// accounts. Statements of other accounts are expected if the portfolio has account filter.
+pub fn check_statement_files(broker: Broker, account: Option<&str>, path: &str, files: &StatementFiles) {
+    let mut accounts: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+
+    for file in files.iter() {
+        let identity = match identify_statement(&file.path) {
+            Ok(Some(identity)) => identity,
+            Ok(None) => continue,
+            Err(e) => {
+                debug!("Unable to identify {} broker statement: {}.", file.description(), e);
+                continue;
+            },
+        };
+
+        if identity.broker != broker {
+            warn!(
+                "{} looks like {} {}, but the portfolio is configured for {}.",
+                file.description(), identity.broker.brief_name(), identity.format.to_lowercase(),
+                broker.brief_name());
+            continue;
+        }
+
+        for account in identity.accounts {
+            accounts.entry(account).or_default().push(&file.name);
+        }
+    }
+
+    if account.is_none() && accounts.len() > 1 {
+        warn!(
+            "{:?} contains {} statements of several accounts: {}.", path, broker.brief_name(),
+            accounts.iter().map(|(account, files)| format!("{} ({})", account, files.join(", ")))
+                .collect::<Vec<_>>().join("; "));
+    }
+}
+
+fn identify_csv(path: &str) -> GenericResult<Option<StatementIdentity>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(BufReader::new(fs::File::open(path)?));
+
+    let mut records = reader.records();
+
+    let header = match records.next() {
+        Some(record) => record?,
+        None => return Ok(None),
+    };
+
+    let get = |record: &csv::StringRecord, index: usize| -> String {
+        record.get(index).unwrap_or_default().trim_start_matches('\u{feff}').to_owned()
+    };
+
+    match get(&header, 0).as_str() {
+        "ClientAccountID" => {
+            let mut accounts = Vec::new();
+
+            for record in records {
+                let account = get(&record?, 0);
+                if !account.is_empty() && !accounts.contains(&account) {
+                    accounts.push(account);
+                }
+            }
+
+            return Ok(Some(StatementIdentity::new_multi_account(
+                Broker::InteractiveBrokers, "Trade confirmation report", accounts)));
+        },
+        "Statement" if get(&header, 1) == "Header" => {},
+        _ => {
+            let headers: Vec<String> = (0..header.len()).map(|index| get(&header, index)).collect();
+            if firstrade::is_account_history(&headers.iter().map(String::as_str).collect::<Vec<_>>()) {
+                return Ok(Some(StatementIdentity::new(Broker::Firstrade, "Account history", None)));
+            }
+            return Ok(None);
+        },
+    }
+
+    let accounts = ib::read_accounts(path)?;
+    let format = if accounts.len() > 1 {
+        "Consolidated activity statement"
+    } else {
+        "Activity statement"
+    };
+
+    Ok(Some(StatementIdentity::new_multi_account(Broker::InteractiveBrokers, format, accounts)))
+}
+
+fn identify_xml(path: &str) -> GenericResult<Option<StatementIdentity>> {
+    lazy_static! {
+        static ref IB_REGEX: BytesRegex = BytesRegex::new(r"<FlexQueryResponse[\s>]").unwrap();
+        static ref IB_ACCOUNT_REGEX: BytesRegex = BytesRegex::new(
+            r#"<FlexStatement\s[^>]*\baccountId="([^"]+)""#).unwrap();
+        static ref OPEN_REGEX: BytesRegex = BytesRegex::new(
+            r#"<\?xml-stylesheet [^>]*href="https://account\.open-broker\.ru/common/report/broker_report_"#).unwrap();
+        static ref OPEN_ACCOUNT_REGEX: BytesRegex = BytesRegex::new(r#"\bclient_code="([^"]+)""#).unwrap();
+    }
+
+    let data = fs::read(path)?;
+    let account = |regex: &BytesRegex| {
+        regex.captures(&data).map(|captures| String::from_utf8_lossy(&captures[1]).into_owned())
+    };
+
+    Ok(if IB_REGEX.is_match(&data) {
+        let accounts = IB_ACCOUNT_REGEX.captures_iter(&data)
+            .map(|captures| String::from_utf8_lossy(&captures[1]).into_owned())
+            .collect();
+        Some(StatementIdentity::new_multi_account(Broker::InteractiveBrokers, "Flex Query statement", accounts))
+    } else if OPEN_REGEX.is_match(&data) {
+        Some(StatementIdentity::new(Broker::Open, "Broker report", account(&OPEN_ACCOUNT_REGEX)))
+    } else {
+        None
+    })
+}
+
+fn identify_json(path: &str) -> GenericResult<Option<StatementIdentity>> {
+    #[derive(Deserialize)]
+    struct ReportInfo {
+        #[serde(rename = "plainAccountInfoData")]
+        freedom_account_info: Option<freedom::AccountInfo>,
+    }
+
+    let info: ReportInfo = serde_json::from_slice(&fs::read(path)?)?;
+
+    Ok(Some(match info.freedom_account_info {
+        Some(account_info) => StatementIdentity::new(
+            Broker::FreedomFinance, "Broker report", Some(account_info.client_code)),
+        None => StatementIdentity::new(Broker::Ledger, "Normalized JSON statement", None),
+    }))
+}
+
+fn identify_ofx(path: &str) -> GenericResult<Option<StatementIdentity>> {
+    lazy_static! {
+        static ref ACCOUNT_REGEX: Regex = Regex::new(r"<ACCTID>([^<\s]+)").unwrap();
+    }
+
+    let mut reader = BufReader::new(fs::File::open(path)?);
+
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    if !header.trim_start_matches('\u{feff}').starts_with("OFXHEADER:") {
+        return Ok(None);
+    }
+
+    let data = fs::read_to_string(path)?;
+    let account = ACCOUNT_REGEX.captures(&data).map(|captures| captures[1].to_owned());
+
+    Ok(Some(StatementIdentity::new(Broker::Firstrade, "OFX statement", account)))
+}
+
+fn identify_spreadsheet(path: &str) -> GenericResult<Option<StatementIdentity>> {
+    let sheet_names = xls::sheet_names(path)?;
+    let has_sheet = |name: &str| sheet_names.iter().any(|other| other == name);
+
+    let (broker, format, sheet_name) = if has_sheet("broker_rep") {
+        (Broker::Tinkoff, "Broker statement", "broker_rep")
+    } else if ForeignIncomeStatementReader::is_statement(path)? {
+        return Ok(Some(StatementIdentity::new(Broker::Tinkoff, "Foreign income statement", None)));
+    } else if has_sheet("TDSheet") {
+        (Broker::Bcs, "Broker statement", "TDSheet")
+    } else if has_sheet(vtb::StatementSheetParser::SHEET_NAME) {
+        (Broker::Vtb, "Broker statement", vtb::StatementSheetParser::SHEET_NAME)
+    } else {
+        return Ok(None);
+    };
+
+    let account = match xls::open_sheet(path, sheet_name)? {
+        Some(sheet) => find_spreadsheet_account(sheet.rows().take(30)),
+        None => None,
+    };
+
+    Ok(Some(StatementIdentity::new(broker, format, account)))
+}
+
+fn identify_html(path: &str) -> GenericResult<Option<StatementIdentity>> {
+    let sheet = xls::read_html(path)?;
+
+    let is_sber = sheet.rows().take(10).any(|row| matches!(
+        row.first(), Some(Cell::String(value)) if value.starts_with("Отчет брокера")));
+
+    if !is_sber {
+        return Ok(None);
+    }
+
+    let account = find_spreadsheet_account(sheet.rows().take(30));
+    Ok(Some(StatementIdentity::new(Broker::Sber, "Broker statement", account)))
+}
+
+// The account is specified in the statement header either in the label cell or in the next one
+fn find_spreadsheet_account<'a>(rows: impl Iterator<Item=&'a [Cell]>) -> Option<String> {
+    lazy_static! {
+        static ref LABEL_REGEX: Regex = Regex::new(
+            r"(?i)^(?:генеральное соглашение|номер договора|договор)\s*(?::|№)\s*(.*)$").unwrap();
+    }
+
+    for row in rows {
+        let mut cells = row.iter().filter_map(|cell| match cell {
+            Cell::String(value) => Some(value.trim().to_owned()),
+            Cell::Int(value) => Some(value.to_string()),
+            _ => None,
+        }).filter(|value| !value.is_empty());
+
+        while let Some(value) = cells.next() {
+            let captures = match LABEL_REGEX.captures(&value) {
+                Some(captures) => captures,
+                None => continue,
+            };
+
+            let account = captures[1].trim();
+            if !account.is_empty() {
+                return Some(account.to_owned());
+            }
+
+            return cells.next();
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(path, broker, format, account,
+        case("testdata/interactive-brokers/flex/statement.xml", Broker::InteractiveBrokers, "Flex Query statement", Some("U0000000")),
+        case("testdata/ledger/2020.yaml", Broker::Ledger, "Ledger", None),
+        case("testdata/ledger-json/2020.json", Broker::Ledger, "Normalized JSON statement", None),
+        case("testdata/firstrade-history/history-20190101-20191231.csv", Broker::Firstrade, "Account history", None),
+        case("testdata/freedom-finance/broker-report-2021-03.json", Broker::FreedomFinance, "Broker report", Some("FF0123456")),
+        case("testdata/sber/broker-report-2021-03.html", Broker::Sber, "Broker statement", Some("4000AB")),
+        case("testdata/vtb/broker-report-2021-03.xlsx", Broker::Vtb, "Broker statement", Some("1A2B3C")),
+    )]
+    fn identification(path: &str, broker: Broker, format: &str, account: Option<&str>) {
+        let identity = identify_statement(path).unwrap().unwrap();
+        assert_eq!(identity.broker, broker);
+        assert_eq!(identity.format, format);
+        assert_eq!(identity.accounts.first().map(String::as_str), account);
+    }
+
+    #[test]
+    fn period() {
+        let period = read_statement_period(Broker::Ledger, None, "testdata/ledger/2020.yaml").unwrap().unwrap();
+        assert_eq!(period, Period::new(date!(2020, 6, 1), date!(2020, 12, 31)).unwrap());
+
+        assert_eq!(read_statement_period(Broker::Firstrade, None, "testdata/ledger/2020.yaml").unwrap(), None);
+    }
+
+    #[test]
+    fn spreadsheet_account() {
+        let rows = vec![
+            vec![Cell::String(s!("Отчет брокера")), Cell::Empty],
+            vec![Cell::String(s!("Номер договора:")), Cell::Empty, Cell::String(s!("1234567"))],
+        ];
+        assert_eq!(find_spreadsheet_account(rows.iter().map(Vec::as_slice)), Some(s!("1234567")));
+
+        let rows = vec![vec![Cell::String(s!("Генеральное соглашение: 123-456"))]];
+        assert_eq!(find_spreadsheet_account(rows.iter().map(Vec::as_slice)), Some(s!("123-456")));
+    }
+}
@@ -5,7 +5,9 @@ use crate::localities::Country;
 use crate::taxes::IncomeType;
 use crate::time::Date;
 use chrono::Datelike;
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize)]
 pub struct IdleCashInterest {
     pub date: Date,
     pub amount: Cash, // May be negative
@@ -0,0 +1,392 @@
+use serde::Deserialize;
+
+use crate::broker_statement::{ForexTrade, StockBuy, StockSell, IdleCashInterest, Fee, FeeType};
+use crate::broker_statement::corporate_actions::{CorporateAction, CorporateActionType, StockSplitRatio};
+use crate::broker_statement::partial::PartialBrokerStatement;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, CashAssets, MultiCurrencyCashAccount};
+use crate::exchanges::Exchange;
+use crate::instruments::{InstrumentId, parse_isin};
+use crate::time::{Date, DateOptTime, Period, Time};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+// Must be incremented on any backward incompatible change of the format (see docs/brokers.md#json)
+const FORMAT_VERSION: u32 = 1;
+
+// Normalized broker statement in JSON format which is intended to be produced by third-party
+// converters for unsupported brokers. See docs/brokers.md#json for the format description.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonStatement {
+    version: u32,
+
+    period: PeriodInfo,
+    #[serde(default)]
+    starting_assets: bool,
+
+    #[serde(default)]
+    instruments: Vec<InstrumentInfo>,
+
+    #[serde(default)]
+    deposits_and_withdrawals: Vec<CashFlowInfo>,
+    #[serde(default)]
+    fees: Vec<FeeInfo>,
+    #[serde(default)]
+    idle_cash_interest: Vec<CashFlowInfo>,
+
+    #[serde(default)]
+    forex_trades: Vec<ForexTradeInfo>,
+    #[serde(default)]
+    trades: Vec<TradeInfo>,
+    #[serde(default)]
+    dividends: Vec<DividendInfo>,
+    #[serde(default)]
+    corporate_actions: Vec<CorporateActionInfo>,
+
+    cash_assets: Vec<AmountInfo>,
+    #[serde(default)]
+    open_positions: Vec<PositionInfo>,
+}
+
+impl JsonStatement {
+    pub fn read(data: &[u8]) -> GenericResult<JsonStatement> {
+        // Check the version first to not get confusing errors on unknown fields of other versions
+        #[derive(Deserialize)]
+        struct VersionInfo {
+            version: u32,
+        }
+
+        let VersionInfo {version} = serde_json::from_slice(data)?;
+        if version != FORMAT_VERSION {
+            return Err!("Unsupported statement format version: {} (expected {})", version, FORMAT_VERSION);
+        }
+
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    pub fn parse(self) -> GenericResult<PartialBrokerStatement> {
+        assert_eq!(self.version, FORMAT_VERSION);
+        let mut statement = PartialBrokerStatement::new(&[Exchange::Us], false);
+
+        statement.set_period(Period::new(self.period.first_date, self.period.last_date)?)?;
+        statement.set_has_starting_assets(self.starting_assets)?;
+
+        for instrument in self.instruments {
+            instrument.parse(&mut statement)?;
+        }
+
+        for operation in self.deposits_and_withdrawals {
+            let amount = operation.amount.parse("deposit or withdrawal amount", DecimalRestrictions::NonZero)?;
+            statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(operation.date, amount));
+        }
+
+        for fee in self.fees {
+            let amount = fee.amount.parse("fee amount", DecimalRestrictions::NonZero)?;
+            statement.fees.push(Fee::new_with_type(fee.date, fee.type_.into(), amount, fee.description));
+        }
+
+        for interest in self.idle_cash_interest {
+            let amount = interest.amount.parse("interest amount", DecimalRestrictions::NonZero)?;
+            statement.idle_cash_interest.push(IdleCashInterest::new(interest.date, amount));
+        }
+
+        for trade in self.forex_trades {
+            trade.parse(&mut statement)?;
+        }
+
+        for trade in self.trades {
+            trade.parse(&mut statement)?;
+        }
+
+        for dividend in self.dividends {
+            dividend.parse(&mut statement)?;
+        }
+
+        for action in self.corporate_actions {
+            statement.corporate_actions.push(action.parse());
+        }
+
+        let mut cash_assets = MultiCurrencyCashAccount::new();
+        for assets in self.cash_assets {
+            if cash_assets.has_assets(&assets.currency) {
+                return Err!("Got duplicated {} cash assets", assets.currency);
+            }
+            cash_assets.deposit(assets.parse("cash assets", DecimalRestrictions::No)?);
+        }
+        statement.assets.cash.replace(cash_assets);
+
+        for position in self.open_positions {
+            statement.add_open_position(&position.symbol, position.quantity)?;
+        }
+
+        statement.validate()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PeriodInfo {
+    first_date: Date,
+    last_date: Date,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AmountInfo {
+    currency: String,
+    amount: Decimal,
+}
+
+impl AmountInfo {
+    fn parse(&self, name: &str, restrictions: DecimalRestrictions) -> GenericResult<Cash> {
+        util::validate_named_cash(name, &self.currency, self.amount, restrictions)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstrumentInfo {
+    symbol: String,
+    name: Option<String>,
+    #[serde(default)]
+    isin: Vec<String>,
+}
+
+impl InstrumentInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let instrument = statement.instrument_info.add(&self.symbol)?;
+
+        if let Some(name) = self.name {
+            instrument.set_name(&name);
+        }
+
+        for isin in self.isin {
+            instrument.add_isin(parse_isin(&isin)?);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CashFlowInfo {
+    date: Date,
+    amount: AmountInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeeInfo {
+    date: Date,
+    #[serde(rename = "type", default)]
+    type_: FeeTypeInfo,
+    amount: AmountInfo, // Positive for fees and negative for refunds
+    description: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum FeeTypeInfo {
+    Commission,
+    MarginInterest,
+    BorrowFee,
+}
+
+impl Default for FeeTypeInfo {
+    fn default() -> FeeTypeInfo {
+        FeeTypeInfo::Commission
+    }
+}
+
+impl From<FeeTypeInfo> for FeeType {
+    fn from(type_: FeeTypeInfo) -> FeeType {
+        match type_ {
+            FeeTypeInfo::Commission => FeeType::Commission,
+            FeeTypeInfo::MarginInterest => FeeType::MarginInterest,
+            FeeTypeInfo::BorrowFee => FeeType::BorrowFee,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ForexTradeInfo {
+    conclusion_date: Date,
+    conclusion_time: Option<Time>,
+    from: AmountInfo,
+    to: AmountInfo,
+    commission: Option<AmountInfo>,
+}
+
+impl ForexTradeInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let from = self.from.parse("forex trade source amount", DecimalRestrictions::StrictlyPositive)?;
+        let to = self.to.parse("forex trade target amount", DecimalRestrictions::StrictlyPositive)?;
+
+        let commission = match self.commission {
+            Some(commission) => commission.parse("commission", DecimalRestrictions::PositiveOrZero)?,
+            None => Cash::zero(from.currency),
+        };
+
+        let time = DateOptTime {date: self.conclusion_date, time: self.conclusion_time};
+        statement.forex_trades.push(ForexTrade::new(time, from, to, commission));
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TradeInfo {
+    #[serde(rename = "type")]
+    type_: TradeTypeInfo,
+    symbol: String,
+    quantity: Decimal,
+
+    price: AmountInfo,
+    volume: Option<AmountInfo>, // Calculated from price and quantity by default
+    commission: Option<AmountInfo>,
+
+    conclusion_date: Date,
+    conclusion_time: Option<Time>,
+    execution_date: Option<Date>, // Defaults to conclusion date
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum TradeTypeInfo {
+    Buy,
+    Sell,
+}
+
+impl TradeInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let quantity = util::validate_named_decimal(
+            "trade quantity", self.quantity, DecimalRestrictions::StrictlyPositive)?.normalize();
+
+        let price = self.price.parse("price", DecimalRestrictions::StrictlyPositive)?;
+        let price = Cash::new(price.currency, price.amount.normalize());
+
+        let volume = match self.volume {
+            Some(volume) => volume.parse("trade volume", DecimalRestrictions::StrictlyPositive)?,
+            None => (price * quantity).round(),
+        };
+        if volume.currency != price.currency {
+            return Err!("Got {} trade with different price and volume currency", self.symbol);
+        }
+
+        let commission = match self.commission {
+            Some(commission) => commission.parse("commission", DecimalRestrictions::PositiveOrZero)?,
+            None => Cash::zero(price.currency),
+        };
+
+        let conclusion_time = DateOptTime {date: self.conclusion_date, time: self.conclusion_time};
+        let execution_date = self.execution_date.unwrap_or(self.conclusion_date);
+        if execution_date < self.conclusion_date {
+            return Err!("Got {} trade with execution date before conclusion date", self.symbol);
+        }
+
+        match self.type_ {
+            TradeTypeInfo::Buy => statement.stock_buys.push(StockBuy::new_trade(
+                &self.symbol, quantity, price, volume, commission, conclusion_time, execution_date)),
+
+            TradeTypeInfo::Sell => statement.stock_sells.push(StockSell::new_trade(
+                &self.symbol, quantity, price, volume, commission, conclusion_time, execution_date,
+                false)),
+        };
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DividendInfo {
+    date: Date,
+    issuer: String,
+    amount: AmountInfo, // Gross amount before tax withholding
+    paid_tax: Option<AmountInfo>,
+}
+
+impl DividendInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let issuer = InstrumentId::Symbol(self.issuer.clone());
+
+        let amount = self.amount.parse("dividend amount", DecimalRestrictions::StrictlyPositive)?;
+        statement.dividend_accruals(self.date, issuer.clone(), true).add(self.date, amount);
+
+        if let Some(tax) = self.paid_tax {
+            let tax = tax.parse("dividend tax", DecimalRestrictions::PositiveOrZero)?;
+            if !tax.is_zero() {
+                statement.tax_accruals(self.date, issuer, true).add(self.date, tax);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Please note that deny_unknown_fields isn't supported in combination with flatten
+#[derive(Deserialize)]
+struct CorporateActionInfo {
+    date: Date,
+    time: Option<Time>,
+    report_date: Option<Date>,
+    symbol: String,
+    #[serde(flatten)]
+    action: CorporateActionTypeInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum CorporateActionTypeInfo {
+    StockSplit {ratio: StockSplitRatio},
+    StockDividend {quantity: Decimal, cost_basis_ratio: Option<Decimal>},
+    Rename {new_symbol: String},
+    Spinoff {symbol: String, quantity: Decimal, currency: String, cost_basis_ratio: Option<Decimal>},
+    CashMerger {price: Decimal, currency: String},
+    StockMerger {new_symbol: String, ratio: Decimal},
+    CashInLieu {quantity: Decimal, price: Decimal, currency: String},
+}
+
+impl CorporateActionInfo {
+    fn parse(self) -> CorporateAction {
+        CorporateAction {
+            time: DateOptTime {date: self.date, time: self.time},
+            report_date: self.report_date,
+            symbol: self.symbol,
+            action: match self.action {
+                CorporateActionTypeInfo::StockSplit {ratio} => CorporateActionType::StockSplit {
+                    ratio, from_change: None, to_change: None,
+                },
+                CorporateActionTypeInfo::StockDividend {quantity, cost_basis_ratio} => {
+                    CorporateActionType::StockDividend {quantity, cost_basis_ratio}
+                },
+                CorporateActionTypeInfo::Rename {new_symbol} => CorporateActionType::Rename {
+                    new_symbol,
+                },
+                CorporateActionTypeInfo::Spinoff {symbol, quantity, currency, cost_basis_ratio} => {
+                    CorporateActionType::Spinoff {symbol, quantity, currency, cost_basis_ratio}
+                },
+                CorporateActionTypeInfo::CashMerger {price, currency} => CorporateActionType::CashMerger {
+                    price, currency,
+                },
+                CorporateActionTypeInfo::StockMerger {new_symbol, ratio} => CorporateActionType::StockMerger {
+                    new_symbol, ratio,
+                },
+                CorporateActionTypeInfo::CashInLieu {quantity, price, currency} => {
+                    CorporateActionType::CashInLieu {quantity, price, currency}
+                },
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PositionInfo {
+    symbol: String,
+    quantity: Decimal, // Negative for short positions
+}
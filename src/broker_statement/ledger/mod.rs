@@ -0,0 +1,142 @@
+mod json;
+mod parser;
+
+use std::fs::{self, File};
+
+#[cfg(test)] use indoc::indoc;
+#[cfg(test)] use tempfile::NamedTempFile;
+
+#[cfg(test)] use crate::brokers::Broker;
+#[cfg(test)] use crate::config::Config;
+#[cfg(test)] use crate::localities::Jurisdiction;
+#[cfg(test)] use crate::tax_statement::CountryCode;
+use crate::core::GenericResult;
+#[cfg(test)] use crate::taxes::TaxRemapping;
+
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
+use super::{BrokerStatementReader, PartialBrokerStatement};
+
+use self::json::JsonStatement;
+use self::parser::Ledger;
+
+pub struct StatementReader {
+}
+
+impl StatementReader {
+    pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
+        Ok(Box::new(StatementReader{}))
+    }
+}
+
+impl BrokerStatementReader for StatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        Ok(path.ends_with(".yaml") || path.ends_with(".yml") || path.ends_with(".json"))
+    }
+
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        if path.ends_with(".json") {
+            JsonStatement::read(&fs::read(path)?)?.parse()
+        } else {
+            let ledger: Ledger = serde_yaml::from_reader(File::open(path)?)?;
+            ledger.parse()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_example() {
+        let broker = Broker::Ledger.get_info(&Config::mock(), None).unwrap();
+
+        let statement = BrokerStatement::read(
+            broker, "testdata/ledger", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(),
+            &[], StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
+
+        assert!(!statement.cash_assets.is_empty());
+        assert!(!statement.deposits_and_withdrawals.is_empty());
+
+        assert!(!statement.fees.is_empty());
+        assert!(!statement.idle_cash_interest.is_empty());
+        assert!(statement.tax_agent_withholdings.is_empty());
+
+        assert!(statement.forex_trades.is_empty());
+        assert!(!statement.stock_buys.is_empty());
+        assert!(!statement.stock_sells.is_empty());
+        assert!(!statement.dividends.is_empty());
+
+        assert!(!statement.open_positions.is_empty());
+        assert!(!statement.instrument_info.is_empty());
+    }
+    #[test]
+    fn parse_json_example() {
+        let broker = Broker::Ledger.get_info(&Config::mock(), None).unwrap();
+
+        let statement = BrokerStatement::read(
+            broker, "testdata/ledger-json", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(),
+            &[], StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
+
+        assert!(!statement.cash_assets.is_empty());
+        assert!(!statement.deposits_and_withdrawals.is_empty());
+
+        assert!(!statement.fees.is_empty());
+        assert!(!statement.idle_cash_interest.is_empty());
+        assert!(statement.tax_agent_withholdings.is_empty());
+
+        assert!(!statement.forex_trades.is_empty());
+        assert!(!statement.stock_buys.is_empty());
+        assert!(!statement.stock_sells.is_empty());
+        assert!(!statement.dividends.is_empty());
+        assert!(!statement.corporate_actions.is_empty());
+
+        assert!(!statement.open_positions.is_empty());
+        assert!(!statement.instrument_info.is_empty());
+    }
+
+    #[test]
+    fn json_version() {
+        let error = JsonStatement::read(br#"{"version": 2, "new_field": true}"#).err().unwrap();
+        assert_eq!(error.to_string(), "Unsupported statement format version: 2 (expected 1)");
+    }
+
+    #[test]
+    fn jurisdiction() {
+        let config = Config::load("testdata/configs/ledger/config.yaml").unwrap();
+
+        for (name, jurisdiction, country_code) in [
+            ("usa", Jurisdiction::Usa, 840),
+            ("kazakhstan", Jurisdiction::Other(CountryCode::new("KZ").unwrap()), 398),
+            ("cyprus", Jurisdiction::Other(CountryCode::new("CY").unwrap()), 196),
+            ("russia", Jurisdiction::Russia, 643),
+        ] {
+            let broker = config.get_portfolio(name).unwrap().broker_info(&config).unwrap();
+            assert_eq!(broker.jurisdiction, jurisdiction);
+            assert_eq!(broker.jurisdiction.country_code().to_code(), country_code);
+        }
+
+        let invalid_config = NamedTempFile::new().unwrap();
+        fs::write(invalid_config.path(), indoc!("
+            portfolios:
+              - name: ib
+                broker: interactive-brokers
+                jurisdiction: KZ
+        ")).unwrap();
+
+        let error = Config::load(invalid_config.path().to_str().unwrap()).err().unwrap().to_string();
+        assert!(error.ends_with("Jurisdiction can be specified only for manual ledger portfolios"), "{}", error);
+
+        fs::write(invalid_config.path(), indoc!("
+            portfolios:
+              - name: ledger
+                broker: ledger
+                jurisdiction: kazakhstan
+        ")).unwrap();
+
+        let error = Config::load(invalid_config.path().to_str().unwrap()).err().unwrap().to_string();
+        assert!(error.contains(r#"Invalid jurisdiction: "kazakhstan""#), "{}", error);
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::broker_statement::{StockBuy, StockSell, IdleCashInterest, Fee};
+use crate::broker_statement::partial::PartialBrokerStatement;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, CashAssets, MultiCurrencyCashAccount};
+use crate::exchanges::Exchange;
+use crate::instruments::{InstrumentId, parse_isin};
+use crate::time::{
+    Date, DateOptTime, Period, deserialize_date, deserialize_date_opt_time, deserialize_optional_date};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+// Manually maintained ledger for accounts without a supported broker statement format. See
+// docs/brokers.md#ledger for the format description.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ledger {
+    period: PeriodInfo,
+    #[serde(default)]
+    starting_assets: bool,
+
+    #[serde(default)]
+    instruments: HashMap<String, InstrumentInfo>,
+
+    #[serde(default)]
+    deposits: Vec<CashFlowInfo>,
+    #[serde(default)]
+    withdrawals: Vec<CashFlowInfo>,
+    #[serde(default)]
+    trades: Vec<TradeInfo>,
+    #[serde(default)]
+    dividends: Vec<DividendInfo>,
+    #[serde(default)]
+    fees: Vec<FeeInfo>,
+    #[serde(default)]
+    interest: Vec<CashFlowInfo>,
+
+    closing: ClosingInfo,
+}
+
+impl Ledger {
+    pub fn parse(self) -> GenericResult<PartialBrokerStatement> {
+        let mut statement = PartialBrokerStatement::new(&[Exchange::Us], false);
+
+        statement.set_period(Period::new(self.period.start, self.period.end)?)?;
+        statement.set_has_starting_assets(self.starting_assets)?;
+
+        for (symbol, info) in self.instruments {
+            info.parse(&mut statement, &symbol)?;
+        }
+
+        for deposit in self.deposits {
+            let amount = util::validate_named_decimal(
+                "deposit amount", deposit.amount, DecimalRestrictions::StrictlyPositive)?;
+            statement.deposits_and_withdrawals.push(CashAssets::new(deposit.date, &deposit.currency, amount));
+        }
+
+        for withdrawal in self.withdrawals {
+            let amount = util::validate_named_decimal(
+                "withdrawal amount", withdrawal.amount, DecimalRestrictions::StrictlyPositive)?;
+            statement.deposits_and_withdrawals.push(CashAssets::new(withdrawal.date, &withdrawal.currency, -amount));
+        }
+
+        for trade in self.trades {
+            trade.parse(&mut statement)?;
+        }
+
+        for dividend in self.dividends {
+            dividend.parse(&mut statement)?;
+        }
+
+        for fee in self.fees {
+            let amount = util::validate_named_cash(
+                "fee amount", &fee.currency, fee.amount, DecimalRestrictions::NonZero)?;
+            statement.fees.push(Fee::new(fee.date, amount, fee.description));
+        }
+
+        for interest in self.interest {
+            let amount = util::validate_named_cash(
+                "interest amount", &interest.currency, interest.amount, DecimalRestrictions::NonZero)?;
+            statement.idle_cash_interest.push(IdleCashInterest::new(interest.date, amount));
+        }
+
+        self.closing.parse(&mut statement)?;
+
+        statement.validate()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PeriodInfo {
+    #[serde(deserialize_with = "deserialize_date")]
+    start: Date,
+    #[serde(deserialize_with = "deserialize_date")]
+    end: Date,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstrumentInfo {
+    name: Option<String>,
+    isin: Option<String>,
+}
+
+impl InstrumentInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement, symbol: &str) -> EmptyResult {
+        let instrument = statement.instrument_info.add(symbol)?;
+
+        if let Some(name) = self.name {
+            instrument.set_name(&name);
+        }
+
+        if let Some(isin) = self.isin {
+            instrument.add_isin(parse_isin(&isin)?);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CashFlowInfo {
+    #[serde(deserialize_with = "deserialize_date")]
+    date: Date,
+    amount: Decimal,
+    currency: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TradeInfo {
+    #[serde(deserialize_with = "deserialize_date_opt_time")]
+    date: DateOptTime,
+    #[serde(default, deserialize_with = "deserialize_optional_date")]
+    settle_date: Option<Date>,
+
+    symbol: String,
+    quantity: Decimal, // Positive for buys and negative for sells
+    price: Decimal,
+    volume: Option<Decimal>, // Calculated from price and quantity by default
+    #[serde(default)]
+    commission: Decimal,
+    currency: String,
+}
+
+impl TradeInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let currency = &self.currency;
+        let quantity = util::validate_named_decimal(
+            "trade quantity", self.quantity, DecimalRestrictions::NonZero)?;
+
+        let price = util::validate_named_cash(
+            "price", currency, self.price.normalize(), DecimalRestrictions::StrictlyPositive)?;
+
+        let volume = match self.volume {
+            Some(volume) => util::validate_named_cash(
+                "trade volume", currency, volume, DecimalRestrictions::StrictlyPositive)?,
+            None => (price * quantity.abs()).round(),
+        };
+
+        let commission = util::validate_named_cash(
+            "commission", currency, self.commission, DecimalRestrictions::PositiveOrZero)?;
+
+        let execution_date = self.settle_date.unwrap_or(self.date.date);
+        if execution_date < self.date.date {
+            return Err!("Got {} trade with settle date before trade date", self.symbol);
+        }
+
+        if quantity.is_sign_positive() {
+            statement.stock_buys.push(StockBuy::new_trade(
+                &self.symbol, quantity.normalize(), price, volume, commission,
+                self.date, execution_date));
+        } else {
+            statement.stock_sells.push(StockSell::new_trade(
+                &self.symbol, -quantity.normalize(), price, volume, commission,
+                self.date, execution_date, false));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DividendInfo {
+    #[serde(deserialize_with = "deserialize_date")]
+    date: Date,
+    symbol: String,
+    amount: Decimal, // Gross amount before tax withholding
+    #[serde(default)]
+    tax: Decimal,
+    currency: String,
+}
+
+impl DividendInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let issuer = InstrumentId::Symbol(self.symbol.clone());
+
+        let amount = util::validate_named_cash(
+            "dividend amount", &self.currency, self.amount, DecimalRestrictions::StrictlyPositive)?;
+        statement.dividend_accruals(self.date, issuer.clone(), true).add(self.date, amount);
+
+        let tax = util::validate_named_cash(
+            "dividend tax", &self.currency, self.tax, DecimalRestrictions::PositiveOrZero)?;
+        if !tax.is_zero() {
+            statement.tax_accruals(self.date, issuer, true).add(self.date, tax);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeeInfo {
+    #[serde(deserialize_with = "deserialize_date")]
+    date: Date,
+    amount: Decimal, // Positive for fees and negative for refunds
+    currency: String,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClosingInfo {
+    cash: HashMap<String, Decimal>,
+    #[serde(default)]
+    positions: HashMap<String, Decimal>,
+}
+
+impl ClosingInfo {
+    fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let mut cash_assets = MultiCurrencyCashAccount::new();
+        for (currency, amount) in self.cash {
+            cash_assets.deposit(Cash::new(&currency, amount));
+        }
+        statement.assets.cash.replace(cash_assets);
+
+        for (symbol, quantity) in self.positions {
+            statement.add_open_position(&symbol, quantity)?;
+        }
+
+        Ok(())
+    }
+}
@@ -14,6 +14,16 @@ pub enum StatementsMergingStrategy {
 }
 
 impl StatementsMergingStrategy {
+    pub fn description(self) -> String {
+        match self {
+            StatementsMergingStrategy::ContinuousOnly => s!("only continuous periods are allowed"),
+            StatementsMergingStrategy::SparseOnHolidays(max_days) => format!(
+                "gaps on weekends and up to {} missing business days are allowed", max_days),
+            StatementsMergingStrategy::SparseSingleDaysLastMonth(_) => s!(
+                "gaps between single day statements of the last month are allowed"),
+        }
+    }
+
     pub fn validate(self, first: Period, second: Period, last_date: Date) -> EmptyResult {
         let error = |message| Err!("{}: {}, {}", message, first.format(), second.format());
 
@@ -1,21 +1,32 @@
+mod bonds;
+mod cache;
 mod cash_flows;
 mod corporate_actions;
+mod derivatives;
 mod dividends;
 mod fees;
+mod files;
+mod identification;
 mod interest;
 mod merging;
 mod partial;
 mod payments;
 mod reader;
+mod repo;
 mod taxes;
 mod trades;
+mod transfers;
 mod validators;
 
 mod bcs;
 mod firstrade;
+mod freedom;
 mod ib;
+mod ledger;
 mod open;
+mod sber;
 mod tinkoff;
+mod vtb;
 
 use std::cmp::Ordering;
 use std::collections::{HashMap, BTreeMap, BTreeSet, hash_map::Entry};
@@ -24,12 +35,13 @@ use log::warn;
 
 use crate::brokers::{BrokerInfo, Broker};
 use crate::commissions::CommissionCalc;
+use crate::config::{Config, PortfolioConfig};
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::{Cash, CashAssets, MultiCurrencyCashAccount};
 use crate::currency::converter::CurrencyConverter;
 use crate::exchanges::Exchanges;
 use crate::formatting;
-use crate::instruments::{InstrumentInternalIds, InstrumentInfo};
+use crate::instruments::{InstrumentId, InstrumentInternalIds, InstrumentInfo};
 use crate::localities;
 use crate::quotes::{Quotes, QuoteQuery};
 use crate::taxes::TaxRemapping;
@@ -37,28 +49,42 @@ use crate::time::{self, Date, DateOptTime, Period};
 use crate::types::{Decimal, TradeType};
 use crate::util;
 
-use self::dividends::{DividendAccruals, process_dividend_accruals};
+use self::bonds::process_bond_payments;
+use self::derivatives::{
+    ProcessedDerivativeTrades, process_derivative_closings, process_derivative_deliveries,
+    process_derivative_trades};
+use self::dividends::{DividendId, DividendAccruals, process_dividend_accruals};
 use self::partial::PartialBrokerStatement;
-use self::reader::BrokerStatementReader;
+use self::reader::{BrokerStatementReader, StatementCaching};
+use self::repo::process_repo_trades;
 use self::taxes::{TaxId, TaxAccruals};
 use self::validators::{DateValidator, sort_and_validate_trades};
 
+pub use self::bonds::{
+    BondAmortization, BondIncome, BondIncomeType, calculate_bond_income_taxes};
+pub use self::cache::StatementCache;
 pub use self::cash_flows::{CashFlow, CashFlowType};
 pub use self::corporate_actions::{
     CorporateAction, CorporateActionType, StockSplitController, process_corporate_actions};
+pub use self::derivatives::{DerivativeResult, DerivativeTrade, calculate_derivative_taxes};
 pub use self::dividends::Dividend;
-pub use self::fees::Fee;
+pub use self::fees::{Fee, FeeType};
+pub use self::files::StatementFiles;
+pub use self::identification::{identify_statement, read_statement_period};
 pub use self::interest::IdleCashInterest;
 pub use self::merging::StatementsMergingStrategy;
 pub use self::reader::ReadingStrictness;
+pub use self::repo::{RepoIncome, calculate_repo_income_taxes};
 pub use self::taxes::TaxWithholding;
 pub use self::trades::{
     ForexTrade, StockBuy, StockSource, StockSell, StockSellType, StockSellSource, StockSourceDetails,
-    SellDetails, FifoDetails};
+    SellDetails, FifoDetails, PurchaseCostType};
+pub use self::transfers::{StockTransfer, StockTransfers, validate_stock_transfers};
 
 pub struct BrokerStatement {
     pub broker: BrokerInfo,
     pub period: Period,
+    pub statement_periods: Vec<Period>,
 
     pub cash_assets: MultiCurrencyCashAccount,
     pub historical_assets: BTreeMap<Date, NetAssets>,
@@ -74,6 +100,14 @@ pub struct BrokerStatement {
     pub stock_buys: Vec<StockBuy>,
     pub stock_sells: Vec<StockSell>,
     pub dividends: Vec<Dividend>,
+    pub bond_income: Vec<BondIncome>,
+    pub bond_amortizations: Vec<BondAmortization>,
+    pub repo_income: Vec<RepoIncome>,
+
+    pub derivative_contracts: HashMap<String, derivatives::DerivativeContract>,
+    pub derivative_trades: Vec<DerivativeTrade>,
+    pub derivative_results: Vec<DerivativeResult>,
+    pub derivative_positions: HashMap<String, Decimal>,
 
     corporate_actions: Vec<CorporateAction>,
     pub stock_splits: StockSplitController,
@@ -83,15 +117,30 @@ pub struct BrokerStatement {
 }
 
 impl BrokerStatement {
+    pub fn read_portfolio(
+        config: &Config, portfolio: &PortfolioConfig, strictness: ReadingStrictness,
+    ) -> GenericResult<BrokerStatement> {
+        let broker = portfolio.broker_info(config)?;
+        let cache = StatementCache::new(config)?;
+        let transfers = StockTransfers::load(config, portfolio, &cache, strictness)?;
+
+        BrokerStatement::read(
+            broker, portfolio.statements_path()?, portfolio.account.as_deref(), &portfolio.symbol_remapping,
+            &portfolio.instrument_internal_ids, &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.corporate_actions,
+            transfers, &cache, strictness)
+    }
+
     pub fn read(
-        broker: BrokerInfo, statement_dir_path: &str,
+        broker: BrokerInfo, statement_dir_path: &str, account: Option<&str>,
         symbol_remapping: &HashMap<String, String>, instrument_internal_ids: &InstrumentInternalIds,
         instrument_names: &HashMap<String, String>, tax_remapping: TaxRemapping,
-        corporate_actions: &[CorporateAction], strictness: ReadingStrictness,
+        corporate_actions: &[CorporateAction], transfers: StockTransfers, cache: &StatementCache,
+        strictness: ReadingStrictness,
     ) -> GenericResult<BrokerStatement> {
-        let broker_jurisdiction = broker.type_.jurisdiction();
+        let broker_jurisdiction = broker.jurisdiction;
 
-        let mut statements = reader::read(broker.type_, statement_dir_path, tax_remapping, strictness)?;
+        let mut statements = reader::read(
+            broker.type_, statement_dir_path, account, tax_remapping, cache, strictness)?;
         statements.sort_by_key(|statement| statement.period.unwrap());
 
         let mut last_period = statements.first().unwrap().period.unwrap();
@@ -107,12 +156,18 @@ impl BrokerStatement {
 
         let last_index = statements.len() - 1;
         let mut statement = BrokerStatement::new_empty_from(broker, statements.first().unwrap())?;
+        statement.statement_periods = statements.iter().map(|statement| statement.period.unwrap()).collect();
         statement.instrument_info.set_internal_ids(instrument_internal_ids.clone());
 
         let mut dividend_accruals = HashMap::new();
         let mut tax_accruals = HashMap::new();
+        let mut bond_payments = Vec::new();
+        let mut derivative_closings = Vec::new();
 
         for (index, mut partial) in statements.into_iter().enumerate() {
+            bond_payments.append(&mut partial.bond_payments);
+            derivative_closings.append(&mut partial.derivative_closings);
+
             for (dividend_id, accruals) in partial.dividend_accruals.drain() {
                 dividend_accruals.entry(dividend_id)
                     .and_modify(|existing: &mut DividendAccruals| existing.merge(&accruals))
@@ -129,6 +184,21 @@ impl BrokerStatement {
                 "Failed to merge broker statements: {}", e))?;
         }
 
+        let unmapped_ids: BTreeSet<&str> = dividend_accruals.keys().map(|dividend: &DividendId| &dividend.issuer)
+            .chain(tax_accruals.keys().map(|tax: &TaxId| &tax.issuer))
+            .filter_map(|issuer| match issuer {
+                InstrumentId::InternalId(id) if !instrument_internal_ids.contains(id) => Some(id.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if !unmapped_ids.is_empty() {
+            return Err!(concat!(
+                "Unable to determine stock symbols by their broker-specific internal IDs: {}. ",
+                "Please specify the mapping via `instrument_internal_ids` configuration option"
+            ), unmapped_ids.into_iter().collect::<Vec<_>>().join(", "));
+        }
+
         for (dividend_id, accruals) in dividend_accruals {
             let instrument = statement.instrument_info.get_or_add_by_id(&dividend_id.issuer)?;
             let taxation_type = instrument.get_taxation_type(broker_jurisdiction)?;
@@ -161,18 +231,27 @@ impl BrokerStatement {
             return Err!("Unable to find origin operations for the following taxes:\n{}{}", taxes, hint);
         }
 
+        process_bond_payments(&mut statement, bond_payments)?;
+        process_derivative_closings(&mut statement, derivative_closings)?;
+
         for (symbol, new_symbol) in symbol_remapping.iter() {
             statement.rename_symbol(symbol, new_symbol, None).map_err(|e| format!(
                 "Failed to remap {} to {}: {}", symbol, new_symbol, e))?;
         }
 
         statement.corporate_actions.extend(corporate_actions.iter().cloned());
+        transfers.apply(&mut statement);
 
         for (symbol, name) in instrument_names {
             statement.instrument_info.get_or_add(symbol).set_name(name);
         }
 
-        statement.validate(strictness)?;
+        statement.validate()?;
+        statement.repo_income = process_repo_trades(&statement.cash_flows)?;
+
+        // Option deliveries change cost of the underlying trades, so derivatives must be processed
+        // first
+        statement.process_derivatives()?;
 
         process_corporate_actions(&mut statement)?;
         statement.process_trades(None)?;
@@ -193,6 +272,7 @@ impl BrokerStatement {
 
         Ok(BrokerStatement {
             broker, period,
+            statement_periods: Vec::new(),
 
             cash_assets: MultiCurrencyCashAccount::new(),
             historical_assets: BTreeMap::new(),
@@ -208,6 +288,14 @@ impl BrokerStatement {
             stock_buys: Vec::new(),
             stock_sells: Vec::new(),
             dividends: Vec::new(),
+            bond_income: Vec::new(),
+            bond_amortizations: Vec::new(),
+            repo_income: Vec::new(),
+
+            derivative_contracts: HashMap::new(),
+            derivative_trades: Vec::new(),
+            derivative_results: Vec::new(),
+            derivative_positions: HashMap::new(),
 
             corporate_actions: Vec::new(),
             stock_splits: StockSplitController::default(),
@@ -217,6 +305,10 @@ impl BrokerStatement {
         })
     }
 
+    pub fn corporate_actions(&self) -> &[CorporateAction] {
+        &self.corporate_actions
+    }
+
     pub fn check_date(&self) {
         let days = (time::today() - self.period.last_date()).num_days();
         let months = Decimal::from(days) / dec!(30);
@@ -330,6 +422,59 @@ impl BrokerStatement {
         Ok(())
     }
 
+    // Emulates closing of a short position
+    pub fn emulate_buy(
+        &mut self, symbol: &str, quantity: Decimal, price: Cash,
+        commission_calc: &mut CommissionCalc,
+    ) -> EmptyResult {
+        let conclusion_time = time::today_trade_conclusion_time();
+        let mut execution_date = time::today_trade_execution_date();
+
+        for trade in self.stock_buys.iter().rev() {
+            if trade.execution_date > execution_date {
+                execution_date = trade.execution_date;
+            }
+
+            if trade.symbol == symbol {
+                break
+            }
+        }
+
+        let volume = price * quantity;
+        let commission = commission_calc.add_trade(
+            conclusion_time.date, TradeType::Buy, quantity, price)?;
+
+        let mut stock_buy = StockBuy::new_trade(
+            symbol, quantity, price, volume, commission,
+            conclusion_time, execution_date);
+        stock_buy.emulation = true;
+
+        match self.open_positions.entry(symbol.to_owned()) {
+            Entry::Occupied(mut open_position) if open_position.get().is_sign_negative() => {
+                let position = open_position.get_mut();
+
+                match quantity.cmp(&-*position) {
+                    Ordering::Equal => {
+                        open_position.remove();
+                    },
+                    Ordering::Less => {
+                        *position = (*position + quantity).normalize();
+                    },
+                    Ordering::Greater => {
+                        return Err!("The portfolio has not enough short positions for {}", symbol);
+                    },
+                }
+            },
+            _ => return Err!("The portfolio has no short {} position", symbol),
+        }
+
+        self.cash_assets.withdraw(volume);
+        self.cash_assets.withdraw(commission);
+        self.stock_buys.push(stock_buy);
+
+        Ok(())
+    }
+
     pub fn emulate_commissions(&mut self, commission_calc: CommissionCalc) -> GenericResult<MultiCurrencyCashAccount> {
         let mut total = MultiCurrencyCashAccount::new();
 
@@ -365,7 +510,16 @@ impl BrokerStatement {
             symbol_buys.push(index);
         }
 
-        for stock_sell in &mut self.stock_sells {
+        let mut amortizations = self.bond_amortizations.iter_mut().filter(|amortization| {
+            !amortization.is_processed() && until.map_or(true, |time| amortization.date < time.date)
+        }).peekable();
+
+        let mut sell_index = 0;
+
+        while sell_index < self.stock_sells.len() {
+            let stock_sell = &mut self.stock_sells[sell_index];
+            sell_index += 1;
+
             if let Some(time) = until {
                 if stock_sell.conclusion_time >= time {
                     continue;
@@ -376,19 +530,47 @@ impl BrokerStatement {
                 continue;
             }
 
-            let mut remaining_quantity = stock_sell.quantity;
+            while let Some(amortization) = amortizations.next_if(|amortization| {
+                amortization.date <= stock_sell.conclusion_time.date
+            }) {
+                process_bond_amortization(amortization, &mut self.stock_buys, &unsold_buys)?;
+            }
+
+            let mut remaining_quantity = stock_sell.get_unprocessed();
+            let mut short_quantity = None;
             let mut sources = Vec::new();
 
-            let symbol_buys = unsold_buys.get_mut(&stock_sell.symbol).ok_or_else(|| format!(
-                "Error while processing {} position closing: There are no open positions for it",
-                stock_sell.original_symbol
-            ))?;
+            let symbol_buys = unsold_buys.entry(stock_sell.symbol.clone()).or_default();
 
             while !remaining_quantity.is_zero() {
-                let index = symbol_buys.last().copied().ok_or_else(|| format!(
-                    "Error while processing {} position closing: There are no open positions for it",
-                    stock_sell.original_symbol
-                ))?;
+                // Buys made after the sell can only close a short position
+                let index = match symbol_buys.last().copied() {
+                    Some(index) if stock_sell.is_short() || (
+                        self.stock_buys[index].conclusion_time.date <= stock_sell.conclusion_time.date
+                    ) => index,
+
+                    _ => {
+                        if stock_sell.is_short() {
+                            break;
+                        }
+
+                        if matches!(stock_sell.type_, StockSellType::CorporateAction | StockSellType::Transfer) {
+                            return Err!(
+                                "Error while processing {} position closing: There are no open positions for it",
+                                stock_sell.original_symbol);
+                        }
+
+                        if remaining_quantity == stock_sell.quantity {
+                            stock_sell.open_short();
+                            continue;
+                        }
+
+                        // The sell closes the long position and opens a short one, so split it into
+                        // two trades, because they have different realization dates.
+                        short_quantity.replace(remaining_quantity);
+                        break;
+                    },
+                };
 
                 let stock_buy = &mut self.stock_buys[index];
                 let multiplier = self.stock_splits.get_multiplier(
@@ -409,7 +591,17 @@ impl BrokerStatement {
                 }
             }
 
-            stock_sell.process(sources);
+            if let Some(quantity) = short_quantity {
+                let short_sell = stock_sell.split(quantity);
+                stock_sell.process(sources);
+                self.stock_sells.insert(sell_index, short_sell);
+            } else if !sources.is_empty() {
+                stock_sell.process(sources);
+            }
+        }
+
+        for amortization in amortizations {
+            process_bond_amortization(amortization, &mut self.stock_buys, &unsold_buys)?;
         }
 
         if until.is_none() {
@@ -419,6 +611,46 @@ impl BrokerStatement {
         Ok(())
     }
 
+    fn process_derivatives(&mut self) -> EmptyResult {
+        let ProcessedDerivativeTrades {results, deliveries, open_positions} = process_derivative_trades(
+            &self.derivative_trades, &self.derivative_contracts)?;
+
+        for (symbol, quantity) in &open_positions {
+            let expiration = self.derivative_contracts[symbol].expiration;
+            if expiration < self.period.last_date() {
+                return Err!(
+                    "The broker statement has an open {} {} position which has expired on {}",
+                    quantity, symbol, formatting::format_date(expiration));
+            }
+        }
+
+        let symbols: BTreeSet<&str> = self.derivative_positions.keys().map(String::as_str)
+            .chain(open_positions.keys().map(String::as_str))
+            .collect();
+
+        for &symbol in &symbols {
+            let calculated = open_positions.get(symbol);
+            let actual = self.derivative_positions.get(symbol);
+
+            if calculated != actual {
+                let calculated = calculated.copied().unwrap_or_default();
+                let actual = actual.copied().unwrap_or_default();
+
+                return Err!(concat!(
+                    "Calculated open derivative positions don't match declared ones in the statement: ",
+                    "{}: {} vs {}"
+                ), symbol, calculated, actual);
+            }
+        }
+
+        process_derivative_deliveries(self, deliveries)?;
+
+        self.derivative_results = results;
+        self.derivative_positions = open_positions;
+
+        Ok(())
+    }
+
     fn merge(
         &mut self, statement: PartialBrokerStatement, last_date: Date, first: bool, last: bool,
     ) -> EmptyResult {
@@ -448,8 +680,12 @@ impl BrokerStatement {
         self.stock_buys.extend(statement.stock_buys.into_iter());
         self.stock_sells.extend(statement.stock_sells.into_iter());
 
+        self.derivative_contracts.extend(statement.derivative_contracts.into_iter());
+        self.derivative_trades.extend(statement.derivative_trades.into_iter());
+
         self.corporate_actions.extend(statement.corporate_actions.into_iter());
         self.open_positions = statement.open_positions;
+        self.derivative_positions = statement.derivative_positions;
         self.instrument_info.merge(statement.instrument_info);
 
         Ok(())
@@ -492,6 +728,12 @@ impl BrokerStatement {
             }
 
             self.instrument_info.remap(symbol, new_symbol)?;
+
+            for contract in self.derivative_contracts.values_mut() {
+                if contract.underlying == symbol {
+                    contract.underlying = new_symbol.to_owned();
+                }
+            }
         } else {
             self.stock_splits.rename(symbol, new_symbol)?;
         }
@@ -508,6 +750,14 @@ impl BrokerStatement {
             rename(dividend.date.into(), &mut dividend.issuer, &mut dividend.original_issuer);
         }
 
+        for income in &mut self.bond_income {
+            rename(income.date.into(), &mut income.symbol, &mut income.original_symbol);
+        }
+
+        for amortization in &mut self.bond_amortizations {
+            rename(amortization.date.into(), &mut amortization.symbol, &mut amortization.original_symbol);
+        }
+
         if remapping {
             for cash_flow in &mut self.cash_flows {
                 if let Some(original_symbol) = cash_flow.mut_symbol() {
@@ -525,7 +775,7 @@ impl BrokerStatement {
         Ok(())
     }
 
-    fn validate(&mut self, strictness: ReadingStrictness) -> EmptyResult {
+    fn validate(&mut self) -> EmptyResult {
         let validator = DateValidator::new(self.period);
 
         validator.sort_and_validate(
@@ -535,16 +785,6 @@ impl BrokerStatement {
         self.sort_and_alter_fees(self.period.last_date());
         validator.validate("a fee", &self.fees, |fee| fee.date)?;
 
-        if
-            strictness.contains(ReadingStrictness::REPO_TRADES) &&
-            self.cash_flows.iter().any(|cash_flow| matches!(cash_flow.type_, CashFlowType::Repo{..}))
-        {
-            warn!(concat!(
-                "Broker statement contains repo trades which aren't supported yet. ",
-                "All repo trades will be ignored during the calculations."
-            ));
-        }
-
         self.cash_flows.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
         validator.validate("a cash flow", &self.cash_flows, |cash_flow| cash_flow.date)?;
 
@@ -564,6 +804,15 @@ impl BrokerStatement {
         self.dividends.sort_by(|a, b| (a.date, &a.issuer).cmp(&(b.date, &b.original_issuer)));
         validator.validate("a dividend", &self.dividends, |dividend| dividend.date)?;
 
+        self.bond_income.sort_by(|a, b| (a.date, &a.symbol).cmp(&(b.date, &b.symbol)));
+        validator.validate("a bond income", &self.bond_income, |income| income.date)?;
+
+        validator.sort_and_validate(
+            "a bond amortization", &mut self.bond_amortizations, |amortization| amortization.date)?;
+
+        validator.sort_and_validate(
+            "a derivative trade", &mut self.derivative_trades, |trade| trade.conclusion_time)?;
+
         validator.sort_and_validate(
             "a corporate action", &mut self.corporate_actions, |action| action.time)?;
 
@@ -613,6 +862,22 @@ impl BrokerStatement {
                 .or_insert(quantity);
         }
 
+        for stock_sell in &self.stock_sells {
+            if stock_sell.is_processed() {
+                continue;
+            }
+
+            let multiplier = self.stock_splits.get_multiplier(
+                &stock_sell.symbol, stock_sell.conclusion_time,
+                DateOptTime::new_max_time(self.period.last_date()));
+
+            let quantity = -multiplier * stock_sell.get_unprocessed();
+
+            open_positions.entry(&stock_sell.symbol)
+                .and_modify(|position| *position += quantity)
+                .or_insert(quantity);
+        }
+
         let symbols: BTreeSet<&str> = self.open_positions.keys().map(String::as_str)
             .chain(open_positions.keys().copied())
             .collect();
@@ -636,6 +901,34 @@ impl BrokerStatement {
     }
 }
 
+fn process_bond_amortization(
+    amortization: &mut BondAmortization, stock_buys: &mut [StockBuy],
+    unsold_buys: &HashMap<String, Vec<usize>>,
+) -> EmptyResult {
+    let lots = unsold_buys.get(&amortization.symbol).map(|indexes| {
+        indexes.iter().copied().filter(|&index| {
+            let stock_buy = &stock_buys[index];
+            !stock_buy.is_sold() && stock_buy.conclusion_time.date < amortization.date
+        }).collect::<Vec<_>>()
+    }).unwrap_or_default();
+
+    let quantity: Decimal = lots.iter().map(|&index| stock_buys[index].get_unsold()).sum();
+    if quantity.is_zero() {
+        return Err!(
+            "Error while processing {}: There are no open positions for it",
+            amortization.description());
+    }
+
+    for index in lots {
+        let stock_buy = &mut stock_buys[index];
+        let amount = amortization.amount * stock_buy.get_unsold() / quantity;
+        stock_buy.reduce_cost(amortization.date, amount);
+    }
+
+    amortization.process();
+    Ok(())
+}
+
 pub struct NetAssets {
     pub cash: MultiCurrencyCashAccount,
     pub other: Option<Cash>, // Supported only for some brokers
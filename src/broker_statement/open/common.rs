@@ -7,6 +7,7 @@ use crate::types::Decimal;
 pub enum InstrumentType {
     Stock,
     DepositaryReceipt,
+    Bond,
 }
 
 impl InstrumentType {
@@ -14,6 +15,7 @@ impl InstrumentType {
         Ok(match name {
             "Акции" | "АО" | "ПАИ" => InstrumentType::Stock,
             "ADR" | "GDR" => InstrumentType::DepositaryReceipt,
+            "Облигации" | "ОФЗ" => InstrumentType::Bond,
             _ => return Err!("Unsupported instrument type: {:?}", name),
         })
     }
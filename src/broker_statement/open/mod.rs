@@ -7,7 +7,7 @@ use xml::reader::{EventReader, XmlEvent};
 use crate::core::GenericResult;
 #[cfg(test)] use crate::taxes::TaxRemapping;
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
 use super::{BrokerStatementReader, PartialBrokerStatement};
 
 mod common;
@@ -28,7 +28,7 @@ impl BrokerStatementReader for StatementReader {
         Ok(path.ends_with(".xml"))
     }
 
-    fn read(&mut self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
         let data = std::fs::read(path)?;
         let (encoding_name, report_type) = preprocess_statement(&data)?;
 
@@ -93,8 +93,15 @@ fn preprocess_statement(data: &[u8]) -> GenericResult<(String, String)> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use rstest::rstest;
 
+    use crate::broker_statement::BondIncomeType;
+    use crate::currency::Cash;
+    use crate::currency::converter::CurrencyConverter;
+    use crate::localities;
+
     use super::*;
 
     #[rstest(name => ["main/my", "main/iia", "other/first-iia-a", "other/inactive-with-forex"])]
@@ -124,6 +131,44 @@ mod tests {
         assert!(!statement.dividends.is_empty());
     }
 
+    #[test]
+    fn parse_bonds() {
+        let mut statement = parse("bonds", "bonds");
+
+        let income = statement.bond_income.iter()
+            .map(|income| (income.date, income.symbol.as_str(), income.type_, income.amount))
+            .collect::<Vec<_>>();
+
+        assert_eq!(income, vec![
+            (date!(2021, 1, 13), "SU26215RMFS2", BondIncomeType::AccruedInterest, Cash::new("RUB", dec!(-10))),
+            (date!(2021, 1, 13), "SU46020RMFS2", BondIncomeType::AccruedInterest, Cash::new("RUB", dec!(-60.5))),
+            (date!(2021, 2, 3), "SU46020RMFS2", BondIncomeType::Coupon, Cash::new("RUB", dec!(406.9))),
+            (date!(2021, 2, 17), "SU26215RMFS2", BondIncomeType::Coupon, Cash::new("RUB", dec!(174.5))),
+        ]);
+
+        assert_eq!(statement.bond_amortizations.len(), 1);
+        let amortization = &statement.bond_amortizations[0];
+        assert_eq!(
+            (amortization.date, amortization.symbol.as_str(), amortization.amount),
+            (date!(2021, 8, 11), "SU46020RMFS2", Cash::new("RUB", dec!(2000))));
+
+        assert_eq!(statement.stock_sells.len(), 1);
+        let redemption = &statement.stock_sells[0];
+        assert_eq!(
+            (redemption.execution_date, redemption.symbol.as_str(), redemption.quantity),
+            (date!(2021, 8, 16), "SU26215RMFS2", dec!(5)));
+
+        assert_eq!(statement.open_positions, hashmap!{s!("SU46020RMFS2") => dec!(10)});
+
+        statement.process_trades(None).unwrap();
+        assert!(statement.bond_amortizations[0].is_processed());
+
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &BTreeMap::new());
+        let details = statement.stock_sells[0].calculate(&country, 2021, &[], &CurrencyConverter::mock()).unwrap();
+        assert_eq!(details.profit, Cash::new("RUB", dec!(-2.5)));
+    }
+
     fn parse(namespace: &str, name: &str) -> BrokerStatement {
         let portfolio_name = match (namespace, name) {
             ("main", "my") => s!("open"),
@@ -136,9 +181,10 @@ mod tests {
         let portfolio = config.get_portfolio(&portfolio_name).unwrap();
 
         BrokerStatement::read(
-            broker, &format!("testdata/open-broker/{}", name),
+            broker, &format!("testdata/open-broker/{}", name), None,
             &Default::default(), &portfolio.instrument_internal_ids, &Default::default(),
-            TaxRemapping::new(), &portfolio.corporate_actions, ReadingStrictness::all(),
+            TaxRemapping::new(), &portfolio.corporate_actions, StockTransfers::new(), &StatementCache::new_disabled(),
+            ReadingStrictness::all(),
         ).unwrap()
     }
 }
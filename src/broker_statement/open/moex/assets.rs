@@ -81,7 +81,7 @@ struct Asset {
 impl Asset {
     fn parse(&self, statement: &mut PartialBrokerStatement, securities: &HashMap<String, String>) -> EmptyResult {
         match InstrumentType::parse(&self.type_) {
-            Ok(InstrumentType::Stock | InstrumentType::DepositaryReceipt) => {
+            Ok(InstrumentType::Stock | InstrumentType::DepositaryReceipt | InstrumentType::Bond) => {
                 let symbol = get_symbol(securities, &self.name)?;
 
                 let quantity = util::validate_named_decimal(
@@ -2,6 +2,8 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use serde::Deserialize;
 
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
+use crate::broker_statement::cash_flows::{CashFlow as CashFlowDetails, CashFlowType as CashFlowDetailsType};
 use crate::broker_statement::fees::Fee;
 use crate::broker_statement::open::common::deserialize_date;
 use crate::broker_statement::partial::PartialBrokerStatement;
@@ -77,6 +79,20 @@ impl CashFlow {
                     "tax amount", currency, amount, DecimalRestrictions::StrictlyNegative)?;
                 statement.tax_accruals(date, issuer_id, true).add(date, amount);
             },
+
+            CashFlowType::VariationMargin => {
+                let amount = util::validate_named_cash(
+                    "variation margin", currency, amount, DecimalRestrictions::NonZero)?;
+                statement.cash_flows.push(CashFlowDetails::new(
+                    date.into(), amount, CashFlowDetailsType::VariationMargin));
+            },
+
+            CashFlowType::BondPayment(type_, issuer) => {
+                let issuer_id = InstrumentId::InternalId(issuer);
+                let amount = util::validate_named_cash(
+                    "bond payment amount", currency, amount, DecimalRestrictions::StrictlyPositive)?;
+                statement.bond_payments.push(BondPayment::new(date, issuer_id, type_, amount));
+            },
         };
 
         Ok(())
@@ -92,6 +108,9 @@ enum CashFlowType {
 
     Dividend(String),
     DividendTax(String),
+
+    BondPayment(BondPaymentType, String),
+    VariationMargin,
 }
 
 impl CashFlowType {
@@ -118,6 +137,10 @@ impl CashFlowType {
             }
         }
 
+        if description.starts_with("Вариационная маржа") {
+            return Ok(CashFlowType::VariationMargin);
+        }
+
         for &fee_description in &[
             "Комиссия за ведение учета ЦБ",
             "Ежегодная комиссия за ведение учета ЦБ",
@@ -155,6 +178,24 @@ impl CashFlowType {
             return Ok(CashFlowType::DividendTax(issuer));
         }
 
+        lazy_static! {
+            static ref BOND_PAYMENT_REGEX: Regex = Regex::new(concat!(
+                r"^Выплата дохода клиент [^ ]+ ",
+                r"(?P<type>купон|частичное погашение|погашение номинала) (?P<issuer>[^,]+?)",
+                r"(?:,? налог к удержанию .+)?$",
+            )).unwrap();
+        }
+
+        if let Some(captures) = BOND_PAYMENT_REGEX.captures(&description) {
+            let type_ = match captures.name("type").unwrap().as_str() {
+                "купон" => BondPaymentType::Coupon,
+                "частичное погашение" => BondPaymentType::Amortization,
+                _ => BondPaymentType::Redemption,
+            };
+            let issuer = captures.name("issuer").unwrap().as_str().to_owned();
+            return Ok(CashFlowType::BondPayment(type_, issuer));
+        }
+
         return Err!("Unable to determine cash flow type by its description: {:?}", description);
     }
 }
@@ -189,6 +230,16 @@ mod tests {
         );
     }
 
+    #[rstest(description => [
+        "Вариационная маржа по договору 123456 за 15.03.2021",
+    ])]
+    fn variation_margin_description_parsing(description: &str) {
+        assert_matches!(
+            CashFlowType::parse(description).unwrap(),
+            CashFlowType::VariationMargin
+        );
+    }
+
     #[rstest(description, expected,
         case("Комиссия за ведение учета ЦБ в НКО АО НРД за февраль, 2018 г.",
              "Комиссия за ведение учета ЦБ"),
@@ -239,4 +290,19 @@ mod tests {
             CashFlowType::DividendTax(issuer) if issuer == expected
         );
     }
+
+    #[rstest(description, expected_type, expected_issuer,
+        case("Выплата дохода клиент 123456 купон ОФЗ 26207 налог к удержанию 12.00 рублей",
+             BondPaymentType::Coupon, "ОФЗ 26207"),
+        case("Выплата дохода клиент 123456 частичное погашение РЖД БО-07",
+             BondPaymentType::Amortization, "РЖД БО-07"),
+        case("Выплата дохода клиент 123456 погашение номинала ОФЗ 26207",
+             BondPaymentType::Redemption, "ОФЗ 26207"),
+    )]
+    fn bond_payment_description_parsing(description: &str, expected_type: BondPaymentType, expected_issuer: &str) {
+        assert_matches!(
+            CashFlowType::parse(description).unwrap(),
+            CashFlowType::BondPayment(type_, issuer) if type_ == expected_type && issuer == expected_issuer
+        );
+    }
 }
\ No newline at end of file
@@ -5,8 +5,11 @@ use serde::Deserialize;
 use crate::broker_statement::open::common::InstrumentType;
 use crate::broker_statement::partial::PartialBrokerStatement;
 use crate::core::GenericResult;
+use crate::currency::Cash;
 use crate::exchanges::Exchange;
 use crate::instruments::parse_isin;
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
 
 #[derive(Deserialize)]
 pub struct Securities {
@@ -27,6 +30,10 @@ struct Security {
     symbol: String,
     #[serde(rename = "board_name")]
     exchange: String,
+    #[serde(rename = "nominal")]
+    face_value: Option<Decimal>,
+    #[serde(rename = "nominal_curr")]
+    face_value_currency: Option<String>,
 }
 
 impl Securities {
@@ -34,9 +41,10 @@ impl Securities {
         let mut securities = HashMap::new();
 
         for security in &self.securities {
-            let name = match InstrumentType::parse(&security.type_)? {
+            let instrument_type = InstrumentType::parse(&security.type_)?;
+            let name = match instrument_type {
                 InstrumentType::Stock => parse_issuer_name(&security.issuer),
-                InstrumentType::DepositaryReceipt => parse_security_name(&security.name),
+                InstrumentType::DepositaryReceipt | InstrumentType::Bond => parse_security_name(&security.name),
             };
 
             let exchange = match security.exchange.as_str() {
@@ -52,6 +60,16 @@ impl Securities {
             instrument.set_name(name);
             instrument.add_isin(parse_isin(&security.isin)?);
             instrument.exchanges.add_prioritized(exchange);
+
+            if let InstrumentType::Bond = instrument_type {
+                let (face_value, currency) = match (security.face_value, security.face_value_currency.as_ref()) {
+                    (Some(face_value), Some(currency)) => (face_value, currency),
+                    _ => return Err!("Got {} bond without face value", security.symbol),
+                };
+
+                instrument.set_face_value(Cash::new(currency, util::validate_named_decimal(
+                    "bond face value", face_value, DecimalRestrictions::StrictlyPositive)?));
+            }
         }
 
         Ok(securities)
@@ -3,12 +3,14 @@ use std::collections::HashMap;
 use log::warn;
 use serde::Deserialize;
 
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
 use crate::broker_statement::cash_flows::{CashFlow, CashFlowType};
 use crate::broker_statement::open::common::{deserialize_date, parse_quantity};
 use crate::broker_statement::partial::PartialBrokerStatement;
 use crate::broker_statement::trades::{StockBuy, StockSell};
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::Cash;
+use crate::instruments::InstrumentId;
 use crate::types::{Date, DateTime};
 use crate::types::Decimal;
 use crate::util::{self, DecimalRestrictions};
@@ -63,6 +65,9 @@ struct ConcludedTrade<const REPO: bool> {
     #[serde(alias="volume_currency")]
     volume: Decimal,
 
+    #[serde(rename = "nkd")]
+    accrued_interest: Option<Decimal>,
+
     #[serde(rename = "accounting_currency_code")]
     accounting_currency: String,
 
@@ -85,7 +90,7 @@ impl<const REPO: bool> ConcludedTrade<REPO> {
                  symbol);
         }
 
-        let price = util::validate_named_cash(
+        let mut price = util::validate_named_cash(
             "price", &self.price_currency, self.price,
             DecimalRestrictions::StrictlyPositive)?.normalize();
 
@@ -93,6 +98,15 @@ impl<const REPO: bool> ConcludedTrade<REPO> {
             "trade volume", &self.price_currency, self.volume,
             DecimalRestrictions::StrictlyPositive)?.normalize();
 
+        let accrued_interest = util::validate_named_cash(
+            "accrued interest", &self.accounting_currency, self.accrued_interest.unwrap_or_default(),
+            DecimalRestrictions::PositiveOrZero)?;
+
+        // Bond prices are specified in percents of face value
+        let bond = statement.instrument_info.get(symbol)
+            .map(|instrument| instrument.face_value.is_some())
+            .unwrap_or_default();
+
         let commission = util::validate_named_decimal(
             "commission", self.commission, DecimalRestrictions::PositiveOrZero)?;
 
@@ -118,34 +132,56 @@ impl<const REPO: bool> ConcludedTrade<REPO> {
             (Some(quantity), None) => {
                 let quantity = util::validate_decimal(
                     parse_quantity(quantity), DecimalRestrictions::StrictlyPositive)?;
+
+                if bond {
+                    price = (volume / quantity).normalize();
+                }
                 debug_assert_eq!(volume, price * quantity);
 
                 if REPO {
                     statement.cash_flows.push(CashFlow::new(self.conclusion_time.into(), -volume, CashFlowType::Repo {
                         symbol: symbol.to_owned(),
+                        quantity,
                         commission,
                     }));
                 } else {
                     statement.stock_buys.push(StockBuy::new_trade(
                         symbol, quantity, price, volume, commission,
                         self.conclusion_time.into(), execution_date));
+
+                    if !accrued_interest.is_zero() {
+                        statement.bond_payments.push(BondPayment::new(
+                            self.conclusion_time.date(), InstrumentId::Symbol(symbol.to_owned()),
+                            BondPaymentType::AccruedInterest, -accrued_interest));
+                    }
                 }
             },
 
             (None, Some(quantity)) => {
                 let quantity = util::validate_decimal(
                     parse_quantity(quantity), DecimalRestrictions::StrictlyPositive)?;
+
+                if bond {
+                    price = (volume / quantity).normalize();
+                }
                 debug_assert_eq!(volume, price * quantity);
 
                 if REPO {
                     statement.cash_flows.push(CashFlow::new(self.conclusion_time.into(), volume, CashFlowType::Repo {
                         symbol: symbol.to_owned(),
+                        quantity,
                         commission,
                     }));
                 } else {
                     statement.stock_sells.push(StockSell::new_trade(
                         symbol, quantity, price, volume, commission,
                         self.conclusion_time.into(), execution_date, false));
+
+                    if !accrued_interest.is_zero() {
+                        statement.bond_payments.push(BondPayment::new(
+                            self.conclusion_time.date(), InstrumentId::Symbol(symbol.to_owned()),
+                            BondPaymentType::AccruedInterest, accrued_interest));
+                    }
                 }
             },
 
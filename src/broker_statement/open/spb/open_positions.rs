@@ -61,6 +61,7 @@ impl OpenPositions {
                     instrument.set_name(&security.issuer);
                 }
                 InstrumentType::DepositaryReceipt => {},
+                InstrumentType::Bond => return Err!("Bonds aren't supported for SPB exchange yet"),
             };
         }
 
@@ -3,16 +3,20 @@ use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use std::rc::Rc;
 
+use serde::{Deserialize, Serialize};
+
 use crate::core::{EmptyResult, GenericResult};
 use crate::currency::{Cash, CashAssets, MultiCurrencyCashAccount};
 use crate::exchanges::{Exchange, Exchanges};
 use crate::instruments::{InstrumentId, InstrumentInfo};
 use crate::time::{Date, Period};
 use crate::types::Decimal;
-use crate::util::{DecimalRestrictions, validate_named_decimal};
+use crate::util::{self, DecimalRestrictions, validate_named_decimal};
 
+use super::bonds::BondPayment;
 use super::cash_flows::CashFlow;
-use super::corporate_actions::CorporateAction;
+use super::corporate_actions::{self, CorporateAction};
+use super::derivatives::{DerivativeClosing, DerivativeContract, DerivativeTrade};
 use super::dividends::{DividendId, DividendAccruals};
 use super::fees::Fee;
 use super::interest::IdleCashInterest;
@@ -21,6 +25,7 @@ use super::taxes::{TaxId, TaxAccruals, TaxWithholding};
 
 pub type PartialBrokerStatementRc = Rc<RefCell<PartialBrokerStatement>>;
 
+#[derive(Serialize, Deserialize)]
 pub struct PartialBrokerStatement {
     pub period: Option<Period>,
 
@@ -35,18 +40,28 @@ pub struct PartialBrokerStatement {
     pub forex_trades: Vec<ForexTrade>,
     pub stock_buys: Vec<StockBuy>,
     pub stock_sells: Vec<StockSell>,
+    pub bond_payments: Vec<BondPayment>,
+
+    pub derivative_contracts: HashMap<String, DerivativeContract>,
+    pub derivative_trades: Vec<DerivativeTrade>,
+    pub derivative_closings: Vec<DerivativeClosing>,
 
+    #[serde(with = "util::map_as_pairs")]
     pub dividend_accruals: HashMap<DividendId, DividendAccruals>,
+    #[serde(with = "util::map_as_pairs")]
     pub tax_accruals: HashMap<TaxId, TaxAccruals>,
+    #[serde(with = "corporate_actions::cache_format")]
     pub corporate_actions: Vec<CorporateAction>,
 
     // Please note that some brokers (Firstrade) provide this information only for the last
     // statement (current date).
     pub assets: NetAssets,
     pub open_positions: HashMap<String, Decimal>,
+    pub derivative_positions: HashMap<String, Decimal>,
     pub instrument_info: InstrumentInfo,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct NetAssets {
     pub cash: Option<MultiCurrencyCashAccount>,
     pub other: Option<Cash>, // Supported only for some brokers
@@ -68,6 +83,11 @@ impl PartialBrokerStatement {
             forex_trades: Vec::new(),
             stock_buys: Vec::new(),
             stock_sells: Vec::new(),
+            bond_payments: Vec::new(),
+
+            derivative_contracts: HashMap::new(),
+            derivative_trades: Vec::new(),
+            derivative_closings: Vec::new(),
 
             dividend_accruals: HashMap::new(),
             tax_accruals: HashMap::new(),
@@ -82,6 +102,7 @@ impl PartialBrokerStatement {
                 other: None
             },
             open_positions: HashMap::new(),
+            derivative_positions: HashMap::new(),
             instrument_info: InstrumentInfo::new(),
         }
     }
@@ -106,9 +127,10 @@ impl PartialBrokerStatement {
         get_option("has starting assets", self.has_starting_assets)
     }
 
+    // Negative quantity means a short position
     pub fn add_open_position(&mut self, symbol: &str, quantity: Decimal) -> EmptyResult {
         validate_named_decimal(
-            &format!("{} open position", symbol), quantity, DecimalRestrictions::StrictlyPositive)?;
+            &format!("{} open position", symbol), quantity, DecimalRestrictions::NonZero)?;
 
         match self.open_positions.entry(symbol.to_owned()) {
             Entry::Vacant(entry) => entry.insert(quantity),
@@ -1,9 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 use crate::core::GenericResult;
 use crate::currency::{Cash, CashAssets};
 use crate::time::Date;
 
 /// Calculates result amount from a series of payments and reversals.
-#[derive(Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Payments {
     strict: bool,
     transactions: Vec<CashAssets>,
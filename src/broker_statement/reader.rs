@@ -1,95 +1,270 @@
-use std::fs;
-use std::path::Path;
-
 use bitflags::bitflags;
+use itertools::Itertools;
+use rayon::prelude::*;
 
 use crate::core::{GenericResult, EmptyResult};
 use crate::brokers::Broker;
+use crate::logging::ThreadContext;
 use crate::taxes::TaxRemapping;
 
-use super::{bcs, firstrade, ib, open, tinkoff};
+use super::{bcs, firstrade, freedom, ib, identification, ledger, open, sber, tinkoff, vtb};
 use super::PartialBrokerStatement;
+use super::cache::{StatementCache, StatementKey};
+use super::files::{StatementFile, StatementFiles};
 
 bitflags! {
     pub struct ReadingStrictness: u32 {
         const TRADE_SETTLE_DATE = 1 << 0;
         const CASH_FLOW_DATES   = 1 << 1;
-        const REPO_TRADES       = 1 << 2;
     }
 }
 
-pub trait BrokerStatementReader {
+pub enum StatementCaching {
+    // Statements are parsed independently of each other, so each of them may be taken from the cache
+    Independent(String),
+
+    // The reader carries state from one statement to another, so the statements may be taken from
+    // the cache only all together
+    Sequential(String),
+
+    Disabled,
+}
+
+// Statements are read in parallel, so the readers must be thread-safe
+pub trait BrokerStatementReader: Send + Sync {
     fn check(&mut self, path: &str) -> GenericResult<bool>;
-    fn read(&mut self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement>;
+
+    // Called after all statements are checked. The context must contain all reader settings and all
+    // data collected during the statements check which affect the parsing results.
+    fn caching(&self) -> StatementCaching {
+        StatementCaching::Independent(String::new())
+    }
+
+    // Readers which carry state from one statement to another must read them one by one in order
+    fn is_sequential(&self) -> bool {
+        false
+    }
+
+    fn read(&self, path: &str, is_last: bool) -> GenericResult<PartialBrokerStatement>;
+
+    // Cached statements are returned silently, so the statements aren't cached if the reader has
+    // warned about data inaccuracy to not lose the warning.
+    fn has_warnings(&self) -> bool {
+        false
+    }
+
+    // Called only if at least one statement has been actually read
     #[allow(clippy::boxed_local)]
     fn close(self: Box<Self>) -> EmptyResult { Ok(()) }
 }
 
 pub fn read(
-    broker: Broker, statement_dir_path: &str, tax_remapping: TaxRemapping,
+    broker: Broker, statement_dir_path: &str, account: Option<&str>, tax_remapping: TaxRemapping, cache: &StatementCache,
     strictness: ReadingStrictness,
 ) -> GenericResult<Vec<PartialBrokerStatement>> {
     let mut tax_remapping = Some(tax_remapping);
-    let mut statement_reader = match broker {
+    let mut statement_reader = new_reader(broker, account, &mut tax_remapping, strictness)?;
+
+    let statement_files = StatementFiles::list(statement_dir_path).map_err(|e| format!(
+        "Error while reading {:?}: {}", statement_dir_path, e))?;
+    identification::check_statement_files(broker, account, statement_dir_path, &statement_files);
+
+    let files = check_statement_files(&statement_files, statement_reader.as_mut())
+        .map_err(|e| format!("Error while reading {:?}: {}", statement_dir_path, e))?;
+
+    if files.is_empty() {
+        return Err!("{:?} doesn't contain any broker statement{}", statement_dir_path,
+                    account.map(|account| format!(" of {} account", account)).unwrap_or_default());
+    }
+
+    let (cache_keys, mut statements) = get_cached_statements(
+        broker, &files, statement_reader.as_ref(), cache)?;
+
+    let to_read: Vec<usize> = statements.iter().enumerate()
+        .filter(|(_, statement)| statement.is_none())
+        .map(|(id, _)| id)
+        .collect();
+
+    // The files may be read by other threads of the pool, so pass the logging context to them
+    let logging_context = ThreadContext::current();
+
+    let read_statement = |&id: &usize| -> GenericResult<(usize, PartialBrokerStatement)> {
+        let _logging_context = logging_context.as_deref().map(ThreadContext::new);
+        let file = files[id];
+        let is_last = id == files.len() - 1;
+
+        let statement = statement_reader.read(&file.path, is_last).map_err(|e| format!(
+            "Error while reading {} broker statement: {}", file.description(), e))?;
+
+        Ok((id, statement))
+    };
+
+    let read_statements: Vec<(usize, PartialBrokerStatement)> = if statement_reader.is_sequential() {
+        to_read.iter().map(read_statement).collect::<GenericResult<_>>()?
+    } else {
+        to_read.par_iter().map(read_statement).collect::<GenericResult<_>>()?
+    };
+
+    let read = !read_statements.is_empty();
+    let cacheable = !cache_keys.is_empty() && !statement_reader.has_warnings();
+    let mut to_cache = Vec::new();
+
+    for (id, statement) in read_statements {
+        if cacheable {
+            to_cache.push(id);
+        }
+        statements[id] = Some(statement);
+    }
+
+    if let Some(tax_remapping) = tax_remapping {
+        tax_remapping.ensure_all_mapped().map_err(|e| format!(
+            "{}. Tax remapping is not supported for {} yet", e, broker.brief_name()))?;
+    }
+
+    // If all statements are taken from the cache, the reader's final checks have been passed when
+    // the statements were cached
+    if read {
+        statement_reader.close()?;
+    }
+
+    for id in to_cache {
+        cache.save(&cache_keys[id], statements[id].as_ref().unwrap())?;
+    }
+
+    Ok(statements.into_iter().map(Option::unwrap).collect())
+}
+
+// Tax remapping is taken by the readers which support it. Account filter is validated by the config
+// to be specified only for the brokers which support it.
+pub fn new_reader(
+    broker: Broker, account: Option<&str>, tax_remapping: &mut Option<TaxRemapping>, strictness: ReadingStrictness,
+) -> GenericResult<Box<dyn BrokerStatementReader>> {
+    match broker {
         Broker::Bcs => bcs::StatementReader::new(),
         Broker::Firstrade => firstrade::StatementReader::new(),
+        Broker::FreedomFinance => freedom::StatementReader::new(),
         Broker::InteractiveBrokers => ib::StatementReader::new(
-            tax_remapping.take().unwrap(), strictness),
+            account, tax_remapping.take().unwrap(), strictness),
+        Broker::Ledger => ledger::StatementReader::new(),
         Broker::Open => open::StatementReader::new(),
+        Broker::Sber => sber::StatementReader::new(),
         Broker::Tinkoff => tinkoff::StatementReader::new(),
-    }?;
+        Broker::Vtb => vtb::StatementReader::new(),
+    }
+}
 
-    let mut file_names = preprocess_statement_directory(statement_dir_path, statement_reader.as_mut())
-        .map_err(|e| format!("Error while reading {:?}: {}", statement_dir_path, e))?;
+fn get_cached_statements(
+    broker: Broker, files: &[&StatementFile], statement_reader: &dyn BrokerStatementReader,
+    cache: &StatementCache,
+) -> GenericResult<(Vec<StatementKey>, Vec<Option<PartialBrokerStatement>>)> {
+    let mut keys = Vec::new();
+    let mut statements: Vec<Option<PartialBrokerStatement>> = files.iter().map(|_| None).collect();
 
-    if file_names.is_empty() {
-        return Err!("{:?} doesn't contain any broker statement", statement_dir_path);
+    if !cache.is_enabled() {
+        return Ok((keys, statements));
     }
-    file_names.sort_unstable();
 
-    let mut statements = Vec::new();
+    let (context, sequential) = match statement_reader.caching() {
+        StatementCaching::Independent(context) => (context, false),
+        // The reader's state depends on the whole list of statements
+        StatementCaching::Sequential(context) => (
+            format!("{}:{}", context, files.iter().map(|file| &file.name).join(":")), true),
+        StatementCaching::Disabled => return Ok((keys, statements)),
+    };
 
-    for (id, file_name) in file_names.iter().enumerate() {
-        let is_last = id == file_names.len() - 1;
+    for (id, file) in files.iter().enumerate() {
+        let is_last = id == files.len() - 1;
+        let context = format!(
+            "{}:{}:{}:{}", env!("CARGO_PKG_VERSION"), broker.id(), is_last, context);
 
-        let path = Path::new(statement_dir_path).join(file_name);
-        let path = path.to_str().unwrap();
+        let key = StatementKey::new(file, &context).map_err(|e| format!(
+            "Error while reading {}: {}", file.description(), e))?;
 
-        let statement = statement_reader.read(path, is_last).map_err(|e| format!(
-            "Error while reading {:?} broker statement: {}", path, e))?;
+        statements[id] = cache.get(&key)?;
+        keys.push(key);
+    }
 
-        statements.push(statement);
+    if sequential && statements.iter().any(Option::is_none) {
+        statements.iter_mut().for_each(|statement| *statement = None);
     }
 
-    if let Some(tax_remapping) = tax_remapping {
-        tax_remapping.ensure_all_mapped().map_err(|e| format!(
-            "{}. Tax remapping is not supported for {} yet", e, broker.brief_name()))?;
+    Ok((keys, statements))
+}
+
+fn check_statement_files<'a>(
+    files: &'a StatementFiles, statement_reader: &mut dyn BrokerStatementReader,
+) -> GenericResult<Vec<&'a StatementFile>> {
+    let mut statements = Vec::new();
+
+    for file in files.iter() {
+        let is_statement = statement_reader.check(&file.path).map_err(|e| format!(
+            "Error while reading {}: {}", file.description(), e))?;
+
+        if is_statement {
+            statements.push(file);
+        }
     }
-    statement_reader.close()?;
 
     Ok(statements)
 }
 
-fn preprocess_statement_directory(
-    statement_dir_path: &str, statement_reader: &mut dyn BrokerStatementReader
-) -> GenericResult<Vec<String>> {
-    let mut file_names = Vec::new();
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::Duration;
+    use indoc::formatdoc;
+    use tempfile::TempDir;
+
+    use crate::time::Period;
+    use crate::types::Decimal;
 
-    for entry in fs::read_dir(statement_dir_path)? {
-        let entry = entry?;
+    use super::*;
 
-        let path = entry.path();
-        let path = path.to_str().ok_or_else(|| format!(
-            "Got an invalid path: {:?}", path.to_string_lossy()))?;
+    #[test]
+    fn parallel_reading() {
+        let dir = TempDir::new().unwrap();
+        let mut periods = Vec::new();
+        let mut cash = 0;
 
-        if !statement_reader.check(path)? {
-            continue;
+        for month in 1..=12 {
+            let start = date!(2021, month, 1);
+            let end = if month == 12 {
+                date!(2021, 12, 31)
+            } else {
+                date!(2021, month + 1, 1) - Duration::days(1)
+            };
+
+            cash += month;
+            periods.push(Period::new(start, end).unwrap());
+
+            fs::write(dir.path().join(format!("{:02}.yaml", month)), formatdoc!("
+                period:
+                  start: {start}
+                  end: {end}
+
+                starting_assets: {starting_assets}
+
+                deposits:
+                  - {{date: {start}, amount: {amount}, currency: USD}}
+
+                closing:
+                  cash:
+                    USD: {cash}
+            ",
+                start=start.format("%Y.%m.%d"), end=end.format("%Y.%m.%d"),
+                starting_assets=month != 1, amount=month, cash=cash,
+            )).unwrap();
         }
 
-        let file_name = entry.file_name().into_string().map_err(|file_name| format!(
-            "Got an invalid file name: {:?}", file_name.to_string_lossy()))?;
-        file_names.push(file_name);
-    }
+        let statements = read(
+            Broker::Ledger, dir.path().to_str().unwrap(), None, TaxRemapping::new(),
+            &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
 
-    Ok(file_names)
-}
\ No newline at end of file
+        // Statements are read in parallel, but must be returned in the original order
+        assert_eq!(statements.iter().map(|statement| statement.period.unwrap()).collect::<Vec<_>>(), periods);
+        assert_eq!(statements.iter().map(|statement| {
+            statement.deposits_and_withdrawals.iter().map(|deposit| deposit.cash.amount).collect::<Vec<_>>()
+        }).collect::<Vec<_>>(), (1..=12).map(|month| vec![Decimal::from(month)]).collect::<Vec<_>>());
+    }
+}
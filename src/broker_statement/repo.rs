@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Datelike;
+
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::formatting;
+use crate::localities::Country;
+use crate::taxes::IncomeType;
+use crate::time::{Date, DateOptTime};
+use crate::types::Decimal;
+
+use super::cash_flows::{CashFlow, CashFlowType};
+
+// Brokers use repo trades to borrow securities of their clients: the first leg sells the securities
+// and the second one buys them back at a lower price. The difference between the legs is an income
+// from the repo rate which is taxed the same way as income from trading.
+pub struct RepoIncome {
+    pub open_date: Date,
+    pub date: Date,
+    pub symbol: String,
+    pub quantity: Decimal,
+
+    pub amount: Cash, // Sum of both legs: may be negative for unusual rates
+    pub commission: Cash,
+}
+
+impl RepoIncome {
+    pub fn profit(&self) -> GenericResult<Cash> {
+        Ok(self.amount.sub(self.commission).map_err(|e| format!(
+            "{}: The commission is paid in currency different from the repo currency: {}",
+            self.description(), e))?)
+    }
+
+    pub fn description(&self) -> String {
+        format!("{} repo from {} - {}", self.symbol,
+                formatting::format_date(self.open_date), formatting::format_date(self.date))
+    }
+}
+
+struct RepoLeg {
+    time: DateOptTime,
+    amount: Cash,
+    commission: Cash,
+}
+
+// Matches repo legs by symbol and quantity. Unmatched legs are open repo trades which will be closed
+// in future statements.
+//
+// Statements don't link the legs to each other, so if there are several open repo trades with the
+// same symbol and quantity, the closing leg is matched with the one which has the closest amount:
+// the difference between the legs is caused only by the repo rate, which is small, while the
+// securities price usually changes between the trades. Equal amounts are matched in FIFO order.
+pub fn process_repo_trades(cash_flows: &[CashFlow]) -> GenericResult<Vec<RepoIncome>> {
+    let mut income = Vec::new();
+    let mut open_legs: HashMap<(&str, Decimal), Vec<RepoLeg>> = HashMap::new();
+
+    for cash_flow in cash_flows {
+        let (symbol, quantity, commission) = match cash_flow.type_ {
+            CashFlowType::Repo {ref symbol, quantity, commission} => (symbol, quantity, commission),
+            _ => continue,
+        };
+
+        let legs = open_legs.entry((symbol.as_str(), quantity)).or_default();
+
+        let open_index = legs.iter().enumerate()
+            .filter(|(_, open)| open.amount.is_positive() != cash_flow.amount.is_positive())
+            .min_by_key(|(_, open)| (open.amount.amount + cash_flow.amount.amount).abs())
+            .map(|(index, _)| index);
+
+        match open_index {
+            Some(index) => {
+                let open = legs.remove(index);
+
+                let repo = RepoIncome {
+                    open_date: open.time.date,
+                    date: cash_flow.date.date,
+                    symbol: symbol.clone(),
+                    quantity,
+                    amount: open.amount.add(cash_flow.amount)?,
+                    commission: open.commission.add(commission)?,
+                };
+
+                repo.profit()?;
+                income.push(repo);
+            },
+            None => legs.push(RepoLeg {
+                time: cash_flow.date,
+                amount: cash_flow.amount,
+                commission,
+            }),
+        }
+    }
+
+    Ok(income)
+}
+
+pub struct RepoIncomeTax {
+    pub income: Cash,
+    pub tax_to_pay: Cash,
+}
+
+pub fn calculate_repo_income_taxes<'a, I>(
+    country: &Country, income: I, converter: &CurrencyConverter,
+) -> GenericResult<BTreeMap<i32, RepoIncomeTax>>
+    where I: IntoIterator<Item=&'a RepoIncome>
+{
+    let mut years = BTreeMap::new();
+
+    for repo in income {
+        let profit = converter.convert_to_cash_rounding(repo.date, repo.profit()?, country.currency)?;
+        *years.entry(repo.date.year()).or_insert_with(|| Cash::zero(country.currency)) += profit;
+    }
+
+    Ok(years.into_iter().map(|(year, income)| {
+        let tax_to_pay = country.tax_to_pay(IncomeType::Trading, year, income, None);
+        (year, RepoIncomeTax {income, tax_to_pay})
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::localities;
+    use super::*;
+
+    #[test]
+    fn leg_matching() {
+        let repo = |date: Date, amount, commission| CashFlow::new(
+            date.into(), Cash::new("RUB", amount), CashFlowType::Repo {
+                symbol: s!("SBER"),
+                quantity: dec!(10),
+                commission: Cash::new("RUB", commission),
+            });
+
+        let cash_flows = [
+            repo(date!(2021, 12, 1), dec!(3000), dec!(1)),
+            repo(date!(2021, 12, 2), dec!(3100), dec!(1)),
+            repo(date!(2021, 12, 3), dec!(-3099.5), dec!(0)),
+            repo(date!(2022, 1, 10), dec!(-2998), dec!(0)),
+            repo(date!(2022, 1, 11), dec!(3200), dec!(0)),
+        ];
+
+        let income = process_repo_trades(&cash_flows).unwrap();
+
+        assert_eq!(income.iter().map(|repo| {
+            (repo.open_date, repo.date, repo.amount.amount, repo.profit().unwrap().amount)
+        }).collect::<Vec<_>>(), vec![
+            (date!(2021, 12, 2), date!(2021, 12, 3), dec!(0.5), dec!(-0.5)),
+            (date!(2021, 12, 1), date!(2022, 1, 10), dec!(2), dec!(1)),
+        ]);
+    }
+
+    #[test]
+    fn income_taxes() {
+        let converter = CurrencyConverter::mock();
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &BTreeMap::new());
+
+        let income = [
+            RepoIncome {
+                open_date: date!(2021, 12, 30),
+                date: date!(2022, 1, 10),
+                symbol: s!("SBER"),
+                quantity: dec!(10),
+                amount: Cash::new("RUB", dec!(1000)),
+                commission: Cash::new("RUB", dec!(23)),
+            },
+            RepoIncome {
+                open_date: date!(2022, 2, 1),
+                date: date!(2022, 2, 2),
+                symbol: s!("GAZP"),
+                quantity: dec!(100),
+                amount: Cash::new("RUB", dec!(-100)),
+                commission: Cash::new("RUB", dec!(0)),
+            },
+        ];
+
+        let taxes = calculate_repo_income_taxes(&country, &income, &converter).unwrap();
+        assert_eq!(taxes.iter().map(|(&year, tax)| {
+            (year, tax.income.amount, tax.tax_to_pay.amount)
+        }).collect::<Vec<_>>(), vec![
+            (2022, dec!(877), dec!(114)),
+        ]);
+    }
+}
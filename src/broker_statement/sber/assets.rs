@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use isin::ISIN;
+
+use xls_table_derive::XlsTableRow;
+
+use crate::broker_statement::partial::PartialBrokerStatementRc;
+use crate::core::{EmptyResult, GenericResult};
+use crate::instruments::parse_isin;
+use crate::types::Decimal;
+use crate::util::DecimalRestrictions;
+use crate::xls::{self, XlsStatementParser, SectionParser, Cell, SkipCell, TableReader};
+
+use super::common::{parse_cash, parse_decimal_cell, parse_quantity_cell};
+
+// Portfolio identifies securities only by ISIN, so open positions are mapped to symbols after
+// parsing of the securities reference book.
+pub type OpenPositionsRc = Rc<RefCell<Vec<(ISIN, Decimal)>>>;
+
+pub struct SecuritiesPortfolioParser {
+    statement: PartialBrokerStatementRc,
+    open_positions: OpenPositionsRc,
+}
+
+impl SecuritiesPortfolioParser {
+    pub fn new(statement: PartialBrokerStatementRc, open_positions: OpenPositionsRc) -> Box<dyn SectionParser> {
+        Box::new(SecuritiesPortfolioParser {statement, open_positions})
+    }
+}
+
+impl SectionParser for SecuritiesPortfolioParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        // The table has two-level header: period columns and their values
+        let first_header_row = parser.sheet.next_row_checked()?.to_vec();
+        let titles = get_column_titles(&first_header_row, parser.sheet.next_row_checked()?);
+
+        let isin_column = find_column(&titles, "ISIN ценной бумаги")?;
+        let starting_column = find_column(&titles, "Начало периода: Количество, шт")?;
+        let planned_column = find_column(&titles, "Плановые показатели: Количество, шт")?;
+
+        let mut statement = self.statement.borrow_mut();
+        let mut open_positions = self.open_positions.borrow_mut();
+
+        while let Some(row) = parser.sheet.next_row() {
+            if xls::is_empty_row(row) {
+                break;
+            }
+
+            let isin = match row.get(isin_column) {
+                Some(Cell::String(value)) => parse_isin(value)?,
+                // Subtotal rows: "Итого по ..."
+                Some(Cell::Empty) | None => continue,
+                Some(cell) => return Err!("Got an unexpected ISIN cell: {:?}", cell),
+            };
+
+            let starting = parse_optional_quantity(row.get(starting_column))?;
+            let planned = parse_optional_quantity(row.get(planned_column))?;
+
+            if !starting.is_zero() {
+                statement.has_starting_assets.replace(true);
+            }
+
+            if !planned.is_zero() {
+                open_positions.push((isin, planned));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn get_column_titles(first: &[Cell], second: &[Cell]) -> Vec<String> {
+    let width = std::cmp::max(first.len(), second.len());
+    let mut titles = Vec::with_capacity(width);
+    let mut group = String::new();
+
+    for index in 0..width {
+        let get_title = |row: &[Cell]| match row.get(index) {
+            Some(Cell::String(value)) => value.trim().to_owned(),
+            _ => String::new(),
+        };
+
+        let first = get_title(first);
+        let second = get_title(second);
+
+        if !first.is_empty() {
+            group = first.clone();
+        }
+
+        titles.push(if second.is_empty() {
+            first
+        } else {
+            format!("{}: {}", group, second)
+        });
+    }
+
+    titles
+}
+
+fn find_column(titles: &[String], name: &str) -> GenericResult<usize> {
+    Ok(titles.iter().position(|title| title == name).ok_or_else(|| format!(
+        "The table has no {:?} column", name))?)
+}
+
+fn parse_optional_quantity(cell: Option<&Cell>) -> GenericResult<Decimal> {
+    Ok(match cell {
+        Some(Cell::Empty) | None => dec!(0),
+        Some(cell) => parse_quantity_cell(cell)?,
+    })
+}
+
+pub struct CashAssetsParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl CashAssetsParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(CashAssetsParser {statement})
+    }
+}
+
+impl SectionParser for CashAssetsParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+        statement.has_starting_assets.get_or_insert(false);
+
+        for assets in xls::read_table::<CashAssetsRow>(&mut parser.sheet)? {
+            if !assets.starting.is_zero() {
+                statement.has_starting_assets.replace(true);
+            }
+
+            let planned = parse_cash(&assets.currency, assets.planned, DecimalRestrictions::No)?;
+            if planned.is_negative() {
+                return Err!("Leverage is not supported yet");
+            } else if !planned.is_zero() {
+                statement.assets.cash.as_mut().unwrap().deposit(planned);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Cash is listed for each trading venue separately
+#[derive(XlsTableRow)]
+struct CashAssetsRow {
+    #[column(name="Торговая площадка")]
+    _0: SkipCell,
+    #[column(name="Описание")]
+    _1: SkipCell,
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Начало периода", parse_with="parse_decimal_cell")]
+    starting: Decimal,
+    #[column(name="Изменение")]
+    _4: SkipCell,
+    #[column(name="Конец периода")]
+    _5: SkipCell,
+    #[column(name="Плановые показатели", parse_with="parse_decimal_cell")]
+    planned: Decimal,
+}
+
+impl TableReader for CashAssetsRow {
+    fn skip_row(row: &[Option<&Cell>]) -> GenericResult<bool> {
+        Ok(matches!(row[0], Some(Cell::String(value)) if value.starts_with("Итого")))
+    }
+}
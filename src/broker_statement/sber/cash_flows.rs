@@ -0,0 +1,178 @@
+use chrono::Datelike;
+use isin::ISIN;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use xls_table_derive::XlsTableRow;
+
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
+use crate::broker_statement::fees::Fee;
+use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
+use crate::broker_statement::taxes::TaxWithholding;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, CashAssets};
+use crate::instruments::{InstrumentId, ISIN_REGEX, parse_isin};
+use crate::time::Date;
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+use crate::xls::{self, XlsStatementParser, SectionParser, Cell, SkipCell, TableReader};
+
+use super::common::{parse_cash, parse_date_cell, parse_decimal, parse_decimal_cell};
+
+pub struct CashFlowsParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl CashFlowsParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(CashFlowsParser {statement})
+    }
+}
+
+impl SectionParser for CashFlowsParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        for cash_flow in xls::read_table::<CashFlowRow>(&mut parser.sheet)? {
+            cash_flow.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {:?} cash flow: {}", cash_flow.operation, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+struct CashFlowRow {
+    #[column(name="Дата", parse_with="parse_date_cell")]
+    date: Date,
+    #[column(name="Торговая площадка")]
+    _1: SkipCell,
+    #[column(name="Описание операции")]
+    operation: String,
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Сумма зачисления", parse_with="parse_decimal_cell")]
+    deposit: Option<Decimal>,
+    #[column(name="Сумма списания", parse_with="parse_decimal_cell")]
+    withdrawal: Option<Decimal>,
+}
+
+impl TableReader for CashFlowRow {
+    fn skip_row(row: &[Option<&Cell>]) -> GenericResult<bool> {
+        Ok(matches!(row[0], Some(Cell::String(value)) if value.starts_with("Итого")))
+    }
+}
+
+impl CashFlowRow {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let date = self.date;
+        let operation = self.operation.as_str();
+
+        let deposit = parse_cash(
+            &self.currency, self.deposit.unwrap_or_default(), DecimalRestrictions::PositiveOrZero)?;
+        let withdrawal = parse_cash(
+            &self.currency, self.withdrawal.unwrap_or_default(), DecimalRestrictions::PositiveOrZero)?;
+
+        let check_amount = |amount: Cash| -> GenericResult<Cash> {
+            if amount.is_zero() || !matches!((deposit.is_zero(), withdrawal.is_zero()), (true, false) | (false, true)) {
+                return Err!("Got an unexpected deposit and withdrawal amounts: {} and {}", deposit, withdrawal);
+            }
+            Ok(amount)
+        };
+
+        if operation == "Ввод ДС" {
+            statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(date, check_amount(deposit)?));
+        } else if operation == "Вывод ДС" {
+            statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(date, -check_amount(withdrawal)?));
+        } else if operation == "Перевод ДС" {
+            // Transfers between trading venues of the account
+        } else if operation.starts_with("Комиссия брокера") || operation.starts_with("Комиссия биржи") {
+            // Trade commissions are taken into account during trades processing
+        } else if operation.starts_with("Плата за") || operation.starts_with("Комиссия за") {
+            statement.fees.push(Fee::new(date, check_amount(withdrawal)?, Some(operation.to_owned())));
+        } else if operation.starts_with("Дивиденды") {
+            let (issuer, paid_tax) = parse_dividend_description(operation)?;
+            let mut amount = check_amount(deposit)?;
+
+            if let Some(paid_tax) = paid_tax {
+                let paid_tax = Cash::new(amount.currency, paid_tax);
+                amount += paid_tax;
+                statement.tax_accruals(date, issuer.clone(), true).add(date, paid_tax);
+            }
+
+            statement.dividend_accruals(date, issuer, true).add(date, amount);
+        } else if operation.starts_with("Купон") || operation.starts_with("Погашение") {
+            let type_ = if operation.starts_with("Купон") {
+                BondPaymentType::Coupon
+            } else if operation.starts_with("Погашение номинала") {
+                BondPaymentType::Redemption
+            } else {
+                BondPaymentType::Amortization
+            };
+
+            let issuer = InstrumentId::Isin(parse_operation_isin(operation)?);
+            statement.bond_payments.push(BondPayment::new(date, issuer, type_, check_amount(deposit)?));
+        } else if operation.starts_with("НДФЛ") || operation.starts_with("Налог") {
+            let tax_withholding = TaxWithholding::new(date, date.year(), check_amount(withdrawal)?)?;
+            statement.tax_agent_withholdings.push(tax_withholding);
+        } else {
+            return Err!("Unsupported cash flow operation");
+        }
+
+        Ok(())
+    }
+}
+
+// Example: "Дивиденды по ЦБ Сбербанк России ПАО ао, ISIN RU0009029540, 100 шт., налог удержан
+// эмитентом 243,10"
+fn parse_dividend_description(description: &str) -> GenericResult<(InstrumentId, Option<Decimal>)> {
+    lazy_static! {
+        static ref PAID_TAX_REGEX: Regex = Regex::new(
+            r"(?i)налог удержан(?: эмитентом)?:? (?P<amount>\d[\d\s]*(?:[.,]\d+)?)").unwrap();
+    }
+
+    let isin = parse_operation_isin(description)?;
+
+    let paid_tax = match PAID_TAX_REGEX.captures(description) {
+        Some(captures) => {
+            let amount = parse_decimal(captures.name("amount").unwrap().as_str().trim())?;
+            Some(util::validate_named_decimal("paid tax", amount, DecimalRestrictions::PositiveOrZero)?)
+        },
+        None => None,
+    }.filter(|paid_tax| !paid_tax.is_zero());
+
+    Ok((InstrumentId::Isin(isin), paid_tax))
+}
+
+fn parse_operation_isin(description: &str) -> GenericResult<ISIN> {
+    lazy_static! {
+        static ref OPERATION_ISIN_REGEX: Regex = Regex::new(&format!(
+            r"\b(?P<isin>{})\b", ISIN_REGEX)).unwrap();
+    }
+
+    let captures = OPERATION_ISIN_REGEX.captures(description).ok_or(
+        "Unable to find security ISIN in the operation description")?;
+
+    parse_isin(captures.name("isin").unwrap().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(description, isin, paid_tax,
+        case(
+            "Дивиденды по ЦБ Сбербанк России ПАО ао, ISIN RU0009029540, 100 шт., налог удержан эмитентом 243,10",
+            "RU0009029540", Some(dec!(243.10)),
+        ),
+        case("Дивиденды по ЦБ FinEx Gold ETF USD, ISIN IE00B8XB7377", "IE00B8XB7377", None),
+    )]
+    fn dividend_description_parsing(description: &str, isin: &str, paid_tax: Option<Decimal>) {
+        assert_eq!(
+            parse_dividend_description(description).unwrap(),
+            (InstrumentId::Isin(parse_isin(isin).unwrap()), paid_tax),
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::time::{self, Date, Time};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+use crate::xls::{self, Cell};
+
+pub fn parse_date(date: &str) -> GenericResult<Date> {
+    time::parse_date(date, "%d.%m.%Y")
+}
+
+pub fn parse_date_cell(cell: &Cell) -> GenericResult<Date> {
+    parse_date(xls::get_string_cell(cell)?)
+}
+
+pub fn parse_time_cell(cell: &Cell) -> GenericResult<Time> {
+    time::parse_time(xls::get_string_cell(cell)?, "%H:%M:%S")
+}
+
+// HTML statements contain numbers with thousands separated by spaces: "1 234,56" or "1 234.56"
+pub fn parse_decimal(value: &str) -> GenericResult<Decimal> {
+    let number: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    Ok(util::parse_decimal(&number.replace(',', "."), DecimalRestrictions::No).map_err(|_| format!(
+        "Invalid number: {:?}", value))?)
+}
+
+pub fn parse_decimal_cell(cell: &Cell) -> GenericResult<Decimal> {
+    parse_decimal(xls::get_string_cell(cell)?)
+}
+
+pub fn parse_quantity_cell(cell: &Cell) -> GenericResult<Decimal> {
+    let quantity = parse_decimal_cell(cell)?;
+    if quantity.is_sign_negative() || !quantity.fract().is_zero() {
+        return Err!("Invalid quantity: {}", quantity);
+    }
+    Ok(quantity)
+}
+
+pub fn parse_currency(currency: &str) -> &str {
+    match currency {
+        "RUR" => "RUB",
+        _ => currency,
+    }
+}
+
+pub fn parse_cash(currency: &str, amount: Decimal, restrictions: DecimalRestrictions) -> GenericResult<Cash> {
+    Ok(Cash::new(parse_currency(currency), util::validate_decimal(amount, restrictions)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(value, expected,
+        case("0", dec!(0)),
+        case("-15,5", dec!(-15.5)),
+        case("1 234.56", dec!(1234.56)),
+        case("12\u{a0}345\u{a0}678,9", dec!(12345678.9)),
+    )]
+    fn decimal_parsing(value: &str, expected: Decimal) {
+        assert_eq!(parse_decimal(value).unwrap(), expected);
+    }
+}
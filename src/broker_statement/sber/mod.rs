@@ -0,0 +1,119 @@
+/*
+Sber sends broker statements in HTML format. The statements are converted to a sheet, so they are
+parsed by the same machinery as XLS statements of other brokers.
+
+Securities portfolio identifies securities only by ISIN, so open positions are mapped to symbols
+using securities reference book which is located at the end of the statement.
+*/
+
+mod assets;
+mod cash_flows;
+mod common;
+mod period;
+mod securities;
+mod trades;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)] use crate::brokers::Broker;
+#[cfg(test)] use crate::config::Config;
+use crate::core::GenericResult;
+use crate::exchanges::Exchange;
+use crate::instruments::InstrumentId;
+#[cfg(test)] use crate::taxes::TaxRemapping;
+use crate::xls::{XlsStatementParser, Section, SheetParser};
+
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
+use super::{BrokerStatementReader, PartialBrokerStatement};
+
+use assets::{CashAssetsParser, SecuritiesPortfolioParser};
+use cash_flows::CashFlowsParser;
+use period::PeriodParser;
+use securities::SecuritiesParser;
+use trades::TradesParser;
+
+pub struct StatementReader {
+}
+
+impl StatementReader {
+    pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
+        Ok(Box::new(StatementReader{}))
+    }
+}
+
+impl BrokerStatementReader for StatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        Ok(path.ends_with(".html"))
+    }
+
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        let parser = Box::new(StatementSheetParser{});
+        let statement = PartialBrokerStatement::new_rc(&[Exchange::Moex, Exchange::Spb], true);
+        let open_positions = Rc::new(RefCell::new(Vec::new()));
+
+        XlsStatementParser::read_html(path, parser, vec![
+            Section::new(PeriodParser::TITLE).by_prefix()
+                .parser(PeriodParser::new(statement.clone())).required(),
+            Section::new("Портфель Ценных Бумаг")
+                .parser(SecuritiesPortfolioParser::new(statement.clone(), open_positions.clone())),
+            Section::new("Денежные средства")
+                .parser(CashAssetsParser::new(statement.clone())).required(),
+            Section::new("Движение денежных средств за период")
+                .parser(CashFlowsParser::new(statement.clone())),
+            Section::new("Сделки купли/продажи ценных бумаг")
+                .parser(TradesParser::new(statement.clone())),
+            Section::new("Справочник Ценных Бумаг")
+                .parser(SecuritiesParser::new(statement.clone())),
+        ])?;
+
+        let mut statement = Rc::try_unwrap(statement).ok().unwrap().into_inner();
+
+        for (isin, quantity) in open_positions.take() {
+            let symbol = statement.instrument_info.get_or_add_by_id(&InstrumentId::Isin(isin))?.symbol.clone();
+            statement.add_open_position(&symbol, quantity)?;
+        }
+
+        statement.validate()
+    }
+}
+
+struct StatementSheetParser {
+}
+
+impl SheetParser for StatementSheetParser {
+    // HTML statement is converted to a single sheet
+    fn sheet_name(&self) -> &str {
+        "HTML"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let broker = Broker::Sber.get_info(&Config::mock(), None).unwrap();
+
+        let statement = BrokerStatement::read(
+            broker, "testdata/sber", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(), &[],
+            StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
+
+        assert!(!statement.cash_assets.is_empty());
+        assert!(!statement.deposits_and_withdrawals.is_empty());
+
+        assert!(statement.fees.is_empty());
+        assert!(statement.idle_cash_interest.is_empty());
+        assert!(statement.tax_agent_withholdings.is_empty());
+
+        assert!(statement.forex_trades.is_empty());
+        assert!(!statement.stock_buys.is_empty());
+        assert!(statement.stock_sells.is_empty());
+        assert!(!statement.dividends.is_empty());
+
+        assert!(!statement.open_positions.is_empty());
+        assert!(!statement.instrument_info.is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::broker_statement::partial::PartialBrokerStatementRc;
+use crate::core::{EmptyResult, GenericResult};
+use crate::time::Period;
+use crate::xls::{self, XlsStatementParser, SectionParser};
+
+use super::common::parse_date;
+
+pub struct PeriodParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl PeriodParser {
+    pub const TITLE: &'static str = "Отчет брокера";
+
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(PeriodParser {statement})
+    }
+}
+
+impl SectionParser for PeriodParser {
+    fn consume_title(&self) -> bool { false }
+
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let row = xls::strip_row_expecting_columns(parser.sheet.next_row_checked()?, 1)?;
+        let title = xls::get_string_cell(row[0])?.strip_prefix(PeriodParser::TITLE).unwrap().trim().to_owned();
+
+        // The period may be specified either in the title or on the next line
+        let period = if title.is_empty() {
+            let row = xls::strip_row_expecting_columns(parser.sheet.next_row_checked()?, 1)?;
+            parse_period(xls::get_string_cell(row[0])?.trim())?
+        } else {
+            parse_period(&title)?
+        };
+
+        self.statement.borrow_mut().set_period(period)?;
+        Ok(())
+    }
+}
+
+fn parse_period(value: &str) -> GenericResult<Period> {
+    lazy_static! {
+        static ref PERIOD_REGEX: Regex = Regex::new(concat!(
+            r"^за период с (?P<start>\d{2}\.\d{2}\.\d{4}) по (?P<end>\d{2}\.\d{2}\.\d{4})",
+            r"(?:,? дата создания (?P<creation_date>\d{2}\.\d{2}\.\d{4}))?$",
+        )).unwrap();
+    }
+
+    let captures = PERIOD_REGEX.captures(value).ok_or_else(|| format!(
+        "Invalid statement period: {:?}", value))?;
+
+    let start = parse_date(captures.name("start").unwrap().as_str())?;
+    let mut end = parse_date(captures.name("end").unwrap().as_str())?;
+
+    // Statements may be created for a period which isn't over yet
+    if let Some(creation_date) = captures.name("creation_date") {
+        end = std::cmp::min(end, parse_date(creation_date.as_str())?);
+    }
+
+    Period::new(start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use crate::time::Date;
+    use super::*;
+
+    #[rstest(value, start, end,
+        case("за период с 01.01.2021 по 31.12.2021", date!(2021, 1, 1), date!(2021, 12, 31)),
+        case("за период с 01.03.2021 по 31.03.2021, дата создания 15.03.2021", date!(2021, 3, 1), date!(2021, 3, 15)),
+    )]
+    fn period_parsing(value: &str, start: Date, end: Date) {
+        assert_eq!(parse_period(value).unwrap(), Period::new(start, end).unwrap());
+    }
+}
@@ -0,0 +1,53 @@
+use xls_table_derive::XlsTableRow;
+
+use crate::broker_statement::partial::PartialBrokerStatementRc;
+use crate::core::{EmptyResult, GenericResult};
+use crate::instruments::parse_isin;
+use crate::xls::{self, XlsStatementParser, SectionParser, Cell, SkipCell, TableReader};
+
+pub struct SecuritiesParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl SecuritiesParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(SecuritiesParser {statement})
+    }
+}
+
+impl SectionParser for SecuritiesParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        for security in xls::read_table::<SecurityRow>(&mut parser.sheet)? {
+            let instrument = statement.instrument_info.get_or_add(&security.symbol);
+            instrument.set_name(&security.name);
+            instrument.add_isin(parse_isin(&security.isin)?);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+struct SecurityRow {
+    #[column(name="Наименование")]
+    name: String,
+    #[column(name="Код")]
+    symbol: String,
+    #[column(name="ISIN ценной бумаги")]
+    isin: String,
+    #[column(name="Эмитент")]
+    _3: SkipCell,
+    #[column(name="Вид, Категория, Тип, иная информация")]
+    _4: SkipCell,
+    #[column(name="Выпуск, Транш, Серия")]
+    _5: SkipCell,
+}
+
+impl TableReader for SecurityRow {
+    fn skip_row(row: &[Option<&Cell>]) -> GenericResult<bool> {
+        // Some securities (like currencies) have no ISIN
+        Ok(matches!(row[2], Some(Cell::Empty)))
+    }
+}
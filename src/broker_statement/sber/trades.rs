@@ -0,0 +1,144 @@
+use xls_table_derive::XlsTableRow;
+
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
+use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
+use crate::broker_statement::trades::{StockBuy, StockSell};
+use crate::core::{EmptyResult, GenericResult};
+use crate::exchanges::Exchange;
+use crate::instruments::InstrumentId;
+use crate::time::{Date, DateTime, Time};
+use crate::types::Decimal;
+use crate::util::DecimalRestrictions;
+use crate::xls::{self, XlsStatementParser, SectionParser, Cell, SkipCell, TableReader};
+
+use super::common::{parse_cash, parse_date_cell, parse_decimal_cell, parse_quantity_cell, parse_time_cell};
+
+pub struct TradesParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl TradesParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(TradesParser {statement})
+    }
+}
+
+impl SectionParser for TradesParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        let mut trades = xls::read_table::<TradeRow>(&mut parser.sheet)?;
+        trades.sort_by_key(|trade| (trade.date, trade.time));
+
+        for trade in trades {
+            trade.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {:?} trade: {}", trade.id, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+struct TradeRow {
+    #[column(name="Дата заключения", parse_with="parse_date_cell")]
+    date: Date,
+    #[column(name="Дата расчетов", parse_with="parse_date_cell")]
+    execution_date: Date,
+    #[column(name="Время заключения", parse_with="parse_time_cell")]
+    time: Time,
+    #[column(name="Наименование ЦБ")]
+    _3: SkipCell,
+    #[column(name="Код ЦБ")]
+    symbol: String,
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Вид")]
+    operation: String,
+    #[column(name="Количество, шт.", parse_with="parse_quantity_cell")]
+    quantity: Decimal,
+    #[column(name="Цена", parse_with="parse_decimal_cell")]
+    price: Decimal,
+    #[column(name="Сумма", parse_with="parse_decimal_cell")]
+    volume: Decimal,
+    #[column(name="НКД", parse_with="parse_decimal_cell")]
+    accrued_interest: Option<Decimal>,
+    #[column(name="Комиссия Брокера", parse_with="parse_decimal_cell")]
+    commission: Decimal,
+    #[column(name="Комиссия Биржи", parse_with="parse_decimal_cell")]
+    exchange_commission: Decimal,
+    #[column(name="Номер сделки")]
+    id: String,
+    #[column(name="Торговая площадка", optional=true)]
+    exchange: Option<String>,
+    #[column(name="Комментарий", optional=true)]
+    _15: Option<SkipCell>,
+    #[column(name="Статус сделки", optional=true)]
+    _16: Option<SkipCell>,
+}
+
+impl TableReader for TradeRow {
+    fn skip_row(row: &[Option<&Cell>]) -> GenericResult<bool> {
+        Ok(matches!(row[0], Some(Cell::String(value)) if value.starts_with("Итого")))
+    }
+}
+
+impl TradeRow {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let symbol = &self.symbol;
+        let conclusion_time = DateTime::new(self.date, self.time);
+
+        let price = parse_cash(&self.currency, self.price, DecimalRestrictions::StrictlyPositive)?;
+        let volume = parse_cash(&self.currency, self.volume, DecimalRestrictions::StrictlyPositive)?;
+
+        // Broker and exchange commissions are charged separately
+        let commission = parse_cash(&self.currency, self.commission, DecimalRestrictions::PositiveOrZero)? +
+            parse_cash(&self.currency, self.exchange_commission, DecimalRestrictions::PositiveOrZero)?;
+
+        // Trade volume doesn't include accrued coupon income which is accounted separately
+        let accrued_interest = parse_cash(
+            &self.currency, self.accrued_interest.unwrap_or_default(), DecimalRestrictions::PositiveOrZero)?;
+
+        let exchange = match self.exchange.as_deref() {
+            Some(exchange) if exchange.contains("СПБ") => Exchange::Spb,
+            _ => Exchange::Moex,
+        };
+        statement.instrument_info.get_or_add(symbol).exchanges.add_prioritized(exchange);
+
+        // Bond prices are specified in percents of face value
+        let price = if accrued_interest.is_zero() {
+            debug_assert_eq!(volume, (price * self.quantity).round());
+            price
+        } else {
+            volume / self.quantity
+        };
+
+        match self.operation.as_str() {
+            "Покупка" => {
+                statement.stock_buys.push(StockBuy::new_trade(
+                    symbol, self.quantity, price, volume, commission,
+                    conclusion_time.into(), self.execution_date));
+
+                if !accrued_interest.is_zero() {
+                    statement.bond_payments.push(BondPayment::new(
+                        self.date, InstrumentId::Symbol(symbol.clone()),
+                        BondPaymentType::AccruedInterest, -accrued_interest));
+                }
+            },
+            "Продажа" => {
+                statement.stock_sells.push(StockSell::new_trade(
+                    symbol, self.quantity, price, volume, commission,
+                    conclusion_time.into(), self.execution_date, false));
+
+                if !accrued_interest.is_zero() {
+                    statement.bond_payments.push(BondPayment::new(
+                        self.date, InstrumentId::Symbol(symbol.clone()),
+                        BondPaymentType::AccruedInterest, accrued_interest));
+                }
+            },
+            _ => return Err!("Unsupported trade operation: {:?}", self.operation),
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::Datelike;
+use serde::{Deserialize, Serialize};
 
 use crate::broker_statement::payments::Payments;
 use crate::core::GenericResult;
@@ -7,7 +8,7 @@ use crate::formatting;
 use crate::instruments::InstrumentId;
 use crate::types::Date;
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct TaxId {
     pub date: Date,
     pub issuer: InstrumentId,
@@ -25,6 +26,7 @@ impl TaxId {
 
 pub type TaxAccruals = Payments;
 
+#[derive(Serialize, Deserialize)]
 pub struct TaxWithholding {
     pub date: Date,
     pub year: i32,
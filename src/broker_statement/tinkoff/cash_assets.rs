@@ -5,6 +5,7 @@ use chrono::Datelike;
 
 use xls_table_derive::XlsTableRow;
 
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
 use crate::broker_statement::fees::Fee;
 use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
 use crate::broker_statement::taxes::TaxWithholding;
@@ -224,7 +225,21 @@ impl CashFlowRow {
                 statement.tax_accruals(self.execution_date, issuer_id, true).add(date, amount);
             },
 
-            "Налог" => {
+            "Выплата купонов" | "Частичное погашение облигации" | "Погашение облигации" => {
+                let type_ = match operation.as_str() {
+                    "Выплата купонов" => BondPaymentType::Coupon,
+                    "Частичное погашение облигации" => BondPaymentType::Amortization,
+                    _ => BondPaymentType::Redemption,
+                };
+
+                let description = self.comment.as_deref().unwrap_or_default();
+                let issuer_name = parse_dividend_description(description)?;
+                let issuer_id = InstrumentId::Name(issuer_name.to_owned());
+                let amount = check_amount(deposit)?;
+                statement.bond_payments.push(BondPayment::new(date, issuer_id, type_, amount));
+            },
+
+            "Налог" | "Налог (купонный доход)" => {
                 let year = date.year();
                 let amount = check_amount(withdrawal)?;
                 let tax_withholding = TaxWithholding::new(date, year, amount)?;
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use log::warn;
 use matches::matches;
@@ -269,7 +270,7 @@ pub fn match_statement_dividends_to_foreign_income(
     dividend_id: &DividendId, instrument: &Instrument,
     dividend_accruals: DividendAccruals, tax_accruals: Option<TaxAccruals>,
     foreign_income: &mut HashMap<DividendId, (DividendAccruals, TaxAccruals)>,
-    show_missing_foreign_income_info_warning: &mut bool,
+    show_missing_foreign_income_info_warning: &AtomicBool,
 ) -> GenericResult<(DividendAccruals, Option<TaxAccruals>)> {
     if instrument.isin.is_empty() {
         return Err!(
@@ -307,7 +308,7 @@ pub fn match_statement_dividends_to_foreign_income(
         }
         details
     } else {
-        if is_foreign && *show_missing_foreign_income_info_warning {
+        if is_foreign && show_missing_foreign_income_info_warning.swap(false, Ordering::Relaxed) {
             // https://github.com/KonishchevDmitry/investments/blob/master/docs/brokers.md#tinkoff-foreign-income
             let url = "https://bit.ly/investments-tinkoff-foreign-income";
 
@@ -317,8 +318,6 @@ pub fn match_statement_dividends_to_foreign_income(
                 "foreign income statement is required (see {}). ",
                 "First occurred dividend: {} from {}",
             ), url, dividend_id.issuer, formatting::format_date(dividend_id.date));
-
-            *show_missing_foreign_income_info_warning = false;
         }
         return Ok((dividend_accruals, tax_accruals))
     };
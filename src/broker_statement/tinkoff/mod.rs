@@ -25,6 +25,8 @@ mod trades;
 use std::collections::HashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use lazy_static::lazy_static;
 use regex::{self, Regex};
@@ -40,35 +42,40 @@ use crate::instruments::InstrumentId;
 #[cfg(test)] use crate::taxes::TaxRemapping;
 use crate::xls::{XlsStatementParser, Section, SheetParser, SectionParserRc, Cell};
 
-#[cfg(test)] use super::{BrokerStatement, ReadingStrictness};
-use super::{BrokerStatementReader, PartialBrokerStatement};
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
+use super::{BrokerStatementReader, PartialBrokerStatement, StatementCaching};
 
 use assets::AssetsParser;
 use cash_assets::CashAssetsParser;
-use foreign_income::ForeignIncomeStatementReader;
 use period::PeriodParser;
 use securities::SecuritiesInfoParser;
-use trades::{TradesParser, TradesRegistryRc};
+use trades::{TradesParser, TradesRegistryArc};
 use itertools::Itertools;
 
+pub use foreign_income::ForeignIncomeStatementReader;
+
+// The reader carries state from one statement to another, so it reads the statements sequentially
+// and the locks are used only to make it thread-safe.
 pub struct StatementReader {
-    trades: TradesRegistryRc,
-    foreign_income: HashMap<DividendId, (DividendAccruals, TaxAccruals)>,
-    show_missing_foreign_income_info_warning: bool,
+    trades: TradesRegistryArc,
+    foreign_income: Mutex<HashMap<DividendId, (DividendAccruals, TaxAccruals)>>,
+    show_missing_foreign_income_info_warning: AtomicBool,
 }
 
 impl StatementReader {
     pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
         Ok(Box::new(StatementReader{
-            trades: TradesRegistryRc::default(),
-            foreign_income: HashMap::new(),
-            show_missing_foreign_income_info_warning: true,
+            trades: TradesRegistryArc::default(),
+            foreign_income: Mutex::new(HashMap::new()),
+            show_missing_foreign_income_info_warning: AtomicBool::new(true),
         }))
     }
 
     fn parse_foreign_income_statement(&mut self, path: &str) -> EmptyResult {
+        let foreign_income = self.foreign_income.get_mut().unwrap();
+
         for (dividend_id, details) in ForeignIncomeStatementReader::read(path)? {
-            if self.foreign_income.insert(dividend_id.clone(), details).is_some() {
+            if foreign_income.insert(dividend_id.clone(), details).is_some() {
                 return Err!(
                     "Got a duplicated {}/{} dividend from different foreign income statements",
                     formatting::format_date(dividend_id.date), dividend_id.issuer);
@@ -78,7 +85,8 @@ impl StatementReader {
         Ok(())
     }
 
-    fn postprocess(&mut self, mut statement: PartialBrokerStatement) -> GenericResult<PartialBrokerStatement> {
+    fn postprocess(&self, mut statement: PartialBrokerStatement) -> GenericResult<PartialBrokerStatement> {
+        let mut foreign_income = self.foreign_income.lock().unwrap();
         let mut dividends = HashMap::new();
         let mut taxes = HashMap::new();
 
@@ -98,7 +106,7 @@ impl StatementReader {
 
             let (dividend_accruals, tax_accruals) = foreign_income::match_statement_dividends_to_foreign_income(
                 &dividend_id, instrument, dividend_accruals, tax_accruals,
-                &mut self.foreign_income, &mut self.show_missing_foreign_income_info_warning)?;
+                &mut foreign_income, &self.show_missing_foreign_income_info_warning)?;
 
             dividend_id.issuer = InstrumentId::Symbol(instrument.symbol.clone());
             assert!(dividends.insert(dividend_id, dividend_accruals).is_none());
@@ -123,19 +131,30 @@ impl StatementReader {
 
 impl BrokerStatementReader for StatementReader {
     fn check(&mut self, path: &str) -> GenericResult<bool> {
-        let is_foreign_income_statement = ForeignIncomeStatementReader::is_statement(path).map_err(|e| format!(
-            "Error while reading {:?}: {}", path, e))?;
-
-        if is_foreign_income_statement {
+        if ForeignIncomeStatementReader::is_statement(path)? {
             self.parse_foreign_income_statement(path).map_err(|e| format!(
-                "Error while reading {:?} foreign income statement: {}", path, e))?;
+                "Failed to read foreign income statement: {}", e))?;
             return Ok(false);
         }
 
         Ok(path.ends_with(".xlsx"))
     }
 
-    fn read(&mut self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+    fn caching(&self) -> StatementCaching {
+        // Trades are deduplicated between the statements and foreign income is consumed by them
+        let foreign_income = self.foreign_income.lock().unwrap();
+        let foreign_income: Vec<_> = foreign_income.iter().sorted_by_key(|(dividend_id, _)| {
+            (dividend_id.date, dividend_id.issuer.to_string())
+        }).collect();
+
+        StatementCaching::Sequential(serde_json::to_string(&foreign_income).unwrap())
+    }
+
+    fn is_sequential(&self) -> bool {
+        true
+    }
+
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
         let parser = Box::new(StatementSheetParser{});
         let statement = PartialBrokerStatement::new_rc(&[Exchange::Moex, Exchange::Spb], true);
 
@@ -169,8 +188,12 @@ impl BrokerStatementReader for StatementReader {
         self.postprocess(Rc::try_unwrap(statement).ok().unwrap().into_inner())
     }
 
+    fn has_warnings(&self) -> bool {
+        !self.show_missing_foreign_income_info_warning.load(Ordering::Relaxed)
+    }
+
     fn close(self: Box<Self>) -> EmptyResult {
-        if let Some(dividend_id) = self.foreign_income.keys().next() {
+        if let Some(dividend_id) = self.foreign_income.lock().unwrap().keys().next() {
             return Err!(
                 "Unable to match {} from foreign income report to any dividend from broker statement",
                 dividend_id.description(),
@@ -270,9 +293,10 @@ mod tests {
         let portfolio = config.get_portfolio(&portfolio_name).unwrap();
 
         BrokerStatement::read(
-            broker, &format!("testdata/tinkoff/{}", name),
+            broker, &format!("testdata/tinkoff/{}", name), None,
             &Default::default(), &Default::default(), &Default::default(),
-            TaxRemapping::new(), &portfolio.corporate_actions, ReadingStrictness::all(),
+            TaxRemapping::new(), &portfolio.corporate_actions, StockTransfers::new(), &StatementCache::new_disabled(),
+            ReadingStrictness::all(),
         ).unwrap()
     }
 }
\ No newline at end of file
@@ -3,9 +3,11 @@ use xls_table_derive::XlsTableRow;
 use crate::broker_statement::partial::PartialBrokerStatementRc;
 use crate::core::EmptyResult;
 use crate::instruments::parse_isin;
+use crate::types::Decimal;
+use crate::util::DecimalRestrictions;
 use crate::xls::{self, XlsStatementParser, SectionParser, SheetReader, Cell, SkipCell, TableReader};
 
-use super::common::read_next_table_row;
+use super::common::{read_next_table_row, parse_cash, parse_decimal_cell};
 
 pub struct SecuritiesInfoParser {
     statement: PartialBrokerStatementRc,
@@ -25,6 +27,17 @@ impl SectionParser for SecuritiesInfoParser {
             let instrument = statement.instrument_info.get_or_add(&security.symbol);
             instrument.set_name(&security.name);
             instrument.add_isin(parse_isin(&security.isin)?);
+
+            if security.type_.as_deref().unwrap_or_default().to_lowercase().starts_with("облигаци") {
+                let face_value = security.face_value.ok_or_else(|| format!(
+                    "Got {} bond without face value", security.symbol))?;
+
+                let currency = security.face_value_currency.as_ref().ok_or_else(|| format!(
+                    "Got {} bond without face value currency", security.symbol))?;
+
+                instrument.set_face_value(parse_cash(
+                    currency, face_value, DecimalRestrictions::StrictlyPositive)?);
+            }
         }
 
         Ok(())
@@ -44,11 +57,11 @@ struct SecuritiesInfoRow {
     #[column(name="Наименование эмитента")]
     _4: SkipCell,
     #[column(name="Тип")]
-    _5: SkipCell,
-    #[column(name="Номинал")]
-    _6: SkipCell,
+    type_: Option<String>,
+    #[column(name="Номинал", parse_with="parse_decimal_cell")]
+    face_value: Option<Decimal>,
     #[column(name="Валюта номинала")]
-    _7: SkipCell,
+    face_value_currency: Option<String>,
 }
 
 impl TableReader for SecuritiesInfoRow {
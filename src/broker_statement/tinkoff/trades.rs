@@ -1,12 +1,13 @@
-use std::cell::RefCell;
 use std::collections::{HashMap, hash_map::Entry};
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 use log::debug;
 use num_traits::FromPrimitive;
 
 use xls_table_derive::XlsTableRow;
 
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
+use crate::broker_statement::cash_flows::{CashFlow, CashFlowType};
 use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
 use crate::broker_statement::trades::{ForexTrade, StockBuy, StockSell};
 use crate::core::{EmptyResult, GenericResult};
@@ -14,6 +15,7 @@ use crate::currency::Cash;
 use crate::exchanges::Exchange;
 use crate::forex::parse_forex_code;
 use crate::formatting::format_date;
+use crate::instruments::InstrumentId;
 use crate::time::{Date, Time, DateTime};
 use crate::types::Decimal;
 use crate::util::DecimalRestrictions;
@@ -23,23 +25,23 @@ use super::common::{
     read_next_table_row, parse_cash, parse_date_cell, parse_decimal_cell, parse_quantity_cell,
     parse_time_cell};
 
-pub type TradesRegistryRc = Rc<RefCell<HashMap<u64, bool>>>;
+pub type TradesRegistryArc = Arc<Mutex<HashMap<u64, bool>>>;
 
 pub struct TradesParser {
     executed: bool,
     statement: PartialBrokerStatementRc,
-    processed_trades: TradesRegistryRc,
+    processed_trades: TradesRegistryArc,
 }
 
 impl TradesParser {
     pub fn new(
-        executed: bool, statement: PartialBrokerStatementRc, processed_trades: TradesRegistryRc,
+        executed: bool, statement: PartialBrokerStatementRc, processed_trades: TradesRegistryArc,
     ) -> Box<dyn SectionParser> {
         Box::new(TradesParser {executed, processed_trades, statement})
     }
 
     fn check_trade_id(&self, trade_id: u64) -> GenericResult<bool> {
-        Ok(match self.processed_trades.borrow_mut().entry(trade_id) {
+        Ok(match self.processed_trades.lock().unwrap().entry(trade_id) {
             Entry::Vacant(entry) => {
                 entry.insert(self.executed);
                 true
@@ -110,10 +112,10 @@ struct TradeRow {
     price_currency: String,
     #[column(name="Количество", parse_with="parse_quantity_cell")]
     quantity: u32,
-    #[column(name="Сумма (без НКД)")]
-    _13: SkipCell,
+    #[column(name="Сумма (без НКД)", parse_with="parse_decimal_cell")]
+    volume_without_accrued_interest: Decimal,
     #[column(name="НКД", parse_with="parse_decimal_cell")]
-    accumulated_coupon_income: Decimal,
+    accrued_interest: Decimal,
     #[column(name="Сумма сделки", parse_with="parse_decimal_cell")]
     volume: Decimal,
     #[column(name="Валюта расчетов")]
@@ -162,23 +164,30 @@ impl TableReader for TradeRow {
 
 impl TradeRow {
     fn parse(self, statement: &mut PartialBrokerStatement) -> EmptyResult {
-        if !self.accumulated_coupon_income.is_zero() {
-            return Err!("Bonds aren't supported yet");
-        } else if self.leverage_rate.is_some() {
-            return Err!("Leverage is not supported yet");
-        }
-
         let conclusion_time = DateTime::new(self.date, self.time);
         if self.quantity == 0 {
             return Err!("Invalid {} trade quantity: {:?}", self.symbol, self.quantity);
         }
 
-        let price = parse_cash(
+        let mut price = parse_cash(
             &self.price_currency, self.price, DecimalRestrictions::StrictlyPositive)?;
 
-        let volume = parse_cash(
+        let mut volume = parse_cash(
             &self.settlement_currency, self.volume, DecimalRestrictions::StrictlyPositive)?;
-        debug_assert_eq!(volume, (price * self.quantity).round());
+
+        // Bond prices are specified in percents of face value and trade volume includes accrued
+        // coupon income which is accounted separately.
+        let accrued_interest = parse_cash(
+            &self.settlement_currency, self.accrued_interest, DecimalRestrictions::PositiveOrZero)?;
+
+        if accrued_interest.is_zero() {
+            debug_assert_eq!(volume, (price * self.quantity).round());
+        } else {
+            volume = parse_cash(
+                &self.settlement_currency, self.volume_without_accrued_interest,
+                DecimalRestrictions::StrictlyPositive)?;
+            price = volume / self.quantity;
+        }
 
         let commission = match self.commission_currency {
             Some(currency) => {
@@ -193,6 +202,23 @@ impl TradeRow {
             ),
         };
 
+        // Repo trades are used by the broker to borrow our securities
+        if self.leverage_rate.is_some() {
+            let amount = match self.operation.as_str() {
+                "Покупка" => -volume,
+                "Продажа" => volume,
+                _ => return Err!("Unsupported repo trade operation: {:?}", self.operation),
+            };
+
+            statement.cash_flows.push(CashFlow::new(conclusion_time.into(), amount, CashFlowType::Repo {
+                symbol: self.symbol.clone(),
+                quantity: self.quantity.into(),
+                commission,
+            }));
+
+            return Ok(());
+        }
+
         let forex = parse_forex_code(&self.symbol);
 
         if forex.is_err() {
@@ -215,6 +241,12 @@ impl TradeRow {
                     statement.stock_buys.push(StockBuy::new_trade(
                         &self.symbol, self.quantity.into(), price, volume, commission,
                         conclusion_time.into(), self.execution_date));
+
+                    if !accrued_interest.is_zero() {
+                        statement.bond_payments.push(BondPayment::new(
+                            self.date, InstrumentId::Symbol(self.symbol.clone()),
+                            BondPaymentType::AccruedInterest, -accrued_interest));
+                    }
                 }
             },
             "Продажа" => {
@@ -227,6 +259,12 @@ impl TradeRow {
                     statement.stock_sells.push(StockSell::new_trade(
                         &self.symbol, self.quantity.into(), price, volume,
                         commission, conclusion_time.into(), self.execution_date, false));
+
+                    if !accrued_interest.is_zero() {
+                        statement.bond_payments.push(BondPayment::new(
+                            self.date, InstrumentId::Symbol(self.symbol.clone()),
+                            BondPaymentType::AccruedInterest, accrued_interest));
+                    }
                 }
             },
             _ => return Err!("Unsupported trade operation: {:?}", self.operation),
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::core::GenericResult;
 use crate::currency::Cash;
 use crate::currency::converter::CurrencyConverter;
@@ -8,6 +10,7 @@ use crate::time::DateOptTime;
 use crate::trades::{self, RealProfit};
 use crate::types::{Date, Decimal};
 
+#[derive(Serialize, Deserialize)]
 pub struct ForexTrade {
     pub from: Cash,
     pub to: Cash,
@@ -21,7 +24,7 @@ impl ForexTrade {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 pub enum StockSource {
     // Ordinary trade
     Trade {
@@ -34,8 +37,13 @@ pub enum StockSource {
     // * Emulated buy to convert position during stock split
     // * Spinoff or stock dividend
     CorporateAction,
+
+    // In-kind transfer from another portfolio. Doesn't affect cash balance, but the lot keeps its
+    // original acquisition details.
+    Transfer,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct StockBuy {
     pub symbol: String,
     pub original_symbol: String,
@@ -48,7 +56,11 @@ pub struct StockBuy {
     pub execution_date: Date,
     pub out_of_order_execution: bool,
 
+    pub emulation: bool,
     sold: Decimal,
+
+    // Original acquisition details of transferred lots
+    acquisition: Option<Acquisition>,
 }
 
 impl StockBuy {
@@ -63,7 +75,7 @@ impl StockBuy {
             symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
             quantity, type_: StockSource::Trade {price, volume, commission}, cost,
             conclusion_time, execution_date, out_of_order_execution: false,
-            sold: dec!(0),
+            emulation: false, sold: dec!(0), acquisition: None,
         }
     }
 
@@ -74,7 +86,36 @@ impl StockBuy {
         StockBuy {
             symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
             quantity, type_: StockSource::CorporateAction, cost, out_of_order_execution: true,
-            conclusion_time, execution_date, sold: dec!(0),
+            conclusion_time, execution_date, emulation: false, sold: dec!(0), acquisition: None,
+        }
+    }
+
+    // Creates a lot which has been received by in-kind transfer from another portfolio: it becomes
+    // available at the transfer time, but keeps acquisition dates and cost of the original lot.
+    pub fn new_transfer(
+        symbol: &str, lot: &StockSellSource, conclusion_time: DateOptTime, execution_date: Date,
+    ) -> StockBuy {
+        let quantity = lot.quantity * lot.multiplier;
+
+        // The lot may have been affected by stock splits in the source portfolio, so adjust the
+        // price to the current quantity
+        let type_ = match lot.type_ {
+            StockSource::Trade {price, volume, commission} => StockSource::Trade {
+                price: price / lot.multiplier, volume, commission,
+            },
+            type_ => type_,
+        };
+
+        StockBuy {
+            symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
+            quantity, type_: StockSource::Transfer, cost: lot.cost.clone(),
+            conclusion_time, execution_date, out_of_order_execution: true,
+            emulation: false, sold: dec!(0),
+            acquisition: Some(Acquisition {
+                type_,
+                conclusion_time: lot.conclusion_time,
+                execution_date: lot.execution_date,
+            }),
         }
     }
 
@@ -86,39 +127,80 @@ impl StockBuy {
         self.quantity - self.sold
     }
 
+    // Reduces purchase cost of the unsold part of the position (bond amortization)
+    pub fn reduce_cost(&mut self, date: Date, unsold_amount: Cash) {
+        assert!(!self.is_sold());
+        let amount = unsold_amount / self.get_unsold() * self.quantity;
+        self.cost.reduce(date, amount);
+    }
+
+    // Adds paid (positive) or received (negative) premium of the exercised or assigned option to the
+    // cost of the delivered stocks
+    pub fn add_option_premium(&mut self, date: Date, premium: Cash) {
+        assert!(self.sold.is_zero());
+        self.cost.add_transaction(date, premium);
+    }
+
+    // Moves the specified share of the unsold part cost to another lot (spinoffs and stock dividends)
+    pub fn split_off_cost(&mut self, ratio: Decimal) -> PurchaseTotalCost {
+        assert!(!self.is_sold());
+
+        let mut cost = self.cost.clone();
+        cost.scale(ratio * self.get_unsold() / self.quantity);
+
+        // The sold part has already taken its cost, so scaling of the whole lot cost affects only
+        // the unsold part
+        self.cost.scale(dec!(1) - ratio);
+
+        cost
+    }
+
     pub fn sell(&mut self, quantity: Decimal, multiplier: Decimal) -> StockSellSource {
         assert!(self.get_unsold() >= quantity);
         self.sold += quantity;
 
+        let (type_, conclusion_time, execution_date) = match self.acquisition {
+            Some(acquisition) => (acquisition.type_, acquisition.conclusion_time, acquisition.execution_date),
+            None => (self.type_, self.conclusion_time, self.execution_date),
+        };
+
         let mut cost = self.cost.clone();
         let type_ = if quantity == self.quantity {
-            self.type_
+            type_
         } else {
             for cost in &mut cost.0 {
                 cost.fraction.0 *= quantity;
                 cost.fraction.1 *= self.quantity;
             }
 
-            match self.type_ {
+            match type_ {
                 StockSource::Trade {price, commission, ..} => StockSource::Trade {
                     price,
                     volume: price * quantity,
                     commission: commission / self.quantity * quantity,
                 },
                 StockSource::CorporateAction => StockSource::CorporateAction,
+                StockSource::Transfer => StockSource::Transfer,
             }
         };
 
         StockSellSource {
             original_symbol: self.original_symbol.clone(),
             quantity, multiplier, type_, cost,
-            conclusion_time: self.conclusion_time,
-            execution_date: self.execution_date,
+            conclusion_time, execution_date,
+            emulation: self.emulation,
         }
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct Acquisition {
+    type_: StockSource,
+    conclusion_time: DateOptTime,
+    execution_date: Date,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
 pub enum StockSellType {
     // Ordinary trade
     Trade {
@@ -131,9 +213,13 @@ pub enum StockSellType {
     // lead to any taxes:
     // * Emulated sell to convert position during stock split
     CorporateAction,
+
+    // In-kind transfer to another portfolio which doesn't lead to any taxes: the sold lots are moved
+    // to the receiving portfolio.
+    Transfer,
 }
 
-#[derive(Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct StockSell {
     pub symbol: String,
     pub original_symbol: String,
@@ -146,7 +232,13 @@ pub struct StockSell {
     pub out_of_order_execution: bool,
 
     pub emulation: bool,
+
+    // Short sell which is closed by the following buys
+    short: bool,
     sources: Vec<StockSellSource>,
+
+    // Premium of the exercised or assigned option which has led to this sell
+    option_premium: PurchaseTotalCost,
 }
 
 impl StockSell {
@@ -158,7 +250,8 @@ impl StockSell {
             symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
             quantity, type_: StockSellType::Trade {price, volume, commission},
             conclusion_time, execution_date, out_of_order_execution: false,
-            emulation, sources: Vec::new(),
+            emulation, short: false, sources: Vec::new(),
+            option_premium: PurchaseTotalCost::new(),
         }
     }
 
@@ -169,23 +262,121 @@ impl StockSell {
             symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
             quantity, type_: StockSellType::CorporateAction,
             conclusion_time, execution_date, out_of_order_execution: true,
-            emulation: false, sources: Vec::new(),
+            emulation: false, short: false, sources: Vec::new(),
+            option_premium: PurchaseTotalCost::new(),
+        }
+    }
+
+    pub fn new_transfer(
+        symbol: &str, quantity: Decimal, conclusion_time: DateOptTime, execution_date: Date,
+    ) -> StockSell {
+        StockSell {
+            symbol: symbol.to_owned(), original_symbol: symbol.to_owned(),
+            quantity, type_: StockSellType::Transfer,
+            conclusion_time, execution_date, out_of_order_execution: true,
+            emulation: false, short: false, sources: Vec::new(),
+            option_premium: PurchaseTotalCost::new(),
         }
     }
 
     pub fn is_processed(&self) -> bool {
-        !self.sources.is_empty()
+        self.get_unprocessed().is_zero()
+    }
+
+    pub fn get_unprocessed(&self) -> Decimal {
+        self.quantity - self.sources.iter()
+            .map(|source| source.multiplier * source.quantity)
+            .sum::<Decimal>()
     }
 
+    // Only short sells may be processed partially: the rest of the quantity is an open short
+    // position which will be closed by the following buys.
     pub fn process(&mut self, sources: Vec<StockSellSource>) {
         assert!(!self.is_processed());
-        assert_eq!(
-            sources.iter()
-                .map(|source| source.multiplier * source.quantity)
-                .sum::<Decimal>(),
-            self.quantity,
-        );
-        self.sources = sources;
+        self.sources.extend(sources.into_iter());
+
+        let unprocessed = self.get_unprocessed();
+        assert!(!unprocessed.is_sign_negative());
+        assert!(self.short || unprocessed.is_zero());
+    }
+
+    pub fn sources(&self) -> &[StockSellSource] {
+        &self.sources
+    }
+
+    // Lots which have been moved to another portfolio by the in-kind transfer
+    pub fn transferred_lots(&self) -> &[StockSellSource] {
+        assert!(matches!(self.type_, StockSellType::Transfer));
+        assert!(self.is_processed());
+        &self.sources
+    }
+
+    pub fn is_short(&self) -> bool {
+        self.short
+    }
+
+    // Adds paid (positive) or received (negative) premium of the exercised or assigned option which
+    // is taken into account as an additional cost of the sell
+    pub fn add_option_premium(&mut self, date: Date, premium: Cash) {
+        assert!(self.sources.is_empty());
+        self.option_premium.add_transaction(date, premium);
+    }
+
+    pub fn open_short(&mut self) {
+        assert!(self.sources.is_empty());
+        self.short = true;
+    }
+
+    // Splits the specified quantity off to a separate trade
+    pub fn split(&mut self, quantity: Decimal) -> StockSell {
+        assert!(self.sources.is_empty());
+        assert!(dec!(0) < quantity && quantity < self.quantity);
+
+        let mut other = self.clone();
+        other.quantity = quantity;
+
+        let ratio = quantity / self.quantity;
+        other.option_premium.scale(ratio);
+        self.option_premium.scale(dec!(1) - ratio);
+
+        if let StockSellType::Trade {price, volume, commission} = self.type_ {
+            let other_volume = volume * quantity / self.quantity;
+            let other_commission = commission * quantity / self.quantity;
+
+            self.type_ = StockSellType::Trade {
+                price,
+                volume: volume - other_volume,
+                commission: commission - other_commission,
+            };
+
+            other.type_ = StockSellType::Trade {
+                price,
+                volume: other_volume,
+                commission: other_commission,
+            };
+        }
+
+        self.quantity -= quantity;
+        other
+    }
+
+    // The date when profit is realized: profit of short positions is realized only when they are
+    // closed.
+    pub fn realization_date(&self) -> Date {
+        assert!(self.is_processed());
+
+        if self.short {
+            self.sources.iter()
+                .map(|source| source.execution_date)
+                .fold(self.execution_date, std::cmp::max)
+        } else {
+            self.execution_date
+        }
+    }
+
+    // Result of the sell is projected if it's emulated or closed by an emulated buy
+    pub fn is_projected(&self) -> bool {
+        self.emulation || self.sources.iter().any(|source| source.emulation)
     }
 
     pub fn calculate(
@@ -206,11 +397,23 @@ impl StockSell {
             _ => unreachable!(),
         };
 
+        // Tax exemptions depend on ownership of the stocks, so they aren't applicable to short
+        // positions.
+        let tax_exemptions: &[TaxExemption] = if self.short {
+            &[]
+        } else {
+            tax_exemptions
+        };
+
+        // Revenue is converted at the date when profit is realized to be consistent with the date
+        // of income in tax statement.
+        let realization_date = self.realization_date();
+
         let currency = price.currency;
         let local_conclusion = |value| converter.convert_to_cash_rounding(
             self.conclusion_time.date, value, country.currency);
         let local_execution = |value| converter.convert_to_cash_rounding(
-            self.execution_date, value, country.currency);
+            realization_date, value, country.currency);
 
         let mut purchase_cost = Cash::zero(currency);
         let mut purchase_local_cost = Cash::zero(country.currency);
@@ -270,6 +473,13 @@ impl StockSell {
         assert_eq!(total_quantity, self.quantity);
         let taxable_ratio = (total_quantity - tax_free_quantity) / total_quantity;
 
+        let option_premium = self.option_premium.calculate(None, currency, converter)?;
+        let local_option_premium = self.option_premium.calculate(None, country.currency, converter)?;
+
+        purchase_cost += option_premium;
+        purchase_local_cost += local_option_premium;
+        deductible_purchase_local_cost += (local_option_premium * taxable_ratio).round();
+
         let revenue = volume.round();
         let local_revenue = local_execution(revenue)?;
         let taxable_local_revenue = local_execution(revenue * taxable_ratio)?;
@@ -294,7 +504,7 @@ impl StockSell {
         assert!(!tax_deduction.is_negative());
 
         Ok(SellDetails {
-            execution_date: self.execution_date,
+            execution_date: self.realization_date(),
 
             revenue,
             local_revenue,
@@ -316,7 +526,7 @@ impl StockSell {
     }
 }
 
-#[derive(Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct StockSellSource {
     pub original_symbol: String,
 
@@ -328,6 +538,7 @@ pub struct StockSellSource {
 
     pub conclusion_time: DateOptTime,
     pub execution_date: Date,
+    pub emulation: bool,
 }
 
 pub struct SellDetails {
@@ -422,7 +633,7 @@ impl FifoDetails {
                     local_cost,
                 }
             },
-            StockSource::CorporateAction => StockSourceDetails::CorporateAction,
+            StockSource::CorporateAction | StockSource::Transfer => StockSourceDetails::CorporateAction,
         };
 
         Ok(FifoDetails {
@@ -469,7 +680,7 @@ impl FifoDetails {
 // calculation in other currencies.
 //
 // Please note that it may be zero due to corporate actions or other non-trade operations.
-#[derive(Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PurchaseTotalCost(Vec<PurchaseCost>);
 
 impl PurchaseTotalCost {
@@ -502,6 +713,30 @@ impl PurchaseTotalCost {
         self.0.extend(cost.0.iter().map(Clone::clone))
     }
 
+    fn scale(&mut self, ratio: Decimal) {
+        for cost in &mut self.0 {
+            cost.fraction.0 *= ratio;
+        }
+    }
+
+    fn reduce(&mut self, date: Date, amount: Cash) {
+        self.add_transaction(date, -amount);
+    }
+
+    fn add_transaction(&mut self, date: Date, amount: Cash) {
+        self.0.push(PurchaseCost {
+            transactions: vec![PurchaseTransaction::new(date, PurchaseCostType::Trade, amount)],
+            fraction: Fraction(dec!(1), dec!(1)),
+        });
+    }
+
+    // Purchase transactions with cost that is proportional to the lot fraction
+    pub fn transactions(&self) -> impl Iterator<Item=(Date, PurchaseCostType, Cash)> + '_ {
+        self.0.iter().flat_map(|cost| cost.transactions.iter().map(move |transaction| {
+            (transaction.date, transaction.type_, transaction.cost / cost.fraction.1 * cost.fraction.0)
+        }))
+    }
+
     fn calculate(&self, type_: Option<PurchaseCostType>, currency: &str, converter: &CurrencyConverter) -> GenericResult<Cash> {
         let mut total_cost = dec!(0);
 
@@ -526,24 +761,24 @@ impl PurchaseTotalCost {
     }
 }
 
-#[derive(Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 struct PurchaseCost {
     transactions: Vec<PurchaseTransaction>,
     fraction: Fraction,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 struct Fraction(Decimal, Decimal);
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 struct PurchaseTransaction {
     date: Date,
     type_: PurchaseCostType,
     cost: Cash,
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum PurchaseCostType {
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PurchaseCostType {
     Trade,
     Commission,
 }
@@ -552,4 +787,55 @@ impl PurchaseTransaction {
     fn new(date: Date, type_: PurchaseCostType, cost: Cash) -> PurchaseTransaction {
         PurchaseTransaction {date, type_, cost}
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::brokers::Broker;
+    use crate::broker_statement::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
+    use crate::config::Config;
+    use crate::taxes::TaxRemapping;
+
+    use super::*;
+
+    #[test]
+    fn short_positions() {
+        let broker = Broker::Ledger.get_info(&Config::mock(), None).unwrap();
+
+        let mut statement = BrokerStatement::read(
+            broker, "testdata/shorts", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(),
+            &[], StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
+
+        statement.process_trades(None).unwrap();
+        assert!(statement.open_positions.is_empty());
+
+        let sells = statement.stock_sells.iter().map(|trade| {
+            let sources = trade.sources().iter()
+                .map(|source| (source.execution_date, source.quantity))
+                .collect::<Vec<_>>();
+
+            (trade.symbol.as_str(), trade.quantity, trade.is_short(), trade.realization_date(), sources)
+        }).collect::<Vec<_>>();
+
+        assert_eq!(sells, vec![
+            // The sell closes the long position and opens a short one
+            ("VTI", dec!(10), false, date!(2021, 3, 3), vec![(date!(2021, 2, 3), dec!(10))]),
+            ("VTI", dec!(5), true, date!(2021, 4, 5), vec![(date!(2021, 4, 5), dec!(5))]),
+
+            // Profit of the short position is realized in the next year
+            ("AAPL", dec!(3), true, date!(2022, 1, 12), vec![(date!(2022, 1, 12), dec!(3))]),
+        ]);
+
+        let volumes = statement.stock_sells.iter().map(|trade| match trade.type_ {
+            StockSellType::Trade {volume, commission, ..} => (volume.amount, commission.amount),
+            _ => unreachable!(),
+        }).collect::<Vec<_>>();
+
+        assert_eq!(volumes, vec![
+            (dec!(2100), dec!(1)),
+            (dec!(1050), dec!(0.5)),
+            (dec!(450), dec!(1)),
+        ]);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+use crate::config::{Config, PortfolioConfig};
+use crate::core::{EmptyResult, GenericResult};
+use crate::formatting::format_date;
+use crate::time::{Date, DateOptTime, deserialize_date, deserialize_optional_date};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+
+use super::{BrokerStatement, ReadingStrictness, StatementCache};
+use super::trades::{StockBuy, StockSell, StockSellSource, StockSellType};
+
+// In-kind transfer of stocks to another portfolio. It's declared in the sending portfolio's
+// configuration: the lots are taken from its open positions by FIFO and are moved to the receiving
+// portfolio with their original acquisition dates and costs.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StockTransfer {
+    // The date when the stocks have left the sending portfolio
+    #[serde(deserialize_with = "deserialize_date")]
+    pub date: Date,
+
+    // The date when the stocks have arrived to the receiving portfolio (the same by default)
+    #[serde(default, deserialize_with = "deserialize_optional_date")]
+    pub arrival_date: Option<Date>,
+
+    pub symbol: String,
+    pub quantity: Decimal,
+
+    // Receiving portfolio name
+    pub to: String,
+}
+
+impl StockTransfer {
+    // The stocks leave the portfolio after all trades of the day and arrive to the receiving
+    // portfolio before any trades of the day, so they can be sold on the same day.
+    fn departure_time(&self) -> DateOptTime {
+        DateOptTime::new_max_time(self.date)
+    }
+
+    fn arrival_time(&self) -> DateOptTime {
+        self.arrival_date.unwrap_or(self.date).into()
+    }
+
+    fn validate(&self) -> EmptyResult {
+        util::validate_named_decimal("quantity", self.quantity, DecimalRestrictions::StrictlyPositive)?;
+
+        if let Some(arrival_date) = self.arrival_date {
+            if arrival_date < self.date {
+                return Err!("Arrival date is less than transfer date: {} < {}",
+                            format_date(arrival_date), format_date(self.date));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn validate_stock_transfers(config: &Config) -> EmptyResult {
+    for portfolio in &config.portfolios {
+        let mut transfers = HashSet::new();
+
+        for transfer in &portfolio.transfers {
+            let description = || format!(
+                "{} {} transfer from {:?} portfolio to {:?}",
+                format_date(transfer.date), transfer.symbol, portfolio.name, transfer.to);
+
+            if !transfers.insert((transfer.date, &transfer.symbol)) {
+                return Err!("Got a duplicated {}", description());
+            }
+
+            if transfer.to == portfolio.name {
+                return Err!("Invalid {}: the stocks are transferred to the same portfolio", description());
+            }
+
+            config.get_portfolio(&transfer.to).and_then(|_| transfer.validate()).map_err(|e| format!(
+                "Invalid {}: {}", description(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Outgoing and incoming stock transfers of the portfolio
+#[derive(Default)]
+pub struct StockTransfers {
+    outgoing: Vec<StockTransfer>,
+    incoming: Vec<IncomingTransfer>,
+}
+
+struct IncomingTransfer {
+    transfer: StockTransfer,
+    lots: Vec<StockSellSource>,
+}
+
+impl StockTransfers {
+    pub fn new() -> StockTransfers {
+        StockTransfers::default()
+    }
+
+    // Reads statements of all portfolios that send stocks to the specified one to get the transferred
+    // lots.
+    pub fn load(
+        config: &Config, portfolio: &PortfolioConfig, cache: &StatementCache, strictness: ReadingStrictness,
+    ) -> GenericResult<StockTransfers> {
+        StockTransfers::load_impl(config, portfolio, cache, strictness, &mut Vec::new())
+    }
+
+    fn load_impl(
+        config: &Config, portfolio: &PortfolioConfig, cache: &StatementCache, strictness: ReadingStrictness,
+        readers: &mut Vec<String>,
+    ) -> GenericResult<StockTransfers> {
+        readers.push(portfolio.name.clone());
+        let mut incoming = Vec::new();
+
+        for source in &config.portfolios {
+            let transfers: Vec<&StockTransfer> = source.transfers.iter()
+                .filter(|transfer| transfer.to == portfolio.name)
+                .collect();
+
+            if transfers.is_empty() {
+                continue;
+            }
+
+            if readers.contains(&source.name) {
+                return Err!(
+                    "Circular stock transfers between {:?} and {:?} portfolios are not supported",
+                    source.name, portfolio.name);
+            }
+
+            let statement = read_statement(config, source, cache, strictness, readers).map_err(|e| format!(
+                "Unable to get stocks transferred from {:?} portfolio to {:?}: {}",
+                source.name, portfolio.name, e))?;
+
+            for transfer in transfers {
+                let lots = get_transferred_lots(&statement, transfer)?;
+                incoming.push(IncomingTransfer {
+                    transfer: transfer.clone(),
+                    lots: lots.to_vec(),
+                });
+            }
+        }
+
+        readers.pop();
+
+        Ok(StockTransfers {
+            outgoing: portfolio.transfers.clone(),
+            incoming,
+        })
+    }
+
+    pub fn apply(self, statement: &mut BrokerStatement) {
+        for transfer in self.outgoing {
+            statement.stock_sells.push(StockSell::new_transfer(
+                &transfer.symbol, transfer.quantity, transfer.departure_time(), transfer.date));
+        }
+
+        for IncomingTransfer {transfer, lots} in self.incoming {
+            let time = transfer.arrival_time();
+
+            for lot in &lots {
+                statement.stock_buys.push(StockBuy::new_transfer(&transfer.symbol, lot, time, time.date));
+            }
+        }
+    }
+}
+
+fn read_statement(
+    config: &Config, portfolio: &PortfolioConfig, cache: &StatementCache, strictness: ReadingStrictness,
+    readers: &mut Vec<String>,
+) -> GenericResult<BrokerStatement> {
+    let broker = portfolio.broker_info(config)?;
+    let transfers = StockTransfers::load_impl(config, portfolio, cache, strictness, readers)?;
+
+    BrokerStatement::read(
+        broker, portfolio.statements_path()?, portfolio.account.as_deref(), &portfolio.symbol_remapping,
+        &portfolio.instrument_internal_ids, &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.corporate_actions,
+        transfers, cache, strictness)
+}
+
+fn get_transferred_lots<'a>(
+    statement: &'a BrokerStatement, transfer: &StockTransfer,
+) -> GenericResult<&'a [StockSellSource]> {
+    let time = transfer.departure_time();
+
+    // The symbol may be renamed later by a corporate action, so match by the original one
+    let trade = statement.stock_sells.iter().find(|trade| {
+        matches!(trade.type_, StockSellType::Transfer) &&
+            trade.original_symbol == transfer.symbol && trade.conclusion_time == time
+    }).ok_or_else(|| format!(
+        "Unable to find {} {} transfer in the broker statement",
+        format_date(transfer.date), transfer.symbol))?;
+
+    Ok(trade.transferred_lots())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer() {
+        let config = Config::load("testdata/configs/transfers/config.yaml").unwrap();
+        let read = |name: &str| {
+            let portfolio = config.get_portfolio(name).unwrap();
+            read_statement(
+                &config, portfolio, &StatementCache::new_disabled(), ReadingStrictness::all(),
+                &mut Vec::new()).unwrap()
+        };
+
+        let sender = read("sender");
+        assert_eq!(sender.open_positions, hashmap!{s!("VTI") => dec!(10)});
+
+        let transfer = &config.get_portfolio("sender").unwrap().transfers[0];
+        let lots = get_transferred_lots(&sender, transfer).unwrap().iter()
+            .map(|lot| (lot.conclusion_time.date, lot.execution_date, lot.quantity))
+            .collect::<Vec<_>>();
+
+        assert_eq!(lots, vec![
+            (date!(2020, 6, 3), date!(2020, 6, 5), dec!(25)),
+            (date!(2020, 9, 1), date!(2020, 9, 3), dec!(5)),
+        ]);
+
+        let receiver = read("receiver");
+        assert_eq!(receiver.open_positions, hashmap!{s!("VTI") => dec!(7)});
+
+        let buys = receiver.stock_buys.iter()
+            .map(|trade| (trade.conclusion_time.date, trade.quantity, trade.get_unsold()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(buys, vec![
+            (date!(2021, 3, 17), dec!(25), dec!(0)),
+            (date!(2021, 3, 17), dec!(5), dec!(2)),
+            (date!(2021, 4, 1), dec!(5), dec!(5)),
+        ]);
+    }
+}
@@ -0,0 +1,112 @@
+use xls_table_derive::XlsTableRow;
+
+use crate::broker_statement::partial::PartialBrokerStatementRc;
+use crate::core::{EmptyResult, GenericResult};
+use crate::instruments::parse_isin;
+use crate::types::Decimal;
+use crate::util::DecimalRestrictions;
+use crate::xls::{self, XlsStatementParser, SectionParser, Cell, TableReader};
+
+use super::common::{parse_cash, parse_quantity_cell};
+
+pub struct CashAssetsParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl CashAssetsParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(CashAssetsParser {statement})
+    }
+}
+
+impl SectionParser for CashAssetsParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+        statement.has_starting_assets.get_or_insert(false);
+
+        for assets in xls::read_table::<CashAssetsRow>(&mut parser.sheet)? {
+            if !assets.starting.is_zero() {
+                statement.has_starting_assets.replace(true);
+            }
+
+            let planned = parse_cash(&assets.currency, assets.planned, DecimalRestrictions::No)?;
+            if planned.is_negative() {
+                return Err!("Leverage is not supported yet");
+            } else if !planned.is_zero() {
+                statement.assets.cash.as_mut().unwrap().deposit(planned);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+struct CashAssetsRow {
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Входящий остаток")]
+    starting: Decimal,
+    #[column(name="Исходящий остаток")]
+    _2: Decimal,
+    #[column(name="Плановый исходящий остаток")]
+    planned: Decimal,
+}
+
+impl TableReader for CashAssetsRow {
+}
+
+pub struct SecuritiesParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl SecuritiesParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(SecuritiesParser {statement})
+    }
+}
+
+impl SectionParser for SecuritiesParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+        statement.has_starting_assets.get_or_insert(false);
+
+        for security in xls::read_table::<SecurityRow>(&mut parser.sheet)? {
+            let instrument = statement.instrument_info.get_or_add(&security.symbol);
+            instrument.set_name(&security.name);
+            instrument.add_isin(parse_isin(&security.isin)?);
+
+            if !security.starting.is_zero() {
+                statement.has_starting_assets.replace(true);
+            }
+
+            if !security.planned.is_zero() {
+                statement.add_open_position(&security.symbol, security.planned)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+struct SecurityRow {
+    #[column(name="Наименование ценной бумаги")]
+    name: String,
+    #[column(name="ISIN")]
+    isin: String,
+    #[column(name="Код")]
+    symbol: String,
+    #[column(name="Входящий остаток", parse_with="parse_quantity_cell")]
+    starting: Decimal,
+    #[column(name="Исходящий остаток", parse_with="parse_quantity_cell")]
+    _4: Decimal,
+    #[column(name="Плановый исходящий остаток", parse_with="parse_quantity_cell")]
+    planned: Decimal,
+}
+
+impl TableReader for SecurityRow {
+    fn skip_row(row: &[Option<&Cell>]) -> GenericResult<bool> {
+        Ok(matches!(row[0], Some(Cell::String(value)) if value.starts_with("Итого")))
+    }
+}
@@ -0,0 +1,155 @@
+use chrono::Datelike;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use xls_table_derive::XlsTableRow;
+
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
+use crate::broker_statement::fees::Fee;
+use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
+use crate::broker_statement::taxes::TaxWithholding;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, CashAssets};
+use crate::instruments::{InstrumentId, ISIN_REGEX, parse_isin};
+use crate::time::Date;
+use crate::types::Decimal;
+use crate::util::DecimalRestrictions;
+use crate::xls::{self, XlsStatementParser, SectionParser, TableReader};
+
+use super::common::{parse_cash, parse_date_cell};
+
+pub struct CashFlowsParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl CashFlowsParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(CashFlowsParser {statement})
+    }
+}
+
+impl SectionParser for CashFlowsParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        for cash_flow in xls::read_table::<CashFlowRow>(&mut parser.sheet)? {
+            cash_flow.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {:?} cash flow: {}", cash_flow.operation, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+struct CashFlowRow {
+    #[column(name="Дата", parse_with="parse_date_cell")]
+    date: Date,
+    #[column(name="Сумма")]
+    amount: Decimal,
+    #[column(name="Валюта")]
+    currency: String,
+    #[column(name="Тип операции")]
+    operation: String,
+    #[column(name="Комментарий")]
+    comment: Option<String>,
+}
+
+impl TableReader for CashFlowRow {
+}
+
+impl CashFlowRow {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let date = self.date;
+        let operation = self.operation.as_str();
+        let comment = self.comment.as_deref().unwrap_or_default();
+
+        let deposit = || parse_cash(&self.currency, self.amount, DecimalRestrictions::StrictlyPositive);
+        let withdrawal = || -> GenericResult<Cash> {
+            Ok(-parse_cash(&self.currency, self.amount, DecimalRestrictions::StrictlyNegative)?)
+        };
+
+        match operation {
+            "Зачисление денежных средств" => {
+                statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(date, deposit()?));
+            },
+            "Списание денежных средств" => {
+                statement.deposits_and_withdrawals.push(CashAssets::new_from_cash(date, -withdrawal()?));
+            },
+
+            // Trade settlements and commissions are taken into account during trades processing
+            "Расчеты по сделке" | "Комиссия за сделку" | "Перевод денежных средств" => {},
+
+            "Вознаграждение Брокера" | "Плата за депозитарное обслуживание" => {
+                statement.fees.push(Fee::new(date, withdrawal()?, Some(comment_or(comment, operation))));
+            },
+
+            "Дивиденды" => {
+                let issuer = parse_comment_isin(comment)?;
+                statement.dividend_accruals(date, issuer, true).add(date, deposit()?);
+            },
+            "Налог по дивидендам" => {
+                let issuer = parse_comment_isin(comment)?;
+                statement.tax_accruals(date, issuer, true).add(date, withdrawal()?);
+            },
+
+            "Купонный доход" | "Погашение ценных бумаг" | "Частичное погашение ценных бумаг" => {
+                let type_ = match operation {
+                    "Купонный доход" => BondPaymentType::Coupon,
+                    "Погашение ценных бумаг" => BondPaymentType::Redemption,
+                    _ => BondPaymentType::Amortization,
+                };
+
+                let issuer = parse_comment_isin(comment)?;
+                statement.bond_payments.push(BondPayment::new(date, issuer, type_, deposit()?));
+            },
+
+            "НДФЛ" => {
+                let tax_withholding = TaxWithholding::new(date, date.year(), withdrawal()?)?;
+                statement.tax_agent_withholdings.push(tax_withholding);
+            },
+
+            _ => return Err!("Unsupported cash flow operation"),
+        }
+
+        Ok(())
+    }
+}
+
+fn comment_or(comment: &str, operation: &str) -> String {
+    if comment.is_empty() {
+        operation
+    } else {
+        comment
+    }.to_owned()
+}
+
+// Example: "Дивиденды по ценным бумагам Сбербанк ао (ISIN RU0009029540), 10 шт."
+fn parse_comment_isin(comment: &str) -> GenericResult<InstrumentId> {
+    lazy_static! {
+        static ref COMMENT_ISIN_REGEX: Regex = Regex::new(&format!(
+            r"\bISIN:? (?P<isin>{})\b", ISIN_REGEX)).unwrap();
+    }
+
+    let captures = COMMENT_ISIN_REGEX.captures(comment).ok_or_else(|| format!(
+        "Unable to find security ISIN in the operation comment: {:?}", comment))?;
+
+    Ok(InstrumentId::Isin(parse_isin(captures.name("isin").unwrap().as_str())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(comment, isin,
+        case("Дивиденды по ценным бумагам Сбербанк ао (ISIN RU0009029540), 10 шт.", "RU0009029540"),
+        case("Купон по облигациям ОФЗ 26207 ISIN: RU000A0JS3W6", "RU000A0JS3W6"),
+    )]
+    fn comment_isin_parsing(comment: &str, isin: &str) {
+        assert_eq!(
+            parse_comment_isin(comment).unwrap(),
+            InstrumentId::Isin(parse_isin(isin).unwrap()),
+        );
+    }
+}
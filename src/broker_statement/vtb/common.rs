@@ -0,0 +1,37 @@
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::time::{self, Date, DateTime};
+use crate::types::Decimal;
+use crate::util::{self, DecimalRestrictions};
+use crate::xls::{self, Cell};
+
+pub fn parse_date(date: &str) -> GenericResult<Date> {
+    time::parse_date(date, "%d.%m.%Y")
+}
+
+pub fn parse_date_cell(cell: &Cell) -> GenericResult<Date> {
+    parse_date(xls::get_string_cell(cell)?)
+}
+
+pub fn parse_date_time_cell(cell: &Cell) -> GenericResult<DateTime> {
+    time::parse_date_time(xls::get_string_cell(cell)?, "%d.%m.%Y %H:%M:%S")
+}
+
+pub fn parse_quantity_cell(cell: &Cell) -> GenericResult<Decimal> {
+    let quantity: Decimal = xls::CellType::parse(cell)?;
+    if quantity.is_sign_negative() || !quantity.fract().is_zero() {
+        return Err!("Invalid quantity: {}", quantity);
+    }
+    Ok(quantity)
+}
+
+pub fn parse_currency(currency: &str) -> &str {
+    match currency {
+        "RUR" => "RUB",
+        _ => currency,
+    }
+}
+
+pub fn parse_cash(currency: &str, amount: Decimal, restrictions: DecimalRestrictions) -> GenericResult<Cash> {
+    Ok(Cash::new(parse_currency(currency), util::validate_decimal(amount, restrictions)?))
+}
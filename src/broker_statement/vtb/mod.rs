@@ -0,0 +1,100 @@
+mod assets;
+mod cash_flows;
+mod common;
+mod period;
+mod trades;
+
+use std::rc::Rc;
+
+#[cfg(test)] use crate::brokers::Broker;
+#[cfg(test)] use crate::config::Config;
+use crate::core::GenericResult;
+use crate::exchanges::Exchange;
+#[cfg(test)] use crate::taxes::TaxRemapping;
+use crate::xls::{XlsStatementParser, Section, SheetParser};
+
+#[cfg(test)] use super::{BrokerStatement, ReadingStrictness, StatementCache, StockTransfers};
+use super::{BrokerStatementReader, PartialBrokerStatement};
+
+use assets::{CashAssetsParser, SecuritiesParser};
+use cash_flows::CashFlowsParser;
+use period::PeriodParser;
+use trades::TradesParser;
+
+pub struct StatementReader {
+}
+
+impl StatementReader {
+    pub fn new() -> GenericResult<Box<dyn BrokerStatementReader>> {
+        Ok(Box::new(StatementReader{}))
+    }
+}
+
+impl BrokerStatementReader for StatementReader {
+    fn check(&mut self, path: &str) -> GenericResult<bool> {
+        Ok(path.ends_with(".xls") || path.ends_with(".xlsx"))
+    }
+
+    fn read(&self, path: &str, _is_last: bool) -> GenericResult<PartialBrokerStatement> {
+        let parser = Box::new(StatementSheetParser{});
+        let statement = PartialBrokerStatement::new_rc(&[Exchange::Moex, Exchange::Spb], true);
+
+        XlsStatementParser::read(path, parser, vec![
+            Section::new(PeriodParser::TITLE).by_prefix()
+                .parser(PeriodParser::new(statement.clone())).required(),
+            Section::new("Отчет об остатках денежных средств")
+                .parser(CashAssetsParser::new(statement.clone())).required(),
+            Section::new("Отчет об остатках ценных бумаг")
+                .parser(SecuritiesParser::new(statement.clone())),
+            Section::new("Заключенные в отчетном периоде сделки с ценными бумагами")
+                .parser(TradesParser::new(statement.clone())),
+            Section::new("Движение денежных средств")
+                .parser(CashFlowsParser::new(statement.clone())),
+        ])?;
+
+        Rc::try_unwrap(statement).ok().unwrap().into_inner().validate()
+    }
+}
+
+pub struct StatementSheetParser {
+}
+
+impl StatementSheetParser {
+    pub const SHEET_NAME: &'static str = "Отчет";
+}
+
+impl SheetParser for StatementSheetParser {
+    fn sheet_name(&self) -> &str {
+        StatementSheetParser::SHEET_NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let broker = Broker::Vtb.get_info(&Config::mock(), None).unwrap();
+
+        let statement = BrokerStatement::read(
+            broker, "testdata/vtb", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(), &[],
+            StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
+
+        assert!(!statement.cash_assets.is_empty());
+        assert!(!statement.deposits_and_withdrawals.is_empty());
+
+        assert!(!statement.fees.is_empty());
+        assert!(statement.idle_cash_interest.is_empty());
+        assert!(statement.tax_agent_withholdings.is_empty());
+
+        assert!(statement.forex_trades.is_empty());
+        assert!(!statement.stock_buys.is_empty());
+        assert!(statement.stock_sells.is_empty());
+        assert!(!statement.dividends.is_empty());
+
+        assert!(!statement.open_positions.is_empty());
+        assert!(!statement.instrument_info.is_empty());
+    }
+}
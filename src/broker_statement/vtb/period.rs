@@ -0,0 +1,49 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::broker_statement::partial::PartialBrokerStatementRc;
+use crate::core::{EmptyResult, GenericResult};
+use crate::time::Period;
+use crate::xls::{self, XlsStatementParser, SectionParser};
+
+use super::common::parse_date;
+
+pub struct PeriodParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl PeriodParser {
+    pub const TITLE: &'static str = "Отчет Банка ВТБ (ПАО) за период";
+
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(PeriodParser {statement})
+    }
+}
+
+impl SectionParser for PeriodParser {
+    fn consume_title(&self) -> bool { false }
+
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let row = xls::strip_row_expecting_columns(parser.sheet.next_row_checked()?, 1)?;
+        let period = parse_period(xls::get_string_cell(row[0])?.trim())?;
+        self.statement.borrow_mut().set_period(period)?;
+        Ok(())
+    }
+}
+
+// Example: "Отчет Банка ВТБ (ПАО) за период с 01.03.2021 по 31.03.2021 о сделках, операциях с ценными
+// бумагами и иными финансовыми инструментами"
+fn parse_period(value: &str) -> GenericResult<Period> {
+    lazy_static! {
+        static ref PERIOD_REGEX: Regex = Regex::new(
+            r"^Отчет Банка ВТБ \(ПАО\) за период с (?P<start>\d{2}\.\d{2}\.\d{4}) по (?P<end>\d{2}\.\d{2}\.\d{4})\b").unwrap();
+    }
+
+    let captures = PERIOD_REGEX.captures(value).ok_or_else(|| format!(
+        "Invalid statement period: {:?}", value))?;
+
+    Period::new(
+        parse_date(captures.name("start").unwrap().as_str())?,
+        parse_date(captures.name("end").unwrap().as_str())?,
+    )
+}
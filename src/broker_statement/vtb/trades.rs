@@ -0,0 +1,133 @@
+use xls_table_derive::XlsTableRow;
+
+use crate::broker_statement::bonds::{BondPayment, BondPaymentType};
+use crate::broker_statement::partial::{PartialBrokerStatement, PartialBrokerStatementRc};
+use crate::broker_statement::trades::{StockBuy, StockSell};
+use crate::core::{EmptyResult, GenericResult};
+use crate::exchanges::Exchange;
+use crate::instruments::InstrumentId;
+use crate::time::{Date, DateTime};
+use crate::types::Decimal;
+use crate::util::DecimalRestrictions;
+use crate::xls::{self, XlsStatementParser, SectionParser, Cell, SkipCell, TableReader};
+
+use super::common::{parse_cash, parse_date_cell, parse_date_time_cell, parse_quantity_cell};
+
+pub struct TradesParser {
+    statement: PartialBrokerStatementRc,
+}
+
+impl TradesParser {
+    pub fn new(statement: PartialBrokerStatementRc) -> Box<dyn SectionParser> {
+        Box::new(TradesParser {statement})
+    }
+}
+
+impl SectionParser for TradesParser {
+    fn parse(&mut self, parser: &mut XlsStatementParser) -> EmptyResult {
+        let mut statement = self.statement.borrow_mut();
+
+        let mut trades = xls::read_table::<TradeRow>(&mut parser.sheet)?;
+        trades.sort_by_key(|trade| trade.time);
+
+        for trade in trades {
+            trade.parse(&mut statement).map_err(|e| format!(
+                "Failed to parse {:?} trade: {}", trade.id, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(XlsTableRow)]
+struct TradeRow {
+    #[column(name="Наименование ценной бумаги")]
+    _0: SkipCell,
+    #[column(name="Код")]
+    symbol: String,
+    #[column(name="№ сделки")]
+    id: String,
+    #[column(name="Дата и время заключения сделки", parse_with="parse_date_time_cell")]
+    time: DateTime,
+    #[column(name="Вид сделки")]
+    operation: String,
+    #[column(name="Количество", parse_with="parse_quantity_cell")]
+    quantity: Decimal,
+    #[column(name="Цена")]
+    price: Decimal,
+    #[column(name="Валюта цены")]
+    currency: String,
+    #[column(name="Сумма сделки")]
+    volume: Decimal,
+    #[column(name="НКД")]
+    accrued_interest: Option<Decimal>,
+    #[column(name="Плановая дата поставки", parse_with="parse_date_cell")]
+    execution_date: Date,
+    #[column(name="Комиссия Банка")]
+    commission: Decimal,
+    #[column(name="Комиссия биржи")]
+    exchange_commission: Decimal,
+    #[column(name="Место заключения сделки", optional=true)]
+    exchange: Option<String>,
+}
+
+impl TableReader for TradeRow {
+    fn skip_row(row: &[Option<&Cell>]) -> GenericResult<bool> {
+        Ok(matches!(row[0], Some(Cell::String(value)) if value.starts_with("Итого")))
+    }
+}
+
+impl TradeRow {
+    fn parse(&self, statement: &mut PartialBrokerStatement) -> EmptyResult {
+        let symbol = &self.symbol;
+
+        let price = parse_cash(&self.currency, self.price, DecimalRestrictions::StrictlyPositive)?;
+        let volume = parse_cash(&self.currency, self.volume, DecimalRestrictions::StrictlyPositive)?;
+
+        // Broker and exchange commissions are charged separately
+        let commission = parse_cash(&self.currency, self.commission, DecimalRestrictions::PositiveOrZero)? +
+            parse_cash(&self.currency, self.exchange_commission, DecimalRestrictions::PositiveOrZero)?;
+
+        // Trade volume doesn't include accrued coupon income which is accounted separately
+        let accrued_interest = parse_cash(
+            &self.currency, self.accrued_interest.unwrap_or_default(), DecimalRestrictions::PositiveOrZero)?;
+
+        let exchange = match self.exchange.as_deref() {
+            Some(exchange) if exchange.contains("СПБ") => Exchange::Spb,
+            _ => Exchange::Moex,
+        };
+        statement.instrument_info.get_or_add(symbol).exchanges.add_prioritized(exchange);
+
+        // Bond prices are specified in percents of face value
+        let price = if accrued_interest.is_zero() {
+            debug_assert_eq!(volume, (price * self.quantity).round());
+            price
+        } else {
+            volume / self.quantity
+        };
+
+        let (buy, accrued_interest) = match self.operation.as_str() {
+            "Покупка" => (true, -accrued_interest),
+            "Продажа" => (false, accrued_interest),
+            _ => return Err!("Unsupported trade operation: {:?}", self.operation),
+        };
+
+        if buy {
+            statement.stock_buys.push(StockBuy::new_trade(
+                symbol, self.quantity, price, volume, commission,
+                self.time.into(), self.execution_date));
+        } else {
+            statement.stock_sells.push(StockSell::new_trade(
+                symbol, self.quantity, price, volume, commission,
+                self.time.into(), self.execution_date, false));
+        }
+
+        if !accrued_interest.is_zero() {
+            statement.bond_payments.push(BondPayment::new(
+                self.time.date(), InstrumentId::Symbol(symbol.clone()),
+                BondPaymentType::AccruedInterest, accrued_interest));
+        }
+
+        Ok(())
+    }
+}
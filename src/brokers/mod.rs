@@ -13,15 +13,20 @@ use crate::core::GenericResult;
 use crate::currency::CashAssets;
 use crate::exchanges::Exchange;
 use crate::localities::Jurisdiction;
+use crate::tax_statement::CountryCode;
 use crate::types::Decimal;
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
 pub enum Broker {
     Bcs,
     Firstrade,
+    FreedomFinance,
     InteractiveBrokers,
+    Ledger,
     Open,
+    Sber,
     Tinkoff,
+    Vtb,
 }
 
 impl Broker {
@@ -36,6 +41,9 @@ impl Broker {
             Broker::Bcs => StatementsMergingStrategy::SparseSingleDaysLastMonth(9),
             Broker::InteractiveBrokers => StatementsMergingStrategy::SparseOnHolidays(1),
             Broker::Open => StatementsMergingStrategy::SparseSingleDaysLastMonth(0),
+            // Sber sends daily statements for the current month and monthly statement of the previous
+            // month on its first day
+            Broker::Sber => StatementsMergingStrategy::SparseSingleDaysLastMonth(0),
             _ => StatementsMergingStrategy::ContinuousOnly,
         };
 
@@ -43,6 +51,7 @@ impl Broker {
             type_: self,
             name: self.name(),
             brief_name: self.brief_name(),
+            jurisdiction: self.jurisdiction(),
 
             config: config,
             commission_spec: self.get_commission_spec(plan)?,
@@ -56,9 +65,13 @@ impl Broker {
         match self {
             Broker::Bcs => "bcs",
             Broker::Firstrade => "firstrade",
+            Broker::FreedomFinance => "freedom-finance",
             Broker::InteractiveBrokers => "interactive-brokers",
+            Broker::Ledger => "ledger",
             Broker::Open => "open",
+            Broker::Sber => "sber",
             Broker::Tinkoff => "tinkoff",
+            Broker::Vtb => "vtb",
         }
     }
 
@@ -66,9 +79,13 @@ impl Broker {
         match self {
             Broker::Bcs => "ООО «Компания БКС»",
             Broker::Firstrade => "Firstrade Securities Inc.",
+            Broker::FreedomFinance => "Freedom Finance Global PLC",
             Broker::InteractiveBrokers => "Interactive Brokers LLC",
+            Broker::Ledger => "Manual ledger",
             Broker::Open => "АО «Открытие Брокер»",
+            Broker::Sber => "ПАО «Сбербанк»",
             Broker::Tinkoff => "АО «Тинькофф Банк»",
+            Broker::Vtb => "Банк ВТБ (ПАО)",
         }
     }
 
@@ -76,16 +93,25 @@ impl Broker {
         match self {
             Broker::Bcs => "БКС",
             Broker::Firstrade => "Firstrade",
+            Broker::FreedomFinance => "Freedom Finance",
             Broker::InteractiveBrokers => "Interactive Brokers",
+            Broker::Ledger => "Ledger",
             Broker::Open => "Открытие",
+            Broker::Sber => "Сбер",
             Broker::Tinkoff => "Тинькофф",
+            Broker::Vtb => "ВТБ",
         }
     }
 
     pub fn jurisdiction(self) -> Jurisdiction {
         match self {
-            Broker::Bcs | Broker::Open | Broker::Tinkoff => Jurisdiction::Russia,
-            Broker::Firstrade | Broker::InteractiveBrokers => Jurisdiction::Usa,
+            Broker::Bcs | Broker::Open | Broker::Sber | Broker::Tinkoff | Broker::Vtb => Jurisdiction::Russia,
+            // Manual ledger is intended for accounts of foreign brokers which aren't tax agents, so
+            // all income is declared by the investor. The jurisdiction may be overridden in the
+            // portfolio config.
+            Broker::Firstrade | Broker::InteractiveBrokers | Broker::Ledger => Jurisdiction::Usa,
+            // Freedom Finance Global PLC is registered in Astana International Financial Centre
+            Broker::FreedomFinance => Jurisdiction::Other(CountryCode::Other(398)),
         }
     }
 
@@ -93,9 +119,13 @@ impl Broker {
         match self {
             Broker::Bcs => &config.bcs,
             Broker::Firstrade => &config.firstrade,
+            Broker::FreedomFinance => &config.freedom_finance,
             Broker::InteractiveBrokers => &config.interactive_brokers,
+            Broker::Ledger => &config.ledger,
             Broker::Open => &config.open_broker,
+            Broker::Sber => &config.sber,
             Broker::Tinkoff => &config.tinkoff,
+            Broker::Vtb => &config.vtb,
         }.as_ref()
     }
 
@@ -109,18 +139,30 @@ impl Broker {
                 "Профессиональный" => plans::bcs::professional as PlanFn,
             }),
             Broker::Firstrade => (plans::firstrade::free, btreemap!{}),
+            Broker::FreedomFinance => (plans::freedom::standard, btreemap!{
+                "Стандартный" => plans::freedom::standard as PlanFn,
+            }),
             Broker::InteractiveBrokers => (plans::ib::fixed, btreemap!{
                 "Fixed" => plans::ib::fixed as PlanFn,
             }),
+            Broker::Ledger => (plans::ledger::free, btreemap!{}),
             Broker::Open => (plans::open::all_inclusive, btreemap!{
                 "Всё включено" => plans::open::all_inclusive as PlanFn,
                 "Самостоятельное управление (ИИС)" => plans::open::iia as PlanFn,
             }),
+            Broker::Sber => (plans::sber::independent, btreemap!{
+                "Самостоятельный" => plans::sber::independent as PlanFn,
+                "Инвестиционный" => plans::sber::investment as PlanFn,
+            }),
             Broker::Tinkoff => (plans::tinkoff::investor, btreemap!{
                 "Инвестор" => plans::tinkoff::investor as PlanFn,
                 "Трейдер" => plans::tinkoff::trader as PlanFn,
                 "Премиум" => plans::tinkoff::premium as PlanFn,
             }),
+            Broker::Vtb => (plans::vtb::my_online, btreemap!{
+                "Мой онлайн" => plans::vtb::my_online as PlanFn,
+                "Инвестор Стандарт" => plans::vtb::investor_standard as PlanFn,
+            }),
         };
 
         let plan = match plan {
@@ -144,12 +186,17 @@ impl<'de> Deserialize<'de> for Broker {
         Ok(match value.as_str() {
             "bcs" => Broker::Bcs,
             "firstrade" => Broker::Firstrade,
+            "freedom-finance" => Broker::FreedomFinance,
             "interactive-brokers" => Broker::InteractiveBrokers,
+            "ledger" => Broker::Ledger,
             "open-broker" => Broker::Open,
+            "sber" => Broker::Sber,
             "tinkoff" => Broker::Tinkoff,
+            "vtb" => Broker::Vtb,
 
             _ => return Err(D::Error::unknown_variant(&value, &[
-                "bcs", "firstrade", "interactive-brokers", "open-broker", "tinkoff",
+                "bcs", "firstrade", "freedom-finance", "interactive-brokers", "ledger", "open-broker",
+                "sber", "tinkoff", "vtb",
             ])),
         })
     }
@@ -160,6 +207,7 @@ pub struct BrokerInfo {
     pub type_: Broker,
     pub name: &'static str,
     pub brief_name: &'static str,
+    pub jurisdiction: Jurisdiction,
 
     config: BrokerConfig,
     pub commission_spec: CommissionSpec,
@@ -184,8 +232,12 @@ impl BrokerInfo {
 
     pub fn exchanges(&self) -> Vec<Exchange> {
         match self.type_ {
-            Broker::Bcs | Broker::Open | Broker::Tinkoff => vec![Exchange::Moex, Exchange::Spb],
-            Broker::Firstrade | Broker::InteractiveBrokers => vec![Exchange::Us],
+            Broker::Bcs | Broker::Open | Broker::Sber | Broker::Tinkoff | Broker::Vtb => {
+                vec![Exchange::Moex, Exchange::Spb]
+            },
+            Broker::Firstrade | Broker::FreedomFinance | Broker::InteractiveBrokers | Broker::Ledger => {
+                vec![Exchange::Us]
+            },
         }
     }
 }
\ No newline at end of file
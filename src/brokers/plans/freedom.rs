@@ -0,0 +1,45 @@
+#[cfg(test)] use crate::commissions::CommissionCalc;
+use crate::commissions::{
+    CommissionSpec, CommissionSpecBuilder, TradeCommissionSpecBuilder,
+    TransactionCommissionSpecBuilder};
+#[cfg(test)] use crate::currency::{Cash, converter::CurrencyConverter};
+#[cfg(test)] use crate::types::TradeType;
+
+pub fn standard() -> CommissionSpec {
+    CommissionSpecBuilder::new("USD")
+        .trade(TradeCommissionSpecBuilder::new()
+            .commission(TransactionCommissionSpecBuilder::new()
+                .percent(dec!(0.12))
+                .per_order(dec!(1.2))
+                .build().unwrap())
+            .build())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(trade_type => [TradeType::Buy, TradeType::Sell])]
+    fn standard(trade_type: TradeType) {
+        let currency = "USD";
+        let converter = CurrencyConverter::mock();
+        let mut calc = CommissionCalc::new(
+            converter, super::standard(), Cash::zero(currency)).unwrap();
+
+        let date = date!(2021, 3, 2);
+
+        for &(quantity, price, commission) in &[
+            (10, dec!(120.5), dec!(2.65)),
+            ( 3, dec!( 50.0), dec!(1.38)),
+        ] {
+            assert_eq!(
+                calc.add_trade(date, trade_type, quantity.into(), Cash::new(currency, price)).unwrap(),
+                Cash::new(currency, commission),
+            );
+        }
+
+        assert!(calc.calculate().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,6 @@
+use crate::commissions::{CommissionSpec, CommissionSpecBuilder};
+
+// Actual commissions are specified in the ledger, so this plan is used only for estimations
+pub fn free() -> CommissionSpec {
+    CommissionSpecBuilder::new("USD").build()
+}
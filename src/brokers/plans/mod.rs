@@ -1,5 +1,9 @@
 pub mod bcs;
 pub mod firstrade;
+pub mod freedom;
 pub mod ib;
+pub mod ledger;
 pub mod open;
-pub mod tinkoff;
\ No newline at end of file
+pub mod sber;
+pub mod tinkoff;
+pub mod vtb;
\ No newline at end of file
@@ -0,0 +1,74 @@
+#[cfg(test)] use crate::commissions::CommissionCalc;
+use crate::commissions::{CommissionSpec, CommissionSpecBuilder, CumulativeCommissionSpecBuilder};
+#[cfg(test)] use crate::currency::{Cash, converter::CurrencyConverter};
+#[cfg(test)] use crate::types::TradeType;
+
+pub fn independent() -> CommissionSpec {
+    CommissionSpecBuilder::new("RUB")
+        .cumulative(CumulativeCommissionSpecBuilder::new()
+            .percent(dec!(0.06))
+            .percent_fee(dec!(0.01)) // Exchange fee
+            .build())
+        .build()
+}
+
+pub fn investment() -> CommissionSpec {
+    CommissionSpecBuilder::new("RUB")
+        .cumulative(CumulativeCommissionSpecBuilder::new()
+            .percent(dec!(0.3))
+            .percent_fee(dec!(0.01)) // Exchange fee
+            .build())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(trade_type => [TradeType::Buy, TradeType::Sell])]
+    fn independent(trade_type: TradeType) {
+        let currency = "RUB";
+        let converter = CurrencyConverter::mock();
+        let mut calc = CommissionCalc::new(
+            converter, super::independent(), Cash::zero(currency)).unwrap();
+
+        for &(date, shares, price) in &[
+            (date!(2021, 3, 1), 10, dec!(250)),
+            (date!(2021, 3, 1), 20, dec!(100)),
+            (date!(2021, 3, 2),  5, dec!(300)),
+        ] {
+            assert_eq!(
+                calc.add_trade(date, trade_type, shares.into(), Cash::new(currency, price)).unwrap(),
+                Cash::zero(currency),
+            );
+        }
+
+        assert_eq!(calc.calculate().unwrap(), hashmap!{
+            date!(2021, 3, 1) => Cash::new(currency, dec!(2.70) + dec!(0.45)).into(),
+            date!(2021, 3, 2) => Cash::new(currency, dec!(0.90) + dec!(0.15)).into(),
+        });
+    }
+
+    #[rstest(trade_type => [TradeType::Buy, TradeType::Sell])]
+    fn investment(trade_type: TradeType) {
+        let currency = "RUB";
+        let converter = CurrencyConverter::mock();
+        let mut calc = CommissionCalc::new(
+            converter, super::investment(), Cash::zero(currency)).unwrap();
+
+        for &(date, shares, price) in &[
+            (date!(2021, 3, 1), 10, dec!(250)),
+            (date!(2021, 3, 1), 20, dec!(100)),
+        ] {
+            assert_eq!(
+                calc.add_trade(date, trade_type, shares.into(), Cash::new(currency, price)).unwrap(),
+                Cash::zero(currency),
+            );
+        }
+
+        assert_eq!(calc.calculate().unwrap(), hashmap!{
+            date!(2021, 3, 1) => Cash::new(currency, dec!(13.50) + dec!(0.45)).into(),
+        });
+    }
+}
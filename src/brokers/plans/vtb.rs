@@ -0,0 +1,81 @@
+#[cfg(test)] use crate::commissions::CommissionCalc;
+use crate::commissions::{
+    CommissionSpec, CommissionSpecBuilder, TradeCommissionSpecBuilder,
+    TransactionCommissionSpecBuilder, CumulativeCommissionSpecBuilder};
+#[cfg(test)] use crate::currency::{Cash, converter::CurrencyConverter};
+#[cfg(test)] use crate::types::TradeType;
+
+pub fn my_online() -> CommissionSpec {
+    CommissionSpecBuilder::new("RUB")
+        .trade(TradeCommissionSpecBuilder::new()
+            .commission(TransactionCommissionSpecBuilder::new()
+                .percent(dec!(0.05))
+                .build().unwrap())
+            .build())
+        .build()
+}
+
+pub fn investor_standard() -> CommissionSpec {
+    CommissionSpecBuilder::new("RUB")
+        .cumulative(CumulativeCommissionSpecBuilder::new()
+            .volume_tiered(btreemap!{
+                         0 => dec!(0.0413),
+                 1_000_000 => dec!(0.0354),
+                10_000_000 => dec!(0.0295),
+            }).unwrap()
+            .build())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(trade_type => [TradeType::Buy, TradeType::Sell])]
+    fn my_online(trade_type: TradeType) {
+        let converter = CurrencyConverter::mock();
+        let mut calc = CommissionCalc::new(
+            converter, super::my_online(), Cash::zero("RUB")).unwrap();
+
+        let date = date!(2021, 3, 1);
+
+        for &(currency, quantity, price, commission) in &[
+            ("RUB", 10, dec!(250.0), dec!(1.25)),
+            ("RUB", 20, dec!(100.0), dec!(1.00)),
+            ("RUB",  7, dec!(123.4), dec!(0.43)),
+            ("USD",  2, dec!(150.0), dec!(0.15)),
+        ] {
+            assert_eq!(
+                calc.add_trade(date, trade_type, quantity.into(), Cash::new(currency, price)).unwrap(),
+                Cash::new(currency, commission),
+            );
+        }
+
+        assert!(calc.calculate().unwrap().is_empty());
+    }
+
+    #[rstest(trade_type => [TradeType::Buy, TradeType::Sell])]
+    fn investor_standard(trade_type: TradeType) {
+        let currency = "RUB";
+        let converter = CurrencyConverter::mock();
+        let mut calc = CommissionCalc::new(
+            converter, super::investor_standard(), Cash::zero(currency)).unwrap();
+
+        for &(date, shares, price) in &[
+            (date!(2021, 3, 1), 100, dec!(5000)),
+            (date!(2021, 3, 2), 100, dec!(5000)),
+            (date!(2021, 3, 2), 200, dec!(5000)),
+        ] {
+            assert_eq!(
+                calc.add_trade(date, trade_type, shares.into(), Cash::new(currency, price)).unwrap(),
+                Cash::zero(currency),
+            );
+        }
+
+        assert_eq!(calc.calculate().unwrap(), hashmap!{
+            date!(2021, 3, 1) => Cash::new(currency, dec!(206.50)).into(),
+            date!(2021, 3, 2) => Cash::new(currency, dec!(531.00)).into(),
+        });
+    }
+}
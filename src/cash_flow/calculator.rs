@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use log::warn;
+use log::{Level, log, warn};
 
 use crate::broker_statement::BrokerStatement;
 use crate::currency::{Cash, MultiCurrencyCashAccount};
@@ -9,7 +9,7 @@ use crate::time::{Date, Period};
 use crate::types::Decimal;
 
 use super::mapper::{CashFlow, map_broker_statement_to_cash_flow};
-use super::comparator::CashAssetsComparator;
+use super::comparator::{CashAssetsComparator, CashAssetsMismatch};
 
 pub struct CashFlowSummary {
     pub starting: Decimal,
@@ -21,9 +21,7 @@ pub struct CashFlowSummary {
 pub fn calculate(statement: &BrokerStatement, period: Period) -> (
     BTreeMap<&'static str, CashFlowSummary>, Vec<CashFlow>
 ) {
-    let historical_cash_assets = statement.historical_assets.iter().map(|(&date, assets)| {
-        (date, assets.cash.clone())
-    }).collect();
+    let historical_cash_assets = get_historical_cash_assets(statement);
 
     let starting_assets_date = period.prev_date();
     let ending_assets_date = period.last_date();
@@ -44,6 +42,35 @@ pub fn calculate(statement: &BrokerStatement, period: Period) -> (
     }.process()
 }
 
+// Compares cash assets calculated from cash flows to the actual ones from broker statements
+pub fn reconcile(statement: &BrokerStatement) -> Vec<CashAssetsMismatch> {
+    let historical_cash_assets = get_historical_cash_assets(statement);
+    let mut comparator = CashAssetsComparator::new(&historical_cash_assets, Vec::new());
+
+    let mut mismatches = Vec::new();
+    let mut assets = MultiCurrencyCashAccount::new();
+
+    for cash_flow in map_broker_statement_to_cash_flow(statement) {
+        mismatches.extend(comparator.compare(cash_flow.time.date, &assets));
+
+        assets.deposit(cash_flow.amount);
+        if let Some(amount) = cash_flow.sibling_amount {
+            assets.deposit(amount);
+        }
+    }
+
+    mismatches.extend(comparator.compare(statement.period.next_date(), &assets));
+    assert!(comparator.consumed());
+
+    mismatches
+}
+
+fn get_historical_cash_assets(statement: &BrokerStatement) -> BTreeMap<Date, MultiCurrencyCashAccount> {
+    statement.historical_assets.iter().map(|(&date, assets)| {
+        (date, assets.cash.clone())
+    }).collect()
+}
+
 struct Calculator<'a> {
     statement: &'a BrokerStatement,
     comparator: CashAssetsComparator<'a>,
@@ -117,7 +144,17 @@ impl<'a> Calculator<'a> {
     }
 
     fn process_date(&mut self, date: Date) {
-        self.comparator.compare(date, &self.assets);
+        for mismatch in self.comparator.compare(date, &self.assets) {
+            let level = if mismatch.significant && mismatch.important {
+                Level::Warn
+            } else {
+                Level::Debug
+            };
+
+            log!(level, "Calculation error for {}: {} vs {} ({})",
+                 format_date(mismatch.date), mismatch.calculated, mismatch.actual,
+                 mismatch.calculated - mismatch.actual);
+        }
 
         if self.starting_assets.is_none() && self.starting_assets_date < date {
             self.starting_assets.replace(self.assets.clone());
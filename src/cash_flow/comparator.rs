@@ -1,11 +1,21 @@
 use std::collections::{BTreeMap, BTreeSet, btree_map};
 
-use log::{Level, log};
-
 use crate::currency::{Cash, MultiCurrencyCashAccount};
-use crate::formatting::format_date;
 use crate::types::Date;
 
+pub struct CashAssetsMismatch {
+    pub date: Date,
+    pub calculated: Cash,
+    pub actual: Cash,
+
+    // The calculations aren't 100% accurate. For example, Forex trades information contains
+    // rounded numbers which may lead to calculation error with around 0.00001 precision.
+    pub significant: bool,
+
+    // The mismatch is for the last or one of the important dates
+    pub important: bool,
+}
+
 pub struct CashAssetsComparator<'a> {
     iter: btree_map::Iter<'a, Date, MultiCurrencyCashAccount>,
     next: Option<(&'a Date, &'a MultiCurrencyCashAccount)>,
@@ -27,19 +37,24 @@ impl<'a> CashAssetsComparator<'a> {
         comparator
     }
 
-    pub fn compare(&mut self, date: Date, calculated: &MultiCurrencyCashAccount) {
+    pub fn compare(&mut self, date: Date, calculated: &MultiCurrencyCashAccount) -> Vec<CashAssetsMismatch> {
+        let mut mismatches = Vec::new();
+
         while let Some((&historical_date, actual)) = self.next {
             if historical_date >= date {
                 break
             }
 
             self.next();
-            self.compare_to(historical_date, actual, calculated);
+            self.compare_to(historical_date, actual, calculated, &mut mismatches);
         }
+
+        mismatches
     }
 
     fn compare_to(&mut self,
         date: Date, actual: &MultiCurrencyCashAccount, calculated: &MultiCurrencyCashAccount,
+        mismatches: &mut Vec<CashAssetsMismatch>,
     ) {
         self.currencies.extend(actual.iter().map(|assets| assets.currency));
         self.currencies.extend(calculated.iter().map(|assets| assets.currency));
@@ -55,19 +70,13 @@ impl<'a> CashAssetsComparator<'a> {
                 continue;
             }
 
-            // The calculations aren't 100% accurate. For example, Forex trades information contains
-            // rounded numbers which may lead to calculation error with around 0.00001 precision.
-            let equal = calculated_amount.round() == actual_amount.round();
-
-            let level = if !equal && (self.consumed() || self.important_dates.contains(&date)) {
-                Level::Warn
-            } else {
-                Level::Debug
-            };
-
-            log!(level, "Calculation error for {}: {} vs {} ({})",
-                 format_date(date), calculated_amount, actual_amount,
-                 calculated_amount - actual_amount);
+            mismatches.push(CashAssetsMismatch {
+                date,
+                calculated: calculated_amount,
+                actual: actual_amount,
+                significant: calculated_amount.round() != actual_amount.round(),
+                important: self.consumed() || self.important_dates.contains(&date),
+            });
         }
     }
 
@@ -2,7 +2,8 @@ use std::cmp::Ordering;
 
 use crate::broker_statement::{
     BrokerStatement, ForexTrade, StockBuy, StockSource, StockSell, StockSellType, Dividend, Fee,
-    IdleCashInterest, TaxWithholding, CashFlow as CashFlowDetails, CashFlowType};
+    IdleCashInterest, TaxWithholding, CashFlow as CashFlowDetails, CashFlowType, BondIncome,
+    BondIncomeType, BondAmortization, DerivativeTrade};
 use crate::currency::{Cash, CashAssets};
 use crate::formatting;
 use crate::time::DateOptTime;
@@ -41,16 +42,28 @@ impl CashFlowMapper {
             self.cash_flow(statement, cash_flow);
         }
 
+        for income in &statement.bond_income {
+            self.bond_income(&statement.instrument_info.get_name(&income.original_symbol), income);
+        }
+
+        for amortization in &statement.bond_amortizations {
+            self.bond_amortization(&statement.instrument_info.get_name(&amortization.original_symbol), amortization);
+        }
+
         for trade in &statement.forex_trades {
             self.forex_trade(trade);
         }
 
         for trade in &statement.stock_sells {
-            self.stock_sell(&statement.instrument_info.get_name(&trade.original_symbol), trade);
+            self.stock_sell(&statement.instrument_info.get_name(&trade.symbol), trade);
         }
 
         for trade in &statement.stock_buys {
-            self.stock_buy(&statement.instrument_info.get_name(&trade.original_symbol), trade);
+            self.stock_buy(&statement.instrument_info.get_name(&trade.symbol), trade);
+        }
+
+        for trade in &statement.derivative_trades {
+            self.derivative_trade(trade);
         }
 
         for fee in &statement.fees {
@@ -106,7 +119,7 @@ impl CashFlowMapper {
                     self.add(trade.conclusion_time, Operation::Commission, -commission, description);
                 };
             },
-            StockSource::CorporateAction => {},
+            StockSource::CorporateAction | StockSource::Transfer => {},
         };
     }
 
@@ -121,7 +134,25 @@ impl CashFlowMapper {
                     self.add(trade.conclusion_time, Operation::Commission, -commission, description);
                 };
             },
-            StockSellType::CorporateAction => {},
+            StockSellType::CorporateAction | StockSellType::Transfer => {},
+        }
+    }
+
+    fn derivative_trade(&mut self, trade: &DerivativeTrade) {
+        let (operation, quantity) = if trade.quantity.is_sign_positive() {
+            ("Покупка", trade.quantity)
+        } else {
+            ("Продажа", -trade.quantity)
+        };
+
+        if !trade.volume.is_zero() {
+            let description = format!("{} {} {}", operation, quantity, trade.symbol);
+            self.add(trade.conclusion_time, Operation::DerivativeTrade, trade.volume, description);
+        }
+
+        if !trade.commission.is_zero() {
+            let description = format!("Комиссия за сделку с {} {}", quantity, trade.symbol);
+            self.add(trade.conclusion_time, Operation::Commission, -trade.commission, description);
         }
     }
 
@@ -177,20 +208,42 @@ impl CashFlowMapper {
                 });
             },
 
-            CashFlowType::Repo {ref symbol, commission} => {
+            CashFlowType::Repo {ref symbol, quantity, commission} => {
                 let description = statement.instrument_info.get_name(symbol);
 
                 self.add(date, Operation::RepoDeal, amount, format!(
-                    "Сделка РЕПО с {}", description));
+                    "Сделка РЕПО с {} {}", quantity, description));
 
                 if !commission.is_zero() {
                     self.add(date, Operation::Commission, -commission, format!(
                         "Комиссия за заключение сделки РЕПО с {}", description));
                 }
             },
+
+            CashFlowType::VariationMargin => {
+                self.add_static(date, Operation::VariationMargin, amount, "Вариационная маржа");
+            },
         }
     }
 
+    fn bond_income(&mut self, name: &str, income: &BondIncome) {
+        let description = match income.type_ {
+            BondIncomeType::Coupon => format!("Купон по {}", name),
+            BondIncomeType::AccruedInterest => if income.amount.is_negative() {
+                format!("НКД при покупке {}", name)
+            } else {
+                format!("НКД при продаже {}", name)
+            },
+        };
+        self.add(income.date.into(), Operation::Coupon, income.amount, description);
+    }
+
+    fn bond_amortization(&mut self, name: &str, amortization: &BondAmortization) {
+        // Amortization returns a part of the invested money, so it's processed as a partial sell
+        let description = format!("Частичное погашение {}", name);
+        self.add(amortization.date.into(), Operation::SellTrade, amortization.amount, description);
+    }
+
     fn tax_agent_withholding(&mut self, withholding: &TaxWithholding) {
         let description = format!("Удержание налога за {} год", withholding.year);
         self.add(withholding.date.into(), Operation::Tax, -withholding.amount, description);
@@ -211,10 +264,13 @@ pub enum Operation {
     Deposit,
     Interest,
     Dividend,
+    Coupon,
 
     ForexTrade,
     SellTrade,
     BuyTrade,
+    DerivativeTrade,
+    VariationMargin,
     RepoDeal,
     Commission,
 
@@ -225,7 +281,9 @@ pub enum Operation {
 
 impl Operation {
     fn is_trade(self) -> bool {
-        matches!(self, Operation::ForexTrade | Operation::SellTrade | Operation::BuyTrade | Operation::RepoDeal)
+        matches!(self,
+            Operation::ForexTrade | Operation::SellTrade | Operation::BuyTrade |
+            Operation::DerivativeTrade | Operation::RepoDeal)
     }
 }
 
@@ -20,17 +20,16 @@ use crate::time::{Date, Period};
 use self::calculator::CashFlowSummary;
 use self::mapper::{CashFlow, Operation};
 
+pub use self::calculator::reconcile as reconcile_cash_assets;
+pub use self::comparator::CashAssetsMismatch;
+
 pub fn generate_cash_flow_report(config: &Config, portfolio_name: &str, year: Option<i32>) -> GenericResult<TelemetryRecordBuilder> {
     let portfolio = config.get_portfolio(portfolio_name)?;
-    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
 
     let database = db::connect(&config.db_path)?;
     let converter = CurrencyConverter::new(database, None, year.is_some());
 
-    let statement = BrokerStatement::read(
-        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
-        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.corporate_actions,
-        ReadingStrictness::CASH_FLOW_DATES)?;
+    let statement = BrokerStatement::read_portfolio(config, portfolio, ReadingStrictness::CASH_FLOW_DATES)?;
 
     let period = match year {
         Some(year) => statement.check_period_against_tax_year(year)?,
@@ -40,7 +39,7 @@ pub fn generate_cash_flow_report(config: &Config, portfolio_name: &str, year: Op
     let (summaries, cash_flows) = calculator::calculate(&statement, period);
     generate_cash_summary_report(period, &summaries);
 
-    if statement.broker.type_.jurisdiction() == Jurisdiction::Usa {
+    if statement.broker.jurisdiction != Jurisdiction::Russia {
         generate_other_summary_report(&statement, period, &cash_flows, &converter, "USD")?;
     }
 
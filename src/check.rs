@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+use log::{error, warn};
+
+use crate::broker_statement::{BrokerStatement, ReadingStrictness};
+use crate::cash_flow::{self, CashAssetsMismatch};
+use crate::config::{Config, PortfolioConfig};
+use crate::core::GenericResult;
+use crate::formatting::{self, table::{Table, Column, Cell}};
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::{self, Date, Period};
+
+// Reads all portfolios and reports all found problems at once instead of failing on the first one
+pub fn check(config: &Config) -> GenericResult<TelemetryRecordBuilder> {
+    if config.portfolios.is_empty() {
+        return Err!("There is no any portfolio defined in the configuration file")
+    }
+
+    let mut telemetry = TelemetryRecordBuilder::new();
+    let mut summary = Table::new(vec![
+        Column::new("Portfolio"), Column::new("Period"), Column::new("Errors"), Column::new("Warnings"),
+    ]);
+    let mut failed = Vec::new();
+
+    for portfolio in &config.portfolios {
+        telemetry.add_broker(portfolio.broker);
+
+        let report = check_portfolio(config, portfolio);
+        if let Some(ref statement) = report.statement {
+            print_statements(portfolio, statement, &report.mismatches);
+        }
+
+        for message in &report.warnings {
+            warn!("{}: {}.", portfolio.name, message);
+        }
+
+        for message in &report.errors {
+            error!("{}: {}.", portfolio.name, message);
+        }
+
+        summary.add_row(vec![
+            portfolio.name.clone().into(),
+            report.statement.as_ref().map(|statement| Cell::from(statement.period.format())).unwrap_or_else(Cell::new_empty),
+            report.errors.len().into(),
+            report.warnings.len().into(),
+        ]);
+
+        if !report.errors.is_empty() {
+            failed.push(portfolio.name.as_str());
+        }
+    }
+
+    summary.print("Summary");
+
+    if !failed.is_empty() {
+        return Err!("Found problems in the following portfolios: {}", failed.join(", "));
+    }
+
+    Ok(telemetry)
+}
+
+#[derive(Default)]
+struct Report {
+    statement: Option<BrokerStatement>,
+    mismatches: BTreeMap<Date, Vec<CashAssetsMismatch>>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn check_portfolio(config: &Config, portfolio: &PortfolioConfig) -> Report {
+    let mut report = Report::default();
+
+    let statement = match BrokerStatement::read_portfolio(config, portfolio, ReadingStrictness::CASH_FLOW_DATES) {
+        Ok(statement) => statement,
+        Err(e) => {
+            report.errors.push(format!("Failed to read broker statements: {}", e));
+            return report;
+        },
+    };
+
+    for mismatch in cash_flow::reconcile_cash_assets(&statement) {
+        if !mismatch.significant {
+            continue;
+        }
+
+        let message = format!(
+            "Calculated cash assets don't match the actual ones for {}: {} vs {}",
+            formatting::format_date(mismatch.date), mismatch.calculated, mismatch.actual);
+
+        // Broker statements may contain inaccurate intermediate data, so only the last date is
+        // considered as a real problem
+        if mismatch.important {
+            report.errors.push(message);
+        } else {
+            report.warnings.push(message);
+        }
+
+        report.mismatches.entry(mismatch.date).or_default().push(mismatch);
+    }
+
+    let days = (time::today() - statement.period.last_date()).num_days();
+    if days >= 30 {
+        report.warnings.push(format!("The last broker statement is {} days old", days));
+    }
+
+    let unnamed_symbols: BTreeSet<&str> = statement.open_positions.keys().map(String::as_str)
+        .chain(statement.stock_buys.iter().map(|trade| trade.symbol.as_str()))
+        .chain(statement.stock_sells.iter().map(|trade| trade.symbol.as_str()))
+        .chain(statement.dividends.iter().map(|dividend| dividend.issuer.as_str()))
+        .filter(|&symbol| {
+            statement.instrument_info.get(symbol).and_then(|instrument| instrument.name()).is_none()
+        })
+        .collect();
+
+    if !unnamed_symbols.is_empty() {
+        report.warnings.push(format!(
+            "Unable to find names of the following instruments: {} (use `instrument_names` configuration option to specify them)",
+            unnamed_symbols.iter().join(", ")));
+    }
+
+    report.statement.replace(statement);
+    report
+}
+
+fn print_statements(
+    portfolio: &PortfolioConfig, statement: &BrokerStatement,
+    mismatches: &BTreeMap<Date, Vec<CashAssetsMismatch>>,
+) {
+    let mut table = Table::new(vec![
+        Column::new("Period"), Column::new("Missing days"), Column::new("Cash assets"),
+    ]);
+    let mut prev_period: Option<Period> = None;
+
+    for &period in &statement.statement_periods {
+        let missing_days = match prev_period {
+            Some(prev) if prev.next_date() < period.first_date() => {
+                Cell::from(Period::new(prev.next_date(), period.prev_date()).unwrap().format())
+            },
+            _ => Cell::new_empty(),
+        };
+
+        let cash_assets = if let Some(mismatches) = mismatches.get(&period.last_date()) {
+            Cell::from(mismatches.iter().map(|mismatch| format!(
+                "{} vs {}", mismatch.calculated, mismatch.actual)).join(", "))
+        } else if statement.historical_assets.contains_key(&period.last_date()) {
+            Cell::from("OK")
+        } else {
+            Cell::new_empty()
+        };
+
+        table.add_row(vec![period.format().into(), missing_days, cash_assets]);
+        prev_period.replace(period);
+    }
+
+    table.print(&format!(
+        "{} broker statements ({}: {})", portfolio.name, statement.broker.brief_name,
+        statement.broker.statements_merging_strategy.description()));
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    #[test]
+    fn check() {
+        let database = NamedTempFile::new().unwrap();
+        let mut config = Config::load("testdata/configs/check/config.yaml").unwrap();
+        config.db_path = database.path().to_str().unwrap().to_owned();
+
+        let report = check_portfolio(&config, config.get_portfolio("shorts").unwrap());
+        let period = report.statement.as_ref().unwrap().period;
+        assert_eq!(period, Period::new(date!(2021, 1, 1), date!(2022, 3, 31)).unwrap());
+        assert!(report.errors.is_empty() && report.mismatches.is_empty());
+        assert_eq!(report.warnings.len(), 2);
+        assert!(report.warnings[0].starts_with("The last broker statement is "));
+        assert!(report.warnings[1].starts_with("Unable to find names of the following instruments: AAPL "));
+
+        let report = check_portfolio(&config, config.get_portfolio("missing").unwrap());
+        assert!(report.statement.is_none());
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("Failed to read broker statements: "));
+    }
+}
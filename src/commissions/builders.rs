@@ -87,6 +87,12 @@ impl TransactionCommissionSpecBuilder {
         self
     }
 
+    // Fixed amount which is charged in addition to per share or percent commission
+    pub fn per_order(mut self, per_order: Decimal) -> TransactionCommissionSpecBuilder {
+        self.0.per_order = Some(per_order);
+        self
+    }
+
     pub fn maximum_percent(mut self, maximum_percent: Decimal) -> TransactionCommissionSpecBuilder {
         self.0.maximum_percent = Some(maximum_percent);
         self
@@ -44,6 +44,7 @@ pub struct TradeCommissionSpec {
 pub struct TransactionCommissionSpec {
     percent: Option<Decimal>,
     per_share: Option<Decimal>,
+    per_order: Option<Decimal>,
 
     minimum: Option<Decimal>,
     maximum_percent: Option<Decimal>,
@@ -63,6 +64,10 @@ impl TransactionCommissionSpec {
             commission += volume.amount * percent / dec!(100);
         }
 
+        if let Some(per_order) = self.per_order {
+            commission += convert(per_order)?;
+        }
+
         if let Some(maximum_percent) = self.maximum_percent {
             let max_commission = volume.amount * maximum_percent / dec!(100);
             if commission > max_commission {
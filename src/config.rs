@@ -9,13 +9,14 @@ use serde::de::{Deserializer, IgnoredAny, Error};
 use serde_yaml::Value;
 
 use crate::analysis::config::PerformanceMergingConfig;
-use crate::broker_statement::CorporateAction;
-use crate::brokers::Broker;
+use crate::broker_statement::{CorporateAction, StockTransfer, validate_stock_transfers};
+use crate::brokers::{Broker, BrokerInfo};
 use crate::core::{GenericResult, EmptyResult};
 use crate::formatting;
 use crate::instruments::InstrumentInternalIds;
 use crate::localities::{self, Country, Jurisdiction};
-use crate::taxes::{self, TaxExemption, TaxPaymentDay, TaxPaymentDaySpec, TaxRemapping};
+use crate::taxes::{
+    self, LossCarryforwardConfig, TaxBracket, TaxExemption, TaxPaymentDay, TaxPaymentDaySpec, TaxRemapping};
 use crate::telemetry::TelemetryConfig;
 use crate::time::{self, deserialize_date};
 use crate::types::{Date, Decimal};
@@ -28,6 +29,8 @@ pub struct Config {
     pub db_path: String,
     #[serde(skip, default = "default_expire_time")]
     pub cache_expire_time: Duration,
+    #[serde(skip)]
+    pub reread_statements: bool,
 
     #[serde(default)]
     pub deposits: Vec<DepositConfig>,
@@ -38,6 +41,8 @@ pub struct Config {
     pub brokers: Option<BrokersConfig>,
     #[serde(default)]
     pub tax_rates: TaxRates,
+    pub tax_loss_carryforward: Option<LossCarryforwardConfig>,
+    pub taxpayer: Option<TaxPayerConfig>,
     #[serde(default)]
     pub metrics: MetricsConfig,
 
@@ -58,6 +63,7 @@ impl Config {
         Config {
             db_path: s!("/mock"),
             cache_expire_time: default_expire_time(),
+            reread_statements: false,
 
             deposits: Vec::new(),
             notify_deposit_closing_days: None,
@@ -65,6 +71,8 @@ impl Config {
             portfolios: Vec::new(),
             brokers: Some(BrokersConfig::mock()),
             tax_rates: Default::default(),
+            tax_loss_carryforward: None,
+            taxpayer: None,
             metrics: Default::default(),
 
             alphavantage: None,
@@ -99,6 +107,8 @@ impl Config {
             }
         }
 
+        validate_stock_transfers(&config)?;
+
         for &tax_rates in &[
             &config.tax_rates.trading,
             &config.tax_rates.dividends,
@@ -113,11 +123,23 @@ impl Config {
             }
         }
 
+        taxes::validate_progressive_tax_rates(&config.tax_rates.progressive)?;
+
+        if let Some(ref loss_carryforward) = config.tax_loss_carryforward {
+            loss_carryforward.validate()?;
+        }
+
+        if let Some(ref taxpayer) = config.taxpayer {
+            taxpayer.validate()?;
+        }
+
         Ok(config)
     }
 
     pub fn get_tax_country(&self) -> Country {
-        localities::russia(&self.tax_rates.trading, &self.tax_rates.dividends, &self.tax_rates.interest)
+        localities::russia(
+            &self.tax_rates.trading, &self.tax_rates.dividends, &self.tax_rates.interest,
+            &self.tax_rates.progressive)
     }
 
     pub fn get_portfolio(&self, name: &str) -> GenericResult<&PortfolioConfig> {
@@ -204,8 +226,10 @@ pub struct PortfolioConfig {
     pub name: String,
     pub broker: Broker,
     pub plan: Option<String>,
+    jurisdiction: Option<Jurisdiction>,
 
     pub statements: Option<String>,
+    pub account: Option<String>,
     #[serde(default)]
     pub symbol_remapping: HashMap<String, String>,
     #[serde(default, deserialize_with = "InstrumentInternalIds::deserialize")]
@@ -216,6 +240,8 @@ pub struct PortfolioConfig {
     tax_remapping: Vec<TaxRemappingConfig>,
     #[serde(default)]
     pub corporate_actions: Vec<CorporateAction>,
+    #[serde(default)]
+    pub transfers: Vec<StockTransfer>,
 
     pub currency: Option<String>,
     pub min_trade_volume: Option<Decimal>,
@@ -244,6 +270,16 @@ impl PortfolioConfig {
         Ok(self.currency.as_ref().ok_or("The portfolio's currency is not specified in the config")?)
     }
 
+    pub fn broker_info(&self, config: &Config) -> GenericResult<BrokerInfo> {
+        let mut broker = self.broker.get_info(config, self.plan.as_ref())?;
+        broker.jurisdiction = self.jurisdiction();
+        Ok(broker)
+    }
+
+    pub fn jurisdiction(&self) -> Jurisdiction {
+        self.jurisdiction.unwrap_or_else(|| self.broker.jurisdiction())
+    }
+
     pub fn statements_path(&self) -> GenericResult<&str> {
         Ok(self.statements.as_ref().ok_or("Broker statements path is not specified in the portfolio's config")?)
     }
@@ -259,7 +295,7 @@ impl PortfolioConfig {
     }
 
     pub fn tax_payment_day(&self) -> TaxPaymentDay {
-        TaxPaymentDay::new(self.broker.jurisdiction(), self.tax_payment_day_spec)
+        TaxPaymentDay::new(self.jurisdiction(), self.tax_payment_day_spec)
     }
 
     pub fn get_tax_remapping(&self) -> GenericResult<TaxRemapping> {
@@ -284,6 +320,14 @@ impl PortfolioConfig {
             };
         }
 
+        if self.account.is_some() && self.broker != Broker::InteractiveBrokers {
+            return Err!("Account filter is only supported for Interactive Brokers statements");
+        }
+
+        if self.jurisdiction.is_some() && self.broker != Broker::Ledger {
+            return Err!("Jurisdiction can be specified only for manual ledger portfolios");
+        }
+
         for (symbol, mapping) in &self.symbol_remapping {
             if self.symbol_remapping.get(mapping).is_some() {
                 return Err!("Invalid symbol remapping configuration: Recursive {} symbol", symbol);
@@ -292,12 +336,12 @@ impl PortfolioConfig {
 
         if
             matches!(self.tax_payment_day_spec, TaxPaymentDaySpec::OnClose(_)) &&
-            self.broker.jurisdiction() != Jurisdiction::Russia
+            self.jurisdiction() != Jurisdiction::Russia
         {
             return Err!("On close tax payment date is only available for brokers with Russia jurisdiction")
         }
 
-        taxes::validate_tax_exemptions(self.broker, &self.tax_exemptions)?;
+        taxes::validate_tax_exemptions(self.jurisdiction(), &self.tax_exemptions)?;
 
         Ok(())
     }
@@ -345,9 +389,13 @@ impl AssetAllocationConfig {
 pub struct BrokersConfig {
     pub bcs: Option<BrokerConfig>,
     pub firstrade: Option<BrokerConfig>,
+    pub freedom_finance: Option<BrokerConfig>,
     pub interactive_brokers: Option<BrokerConfig>,
+    pub ledger: Option<BrokerConfig>,
     pub open_broker: Option<BrokerConfig>,
+    pub sber: Option<BrokerConfig>,
     pub tinkoff: Option<BrokerConfig>,
+    pub vtb: Option<BrokerConfig>,
 }
 
 impl BrokersConfig {
@@ -356,9 +404,13 @@ impl BrokersConfig {
         BrokersConfig {
             bcs: Some(BrokerConfig::mock()),
             firstrade: Some(BrokerConfig::mock()),
+            freedom_finance: Some(BrokerConfig::mock()),
             interactive_brokers: Some(BrokerConfig::mock()),
+            ledger: Some(BrokerConfig::mock()),
             open_broker: Some(BrokerConfig::mock()),
+            sber: Some(BrokerConfig::mock()),
             tinkoff: Some(BrokerConfig::mock()),
+            vtb: Some(BrokerConfig::mock()),
         }
     }
 }
@@ -387,6 +439,38 @@ pub struct TaxRates {
     pub dividends: BTreeMap<i32, Decimal>,
     #[serde(default)]
     pub interest: BTreeMap<i32, Decimal>,
+    #[serde(default)]
+    pub progressive: BTreeMap<i32, Vec<TaxBracket>>,
+}
+
+// Taxpayer information which is required for tax statements in XML format
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TaxPayerConfig {
+    pub inn: String,
+    pub last_name: String,
+    pub first_name: String,
+    pub middle_name: Option<String>,
+    pub tax_authority: String,
+    pub oktmo: String,
+}
+
+impl TaxPayerConfig {
+    fn validate(&self) -> EmptyResult {
+        if self.inn.len() != 12 || !self.inn.chars().all(|c| c.is_ascii_digit()) {
+            return Err!("Invalid taxpayer INN: {:?}", self.inn);
+        }
+
+        if self.tax_authority.len() != 4 || !self.tax_authority.chars().all(|c| c.is_ascii_digit()) {
+            return Err!("Invalid tax authority code: {:?}", self.tax_authority);
+        }
+
+        if !matches!(self.oktmo.len(), 8 | 11) || !self.oktmo.chars().all(|c| c.is_ascii_digit()) {
+            return Err!("Invalid OKTMO code: {:?}", self.oktmo);
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Default)]
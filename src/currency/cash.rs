@@ -4,6 +4,7 @@ use std::str::FromStr;
 
 use num_traits::{ToPrimitive, Zero};
 use separator::Separatable;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::core::{GenericResult, EmptyResult};
 use crate::types::Decimal;
@@ -174,6 +175,20 @@ impl fmt::Display for Cash {
     }
 }
 
+// Currency names are interned, so Cash is serialized as a (currency, amount) pair
+impl Serialize for Cash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        (self.currency, self.amount).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cash {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let (currency, amount): (String, Decimal) = Deserialize::deserialize(deserializer)?;
+        Ok(Cash::new(&currency, amount))
+    }
+}
+
 fn format_currency(currency: &str, mut amount: &str) -> String {
     let mut buffer = String::new();
 
@@ -1,5 +1,7 @@
 #[cfg(test)] use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
 use crate::time::Date;
 use crate::types::Decimal;
 use crate::util;
@@ -22,7 +24,7 @@ pub struct CurrencyRate {
     price: Decimal,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 pub struct CashAssets {
     pub date: Date,
     pub cash: Cash,
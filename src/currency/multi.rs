@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::core::GenericResult;
 use crate::currency::{Cash, converter::CurrencyConverter};
 use crate::time::Date;
 use crate::types::Decimal;
 
+use super::name_cache;
+
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct MultiCurrencyCashAccount {
@@ -88,4 +92,21 @@ impl From<Cash> for MultiCurrencyCashAccount {
         assets.deposit(amount);
         assets
     }
-}
\ No newline at end of file
+}
+
+impl Serialize for MultiCurrencyCashAccount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        self.assets.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MultiCurrencyCashAccount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let assets: HashMap<String, Decimal> = Deserialize::deserialize(deserializer)?;
+        Ok(MultiCurrencyCashAccount {
+            assets: assets.into_iter().map(|(currency, amount)| {
+                (name_cache::get(&currency), amount)
+            }).collect(),
+        })
+    }
+}
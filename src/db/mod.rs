@@ -16,6 +16,10 @@ pub fn connect(url: &str) -> GenericResult<Connection> {
     let connection = SqliteConnection::establish(url).map_err(|e| format!(
         "Unable to open {:?} database: {}", url, e))?;
 
+    // The database may be accessed concurrently from several threads
+    connection.execute("PRAGMA busy_timeout = 60000").map_err(|e| format!(
+        "Failed to configure the database: {}", e))?;
+
     embedded_migrations::run(&connection).map_err(|e| format!(
         "Failed to prepare the database: {}", e))?;
 
@@ -1,4 +1,4 @@
-use crate::db::schema::{AssetType, assets, currency_rates, quotes, settings, telemetry};
+use crate::db::schema::{AssetType, assets, broker_statements, currency_rates, quotes, settings, telemetry};
 use crate::types::{Date, DateTime};
 
 #[derive(Insertable, Queryable)]
@@ -10,6 +10,17 @@ pub struct Asset {
     pub quantity: String,
 }
 
+#[derive(Insertable)]
+#[table_name="broker_statements"]
+pub struct NewBrokerStatement<'a> {
+    pub path: &'a str,
+    pub size: i64,
+    pub modify_time: DateTime,
+    pub hash: &'a str,
+    pub context: &'a str,
+    pub statement: String,
+}
+
 #[derive(Insertable)]
 #[table_name="currency_rates"]
 pub struct NewCurrencyRate<'a> {
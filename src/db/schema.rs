@@ -18,6 +18,17 @@ table! {
     }
 }
 
+table! {
+    broker_statements (path) {
+        path -> Text,
+        size -> BigInt,
+        modify_time -> Timestamp,
+        hash -> Text,
+        context -> Text,
+        statement -> Text,
+    }
+}
+
 table! {
     currency_rates (currency, date) {
         currency -> Text,
@@ -1,10 +1,13 @@
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum Exchange {
     Moex,
     Spb,
     Us,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Exchanges(Vec<Exchange>);
 
 impl Exchanges {
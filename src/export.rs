@@ -0,0 +1,529 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::broker_statement::{
+    BrokerStatement, ReadingStrictness, CashFlowType, CorporateActionType, FeeType, PurchaseCostType,
+    StockSellSource, StockSellType, StockSource};
+use crate::config::Config;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::telemetry::TelemetryRecordBuilder;
+use crate::time::{Date, DateOptTime, Time};
+use crate::types::Decimal;
+
+// Must be incremented on any backward incompatible change of the format (see docs/export.md)
+pub const FORMAT_VERSION: u32 = 1;
+
+pub fn export(config: &Config, portfolio_name: &str, path: Option<&str>) -> GenericResult<TelemetryRecordBuilder> {
+    let portfolio = config.get_portfolio(portfolio_name)?;
+    let statement = BrokerStatement::read_portfolio(config, portfolio, ReadingStrictness::empty())?;
+    let statement = Statement::new(&portfolio.name, &statement);
+
+    match path {
+        Some(path) => {
+            let file = File::create(path).map_err(|e| format!(
+                "Unable to create {:?}: {}", path, e))?;
+
+            write(&statement, BufWriter::new(file)).map_err(|e| format!(
+                "Failed to write {:?}: {}", path, e))?;
+        },
+        None => write(&statement, io::stdout().lock())?,
+    };
+
+    Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
+}
+
+fn write<W: Write>(statement: &Statement, mut writer: W) -> EmptyResult {
+    serde_json::to_writer_pretty(&mut writer, statement)?;
+    writeln!(writer)?;
+    Ok(writer.flush()?)
+}
+
+#[derive(Serialize)]
+struct Statement<'a> {
+    version: u32,
+    portfolio: &'a str,
+    broker: &'static str,
+    period: Period,
+
+    cash_assets: Vec<Amount>,
+    historical_assets: Vec<HistoricalAssets>,
+
+    deposits_and_withdrawals: Vec<CashOperation>,
+    fees: Vec<Fee<'a>>,
+    cash_flows: Vec<CashFlow<'a>>,
+    idle_cash_interest: Vec<CashOperation>,
+    tax_agent_withholdings: Vec<TaxWithholding>,
+
+    forex_trades: Vec<ForexTrade>,
+    stock_buys: Vec<StockBuy<'a>>,
+    stock_sells: Vec<StockSell<'a>>,
+    dividends: Vec<Dividend<'a>>,
+    corporate_actions: Vec<CorporateAction<'a>>,
+
+    open_positions: Vec<Position<'a>>,
+    instruments: Vec<Instrument<'a>>,
+}
+
+impl<'a> Statement<'a> {
+    fn new(portfolio: &'a str, statement: &'a BrokerStatement) -> Statement<'a> {
+        Statement {
+            version: FORMAT_VERSION,
+            portfolio,
+            broker: statement.broker.type_.id(),
+            period: Period {
+                first_date: statement.period.first_date(),
+                last_date: statement.period.last_date(),
+            },
+
+            cash_assets: Amount::new_multi(&statement.cash_assets),
+            historical_assets: statement.historical_assets.iter().map(|(&date, assets)| HistoricalAssets {
+                date,
+                cash: Amount::new_multi(&assets.cash),
+                other: assets.other.map(Amount::from),
+            }).collect(),
+
+            deposits_and_withdrawals: statement.deposits_and_withdrawals.iter().map(|assets| CashOperation {
+                date: assets.date,
+                amount: assets.cash.into(),
+            }).collect(),
+
+            fees: statement.fees.iter().map(|fee| Fee {
+                date: fee.date,
+                type_: match fee.type_ {
+                    FeeType::Commission => "commission",
+                    FeeType::MarginInterest => "margin-interest",
+                    FeeType::BorrowFee => "borrow-fee",
+                },
+                amount: fee.amount.into(),
+                description: fee.description.as_deref(),
+            }).collect(),
+
+            cash_flows: statement.cash_flows.iter().map(|cash_flow| CashFlow {
+                date: cash_flow.date.date,
+                time: cash_flow.date.time,
+                amount: cash_flow.amount.into(),
+                type_: match cash_flow.type_ {
+                    CashFlowType::Dividend {date, ref issuer} => CashFlowDetails::Dividend {
+                        dividend_date: date, issuer,
+                    },
+                    CashFlowType::Tax {date, ref issuer} => CashFlowDetails::Tax {
+                        dividend_date: date, issuer,
+                    },
+                    CashFlowType::Repo {ref symbol, quantity, commission} => CashFlowDetails::Repo {
+                        symbol, quantity, commission: commission.into(),
+                    },
+                    CashFlowType::VariationMargin => CashFlowDetails::VariationMargin,
+                },
+            }).collect(),
+
+            idle_cash_interest: statement.idle_cash_interest.iter().map(|interest| CashOperation {
+                date: interest.date,
+                amount: interest.amount.into(),
+            }).collect(),
+
+            tax_agent_withholdings: statement.tax_agent_withholdings.iter().map(|withholding| TaxWithholding {
+                date: withholding.date,
+                year: withholding.year,
+                amount: withholding.amount.into(),
+            }).collect(),
+
+            forex_trades: statement.forex_trades.iter().map(|trade| ForexTrade {
+                conclusion_date: trade.conclusion_time.date,
+                conclusion_time: trade.conclusion_time.time,
+                from: trade.from.into(),
+                to: trade.to.into(),
+                commission: trade.commission.into(),
+            }).collect(),
+
+            stock_buys: statement.stock_buys.iter().map(|trade| StockBuy {
+                symbol: &trade.symbol,
+                original_symbol: &trade.original_symbol,
+                quantity: trade.quantity,
+                type_: trade.type_.into(),
+                conclusion_date: trade.conclusion_time.date,
+                conclusion_time: trade.conclusion_time.time,
+                execution_date: trade.execution_date,
+                unsold: trade.get_unsold(),
+            }).collect(),
+
+            stock_sells: statement.stock_sells.iter().map(|trade| StockSell {
+                symbol: &trade.symbol,
+                original_symbol: &trade.original_symbol,
+                quantity: trade.quantity,
+                type_: trade.type_.into(),
+                conclusion_date: trade.conclusion_time.date,
+                conclusion_time: trade.conclusion_time.time,
+                execution_date: trade.execution_date,
+                short: trade.is_short(),
+                sources: trade.sources().iter().map(FifoSource::new).collect(),
+            }).collect(),
+
+            dividends: statement.dividends.iter().map(|dividend| Dividend {
+                date: dividend.date,
+                issuer: &dividend.issuer,
+                original_issuer: &dividend.original_issuer,
+                amount: dividend.amount.into(),
+                paid_tax: dividend.paid_tax.into(),
+            }).collect(),
+
+            corporate_actions: statement.corporate_actions().iter().map(|action| CorporateAction {
+                date: action.time.date,
+                time: action.time.time,
+                report_date: action.report_date,
+                symbol: &action.symbol,
+                action: match action.action {
+                    CorporateActionType::StockSplit {ratio, ..} => CorporateActionDetails::StockSplit {
+                        ratio: format!("{}:{}", ratio.to, ratio.from),
+                    },
+                    CorporateActionType::StockDividend {quantity, cost_basis_ratio} => {
+                        CorporateActionDetails::StockDividend {quantity, cost_basis_ratio}
+                    },
+                    CorporateActionType::Rename {ref new_symbol} => CorporateActionDetails::Rename {
+                        new_symbol,
+                    },
+                    CorporateActionType::Spinoff {ref symbol, quantity, ref currency, cost_basis_ratio} => {
+                        CorporateActionDetails::Spinoff {symbol, quantity, currency, cost_basis_ratio}
+                    },
+                    CorporateActionType::CashMerger {price, ref currency} => CorporateActionDetails::CashMerger {
+                        price: Cash::new(currency, price).into(),
+                    },
+                    CorporateActionType::StockMerger {ref new_symbol, ratio} => CorporateActionDetails::StockMerger {
+                        new_symbol, ratio,
+                    },
+                    CorporateActionType::CashInLieu {quantity, price, ref currency} => {
+                        CorporateActionDetails::CashInLieu {quantity, price: Cash::new(currency, price).into()}
+                    },
+                },
+            }).collect(),
+
+            open_positions: statement.open_positions.iter()
+                .map(|(symbol, &quantity)| Position {symbol, quantity})
+                .sorted_by_key(|position| position.symbol)
+                .collect(),
+
+            instruments: statement.instrument_info.iter()
+                .map(|instrument| Instrument {
+                    symbol: &instrument.symbol,
+                    name: instrument.name(),
+                    isin: instrument.isin.iter().map(ToString::to_string).sorted().collect(),
+                    cusip: instrument.cusip.iter().map(ToString::to_string).sorted().collect(),
+                    face_value: instrument.face_value.map(Amount::from),
+                })
+                .sorted_by_key(|instrument| instrument.symbol)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Period {
+    first_date: Date,
+    last_date: Date,
+}
+
+// Decimal values are represented as strings to not lose their precision
+#[derive(Serialize)]
+struct Amount {
+    currency: &'static str,
+    amount: Decimal,
+}
+
+impl Amount {
+    fn new_multi(assets: &MultiCurrencyCashAccount) -> Vec<Amount> {
+        assets.iter()
+            .sorted_by_key(|assets| assets.currency)
+            .map(Amount::from)
+            .collect()
+    }
+}
+
+impl From<Cash> for Amount {
+    fn from(cash: Cash) -> Amount {
+        Amount {
+            currency: cash.currency,
+            amount: cash.amount.normalize(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HistoricalAssets {
+    date: Date,
+    cash: Vec<Amount>,
+    other: Option<Amount>,
+}
+
+#[derive(Serialize)]
+struct CashOperation {
+    date: Date,
+    amount: Amount,
+}
+
+#[derive(Serialize)]
+struct Fee<'a> {
+    date: Date,
+    #[serde(rename = "type")]
+    type_: &'static str,
+    amount: Amount,
+    description: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct CashFlow<'a> {
+    date: Date,
+    time: Option<Time>,
+    amount: Amount,
+    #[serde(flatten)]
+    type_: CashFlowDetails<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum CashFlowDetails<'a> {
+    Dividend {dividend_date: Date, issuer: &'a str},
+    Tax {dividend_date: Date, issuer: &'a str},
+    Repo {symbol: &'a str, quantity: Decimal, commission: Amount},
+    VariationMargin,
+}
+
+#[derive(Serialize)]
+struct TaxWithholding {
+    date: Date,
+    year: i32,
+    amount: Amount,
+}
+
+#[derive(Serialize)]
+struct ForexTrade {
+    conclusion_date: Date,
+    conclusion_time: Option<Time>,
+    from: Amount,
+    to: Amount,
+    commission: Amount,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum TradeDetails {
+    Trade {
+        price: Amount,
+        volume: Amount,
+        commission: Amount,
+    },
+    CorporateAction,
+    Transfer,
+}
+
+impl From<StockSource> for TradeDetails {
+    fn from(source: StockSource) -> TradeDetails {
+        match source {
+            StockSource::Trade {price, volume, commission} => TradeDetails::Trade {
+                price: price.into(), volume: volume.into(), commission: commission.into(),
+            },
+            StockSource::CorporateAction => TradeDetails::CorporateAction,
+            StockSource::Transfer => TradeDetails::Transfer,
+        }
+    }
+}
+
+impl From<StockSellType> for TradeDetails {
+    fn from(type_: StockSellType) -> TradeDetails {
+        match type_ {
+            StockSellType::Trade {price, volume, commission} => TradeDetails::Trade {
+                price: price.into(), volume: volume.into(), commission: commission.into(),
+            },
+            StockSellType::CorporateAction => TradeDetails::CorporateAction,
+            StockSellType::Transfer => TradeDetails::Transfer,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StockBuy<'a> {
+    symbol: &'a str,
+    original_symbol: &'a str,
+    quantity: Decimal,
+    #[serde(flatten)]
+    type_: TradeDetails,
+    conclusion_date: Date,
+    conclusion_time: Option<Time>,
+    execution_date: Date,
+    unsold: Decimal,
+}
+
+#[derive(Serialize)]
+struct StockSell<'a> {
+    symbol: &'a str,
+    original_symbol: &'a str,
+    quantity: Decimal,
+    #[serde(flatten)]
+    type_: TradeDetails,
+    conclusion_date: Date,
+    conclusion_time: Option<Time>,
+    execution_date: Date,
+    short: bool,
+    sources: Vec<FifoSource<'a>>,
+}
+
+// A part of the purchased lot which has been sold by the trade
+#[derive(Serialize)]
+struct FifoSource<'a> {
+    original_symbol: &'a str,
+    quantity: Decimal,
+    multiplier: Decimal,
+    #[serde(flatten)]
+    type_: TradeDetails,
+    conclusion_date: Date,
+    conclusion_time: Option<Time>,
+    execution_date: Date,
+    cost: Vec<PurchaseTransaction>,
+}
+
+impl<'a> FifoSource<'a> {
+    fn new(source: &'a StockSellSource) -> FifoSource<'a> {
+        let DateOptTime {date: conclusion_date, time: conclusion_time} = source.conclusion_time;
+
+        FifoSource {
+            original_symbol: &source.original_symbol,
+            quantity: source.quantity,
+            multiplier: source.multiplier,
+            type_: source.type_.into(),
+            conclusion_date, conclusion_time,
+            execution_date: source.execution_date,
+            cost: source.cost.transactions().map(|(date, type_, cost)| PurchaseTransaction {
+                date,
+                type_: match type_ {
+                    PurchaseCostType::Trade => "trade",
+                    PurchaseCostType::Commission => "commission",
+                },
+                amount: cost.into(),
+            }).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PurchaseTransaction {
+    date: Date,
+    #[serde(rename = "type")]
+    type_: &'static str,
+    amount: Amount,
+}
+
+#[derive(Serialize)]
+struct Dividend<'a> {
+    date: Date,
+    issuer: &'a str,
+    original_issuer: &'a str,
+    amount: Amount,
+    paid_tax: Amount,
+}
+
+#[derive(Serialize)]
+struct CorporateAction<'a> {
+    date: Date,
+    time: Option<Time>,
+    report_date: Option<Date>,
+    symbol: &'a str,
+    #[serde(flatten)]
+    action: CorporateActionDetails<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum CorporateActionDetails<'a> {
+    StockSplit {ratio: String},
+    StockDividend {quantity: Decimal, cost_basis_ratio: Option<Decimal>},
+    Rename {new_symbol: &'a str},
+    Spinoff {symbol: &'a str, quantity: Decimal, currency: &'a str, cost_basis_ratio: Option<Decimal>},
+    CashMerger {price: Amount},
+    StockMerger {new_symbol: &'a str, ratio: Decimal},
+    CashInLieu {quantity: Decimal, price: Amount},
+}
+
+#[derive(Serialize)]
+struct Position<'a> {
+    symbol: &'a str,
+    quantity: Decimal,
+}
+
+#[derive(Serialize)]
+struct Instrument<'a> {
+    symbol: &'a str,
+    name: Option<&'a str>,
+    isin: Vec<String>,
+    cusip: Vec<String>,
+    face_value: Option<Amount>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::brokers::Broker;
+    use crate::broker_statement::{StatementCache, StockTransfers};
+    use crate::taxes::TaxRemapping;
+
+    use super::*;
+
+    #[test]
+    fn export() {
+        let broker = Broker::Ledger.get_info(&Config::mock(), None).unwrap();
+
+        let statement = BrokerStatement::read(
+            broker, "testdata/shorts", None,
+            &Default::default(), &Default::default(), &Default::default(), TaxRemapping::new(),
+            &[], StockTransfers::new(), &StatementCache::new_disabled(), ReadingStrictness::all()).unwrap();
+
+        let mut data = Vec::new();
+        write(&Statement::new("shorts", &statement), &mut data).unwrap();
+        let data: Value = serde_json::from_slice(&data).unwrap();
+
+        assert_eq!(data["version"], json!(FORMAT_VERSION));
+        assert_eq!(data["portfolio"], json!("shorts"));
+        assert_eq!(data["broker"], json!("ledger"));
+        assert_eq!(data["period"], json!({"first_date": "2021-01-01", "last_date": "2022-03-31"}));
+        assert_eq!(data["cash_assets"], json!([{"currency": "USD", "amount": "10224.5"}]));
+        assert_eq!(data["deposits_and_withdrawals"], json!([
+            {"date": "2021-01-11", "amount": {"currency": "USD", "amount": "10000"}},
+        ]));
+        assert_eq!(data["open_positions"], json!([]));
+
+        let usd = |amount| json!({"currency": "USD", "amount": amount});
+
+        assert_eq!(data["stock_sells"].as_array().unwrap().len(), 3);
+        assert_eq!(data["stock_sells"][1], json!({
+            "symbol": "VTI",
+            "original_symbol": "VTI",
+            "quantity": "5",
+            "type": "trade",
+            "price": usd("210"),
+            "volume": usd("1050"),
+            "commission": usd("0.5"),
+            "conclusion_date": "2021-03-01",
+            "conclusion_time": null,
+            "execution_date": "2021-03-03",
+            "short": true,
+            "sources": [{
+                "original_symbol": "VTI",
+                "quantity": "5",
+                "multiplier": "1",
+                "type": "trade",
+                "price": usd("190"),
+                "volume": usd("950"),
+                "commission": usd("1"),
+                "conclusion_date": "2021-04-01",
+                "conclusion_time": null,
+                "execution_date": "2021-04-05",
+                "cost": [
+                    {"date": "2021-04-05", "type": "trade", "amount": usd("950")},
+                    {"date": "2021-04-01", "type": "commission", "amount": usd("1")},
+                ],
+            }],
+        }));
+    }
+}
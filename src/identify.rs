@@ -0,0 +1,70 @@
+use log::warn;
+
+use crate::broker_statement::{StatementFiles, identify_statement, read_statement_period};
+use crate::core::GenericResult;
+use crate::formatting::table::{Table, Column, Cell};
+use crate::telemetry::TelemetryRecordBuilder;
+
+// The path may point to a statement file, a statements directory or a statements archive
+pub fn identify(path: &str) -> GenericResult<TelemetryRecordBuilder> {
+    let files = StatementFiles::open(path).map_err(|e| format!(
+        "Error while reading {:?}: {}", path, e))?;
+
+    let mut telemetry = TelemetryRecordBuilder::new();
+    let mut table = Table::new(vec![
+        Column::new("File"), Column::new("Broker"), Column::new("Format"), Column::new("Account"),
+        Column::new("Period"),
+    ]);
+    let mut unknown = Vec::new();
+
+    for file in files.iter() {
+        let identity = identify_statement(&file.path).map_err(|e| format!(
+            "Failed to identify {} broker statement: {}", file.description(), e))?;
+
+        let identity = match identity {
+            Some(identity) => identity,
+            None => {
+                table.add_row(vec![
+                    file.name.clone().into(), Cell::new_empty(), "Unknown".into(),
+                    Cell::new_empty(), Cell::new_empty(),
+                ]);
+                unknown.push(file);
+                continue;
+            },
+        };
+        telemetry.add_broker(identity.broker);
+
+        // All accounts of consolidated statements have the same period
+        let account = identity.accounts.first().map(String::as_str);
+
+        let period = match read_statement_period(identity.broker, account, &file.path) {
+            Ok(period) => period,
+            Err(e) => {
+                warn!("Failed to read {} broker statement: {}.", file.description(), e);
+                None
+            },
+        };
+
+        table.add_row(vec![
+            file.name.clone().into(),
+            identity.broker.brief_name().into(),
+            identity.format.into(),
+            identity.accounts.join(", ").into(),
+            period.map(|period| Cell::from(period.format())).unwrap_or_else(Cell::new_empty),
+        ]);
+    }
+
+    if let [file] = unknown.as_slice() {
+        if files.iter().count() == 1 {
+            return Err!("Unable to identify {}: it doesn't look like any supported broker statement",
+                        file.description());
+        }
+    }
+
+    if table.is_empty() {
+        return Err!("{:?} doesn't contain any file", path);
+    }
+
+    table.print("Broker statements");
+    Ok(telemetry)
+}
@@ -5,17 +5,19 @@ use std::fmt::{self, Display};
 use cusip::CUSIP;
 use itertools::Itertools;
 use isin::ISIN;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde::de::Deserializer;
 
 use crate::core::{GenericResult, EmptyResult};
+use crate::currency::Cash;
 use crate::exchanges::Exchanges;
 use crate::localities::Jurisdiction;
+use crate::util;
 
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum InstrumentId {
     Symbol(String),
-    Isin(ISIN),
+    Isin(#[serde(with = "util::string_value")] ISIN),
     Name(String),
     InternalId(String), // Some broker-specific ID
 }
@@ -36,6 +38,10 @@ impl Display for InstrumentId {
 pub struct InstrumentInternalIds(HashMap<String, String>);
 
 impl InstrumentInternalIds {
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
+
     fn get_symbol(&self, id: &str) -> GenericResult<&str> {
         Ok(self.0.get(id).ok_or_else(|| format!(concat!(
             "Unable to determine stock symbol by its broker-specific internal ID ({}). ",
@@ -50,8 +56,10 @@ impl InstrumentInternalIds {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct InstrumentInfo {
     instruments: HashMap<String, Instrument>,
+    #[serde(skip)]
     internal_ids: Option<InstrumentInternalIds>,
 }
 
@@ -87,6 +95,10 @@ impl InstrumentInfo {
         }
     }
 
+    pub fn iter(&self) -> impl Iterator<Item=&Instrument> {
+        self.instruments.values()
+    }
+
     pub fn get(&self, symbol: &str) -> Option<&Instrument> {
         self.instruments.get(symbol)
     }
@@ -176,12 +188,16 @@ impl InstrumentInfo {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Instrument {
     pub symbol: String,
     name: Option<String>,
+    #[serde(with = "util::string_set")]
     pub isin: HashSet<ISIN>,
-    cusip: HashSet<CUSIP>,
+    #[serde(with = "util::string_set")]
+    pub cusip: HashSet<CUSIP>,
     pub exchanges: Exchanges,
+    pub face_value: Option<Cash>, // Bonds only
 }
 
 impl Instrument {
@@ -192,9 +208,14 @@ impl Instrument {
             isin:      HashSet::new(),
             cusip:     HashSet::new(),
             exchanges: Exchanges::new_empty(),
+            face_value: None,
         }
     }
 
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
     pub fn set_name(&mut self, name: &str) {
         self.name.replace(name.to_owned());
     }
@@ -207,9 +228,13 @@ impl Instrument {
         self.cusip.insert(cusip);
     }
 
+    pub fn set_face_value(&mut self, face_value: Cash) {
+        self.face_value.replace(face_value);
+    }
+
     pub fn get_taxation_type(&self, broker_jurisdiction: Jurisdiction) -> GenericResult<IssuerTaxationType> {
         let get_taxation_type = |issuer_jurisdiction: &str| -> IssuerTaxationType {
-            if broker_jurisdiction == Jurisdiction::Russia && issuer_jurisdiction == Jurisdiction::Russia.code() {
+            if broker_jurisdiction == Jurisdiction::Russia && issuer_jurisdiction == "RU" {
                 return IssuerTaxationType::TaxAgent;
             }
             IssuerTaxationType::Manual(Some(issuer_jurisdiction.to_owned()))
@@ -218,7 +243,7 @@ impl Instrument {
         let mut result_taxation_type = if self.cusip.is_empty() {
             None
         } else {
-            Some(get_taxation_type(Jurisdiction::Usa.code()))
+            Some(get_taxation_type("US"))
         };
 
         for isin in &self.isin {
@@ -257,6 +282,9 @@ impl Instrument {
         self.isin.extend(other.isin);
         self.cusip.extend(other.cusip);
         self.exchanges.merge(other.exchanges);
+        if let Some(face_value) = other.face_value {
+            self.face_value.replace(face_value);
+        }
     }
 }
 
@@ -7,11 +7,16 @@
 #[macro_use] pub mod types;
 
 pub mod analysis;
+pub mod anonymizer;
 pub mod cash_flow;
+pub mod check;
 pub mod config;
 pub mod cli;
 pub mod db;
 pub mod deposits;
+pub mod export;
+pub mod identify;
+pub mod logging;
 pub mod metrics;
 pub mod portfolio;
 pub mod tax_statement;
@@ -2,9 +2,12 @@ use std::collections::{HashMap, BTreeMap};
 use std::ops::Bound;
 
 use chrono::{Datelike, Duration};
+use serde::Deserialize;
+use serde::de::{Deserializer, Error as _};
 
 use crate::currency::Cash;
-use crate::taxes::IncomeType;
+use crate::tax_statement::CountryCode;
+use crate::taxes::{IncomeType, TaxBracket};
 use crate::time;
 use crate::types::{Date, Decimal};
 
@@ -13,13 +16,15 @@ pub struct Country {
     pub currency: &'static str,
     default_tax_rate: Decimal,
     tax_rates: HashMap<IncomeType, BTreeMap<i32, Decimal>>,
+    progressive_tax_rates: BTreeMap<i32, Vec<TaxBracket>>,
     tax_precision: u32,
 }
 
 impl Country {
     fn new(
         currency: &'static str, mut default_tax_rate: Decimal,
-        mut tax_rates: HashMap<IncomeType, BTreeMap<i32, Decimal>>, tax_precision: u32,
+        mut tax_rates: HashMap<IncomeType, BTreeMap<i32, Decimal>>,
+        mut progressive_tax_rates: BTreeMap<i32, Vec<TaxBracket>>, tax_precision: u32,
     ) -> Country {
         default_tax_rate /= dec!(100);
 
@@ -29,7 +34,13 @@ impl Country {
             }
         }
 
-        Country {currency, default_tax_rate, tax_rates, tax_precision}
+        for brackets in progressive_tax_rates.values_mut() {
+            for bracket in brackets {
+                bracket.rate /= dec!(100);
+            }
+        }
+
+        Country {currency, default_tax_rate, tax_rates, progressive_tax_rates, tax_precision}
     }
 
     pub fn cash(&self, amount: Decimal) -> Cash {
@@ -73,6 +84,66 @@ impl Country {
         (result_income / (dec!(1) - self.tax_rate(income_type, year))).round()
     }
 
+    pub fn has_progressive_tax_rates(&self) -> bool {
+        !self.progressive_tax_rates.is_empty()
+    }
+
+    // Flat tax rates are applied to each income separately. Progressive tax rates are applied to
+    // the combined income of the whole year on top of the default tax rate, so returns the tax
+    // which must be paid in addition to the default tax rate.
+    pub fn progressive_tax_surcharge(&self, year: i32, income: Cash) -> Cash {
+        assert_eq!(income.currency, self.currency);
+
+        let brackets = match self.progressive_tax_brackets(year) {
+            Some(brackets) => brackets,
+            None => return Cash::zero(self.currency),
+        };
+
+        let income = income.round().amount;
+        let mut surcharge = dec!(0);
+        let mut prev_tax_rate = self.default_tax_rate;
+
+        for bracket in brackets {
+            if income <= bracket.threshold {
+                break;
+            }
+
+            surcharge += (income - bracket.threshold) * (bracket.rate - prev_tax_rate);
+            prev_tax_rate = bracket.rate;
+        }
+
+        if surcharge <= dec!(0) {
+            return Cash::zero(self.currency);
+        }
+
+        self.round_tax(self.cash(surcharge))
+    }
+
+    // Tax rates (in percents) which are applied to the combined income of the year: the default tax
+    // rate and progressive tax rates above it if they are configured for the year.
+    pub fn tax_brackets(&self, year: i32) -> Vec<TaxBracket> {
+        let mut brackets = vec![TaxBracket {
+            threshold: dec!(0),
+            rate: (self.default_tax_rate * dec!(100)).normalize(),
+        }];
+
+        if let Some(progressive_brackets) = self.progressive_tax_brackets(year) {
+            brackets.extend(progressive_brackets.iter().map(|bracket| TaxBracket {
+                threshold: bracket.threshold,
+                rate: (bracket.rate * dec!(100)).normalize(),
+            }));
+        }
+
+        brackets
+    }
+
+    fn progressive_tax_brackets(&self, year: i32) -> Option<&Vec<TaxBracket>> {
+        self.progressive_tax_rates
+            .range((Bound::Unbounded, Bound::Included(year)))
+            .map(|entry| entry.1)
+            .last()
+    }
+
     fn tax_rate(&self, income_type: IncomeType, year: i32) -> Decimal {
         self.tax_rates.get(&income_type).and_then(|tax_rates| {
             tax_rates
@@ -83,26 +154,55 @@ impl Country {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Jurisdiction {
     Russia,
     Usa,
+    // Foreign brokers registered in other countries (Freedom Finance Global in Kazakhstan, Freedom
+    // Finance Europe in Cyprus, etc.)
+    Other(CountryCode),
 }
 
 impl Jurisdiction {
     pub fn name(self) -> &'static str {
         match self {
-            Jurisdiction::Russia => "Russia",
-            Jurisdiction::Usa    => "USA",
+            Jurisdiction::Russia   => "Russia",
+            Jurisdiction::Usa      => "USA",
+            Jurisdiction::Other(_) => "Other",
         }
     }
 
-    pub fn code(self) -> &'static str {
+    // Country which is declared in the tax statement as the country where the income is received
+    pub fn country_code(self) -> CountryCode {
         match self {
-            Jurisdiction::Russia => "RU",
-            Jurisdiction::Usa    => "US",
+            Jurisdiction::Russia => CountryCode::Russia,
+            Jurisdiction::Usa => CountryCode::Usa,
+            Jurisdiction::Other(country) => country,
         }
     }
+
+    fn from_country_code(country: CountryCode) -> Jurisdiction {
+        match country {
+            CountryCode::Russia => Jurisdiction::Russia,
+            CountryCode::Usa => Jurisdiction::Usa,
+            CountryCode::Other(_) => Jurisdiction::Other(country),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Jurisdiction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let value = String::deserialize(deserializer)?;
+
+        Ok(match value.as_str() {
+            "russia" => Jurisdiction::Russia,
+            "usa" => Jurisdiction::Usa,
+
+            // Other countries are specified by ISO 3166-1 alpha-2 code
+            _ => Jurisdiction::from_country_code(CountryCode::new(&value).map_err(|_| D::Error::custom(format!(
+                "Invalid jurisdiction: {:?}. Expected russia, usa or two-letter country code", value)))?),
+        })
+    }
 }
 
 // When we work with taxes in Russia, the following rounding rules are applied:
@@ -120,19 +220,21 @@ impl Jurisdiction {
 // 2. tax = round(round(696.12 * 0.13, 2), 0) = 91 (90.4956 without rounding)
 pub fn russia(
     trading_tax_rates: &BTreeMap<i32, Decimal>, dividends_tax_rates: &BTreeMap<i32, Decimal>,
-    interest_tax_rates: &BTreeMap<i32, Decimal>,
+    interest_tax_rates: &BTreeMap<i32, Decimal>, progressive_tax_rates: &BTreeMap<i32, Vec<TaxBracket>>,
 ) -> Country {
     Country::new("RUB", dec!(13), hashmap!{
         IncomeType::Trading => trading_tax_rates.clone(),
+        // Derivatives form a separate tax base, but are taxed with the same rates
+        IncomeType::Derivatives => trading_tax_rates.clone(),
         IncomeType::Dividends => dividends_tax_rates.clone(),
         IncomeType::Interest => interest_tax_rates.clone(),
-    }, 0)
+    }, progressive_tax_rates.clone(), 0)
 }
 
 pub fn us() -> Country {
     Country::new("USD", dec!(0), hashmap!{
         IncomeType::Dividends => btreemap!{0 => dec!(10)},
-    }, 2)
+    }, BTreeMap::new(), 2)
 }
 
 pub fn is_valid_execution_date(conclusion: Date, execution: Date) -> bool {
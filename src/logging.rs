@@ -0,0 +1,103 @@
+// easy_logging's GlobalContext is process-wide, so it can't be used when several portfolios are
+// processed in parallel. Thread pool tasks set their own thread-local context instead which is
+// prepended to all log messages of the thread.
+
+use std::cell::RefCell;
+
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+
+thread_local! {
+    #[allow(clippy::missing_const_for_thread_local)]
+    static CONTEXT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+pub fn init(module_name: &'static str, level: Level) -> Result<(), SetLoggerError> {
+    let (max_level, logger) = easy_logging::builder(module_name, level).into_log();
+    log::set_boxed_logger(Box::new(ContextLogger(logger)))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+pub struct ThreadContext {
+    prev: Option<String>,
+}
+
+impl ThreadContext {
+    // The context may be nested: thread pool may run another task on the same thread while the
+    // current one waits for its subtasks.
+    pub fn new(name: &str) -> ThreadContext {
+        let prev = CONTEXT.with(|context| context.replace(Some(name.to_owned())));
+        ThreadContext {prev}
+    }
+
+    // Returns the current context to pass it to the subtasks
+    pub fn current() -> Option<String> {
+        CONTEXT.with(|context| context.borrow().clone())
+    }
+}
+
+impl Drop for ThreadContext {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CONTEXT.with(|context| context.replace(prev));
+    }
+}
+
+struct ContextLogger(Box<dyn Log>);
+
+impl Log for ContextLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        CONTEXT.with(|context| match context.borrow().as_ref() {
+            Some(name) => self.0.log(&Record::builder()
+                .args(format_args!("[{}] {}", name, record.args()))
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build()),
+            None => self.0.log(record),
+        })
+    }
+
+    fn flush(&self) {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn thread_context() {
+        assert_eq!(ThreadContext::current(), None);
+
+        {
+            let _context = ThreadContext::new("first");
+            assert_eq!(ThreadContext::current().as_deref(), Some("first"));
+
+            {
+                let _context = ThreadContext::new("second");
+                assert_eq!(ThreadContext::current().as_deref(), Some("second"));
+            }
+
+            assert_eq!(ThreadContext::current().as_deref(), Some("first"));
+        }
+
+        assert_eq!(ThreadContext::current(), None);
+
+        let names = ["first", "second", "third", "fourth"];
+        let contexts = names.par_iter().map(|&name| {
+            let _context = ThreadContext::new(name);
+            ThreadContext::current()
+        }).collect::<Vec<_>>();
+
+        assert_eq!(contexts, names.iter().map(|&name| Some(name.to_owned())).collect::<Vec<_>>());
+    }
+}
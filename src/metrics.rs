@@ -104,10 +104,14 @@ fn collect_portfolio_metrics(statistics: &PortfolioCurrencyStatistics) {
     set_structure_metric(&INCOME_STRUCTURE, currency, "Trading", income_structure.net_trading_income());
     set_structure_metric(&INCOME_STRUCTURE, currency, "Dividends", income_structure.net_dividend_income());
     set_structure_metric(&INCOME_STRUCTURE, currency, "Interest", income_structure.net_interest_income());
+    set_structure_metric(&INCOME_STRUCTURE, currency, "Coupons", income_structure.net_coupon_income());
+    set_structure_metric(&INCOME_STRUCTURE, currency, "Repo", income_structure.net_repo_income());
+    set_structure_metric(&INCOME_STRUCTURE, currency, "Derivatives", income_structure.net_derivative_income());
     set_structure_metric(&INCOME_STRUCTURE, currency, "Tax deductions", income_structure.tax_deductions());
 
     set_structure_metric(&EXPENCES_STRUCTURE, currency, "Taxes", income_structure.taxes());
     set_structure_metric(&EXPENCES_STRUCTURE, currency, "Commissions", income_structure.commissions);
+    set_structure_metric(&EXPENCES_STRUCTURE, currency, "Margin expenses", income_structure.margin_expenses);
 
     set_portfolio_metric(&PROJECTED_TAXES, currency, statistics.projected_taxes);
     set_portfolio_metric(&PROJECTED_TAX_DEDUCTIONS, currency, statistics.projected_tax_deductions);
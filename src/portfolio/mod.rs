@@ -22,13 +22,9 @@ mod rebalancing;
 
 pub fn sync(config: &Config, portfolio_name: &str) -> GenericResult<TelemetryRecordBuilder> {
     let portfolio = config.get_portfolio(portfolio_name)?;
-    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
     let database = db::connect(&config.db_path)?;
 
-    let statement = BrokerStatement::read(
-        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
-        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.corporate_actions,
-        ReadingStrictness::empty())?;
+    let statement = BrokerStatement::read_portfolio(config, portfolio, ReadingStrictness::empty())?;
     statement.check_date();
 
     let assets = Assets::new(statement.cash_assets, statement.open_positions);
@@ -129,7 +125,7 @@ pub fn rebalance(config: &Config, portfolio_name: &str, flat: bool) -> GenericRe
 
 fn process(config: &Config, portfolio_name: &str, rebalance: bool, flat: bool) -> GenericResult<TelemetryRecordBuilder> {
     let portfolio_config = config.get_portfolio(portfolio_name)?;
-    let broker = portfolio_config.broker.get_info(config, portfolio_config.plan.as_ref())?;
+    let broker = portfolio_config.broker_info(config)?;
     let database = db::connect(&config.db_path)?;
 
     let quotes = Rc::new(Quotes::new(config, database.clone())?);
@@ -138,12 +134,8 @@ fn process(config: &Config, portfolio_name: &str, rebalance: bool, flat: bool) -
     let assets = Assets::load(database, &portfolio_config.name)?;
     assets.validate(portfolio_config)?;
 
-    let statement = portfolio_config.statements.as_ref().map(|path| {
-        BrokerStatement::read(
-            broker.clone(), path, &portfolio_config.symbol_remapping,
-            &portfolio_config.instrument_internal_ids, &portfolio_config.instrument_names,
-            portfolio_config.get_tax_remapping()?, &portfolio_config.corporate_actions,
-            ReadingStrictness::empty())
+    let statement = portfolio_config.statements.as_ref().map(|_| {
+        BrokerStatement::read_portfolio(config, portfolio_config, ReadingStrictness::empty())
     }).transpose()?;
 
     let mut portfolio = Portfolio::load(
@@ -0,0 +1,187 @@
+use chrono::Datelike;
+use log::warn;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, BondIncome, BondIncomeType, calculate_bond_income_taxes};
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::{Country, Jurisdiction};
+use crate::types::{Date, Decimal};
+
+use super::statement::TaxStatement;
+use super::summary::IncomeSummary;
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Облигация")]
+    bond: String,
+    #[column(name="Операция")]
+    operation: &'static str,
+    #[column(name="Валюта", align="center")]
+    currency: String,
+    #[column(name="Сумма")]
+    foreign_amount: Cash,
+    #[column(name="Курс руб.")]
+    currency_rate: Option<Decimal>,
+    #[column(name="Сумма (руб)")]
+    amount: Cash,
+    #[column(name="К уплате")]
+    tax_to_pay: Option<Cash>,
+    #[column(name="Удержано")]
+    withheld_tax: Option<Cash>,
+}
+
+pub fn process_income(
+    country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
+    mut tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
+) -> GenericResult<IncomeSummary> {
+    let broker_jurisdiction = broker_statement.broker.jurisdiction;
+
+    let income: Vec<&BondIncome> = broker_statement.bond_income.iter().filter(|income| {
+        year.map_or(true, |year| income.date.year() == year)
+    }).collect();
+
+    let mut table = Table::new();
+    let mut total_foreign_amount = MultiCurrencyCashAccount::new();
+    let mut total_amount = Cash::zero(country.currency);
+
+    for income in income.iter().copied() {
+        let foreign_amount = income.amount.round();
+        total_foreign_amount.deposit(foreign_amount);
+
+        let precise_currency_rate = converter.precise_currency_rate(
+            income.date, foreign_amount.currency, country.currency)?;
+
+        let amount = converter.convert_to_cash_rounding(income.date, foreign_amount, country.currency)?;
+        total_amount += amount;
+
+        table.add_row(Row {
+            date: income.date,
+            bond: broker_statement.instrument_info.get_name(&income.original_symbol),
+            operation: income.local_description(),
+            currency: foreign_amount.currency.to_owned(),
+            foreign_amount: foreign_amount,
+            currency_rate: if foreign_amount.currency != country.currency {
+                Some(precise_currency_rate)
+            } else {
+                None
+            },
+            amount,
+            tax_to_pay: None,
+            withheld_tax: None,
+        });
+
+        if let Some(ref mut statement) = tax_statement {
+            match broker_jurisdiction {
+                Jurisdiction::Russia => {
+                    warn!(concat!(
+                        "Don't declare bond income in the tax statement ",
+                        "assuming that it will be declared by broker's tax agent.",
+                    ));
+                    tax_statement = None;
+                },
+
+                _ => {
+                    // Paid accrued interest is taken into account only in the tax calculation here:
+                    // it has to be declared manually as an expense.
+                    if income.type_ == BondIncomeType::AccruedInterest && income.amount.is_negative() {
+                        continue;
+                    }
+
+                    let country_code = broker_jurisdiction.country_code();
+                    let description = format!(
+                        "{}: Купонный доход по облигациям {}",
+                        broker_statement.broker.name, income.original_symbol);
+
+                    statement.add_interest_income(
+                        &description, income.date, country_code,
+                        foreign_amount.currency, precise_currency_rate,
+                        foreign_amount.amount, amount.amount
+                    ).map_err(|e| format!(
+                        "Unable to add {} to the tax statement: {}", income.description(), e
+                    ))?;
+                }
+            }
+        }
+    }
+
+    let summary = calculate_summary(country, broker_jurisdiction, &income, converter)?;
+
+    if !table.is_empty() {
+        let mut totals = table.add_empty_row();
+        totals.set_foreign_amount(total_foreign_amount);
+        totals.set_amount(total_amount);
+
+        if broker_jurisdiction == Jurisdiction::Russia {
+            totals.set_withheld_tax(summary.withheld_tax);
+            table.hide_tax_to_pay();
+        } else {
+            totals.set_tax_to_pay(summary.tax_to_pay);
+            table.hide_withheld_tax();
+        }
+
+        table.print(&format!(
+            "Расчет дохода по облигациям, полученного через {}", broker_statement.broker.name));
+    }
+
+    Ok(summary)
+}
+
+// Brokers with Russia jurisdiction are tax agents, so the tax is withheld by the broker instead of
+// being paid by us.
+fn calculate_summary(
+    country: &Country, broker_jurisdiction: Jurisdiction, income: &[&BondIncome], converter: &CurrencyConverter,
+) -> GenericResult<IncomeSummary> {
+    let mut summary = IncomeSummary::new(country.currency);
+
+    for tax in calculate_bond_income_taxes(country, income.iter().copied(), converter)?.values() {
+        if tax.income.is_positive() {
+            summary.income += tax.income;
+        }
+
+        if broker_jurisdiction == Jurisdiction::Russia {
+            summary.withheld_tax += tax.tax_to_pay;
+        } else {
+            summary.tax_to_pay += tax.tax_to_pay;
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rstest::rstest;
+
+    use crate::localities;
+
+    use super::*;
+
+    #[rstest(jurisdiction, tax_to_pay, withheld_tax,
+        case(Jurisdiction::Usa, dec!(52), dec!(0)),
+        case(Jurisdiction::Russia, dec!(0), dec!(52)),
+    )]
+    fn summary(jurisdiction: Jurisdiction, tax_to_pay: Decimal, withheld_tax: Decimal) {
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &BTreeMap::new());
+
+        let income = [
+            (date!(2021, 1, 13), BondIncomeType::AccruedInterest, dec!(-100)),
+            (date!(2021, 2, 3), BondIncomeType::Coupon, dec!(500)),
+        ].iter().map(|&(date, type_, amount)| {
+            BondIncome::new(date, "SU46020RMFS2", type_, Cash::new("RUB", amount))
+        }).collect::<Vec<_>>();
+        let income = income.iter().collect::<Vec<_>>();
+
+        let summary = calculate_summary(&country, jurisdiction, &income, &CurrencyConverter::mock()).unwrap();
+        assert_eq!(summary.income, Cash::new("RUB", dec!(400)));
+        assert_eq!(summary.tax_to_pay, Cash::new("RUB", tax_to_pay));
+        assert_eq!(summary.withheld_tax, Cash::new("RUB", withheld_tax));
+    }
+}
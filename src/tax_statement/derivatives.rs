@@ -0,0 +1,130 @@
+use chrono::Datelike;
+use log::warn;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, DerivativeResult, calculate_derivative_taxes};
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::{Country, Jurisdiction};
+use crate::types::{Date, Decimal};
+
+use super::statement::TaxStatement;
+use super::summary::IncomeSummary;
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата")]
+    date: Date,
+    #[column(name="Контракт")]
+    symbol: String,
+    #[column(name="Количество")]
+    quantity: Decimal,
+    #[column(name="Валюта", align="center")]
+    currency: String,
+    #[column(name="Результат")]
+    foreign_profit: Cash,
+    #[column(name="Курс руб.")]
+    currency_rate: Option<Decimal>,
+    #[column(name="Результат (руб)")]
+    profit: Cash,
+    #[column(name="К уплате")]
+    tax_to_pay: Option<Cash>,
+}
+
+pub fn process_income(
+    country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
+    mut tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
+) -> GenericResult<IncomeSummary> {
+    let broker_jurisdiction = broker_statement.broker.jurisdiction;
+
+    let results: Vec<&DerivativeResult> = broker_statement.derivative_results.iter().filter(|result| {
+        year.map_or(true, |year| result.date.year() == year)
+    }).collect();
+
+    let mut table = Table::new();
+    let mut total_foreign_profit = MultiCurrencyCashAccount::new();
+    let mut total_profit = Cash::zero(country.currency);
+
+    for result in results.iter().copied() {
+        let foreign_profit = result.profit.round();
+        total_foreign_profit.deposit(foreign_profit);
+
+        let precise_currency_rate = converter.precise_currency_rate(
+            result.date, foreign_profit.currency, country.currency)?;
+
+        let profit = converter.convert_to_cash_rounding(result.date, foreign_profit, country.currency)?;
+        total_profit += profit;
+
+        table.add_row(Row {
+            date: result.date,
+            symbol: result.symbol.clone(),
+            quantity: result.quantity,
+            currency: foreign_profit.currency.to_owned(),
+            foreign_profit: foreign_profit,
+            currency_rate: if foreign_profit.currency != country.currency {
+                Some(precise_currency_rate)
+            } else {
+                None
+            },
+            profit,
+            tax_to_pay: None,
+        });
+
+        if let Some(ref mut statement) = tax_statement {
+            match broker_jurisdiction {
+                Jurisdiction::Russia => {
+                    warn!(concat!(
+                        "Don't declare derivatives income in the tax statement ",
+                        "assuming that it will be declared by broker's tax agent.",
+                    ));
+                    tax_statement = None;
+                },
+
+                _ => {
+                    let country_code = broker_jurisdiction.country_code();
+                    let description = format!(
+                        "{}: Операции с ПФИ {}", broker_statement.broker.name, result.symbol);
+
+                    // Losses are declared as expenses to be offset against the profit of other
+                    // operations with derivatives
+                    let (amount, local_amount, local_expenses) = if foreign_profit.is_negative() {
+                        (dec!(0), dec!(0), -profit.amount)
+                    } else {
+                        (foreign_profit.amount, profit.amount, dec!(0))
+                    };
+
+                    statement.add_derivative_income(
+                        &description, result.date, country_code,
+                        foreign_profit.currency, precise_currency_rate,
+                        amount, local_amount, local_expenses,
+                    ).map_err(|e| format!(
+                        "Unable to add {} to the tax statement: {}", result.description(), e
+                    ))?;
+                }
+            }
+        }
+    }
+
+    let mut total_tax_to_pay = Cash::zero(country.currency);
+    for tax in calculate_derivative_taxes(country, results.iter().copied(), converter)?.values() {
+        total_tax_to_pay += tax.tax_to_pay;
+    }
+
+    if !table.is_empty() {
+        let mut totals = table.add_empty_row();
+        totals.set_foreign_profit(total_foreign_profit);
+        totals.set_profit(total_profit);
+        totals.set_tax_to_pay(total_tax_to_pay);
+
+        table.print(&format!(
+            "Расчет дохода от операций с ПФИ, полученного через {}", broker_statement.broker.name));
+    }
+
+    Ok(IncomeSummary {
+        income: total_profit,
+        tax_to_pay: total_tax_to_pay,
+        ..IncomeSummary::new(country.currency)
+    })
+}
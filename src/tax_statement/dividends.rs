@@ -13,15 +13,16 @@ use crate::core::{EmptyResult, GenericResult};
 use crate::currency::{Cash, MultiCurrencyCashAccount};
 use crate::currency::converter::CurrencyConverter;
 use crate::instruments::IssuerTaxationType;
-use crate::localities::{Country, Jurisdiction};
+use crate::localities::Country;
 use crate::types::{Date, Decimal};
 
 use super::statement::{TaxStatement, CountryCode};
+use super::summary::IncomeSummary;
 
 pub fn process_income(
     country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
     tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
-) -> GenericResult<Cash> {
+) -> GenericResult<IncomeSummary> {
     let mut processor = Processor {
         broker_statement, tax_statement, tax_year: year,
         country, converter,
@@ -40,15 +41,22 @@ pub fn process_income(
         total_paid_tax: Cash::zero(country.currency),
         total_tax_deduction: Cash::zero(country.currency),
         total_tax_to_pay: Cash::zero(country.currency),
+        total_tax_agent_tax: Cash::zero(country.currency),
 
         total_income: Cash::zero(country.currency),
     };
 
     processor.process_dividends()?;
-    let total_tax_to_pay = processor.total_tax_to_pay;
+
+    let summary = IncomeSummary {
+        income: processor.total_amount,
+        tax_to_pay: processor.total_tax_to_pay,
+        withheld_tax: processor.total_tax_agent_tax,
+        ..IncomeSummary::new(country.currency)
+    };
     processor.print();
 
-    Ok(total_tax_to_pay)
+    Ok(summary)
 }
 
 #[derive(StaticTable)]
@@ -103,6 +111,8 @@ struct Processor<'a> {
     total_paid_tax: Cash,
     total_tax_deduction: Cash,
     total_tax_to_pay: Cash,
+    // Tax withheld by issuer's tax agent
+    total_tax_agent_tax: Cash,
 
     total_income: Cash,
 }
@@ -197,6 +207,7 @@ impl<'a> Processor<'a> {
             },
             IssuerTaxationType::TaxAgent => {
                 self.tax_agent_issuers.insert(dividend.original_issuer.clone());
+                self.total_tax_agent_tax += tax;
             },
         }
 
@@ -225,12 +236,12 @@ impl<'a> Processor<'a> {
                             "so all dividend income will be declared with USA jurisdiction (see {})."
                         ), url));
                     }
-                    Jurisdiction::Usa
+                    "US"
                 },
 
                 // Old IB statements may not contain ISIN/CUSIP information (conid was used instead)
                 Broker::InteractiveBrokers if dividend.date.year() < 2020 => {
-                    Jurisdiction::Usa
+                    "US"
                 },
 
                 _ => {
@@ -238,7 +249,7 @@ impl<'a> Processor<'a> {
                         "Unable to determine {} jurisdiction: there is no ISIN information for it in the broker statement",
                         dividend.original_issuer);
                 },
-            }.code()
+            }
         };
 
         if foreign_paid_tax.currency != foreign_amount.currency {
@@ -249,7 +260,7 @@ impl<'a> Processor<'a> {
 
         if let Some(ref mut tax_statement) = self.tax_statement {
             let source_from = CountryCode::new(income_country)?;
-            let received_in = CountryCode::new(broker.type_.jurisdiction().code())?;
+            let received_in = broker.jurisdiction.country_code();
             let description = format!("{}: Дивиденд от {}", broker.name, issuer);
 
             tax_statement.add_dividend_income(
@@ -9,10 +9,10 @@ use crate::currency::{Cash, MultiCurrencyCashAccount};
 use crate::currency::converter::CurrencyConverter;
 use crate::formatting;
 use crate::localities::{Country, Jurisdiction};
-use crate::tax_statement::statement::CountryCode;
 use crate::types::{Date, Decimal};
 
 use super::statement::TaxStatement;
+use super::summary::IncomeSummary;
 
 #[derive(StaticTable)]
 struct Row {
@@ -35,8 +35,8 @@ struct Row {
 pub fn process_income(
     country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
     mut tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
-) -> GenericResult<Cash> {
-    let broker_jurisdiction = broker_statement.broker.type_.jurisdiction();
+) -> GenericResult<IncomeSummary> {
+    let broker_jurisdiction = broker_statement.broker.jurisdiction;
 
     let mut table = Table::new();
 
@@ -85,8 +85,16 @@ pub fn process_income(
 
         if let Some(ref mut statement) = tax_statement {
             match broker_jurisdiction {
-                Jurisdiction::Usa => {
-                    let country_code = CountryCode::new(broker_jurisdiction.code())?;
+                Jurisdiction::Russia => {
+                    warn!(concat!(
+                        "Don't declare interest income in the tax statement ",
+                        "assuming that it will be declared by broker's tax agent.",
+                    ));
+                    tax_statement = None;
+                },
+
+                _ => {
+                    let country_code = broker_jurisdiction.country_code();
                     let description = format!(
                         "{}: Проценты на остаток по брокерскому счету",
                         broker_statement.broker.name);
@@ -99,14 +107,6 @@ pub fn process_income(
                         "Unable to add interest income from {} to the tax statement: {}",
                         formatting::format_date(interest.date), e
                     ))?;
-                },
-
-                Jurisdiction::Russia => {
-                    warn!(concat!(
-                        "Don't declare interest income in the tax statement ",
-                        "assuming that it will be declared by broker's tax agent.",
-                    ));
-                    tax_statement = None;
                 }
             }
         }
@@ -124,5 +124,44 @@ pub fn process_income(
             broker_statement.broker.name));
     }
 
-    Ok(total_tax_to_pay)
+    Ok(summarize(broker_jurisdiction, total_amount, total_tax_to_pay))
+}
+
+// Interest of brokers with Russia jurisdiction is paid by the broker itself, so the tax is withheld by
+// its tax agent instead of being paid by us.
+fn summarize(broker_jurisdiction: Jurisdiction, income: Cash, tax: Cash) -> IncomeSummary {
+    let mut summary = IncomeSummary {
+        income,
+        ..IncomeSummary::new(income.currency)
+    };
+
+    match broker_jurisdiction {
+        Jurisdiction::Russia => summary.withheld_tax = tax,
+        _ => summary.tax_to_pay = tax,
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::tax_statement::CountryCode;
+
+    use super::*;
+
+    #[rstest(jurisdiction, tax_to_pay, withheld_tax,
+        case(Jurisdiction::Usa, dec!(13), dec!(0)),
+        case(Jurisdiction::Other(CountryCode::Other(398)), dec!(13), dec!(0)),
+        case(Jurisdiction::Russia, dec!(0), dec!(13)),
+    )]
+    fn summary(jurisdiction: Jurisdiction, tax_to_pay: Decimal, withheld_tax: Decimal) {
+        let currency = "RUB";
+
+        let summary = summarize(jurisdiction, Cash::new(currency, dec!(100)), Cash::new(currency, dec!(13)));
+        assert_eq!(summary.income, Cash::new(currency, dec!(100)));
+        assert_eq!(summary.tax_to_pay, Cash::new(currency, tax_to_pay));
+        assert_eq!(summary.withheld_tax, Cash::new(currency, withheld_tax));
+    }
 }
\ No newline at end of file
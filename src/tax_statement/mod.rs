@@ -1,16 +1,26 @@
 use crate::broker_statement::{BrokerStatement, ReadingStrictness};
-use crate::config::Config;
-use crate::core::GenericResult;
+use crate::config::{Config, PortfolioConfig};
+use crate::core::{EmptyResult, GenericError, GenericResult};
 use crate::currency::converter::CurrencyConverter;
 use crate::db;
-use crate::localities::Jurisdiction;
+use crate::localities::{Country, Jurisdiction};
+use crate::taxes::{LossCarryforward, LossCarryforwardCalculator};
 use crate::telemetry::TelemetryRecordBuilder;
 
-pub use self::statement::TaxStatement;
+pub use self::progressive::calculate_progressive_tax;
+pub use self::statement::{CountryCode, TaxStatement};
+pub use self::summary::generate_tax_summary;
 
+use self::summary::PortfolioIncome;
+
+mod bonds;
+mod derivatives;
 mod dividends;
 mod interest;
+mod progressive;
+mod repo;
 mod statement;
+mod summary;
 mod tax_agent;
 mod trades;
 
@@ -19,12 +29,8 @@ pub fn generate_tax_statement(
 ) -> GenericResult<TelemetryRecordBuilder> {
     let country = config.get_tax_country();
     let portfolio = config.get_portfolio(portfolio_name)?;
-    let broker = portfolio.broker.get_info(config, portfolio.plan.as_ref())?;
-
-    let broker_statement = BrokerStatement::read(
-        broker, portfolio.statements_path()?, &portfolio.symbol_remapping, &portfolio.instrument_internal_ids,
-        &portfolio.instrument_names, portfolio.get_tax_remapping()?, &portfolio.corporate_actions,
-        ReadingStrictness::TRADE_SETTLE_DATE | ReadingStrictness::REPO_TRADES)?;
+    let broker_statement = BrokerStatement::read_portfolio(
+        config, portfolio, ReadingStrictness::TRADE_SETTLE_DATE)?;
 
     if let Some(year) = year {
         broker_statement.check_period_against_tax_year(year)?;
@@ -33,14 +39,7 @@ pub fn generate_tax_statement(
     let mut tax_statement = match tax_statement_path {
         Some(path) => {
             let year = year.ok_or("Tax year must be specified when tax statement is specified")?;
-
-            let statement = TaxStatement::read(path)?;
-            if statement.year != year {
-                return Err!("Tax statement year ({}) doesn't match the requested year {}",
-                            statement.year, year);
-            }
-
-            Some(statement)
+            Some(open_tax_statement(config, path, year)?)
         },
         None => None,
     };
@@ -48,21 +47,26 @@ pub fn generate_tax_statement(
     let database = db::connect(&config.db_path)?;
     let converter = CurrencyConverter::new(database, None, true);
 
-    let trades_tax = trades::process_income(
-        &country, portfolio, &broker_statement, year, tax_statement.as_mut(), &converter,
-    ).map_err(|e| format!("Failed to process income from stock trading: {}", e))?;
+    let other_statements = read_other_portfolios(config, &[portfolio])?;
+    let statements = std::iter::once((portfolio, &broker_statement))
+        .chain(other_statements.iter().map(|(portfolio, statement)| (*portfolio, statement)))
+        .collect::<Vec<_>>();
 
-    let dividends_tax = dividends::process_income(
-        &country, &broker_statement, year, tax_statement.as_mut(), &converter,
-    ).map_err(|e| format!("Failed to process dividend income: {}", e))?;
+    let loss_carryforward = calculate_loss_carryforward(config, &statements, &converter)?;
+    let progressive_tax = calculate_progressive_tax(
+        config, &statements, loss_carryforward.as_ref(), &converter,
+    )?.map(|calculator| calculator.calculate());
 
-    let interest_tax = interest::process_income(
-        &country, &broker_statement, year, tax_statement.as_mut(), &converter,
-    ).map_err(|e| format!("Failed to process income from idle cash interest: {}", e))?;
+    let income = process_income(
+        &country, portfolio, &broker_statement, year, tax_statement.as_mut(),
+        loss_carryforward.as_ref(), &converter)?;
+
+    if broker_statement.broker.jurisdiction == Jurisdiction::Russia {
+        tax_agent::process_tax_agent_withholdings(&broker_statement, year, income.tax_agent_tax());
+    }
 
-    if broker_statement.broker.type_.jurisdiction() == Jurisdiction::Russia {
-        let total_tax = trades_tax + dividends_tax + interest_tax;
-        tax_agent::process_tax_agent_withholdings(&broker_statement, year, total_tax);
+    if let Some(ref progressive_tax) = progressive_tax {
+        progressive::print(&country, progressive_tax, year);
     }
 
     if let Some(ref tax_statement) = tax_statement {
@@ -70,4 +74,152 @@ pub fn generate_tax_statement(
     }
 
     Ok(TelemetryRecordBuilder::new_with_broker(portfolio.broker))
-}
\ No newline at end of file
+}
+
+fn open_tax_statement(config: &Config, path: &str, year: i32) -> GenericResult<TaxStatement> {
+    if path.ends_with(".xml") {
+        let taxpayer = config.taxpayer.as_ref().ok_or(
+            "Taxpayer information must be specified in the configuration file to generate XML tax statement")?;
+        return TaxStatement::new_xml(path, year, taxpayer, &config.get_tax_country());
+    }
+
+    let statement = TaxStatement::read(path)?;
+    if statement.year != year {
+        return Err!("Tax statement year ({}) doesn't match the requested year {}",
+                    statement.year, year);
+    }
+
+    Ok(statement)
+}
+
+fn process_income(
+    country: &Country, portfolio: &PortfolioConfig, broker_statement: &BrokerStatement,
+    year: Option<i32>, mut tax_statement: Option<&mut TaxStatement>,
+    loss_carryforward: Option<&LossCarryforward>, converter: &CurrencyConverter,
+) -> GenericResult<PortfolioIncome> {
+    let trades = trades::process_income(
+        country, portfolio, broker_statement, year, tax_statement.as_deref_mut(),
+        loss_carryforward, converter,
+    ).map_err(|e| format!("Failed to process income from stock trading: {}", e))?;
+
+    let dividends = dividends::process_income(
+        country, broker_statement, year, tax_statement.as_deref_mut(), converter,
+    ).map_err(|e| format!("Failed to process dividend income: {}", e))?;
+
+    let interest = interest::process_income(
+        country, broker_statement, year, tax_statement.as_deref_mut(), converter,
+    ).map_err(|e| format!("Failed to process income from idle cash interest: {}", e))?;
+
+    let bonds = bonds::process_income(
+        country, broker_statement, year, tax_statement.as_deref_mut(), converter,
+    ).map_err(|e| format!("Failed to process bond income: {}", e))?;
+
+    let repo = repo::process_income(
+        country, broker_statement, year, tax_statement.as_deref_mut(), converter,
+    ).map_err(|e| format!("Failed to process repo income: {}", e))?;
+
+    let derivatives = derivatives::process_income(
+        country, broker_statement, year, tax_statement, converter,
+    ).map_err(|e| format!("Failed to process income from derivatives: {}", e))?;
+
+    Ok(PortfolioIncome {trades, dividends, interest, bonds, repo, derivatives})
+}
+
+// Calculates previous years' trading losses carryforward across all portfolios if it's enabled.
+//
+// Income of all jurisdictions is taken into account, because the tax base is calculated for the
+// whole tax year. Please note that brokers with Russia jurisdiction are tax agents which withhold the
+// tax on their own, so the losses which are applied to their income have to be claimed by a tax
+// statement which claims a refund of the withheld tax.
+pub fn calculate_loss_carryforward(
+    config: &Config, statements: &[(&PortfolioConfig, &BrokerStatement)], converter: &CurrencyConverter,
+) -> GenericResult<Option<LossCarryforward>> {
+    let loss_carryforward_config = match config.tax_loss_carryforward {
+        Some(ref loss_carryforward_config) => loss_carryforward_config,
+        None => return Ok(None),
+    };
+
+    let country = config.get_tax_country();
+    let mut calculator = LossCarryforwardCalculator::new(country.currency, loss_carryforward_config);
+
+    for_each_portfolio(config, statements, |portfolio, statement| {
+        let yearly_profit = trades::calculate_yearly_profit(
+            &country, portfolio, statement, converter,
+        ).map_err(|e| format!(
+            "Failed to calculate trading profit of {:?} portfolio: {}", portfolio.name, e))?;
+
+        for (year, profit) in yearly_profit {
+            calculator.add_profit(year, &portfolio.name, profit);
+        }
+
+        Ok(())
+    })?;
+
+    Ok(Some(calculator.calculate()))
+}
+
+// Loss carryforward and progressive tax rates are calculated across all portfolios, so reads broker
+// statements of the portfolios which aren't read by the caller yet if it's required. Fails if any of
+// them can't be read, because the calculations would be wrong without its income.
+pub fn read_other_portfolios<'a>(
+    config: &'a Config, read_portfolios: &[&PortfolioConfig],
+) -> GenericResult<Vec<(&'a PortfolioConfig, BrokerStatement)>> {
+    if !requires_all_portfolios(config) {
+        return Ok(Vec::new());
+    }
+
+    let (statements, mut errors) = read_portfolios_except(config, read_portfolios);
+    if !errors.is_empty() {
+        return Err(errors.remove(0).1);
+    }
+
+    Ok(statements)
+}
+
+fn requires_all_portfolios(config: &Config) -> bool {
+    config.tax_loss_carryforward.is_some() || config.get_tax_country().has_progressive_tax_rates()
+}
+
+// Broker statements of the read portfolios and errors of the portfolios which can't be read
+type PortfolioStatements<'a> = (Vec<(&'a PortfolioConfig, BrokerStatement)>, Vec<(&'a PortfolioConfig, GenericError)>);
+
+// Reads broker statements of all portfolios except the specified ones
+fn read_portfolios_except<'a>(config: &'a Config, except: &[&PortfolioConfig]) -> PortfolioStatements<'a> {
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+
+    for portfolio in &config.portfolios {
+        if except.iter().any(|other| other.name == portfolio.name) {
+            continue;
+        }
+
+        match read_portfolio(config, portfolio) {
+            Ok(statement) => statements.push((portfolio, statement)),
+            Err(e) => errors.push((portfolio, e)),
+        }
+    }
+
+    (statements, errors)
+}
+
+// Calls the handler for each of the passed portfolios in the configuration order to get stable
+// results regardless of the order in which the broker statements have been read.
+fn for_each_portfolio<F>(
+    config: &Config, statements: &[(&PortfolioConfig, &BrokerStatement)], mut handler: F,
+) -> EmptyResult
+    where F: FnMut(&PortfolioConfig, &BrokerStatement) -> EmptyResult
+{
+    for portfolio in &config.portfolios {
+        if let Some(&(_, statement)) = statements.iter().find(|(other, _)| other.name == portfolio.name) {
+            handler(portfolio, statement)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_portfolio(config: &Config, portfolio: &PortfolioConfig) -> GenericResult<BrokerStatement> {
+    Ok(BrokerStatement::read_portfolio(
+        config, portfolio, ReadingStrictness::TRADE_SETTLE_DATE,
+    ).map_err(|e| format!("Failed to read {:?} portfolio: {}", portfolio.name, e))?)
+}
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{
+    BrokerStatement, calculate_bond_income_taxes, calculate_derivative_taxes, calculate_repo_income_taxes};
+use crate::config::{Config, PortfolioConfig};
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::{Country, Jurisdiction};
+use crate::taxes::{IncomeType, LossCarryforward, ProgressiveTax, ProgressiveTaxCalculator};
+use crate::types::Decimal;
+
+use super::trades;
+
+// Collects income of the passed portfolios for progressive tax calculation if progressive tax rates
+// are configured.
+pub fn calculate_progressive_tax(
+    config: &Config, statements: &[(&PortfolioConfig, &BrokerStatement)],
+    loss_carryforward: Option<&LossCarryforward>, converter: &CurrencyConverter,
+) -> GenericResult<Option<ProgressiveTaxCalculator>> {
+    let country = config.get_tax_country();
+    if !country.has_progressive_tax_rates() {
+        return Ok(None);
+    }
+
+    let mut calculator = ProgressiveTaxCalculator::new(&country);
+
+    super::for_each_portfolio(config, statements, |portfolio, statement| {
+        Ok(add_portfolio_income(
+            &mut calculator, &country, portfolio, statement, loss_carryforward, converter,
+        ).map_err(|e| format!("Failed to calculate income of {:?} portfolio: {}", portfolio.name, e))?)
+    })?;
+
+    Ok(Some(calculator))
+}
+
+fn add_portfolio_income(
+    calculator: &mut ProgressiveTaxCalculator, country: &Country, portfolio: &PortfolioConfig,
+    statement: &BrokerStatement, loss_carryforward: Option<&LossCarryforward>, converter: &CurrencyConverter,
+) -> EmptyResult {
+    // Trading and other income of the portfolio by years to calculate income which is taxed by the
+    // tax agent (if any): trading losses reduce only trading income.
+    let mut portfolio_income: BTreeMap<i32, (Decimal, Decimal)> = BTreeMap::new();
+    let mut add_income = |year, income_type, income| {
+        calculator.add_income(year, income_type, income);
+
+        let (trading, other) = portfolio_income.entry(year).or_default();
+        match income_type {
+            IncomeType::Trading | IncomeType::Derivatives => *trading += income,
+            IncomeType::Dividends | IncomeType::Interest => *other += income,
+        }
+    };
+
+    for (year, mut profit) in trades::calculate_yearly_profit(country, portfolio, statement, converter)? {
+        if let Some(loss_carryforward) = loss_carryforward {
+            profit -= loss_carryforward.applied(year, &portfolio.name);
+        }
+        add_income(year, IncomeType::Trading, profit);
+    }
+
+    for dividend in &statement.dividends {
+        let amount = converter.convert_to_cash_rounding(dividend.date, dividend.amount.round(), country.currency)?;
+        add_income(dividend.date.year(), IncomeType::Dividends, amount.amount);
+    }
+
+    for interest in &statement.idle_cash_interest {
+        if interest.amount.is_negative() {
+            continue;
+        }
+
+        let amount = converter.convert_to_cash_rounding(interest.date, interest.amount.round(), country.currency)?;
+        add_income(interest.date.year(), IncomeType::Interest, amount.amount);
+    }
+
+    // Paid accrued interest reduces only the bond income of the same year
+    for (year, tax) in calculate_bond_income_taxes(country, &statement.bond_income, converter)? {
+        if tax.income.is_positive() {
+            add_income(year, IncomeType::Interest, tax.income.amount);
+        }
+    }
+
+    for (year, tax) in calculate_repo_income_taxes(country, &statement.repo_income, converter)? {
+        add_income(year, IncomeType::Trading, tax.income.amount);
+    }
+
+    for (year, tax) in calculate_derivative_taxes(country, &statement.derivative_results, converter)? {
+        add_income(year, IncomeType::Derivatives, tax.income.amount);
+    }
+
+    if statement.broker.jurisdiction == Jurisdiction::Russia {
+        for (year, (trading, other)) in portfolio_income {
+            calculator.add_tax_agent_income(year, std::cmp::max(trading, dec!(0)) + other);
+        }
+    }
+
+    // The tax may be withheld in the beginning of the next year, so it's booked to the tax year which
+    // it's withheld for.
+    for withholding in &statement.tax_agent_withholdings {
+        let amount = converter.convert_to_cash_rounding(withholding.date, withholding.amount.round(), country.currency)?;
+        calculator.add_withheld_tax(withholding.year, amount.amount);
+    }
+
+    Ok(())
+}
+
+pub fn print(country: &Country, progressive_tax: &ProgressiveTax, year: Option<i32>) {
+    let mut table = Table::new();
+
+    for (tax_year, stat) in progressive_tax.iter() {
+        if year.map_or(false, |year| year != tax_year) || stat.surcharge.is_zero() {
+            continue;
+        }
+
+        table.add_row(Row {
+            year: tax_year,
+            trading: country.cash(stat.trading),
+            dividends: country.cash(stat.dividends),
+            interest: country.cash(stat.interest),
+            income: country.cash(stat.income),
+            withheld_tax: country.cash(stat.withheld_tax),
+            surcharge: country.cash(stat.surcharge),
+        });
+    }
+
+    if table.is_empty() {
+        return;
+    }
+
+    if year.is_some() {
+        table.hide_year();
+    }
+
+    table.print("Доплата НДФЛ по повышенной ставке (по всем портфелям)");
+}
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Год")]
+    year: i32,
+    #[column(name="Торговля")]
+    trading: Cash,
+    #[column(name="Дивиденды")]
+    dividends: Cash,
+    #[column(name="Проценты")]
+    interest: Cash,
+    #[column(name="Налоговая\nбаза")]
+    income: Cash,
+    #[column(name="Удержано\nналоговыми\nагентами")]
+    withheld_tax: Cash,
+    #[column(name="К доплате")]
+    surcharge: Cash,
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::NamedTempFile;
+
+    use crate::broker_statement::{
+        BondIncome, BondIncomeType, DerivativeResult, Dividend, IdleCashInterest, RepoIncome, TaxWithholding};
+    use crate::instruments::IssuerTaxationType;
+    use crate::localities;
+    use crate::taxes::TaxBracket;
+
+    use super::*;
+
+    #[test]
+    fn portfolio_income() {
+        let database = NamedTempFile::new().unwrap();
+        let mut config = Config::load("testdata/configs/loss-carryforward/config.yaml").unwrap();
+        config.db_path = database.path().to_str().unwrap().to_owned();
+
+        let country = config.get_tax_country();
+        let converter = CurrencyConverter::mock();
+
+        let portfolio = config.get_portfolio("self-declared").unwrap();
+        let mut statement = super::super::read_portfolio(&config, portfolio).unwrap();
+
+        let date = date!(2021, 6, 1);
+        let cash = |amount| Cash::new("RUB", amount);
+
+        statement.dividends.push(Dividend {
+            date,
+            issuer: s!("VTI"),
+            original_issuer: s!("VTI"),
+            amount: cash(dec!(100)),
+            paid_tax: cash(dec!(0)),
+            taxation_type: IssuerTaxationType::Manual(None),
+            skip_from_cash_flow: false,
+        });
+        statement.idle_cash_interest.push(IdleCashInterest::new(date, cash(dec!(10))));
+
+        statement.bond_income.push(BondIncome::new(date, "BOND", BondIncomeType::AccruedInterest, cash(dec!(-20))));
+        statement.bond_income.push(BondIncome::new(date, "BOND", BondIncomeType::Coupon, cash(dec!(50))));
+
+        statement.repo_income.push(RepoIncome {
+            open_date: date,
+            date: date!(2021, 7, 1),
+            symbol: s!("SBER"),
+            quantity: dec!(10),
+            amount: cash(dec!(40)),
+            commission: cash(dec!(5)),
+        });
+
+        statement.derivative_results.push(DerivativeResult {
+            date,
+            symbol: s!("SBER 210716C100"),
+            quantity: dec!(1),
+            profit: cash(dec!(-25)),
+        });
+
+        // The tax is withheld for the previous year and isn't rounded by the broker
+        statement.tax_agent_withholdings.push(
+            TaxWithholding::new(date!(2022, 1, 15), 2021, cash(dec!(13.004))).unwrap());
+
+        let mut calculator = ProgressiveTaxCalculator::new(&country);
+        add_portfolio_income(&mut calculator, &country, portfolio, &statement, None, &converter).unwrap();
+
+        let result = calculator.calculate();
+        let stat = result.get(2021).unwrap();
+
+        // 150 of stock trading profit + 35 of repo income - 25 of derivatives loss
+        assert_eq!(stat.trading, dec!(160));
+        assert_eq!(stat.dividends, dec!(100));
+        assert_eq!(stat.interest, dec!(40));
+        assert_eq!(stat.withheld_tax, dec!(13));
+        assert_eq!(stat.income, dec!(300));
+        assert!(result.get(2022).is_none());
+    }
+
+    #[test]
+    fn tax_agent_withholding() {
+        let database = NamedTempFile::new().unwrap();
+        let mut config = Config::load("testdata/configs/loss-carryforward/config.yaml").unwrap();
+        config.db_path = database.path().to_str().unwrap().to_owned();
+
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &btreemap!{
+            2021 => vec![TaxBracket {threshold: dec!(5_000_000), rate: dec!(15)}],
+        });
+        let converter = CurrencyConverter::mock();
+
+        let date = date!(2021, 6, 1);
+        let cash = |amount| Cash::new("RUB", amount);
+        let dividend = |amount| Dividend {
+            date,
+            issuer: s!("VTI"),
+            original_issuer: s!("VTI"),
+            amount: cash(amount),
+            paid_tax: cash(dec!(0)),
+            taxation_type: IssuerTaxationType::Manual(None),
+            skip_from_cash_flow: false,
+        };
+
+        let mut calculator = ProgressiveTaxCalculator::new(&country);
+
+        // 150 of stock trading profit + dividends. The tax agent has applied the increased rate to its
+        // income above the threshold: 5 000 000 * 13% + 1 000 000 * 15%.
+        let portfolio = config.get_portfolio("tax-agent").unwrap();
+        let mut statement = super::super::read_portfolio(&config, portfolio).unwrap();
+        statement.dividends.push(dividend(dec!(5_999_850)));
+        statement.tax_agent_withholdings.push(
+            TaxWithholding::new(date!(2022, 1, 15), 2021, cash(dec!(800_000))).unwrap());
+        add_portfolio_income(&mut calculator, &country, portfolio, &statement, None, &converter).unwrap();
+
+        let portfolio = config.get_portfolio("self-declared").unwrap();
+        let mut statement = super::super::read_portfolio(&config, portfolio).unwrap();
+        statement.dividends.push(dividend(dec!(999_850)));
+        add_portfolio_income(&mut calculator, &country, portfolio, &statement, None, &converter).unwrap();
+
+        // (7 000 000 - 5 000 000) * (15% - 13%) - 1 000 000 * (15% - 13%) withheld by the tax agent
+        let result = calculator.calculate();
+        let stat = result.get(2021).unwrap();
+        assert_eq!(stat.income, dec!(7_000_000));
+        assert_eq!(stat.tax_agent_income, dec!(6_000_000));
+        assert_eq!(stat.withheld_tax, dec!(800_000));
+        assert_eq!(stat.surcharge, dec!(20_000));
+    }
+}
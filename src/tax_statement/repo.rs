@@ -0,0 +1,141 @@
+use chrono::Datelike;
+use log::warn;
+
+use static_table_derive::StaticTable;
+
+use crate::broker_statement::{BrokerStatement, RepoIncome, calculate_repo_income_taxes};
+use crate::core::GenericResult;
+use crate::currency::{Cash, MultiCurrencyCashAccount};
+use crate::currency::converter::CurrencyConverter;
+use crate::localities::{Country, Jurisdiction};
+use crate::types::{Date, Decimal};
+
+use super::statement::TaxStatement;
+use super::summary::IncomeSummary;
+
+#[derive(StaticTable)]
+struct Row {
+    #[column(name="Дата открытия")]
+    open_date: Date,
+    #[column(name="Дата закрытия")]
+    date: Date,
+    #[column(name="Инструмент")]
+    security: String,
+    #[column(name="Количество")]
+    quantity: Decimal,
+    #[column(name="Валюта", align="center")]
+    currency: String,
+    #[column(name="Доход")]
+    foreign_amount: Cash,
+    #[column(name="Комиссия")]
+    foreign_commission: Cash,
+    #[column(name="Курс руб.")]
+    currency_rate: Option<Decimal>,
+    #[column(name="Доход (руб)")]
+    profit: Cash,
+    #[column(name="К уплате")]
+    tax_to_pay: Option<Cash>,
+}
+
+pub fn process_income(
+    country: &Country, broker_statement: &BrokerStatement, year: Option<i32>,
+    mut tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
+) -> GenericResult<IncomeSummary> {
+    let broker_jurisdiction = broker_statement.broker.jurisdiction;
+
+    let income: Vec<&RepoIncome> = broker_statement.repo_income.iter().filter(|repo| {
+        year.map_or(true, |year| repo.date.year() == year)
+    }).collect();
+
+    let mut table = Table::new();
+    let mut total_foreign_amount = MultiCurrencyCashAccount::new();
+    let mut total_foreign_commission = MultiCurrencyCashAccount::new();
+    let mut total_profit = Cash::zero(country.currency);
+
+    for repo in income.iter().copied() {
+        let foreign_amount = repo.amount.round();
+        total_foreign_amount.deposit(foreign_amount);
+
+        let foreign_commission = repo.commission.round();
+        total_foreign_commission.deposit(foreign_commission);
+
+        let foreign_profit = repo.profit()?.round();
+
+        let precise_currency_rate = converter.precise_currency_rate(
+            repo.date, foreign_profit.currency, country.currency)?;
+
+        let profit = converter.convert_to_cash_rounding(repo.date, foreign_profit, country.currency)?;
+        total_profit += profit;
+
+        table.add_row(Row {
+            open_date: repo.open_date,
+            date: repo.date,
+            security: broker_statement.instrument_info.get_name(&repo.symbol),
+            quantity: repo.quantity,
+            currency: foreign_profit.currency.to_owned(),
+            foreign_amount,
+            foreign_commission,
+            currency_rate: if foreign_profit.currency != country.currency {
+                Some(precise_currency_rate)
+            } else {
+                None
+            },
+            profit,
+            tax_to_pay: None,
+        });
+
+        if let Some(ref mut statement) = tax_statement {
+            match broker_jurisdiction {
+                Jurisdiction::Russia => {
+                    warn!(concat!(
+                        "Don't declare repo income in the tax statement ",
+                        "assuming that it will be declared by broker's tax agent.",
+                    ));
+                    tax_statement = None;
+                },
+
+                _ => {
+                    let country_code = broker_jurisdiction.country_code();
+                    let description = format!(
+                        "{}: Доход от сделок РЕПО с {}", broker_statement.broker.name, repo.symbol);
+
+                    let (amount, local_amount, local_expenses) = if foreign_profit.is_negative() {
+                        (dec!(0), dec!(0), -profit.amount)
+                    } else {
+                        (foreign_profit.amount, profit.amount, dec!(0))
+                    };
+
+                    statement.add_stock_income(
+                        &description, repo.date, country_code,
+                        foreign_profit.currency, precise_currency_rate,
+                        amount, local_amount, local_expenses,
+                    ).map_err(|e| format!(
+                        "Unable to add {} to the tax statement: {}", repo.description(), e
+                    ))?;
+                }
+            }
+        }
+    }
+
+    let mut total_tax_to_pay = Cash::zero(country.currency);
+    for tax in calculate_repo_income_taxes(country, income.iter().copied(), converter)?.values() {
+        total_tax_to_pay += tax.tax_to_pay;
+    }
+
+    if !table.is_empty() {
+        let mut totals = table.add_empty_row();
+        totals.set_foreign_amount(total_foreign_amount);
+        totals.set_foreign_commission(total_foreign_commission);
+        totals.set_profit(total_profit);
+        totals.set_tax_to_pay(total_tax_to_pay);
+
+        table.print(&format!(
+            "Расчет дохода от сделок РЕПО, полученного через {}", broker_statement.broker.name));
+    }
+
+    Ok(IncomeSummary {
+        income: total_profit,
+        tax_to_pay: total_tax_to_pay,
+        ..IncomeSummary::new(country.currency)
+    })
+}
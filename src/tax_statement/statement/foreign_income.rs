@@ -35,6 +35,10 @@ impl Record for ForeignIncome {
         ForeignIncome::RECORD_NAME
     }
 
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn as_mut_any(&mut self) -> &mut dyn Any {
         self
     }
@@ -71,7 +75,7 @@ tax_statement_array_record!(CurrencyIncome {
     deduction: DeductionInfo,
 
     controlled_foreign_company: ControlledForeignCompanyInfo,
-}, index_length=4);
+});
 
 tax_statement_inner_record!(CurrencyInfo {
     automatic_convertion: bool,
@@ -150,14 +154,16 @@ pub enum IncomeType {
     Dividend,
     Interest,
     Stock,
+    Derivative,
     Other(GenericIncomeType),
 }
 
 impl IncomeType {
-    fn to_generic(&self) -> GenericIncomeType {
+    pub fn to_generic(&self) -> GenericIncomeType {
         let (category, code, name) = match self {
             IncomeType::Dividend => (0, 1010, "Дивиденды"),
             IncomeType::Stock => (0, 1530, "(01)Доходы от реализации ЦБ (обращ-ся на орг. рынке ЦБ)"),
+            IncomeType::Derivative => (0, 1532, "(01)Доходы по операциям с ПФИ (обращ-ся на орг. рынке ЦБ)"),
             IncomeType::Interest => (0, 6013, "Доходы в виде процентов, полученных от источников за пределами Российской Федерации, в отношении которых применяется налоговая ставка, предусмотренная пунктом 1 статьи 224 Кодекса"),
             IncomeType::Other(other) => return other.clone(),
         };
@@ -169,7 +175,7 @@ impl TaxStatementType for IncomeType {
     fn read(reader: &mut TaxStatementReader) -> GenericResult<IncomeType> {
         let generic = GenericIncomeType::read(reader)?;
 
-        for income_type in [IncomeType::Dividend, IncomeType::Interest, IncomeType::Stock] {
+        for income_type in [IncomeType::Dividend, IncomeType::Interest, IncomeType::Stock, IncomeType::Derivative] {
             if income_type.to_generic() == generic {
                 return Ok(income_type);
             }
@@ -0,0 +1,63 @@
+use itertools::Itertools;
+
+use crate::core::GenericResult;
+
+// Декларация program is released every year and each release works only with statements of its
+// own year: it writes its own header and may add new records or change encoding of the existing
+// ones. So everything that depends on the statement year is described here.
+//
+// Statements for years that aren't described here are refused: the program may fail to open a
+// file with unexpected layout or, even worse, silently lose some of its data. Each format must have
+// a sample statement of its year in testdata which is used for round-trip test.
+pub struct StatementFormat {
+    pub year: i32,
+
+    // Program format version which is written to the file header
+    version: &'static str,
+
+    // Records which are always written by the program regardless of statement contents
+    pub required_records: &'static [&'static str],
+
+    // Index length of array records (@CurrencyIncome0000, ...)
+    pub array_index_length: usize,
+
+    // Version of 3-NDFL XML format (ВерсФорм) which is accepted for the year. It changes
+    // independently of the program format, so must be checked against the format description
+    // published by FNS when a new year is added.
+    pub xml_version: &'static str,
+}
+
+const REQUIRED_RECORDS: &[&str] = &[
+    "@DeclInfo", "@PersonName", "@PersonDocument", "@Foreigner", "@PhoneForeignerHome",
+    "@PhoneForeignerWork", "@PersonAddress", "@HomePhone", "@WorkPhone", "@DeclInquiry",
+    "@StandartDeduct", "@SocialDeduct", "@ConstructionDeduct", "@CBDeduct", "@InvDeduct",
+];
+
+// Only the formats which are verified against real statements of the program are listed here. Formats
+// of 2022+ years (*.dc2...*.dc5) aren't supported yet: there are no sample statements to verify them.
+const FORMATS: &[StatementFormat] = &[
+    StatementFormat {
+        year: 2021,
+        version: "0103",
+        required_records: REQUIRED_RECORDS,
+        array_index_length: 4,
+        xml_version: "5.07",
+    },
+];
+
+impl StatementFormat {
+    pub fn get(year: i32) -> GenericResult<&'static StatementFormat> {
+        Ok(FORMATS.iter().find(|format| format.year == year).ok_or_else(|| format!(
+            "Tax statements for {} year are not supported. Supported years: {}",
+            year, FORMATS.iter().map(|format| format.year).join(", ")))?)
+    }
+
+    #[cfg(test)]
+    pub fn all() -> &'static [StatementFormat] {
+        FORMATS
+    }
+
+    pub fn header(&self) -> String {
+        format!(r"DLSG            Decl{}{}FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", self.year, self.version)
+    }
+}
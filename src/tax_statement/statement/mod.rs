@@ -3,18 +3,25 @@
 mod countries;
 mod encoding;
 mod foreign_income;
+mod format;
 mod parser;
 mod types;
+mod xml;
 
 use std::fs;
 
+use crate::config::TaxPayerConfig;
 use crate::core::{EmptyResult, GenericResult};
+use crate::localities::Country;
+use crate::taxes::TaxBracket;
 use crate::types::{Date, Decimal};
 
 use self::foreign_income::{ForeignIncome, CurrencyIncome, CurrencyInfo, DeductionInfo, IncomeType,
                            ControlledForeignCompanyInfo};
+use self::format::StatementFormat;
 use self::record::Record;
 use self::parser::{TaxStatementReader, TaxStatementWriter};
+use self::xml::XmlTaxStatementWriter;
 
 pub use self::countries::CountryCode;
 
@@ -22,19 +29,53 @@ pub use self::countries::CountryCode;
 pub struct TaxStatement {
     path: String,
     pub year: i32,
+    file_type: FileType,
     records: Vec<Box<dyn Record>>,
 }
 
+#[derive(Debug)]
+enum FileType {
+    // *.dcX file of Декларация program which is patched in place
+    Declaration,
+    // 3-NDFL in FNS XML format which is generated from scratch
+    Xml {
+        taxpayer: TaxPayerConfig,
+        tax_brackets: Vec<TaxBracket>,
+        loss_carryforward: Decimal,
+    },
+}
+
 impl TaxStatement {
     pub fn read(path: &str) -> GenericResult<TaxStatement> {
         Ok(TaxStatementReader::read(path).map_err(|e| format!(
             "Error while reading {:?} tax statement: {}", path, e))?)
     }
 
+    pub fn new_xml(
+        path: &str, year: i32, taxpayer: &TaxPayerConfig, country: &Country,
+    ) -> GenericResult<TaxStatement> {
+        StatementFormat::get(year)?;
+
+        Ok(TaxStatement {
+            path: path.to_owned(),
+            year: year,
+            file_type: FileType::Xml {
+                taxpayer: taxpayer.clone(),
+                tax_brackets: country.tax_brackets(year),
+                loss_carryforward: dec!(0),
+            },
+            records: vec![Box::new(ForeignIncome {incomes: Vec::new()})],
+        })
+    }
+
     pub fn save(&self) -> EmptyResult {
         let temp_path = format!("{}.new", self.path);
 
-        TaxStatementWriter::write(self, &temp_path).map_err(|e| {
+        match self.file_type {
+            FileType::Declaration => TaxStatementWriter::write(self, &temp_path),
+            FileType::Xml {ref taxpayer, ref tax_brackets, loss_carryforward} => XmlTaxStatementWriter::write(
+                self, taxpayer, tax_brackets, loss_carryforward, &temp_path),
+        }.map_err(|e| {
             let _ = fs::remove_file(&temp_path);
             format!("Failed to save the tax statement to {:?}: {}", temp_path, e)
         })?;
@@ -137,12 +178,90 @@ impl TaxStatement {
         Ok(())
     }
 
+    pub fn add_derivative_income(
+        &mut self, description: &str, date: Date, broker_jurisdiction: CountryCode,
+        currency: &str, currency_rate: Decimal, amount: Decimal, local_amount: Decimal,
+        local_expenses: Decimal,
+    ) -> EmptyResult {
+        self.get_foreign_incomes()?.push(CurrencyIncome {
+            type_: IncomeType::Derivative,
+            description: description.to_owned(),
+
+            source_from: broker_jurisdiction,
+            received_in: broker_jurisdiction,
+
+            date: date,
+            tax_payment_date: date,
+            currency: CurrencyInfo::new(currency, currency_rate)?,
+
+            amount: amount,
+            local_amount: local_amount,
+
+            paid_tax: dec!(0),
+            local_paid_tax: dec!(0),
+
+            // Derivatives form a separate tax base, so their expenses have their own deduction code
+            deduction: DeductionInfo {
+                code: 206,
+                amount: local_expenses,
+            },
+
+            controlled_foreign_company: ControlledForeignCompanyInfo::new_none(),
+        });
+
+        Ok(())
+    }
+
+    // Previous years' losses from trading securities which are applied to the year's profit. They are
+    // declared as a separate deduction of the securities tax base, not as expenses of the trades.
+    pub fn add_loss_carryforward(&mut self, amount: Decimal) -> EmptyResult {
+        match self.file_type {
+            FileType::Declaration => Err!(concat!(
+                "Loss carryforward can't be declared in *.dcX tax statements yet: ",
+                "declare it manually in Декларация program or generate XML tax statement")),
+            FileType::Xml {ref mut loss_carryforward, ..} => {
+                *loss_carryforward += amount;
+                Ok(())
+            },
+        }
+    }
+
+    #[cfg(test)]
+    pub fn get_loss_carryforward(&self) -> Decimal {
+        match self.file_type {
+            FileType::Declaration => dec!(0),
+            FileType::Xml {loss_carryforward, ..} => loss_carryforward,
+        }
+    }
+
     fn get_foreign_incomes(&mut self) -> GenericResult<&mut Vec<CurrencyIncome>> {
         Ok(self.get_mut_record(ForeignIncome::RECORD_NAME)?
             .map(|record: &mut ForeignIncome| &mut record.incomes)
             .ok_or("Foreign income must be enabled in the tax statement")?)
     }
 
+    pub fn get_foreign_incomes_ref(&self) -> GenericResult<&[CurrencyIncome]> {
+        Ok(self.get_record(ForeignIncome::RECORD_NAME)?
+            .map(|record: &ForeignIncome| record.incomes.as_slice())
+            .ok_or("Foreign income must be enabled in the tax statement")?)
+    }
+
+    fn get_record<T: 'static + Record>(&self, name: &str) -> GenericResult<Option<&T>> {
+        let mut records = self.records.iter().filter(|record| record.name() == name);
+
+        let record = match records.next() {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        if records.next().is_some() {
+            return Err!("The statement has several {} records", name);
+        }
+
+        Ok(Some(record.as_any().downcast_ref::<T>().ok_or_else(|| format!(
+            "Failed to cast {} record to the underlaying type", name))?))
+    }
+
     fn get_mut_record<T: 'static + Record>(&mut self, name: &str) -> GenericResult<Option<&mut T>> {
         let mut found_record = None;
 
@@ -7,7 +7,7 @@ use std::rc::Rc;
 
 use chrono::Datelike;
 use lazy_static::lazy_static;
-use log::{trace, debug, warn};
+use log::{trace, debug};
 use num_integer::Integer;
 use regex::Regex;
 #[cfg(test)] use tempfile::NamedTempFile;
@@ -16,15 +16,15 @@ use crate::core::{EmptyResult, GenericResult};
 use crate::time;
 #[cfg(test)] use crate::util;
 
-use super::TaxStatement;
+use super::{TaxStatement, FileType};
 #[cfg(test)] use super::countries::CountryCode;
+use super::format::StatementFormat;
 use super::record::{Record, UnknownRecord, is_record_name};
 use super::encoding::{TaxStatementType, TaxStatementPrimitiveType};
 use super::foreign_income::ForeignIncome;
 
-const SUPPORTED_YEAR: i32 = 2021;
-
 pub struct TaxStatementReader {
+    format: &'static StatementFormat,
     file: BufReader<File>,
     buffer: Vec<u8>,
 }
@@ -44,21 +44,15 @@ impl TaxStatementReader {
             decade -= 1;
         }
         let year = decade * 10 + short_year;
-
-        if year != SUPPORTED_YEAR {
-            warn!(concat!(
-                "Only *{} tax statements ({} year) are supported by the program. ",
-                "Reading or writing tax statements for other years may have issues or won't work ",
-                "at all."
-            ), get_extension(SUPPORTED_YEAR), SUPPORTED_YEAR);
-        }
+        let format = StatementFormat::get(year)?;
 
         let mut reader = TaxStatementReader {
+            format,
             file: BufReader::new(File::open(path)?),
             buffer: Vec::new(),
         };
 
-        let header = get_header(year);
+        let header = format.header();
         if reader.read_raw(header.len())? != header {
             return Err!("The file has an unexpected header");
         }
@@ -104,13 +98,20 @@ impl TaxStatementReader {
         let statement = TaxStatement {
             path: path.to_owned(),
             year: year,
+            file_type: FileType::Declaration,
             records: records,
         };
+        validate_records(&statement, format)?;
+
         debug!("Read statement:\n{:#?}", statement);
 
         Ok(statement)
     }
 
+    pub fn format(&self) -> &'static StatementFormat {
+        self.format
+    }
+
     pub fn read_value<T>(&mut self) -> GenericResult<T> where T: TaxStatementType {
         TaxStatementType::read(self)
     }
@@ -184,6 +185,7 @@ impl TaxStatementReader {
 }
 
 pub struct TaxStatementWriter {
+    format: &'static StatementFormat,
     file: BufWriter<File>,
 
     #[allow(clippy::rc_buffer)]
@@ -194,12 +196,17 @@ impl TaxStatementWriter {
     pub fn write(statement: &TaxStatement, path: &str) -> EmptyResult {
         debug!("Statement to write:\n{:#?}", statement);
 
+        // Check everything before creating the file to not leave a broken statement
+        let format = StatementFormat::get(statement.year)?;
+        validate_records(statement, format)?;
+
         let mut writer = TaxStatementWriter {
+            format,
             file: BufWriter::new(File::create(path)?),
             buffer: Rc::default(),
         };
 
-        writer.write_raw(&get_header(statement.year))?;
+        writer.write_raw(&format.header())?;
 
         for record in &statement.records {
             record.write(&mut writer)?;
@@ -208,6 +215,10 @@ impl TaxStatementWriter {
         Ok(())
     }
 
+    pub fn format(&self) -> &'static StatementFormat {
+        self.format
+    }
+
     pub fn write_value<T>(&mut self, value: &T) -> EmptyResult where T: TaxStatementType {
         TaxStatementType::write(value, self)
     }
@@ -247,12 +258,15 @@ impl TaxStatementWriter {
     }
 }
 
-fn get_extension(year: i32) -> String {
-    format!(".dc{}", year % 10)
-}
-
-fn get_header(year: i32) -> String {
-    format!(r"DLSG            Decl{}0103FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", year)
+fn validate_records(statement: &TaxStatement, format: &StatementFormat) -> EmptyResult {
+    for &name in format.required_records {
+        if !statement.records.iter().any(|record| record.name() == name) {
+            return Err!(
+                "The tax statement has no {} record which is expected for {} year statements",
+                name, format.year);
+        }
+    }
+    Ok(())
 }
 
 fn encode(data: &str) -> GenericResult<Cow<[u8]>> {
@@ -267,10 +281,13 @@ fn encode(data: &str) -> GenericResult<Cow<[u8]>> {
 mod tests {
     use super::*;
 
+    // The year of the statements in testdata
+    const TEST_YEAR: i32 = 2021;
+
     #[test]
     fn parse_empty() {
         let path = Path::new(file!()).parent().unwrap().join(get_path("empty"));
-        test_parsing(path.to_str().unwrap());
+        test_parsing(path.to_str().unwrap(), TEST_YEAR);
     }
 
     #[test]
@@ -279,7 +296,7 @@ mod tests {
             .to_str().unwrap().to_owned();
 
         let data = get_contents(&path);
-        let mut statement = test_parsing(&path);
+        let mut statement = test_parsing(&path, TEST_YEAR);
         let year = statement.year;
 
         let mut incomes = Vec::new();
@@ -341,14 +358,78 @@ mod tests {
     // FIXME(konishchev): Generate
     #[test]
     fn parse_real() {
-        test_parsing(&get_path("statement"));
+        test_parsing(&get_path("statement"), TEST_YEAR);
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in StatementFormat::all() {
+            let path = Path::new(file!()).parent().unwrap().join(format!("testdata/empty.dc{}", format.year % 10));
+            let path = path.to_str().unwrap();
+
+            let statement = test_parsing(path, format.year);
+            assert_eq!(statement.year, format.year);
+            assert!(get_contents(path).starts_with(&format.header()));
+        }
     }
 
-    fn test_parsing(path: &str) -> TaxStatement {
+    #[test]
+    fn unexpected_header() {
+        let header = StatementFormat::get(TEST_YEAR).unwrap().header();
+        let data = get_contents(&get_test_path("empty"));
+        assert!(data.starts_with(&header));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(get_file_name("statement"));
+        let other_year_header = header.replacen(&TEST_YEAR.to_string(), &(TEST_YEAR + 1).to_string(), 1);
+        std::fs::write(&path, encode(&data.replacen(&header, &other_year_header, 1)).unwrap()).unwrap();
+
+        let error = TaxStatementReader::read(path.to_str().unwrap()).err().unwrap().to_string();
+        assert_eq!(error, "The file has an unexpected header");
+    }
+
+    #[test]
+    fn missing_required_record() {
+        let mut statement = TaxStatementReader::read(&get_test_path("empty")).unwrap();
+        statement.records.retain(|record| record.name() != "@DeclInfo");
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(get_file_name("statement"));
+
+        let error = TaxStatementWriter::write(&statement, path.to_str().unwrap()).err().unwrap().to_string();
+        assert_eq!(error, format!(
+            "The tax statement has no @DeclInfo record which is expected for {} year statements", TEST_YEAR));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn unsupported_year() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("statement.dc0");
+        std::fs::copy(get_test_path("empty"), &path).unwrap();
+
+        let error = TaxStatementReader::read(path.to_str().unwrap()).err().unwrap().to_string();
+        assert!(error.starts_with("Tax statements for 2020 year are not supported"), "{}", error);
+
+        // Formats of the next years aren't verified yet
+        for year in 2022..=2025 {
+            let error = StatementFormat::get(year).err().unwrap().to_string();
+            assert_eq!(error, format!("Tax statements for {} year are not supported. Supported years: 2021", year));
+        }
+
+        let mut statement = TaxStatementReader::read(&get_test_path("empty")).unwrap();
+        statement.year = 2020;
+
+        let path = temp_dir.path().join("written.dc0");
+        assert!(TaxStatementWriter::write(&statement, path.to_str().unwrap()).is_err());
+        assert!(!path.exists());
+    }
+
+    fn test_parsing(path: &str, year: i32) -> TaxStatement {
         let data = get_contents(path);
 
         let statement = TaxStatementReader::read(path).unwrap();
-        assert_eq!(statement.year, SUPPORTED_YEAR);
+        assert_eq!(statement.year, year);
         compare_to(&statement, &data);
 
         statement
@@ -363,7 +444,15 @@ mod tests {
     }
 
     fn get_path(name: &str) -> String {
-        format!("testdata/{}{}", name, get_extension(SUPPORTED_YEAR))
+        format!("testdata/{}", get_file_name(name))
+    }
+
+    fn get_file_name(name: &str) -> String {
+        format!("{}.dc{}", name, TEST_YEAR % 10)
+    }
+
+    fn get_test_path(name: &str) -> String {
+        Path::new(file!()).parent().unwrap().join(get_path(name)).to_str().unwrap().to_owned()
     }
 
     fn get_contents(path: &str) -> String {
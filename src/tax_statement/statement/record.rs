@@ -7,6 +7,7 @@ use super::parser::{TaxStatementReader, TaxStatementWriter};
 
 pub trait Record: Debug {
     fn name(&self) -> &str;
+    fn as_any(&self) -> &dyn Any;
     fn as_mut_any(&mut self) -> &mut dyn Any;
     fn write(&self, writer: &mut TaxStatementWriter) -> EmptyResult;
 }
@@ -47,6 +48,10 @@ impl Record for UnknownRecord {
         &self.name
     }
 
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn as_mut_any(&mut self) -> &mut dyn Any {
         self
     }
@@ -92,6 +97,10 @@ macro_rules! tax_statement_record {
                 $name::RECORD_NAME
             }
 
+            fn as_any(&self) -> &::std::any::Any {
+                self
+            }
+
             fn as_mut_any(&mut self) -> &mut ::std::any::Any {
                 self
             }
@@ -140,7 +149,7 @@ macro_rules! tax_statement_array_record {
     (
         $name:ident {
             $($field_name:ident: $field_type:ty,)*
-        }, index_length=$index_length:expr
+        }
     ) => {
         declare_tax_statement_record!($name {
             $($field_name: $field_type,)*
@@ -152,7 +161,7 @@ macro_rules! tax_statement_array_record {
                 index: usize
             ) -> $crate::core::GenericResult<$name> {
                 {
-                    let name = $name::get_name(index)?;
+                    let name = $name::get_name(index, reader.format().array_index_length)?;
 
                     let record_name = reader.read_data()?;
                     if record_name != name {
@@ -174,16 +183,15 @@ macro_rules! tax_statement_array_record {
                 &self, writer: &mut $crate::tax_statement::statement::parser::TaxStatementWriter,
                 index: usize
             ) -> $crate::core::EmptyResult {
-                let name = $name::get_name(index)?;
+                let name = $name::get_name(index, writer.format().array_index_length)?;
                 writer.write_data(&name)?;
                 $(writer.write_value(&self.$field_name)?;)*
                 Ok(())
             }
 
-            fn get_name(index: usize) -> $crate::core::GenericResult<String> {
+            fn get_name(index: usize, index_length: usize) -> $crate::core::GenericResult<String> {
                 use ::std::fmt::Write;
 
-                let index_length = $index_length;
                 let name_length = $name::RECORD_NAME.len() + index_length;
 
                 let mut name = String::with_capacity(name_length);
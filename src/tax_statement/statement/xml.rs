@@ -0,0 +1,590 @@
+// 3-NDFL in FNS XML format which can be uploaded to the taxpayer's personal account on nalog.ru.
+//
+// Unlike *.dcX statements which are patched in place, XML statement is generated from scratch. It
+// contains foreign income sheets (Приложение 2) filled with the same records that are added to *.dcX
+// statements and the tax calculation for them: tax base and tax amount (Раздел 2) and the resulting
+// tax to pay (Раздел 1). Previous years' losses are deducted from the securities tax base.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+
+use xml::common::XmlVersion;
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use crate::config::TaxPayerConfig;
+use crate::core::{EmptyResult, GenericResult};
+use crate::currency;
+use crate::taxes::TaxBracket;
+use crate::time::{self, Date};
+use crate::types::Decimal;
+
+use super::TaxStatement;
+use super::foreign_income::{CurrencyIncome, ControlledForeignCompanyInfo, IncomeType};
+use super::format::StatementFormat;
+
+const DOCUMENT_CODE: &str = "1151020"; // КНД 3-НДФЛ
+const TAX_PERIOD_CODE: &str = "34"; // Календарный год
+const SUBMISSION_PLACE_CODE: &str = "760"; // По месту учета налогоплательщика
+const SIGNER_TYPE_CODE: &str = "1"; // Налогоплательщик
+
+// КБК of personal income tax which is paid by the taxpayer on their own (статья 228 НК РФ) for each
+// tax bracket: the default rate and the progressive rate of the tax base above 5 million RUB.
+const TAX_CLASSIFICATION_CODES: [&str; 2] = ["18210102030011000110", "18210102080011000110"];
+
+pub struct XmlTaxStatementWriter;
+
+impl XmlTaxStatementWriter {
+    pub fn write(
+        statement: &TaxStatement, taxpayer: &TaxPayerConfig, tax_brackets: &[TaxBracket],
+        loss_carryforward: Decimal, path: &str,
+    ) -> EmptyResult {
+        let format = StatementFormat::get(statement.year)?;
+
+        let incomes = statement.get_foreign_incomes_ref()?;
+        if incomes.is_empty() {
+            return Err!("The tax statement has no foreign income");
+        }
+
+        let file_uuid = uuid::Uuid::new_v4().to_hyphenated().to_string().to_uppercase();
+        let data = generate(
+            format, taxpayer, tax_brackets, incomes, loss_carryforward, time::today(), &file_uuid)?;
+
+        let (encoded_data, _, errors) = encoding_rs::WINDOWS_1251.encode(&data);
+        if errors {
+            return Err!("Unable to encode the tax statement with Windows-1251 character encoding");
+        }
+
+        Ok(File::create(path)?.write_all(&encoded_data)?)
+    }
+}
+
+fn generate(
+    format: &StatementFormat, taxpayer: &TaxPayerConfig, tax_brackets: &[TaxBracket],
+    incomes: &[CurrencyIncome], loss_carryforward: Decimal, today: Date, file_uuid: &str,
+) -> GenericResult<String> {
+    let calculation = TaxCalculation::new(tax_brackets, incomes, loss_carryforward)?;
+    let mut data = Vec::new();
+
+    {
+        let config = EmitterConfig::new().perform_indent(true);
+        let mut writer = Writer(config.create_writer(&mut data));
+
+        writer.0.write(XmlEvent::StartDocument {
+            version: XmlVersion::Version10,
+            encoding: Some("windows-1251"),
+            standalone: None,
+        })?;
+
+        let file_id = format!(
+            "NO_NDFL3_{authority}_{authority}_{inn}_{date}_{uuid}",
+            authority=taxpayer.tax_authority, inn=taxpayer.inn, date=today.format("%Y%m%d"),
+            uuid=file_uuid);
+
+        writer.start("Файл", &[
+            ("ИдФайл", file_id),
+            ("ВерсПрог", format!("investments {}", env!("CARGO_PKG_VERSION"))),
+            ("ВерсФорм", format.xml_version.to_owned()),
+        ])?;
+
+        writer.start("Документ", &[
+            ("КНД", DOCUMENT_CODE.to_owned()),
+            ("ДатаДок", format_date(today)),
+            ("Период", TAX_PERIOD_CODE.to_owned()),
+            ("ОтчетГод", format.year.to_string()),
+            ("КодНО", taxpayer.tax_authority.clone()),
+            ("НомКорр", s!("0")),
+            ("ПоМесту", SUBMISSION_PLACE_CODE.to_owned()),
+        ])?;
+
+        writer.start("СвНП", &[])?;
+        writer.start("НПФЛ3", &[("ИННФЛ", taxpayer.inn.clone())])?;
+        write_name(&mut writer, taxpayer)?;
+        writer.end()?; // НПФЛ3
+        writer.end()?; // СвНП
+
+        writer.start("Подписант", &[("ПрПодп", SIGNER_TYPE_CODE.to_owned())])?;
+        write_name(&mut writer, taxpayer)?;
+        writer.end()?;
+
+        writer.start("НДФЛ3", &[])?;
+        write_tax_to_pay(&mut writer, taxpayer, &calculation)?;
+        write_tax_calculation(&mut writer, &calculation)?;
+        writer.start("Прил2", &[])?;
+
+        for (index, income) in incomes.iter().enumerate() {
+            write_income(&mut writer, income).map_err(|e| format!(
+                "Failed to write #{} foreign income ({:?}): {}", index + 1, income.description, e))?;
+        }
+
+        writer.end()?; // Прил2
+        writer.end()?; // НДФЛ3
+        writer.end()?; // Документ
+        writer.end()?; // Файл
+    }
+
+    Ok(String::from_utf8(data)?)
+}
+
+// Раздел 2: tax base and tax amount for each tax rate
+struct TaxCalculation {
+    brackets: Vec<BracketCalculation>,
+}
+
+struct BracketCalculation {
+    rate: Decimal,
+    income: Decimal,
+    deductions: Decimal,
+    tax_base: Decimal,
+    tax: Decimal,
+    foreign_tax_credit: Decimal,
+}
+
+impl BracketCalculation {
+    fn tax_to_pay(&self) -> Decimal {
+        self.tax - self.foreign_tax_credit
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum TaxBase {
+    Main,
+    Dividends,
+    Securities,
+}
+
+impl TaxCalculation {
+    fn new(
+        tax_brackets: &[TaxBracket], incomes: &[CurrencyIncome], loss_carryforward: Decimal,
+    ) -> GenericResult<TaxCalculation> {
+        let default_rate = match tax_brackets.first() {
+            Some(bracket) if bracket.threshold.is_zero() => bracket.rate,
+            _ => return Err!("Got an invalid tax brackets: {:?}", tax_brackets),
+        };
+
+        if tax_brackets.len() > TAX_CLASSIFICATION_CODES.len() {
+            return Err!(
+                "Tax base above {} RUB is not supported for XML tax statements yet",
+                tax_brackets[TAX_CLASSIFICATION_CODES.len()].threshold);
+        }
+
+        let mut bases = BTreeMap::new();
+        let mut foreign_tax_credit = dec!(0);
+
+        for income in incomes {
+            let base = match income.type_ {
+                IncomeType::Dividend => TaxBase::Dividends,
+                IncomeType::Stock | IncomeType::Derivative => TaxBase::Securities,
+                IncomeType::Interest | IncomeType::Other(_) => TaxBase::Main,
+            };
+
+            let (base_income, base_deductions) = bases.entry(base).or_insert((dec!(0), dec!(0)));
+            *base_income += income.local_amount;
+            *base_deductions += income.deduction.amount;
+
+            // Tax paid abroad is credited only within the tax which is due in Russia for the income
+            if !income.local_paid_tax.is_zero() {
+                foreign_tax_credit += std::cmp::min(
+                    income.local_paid_tax, calculate_tax(income.local_amount, default_rate));
+            }
+        }
+
+        if !loss_carryforward.is_zero() {
+            bases.entry(TaxBase::Securities).or_insert((dec!(0), dec!(0)));
+        }
+
+        let mut income = dec!(0);
+        let mut deductions = dec!(0);
+
+        // Loss of one tax base can't reduce income of another one
+        for (base, (base_income, base_deductions)) in bases {
+            let mut base_deductions = std::cmp::min(base_deductions, base_income);
+
+            if base == TaxBase::Securities {
+                if loss_carryforward > base_income - base_deductions {
+                    return Err!(
+                        "Previous years' losses ({}) exceed the profit from securities ({})",
+                        loss_carryforward, base_income - base_deductions);
+                }
+                base_deductions += loss_carryforward;
+            }
+
+            income += base_income;
+            deductions += base_deductions;
+        }
+
+        let tax_base = income - deductions;
+        let mut foreign_tax_credit = round_tax(foreign_tax_credit);
+        let mut brackets = Vec::new();
+
+        // Each bracket gets the part of the tax base between its threshold and the next one. All
+        // deductions are accounted in the first bracket, so the others get the tax base as income.
+        for (index, bracket) in tax_brackets.iter().enumerate() {
+            if index != 0 && tax_base <= bracket.threshold {
+                break;
+            }
+
+            let bracket_tax_base = match tax_brackets.get(index + 1) {
+                Some(next) => std::cmp::min(tax_base, next.threshold),
+                None => tax_base,
+            } - bracket.threshold;
+
+            let (bracket_income, bracket_deductions) = if index == 0 {
+                (bracket_tax_base + deductions, deductions)
+            } else {
+                (bracket_tax_base, dec!(0))
+            };
+
+            let tax = calculate_tax(bracket_tax_base, bracket.rate);
+            let bracket_foreign_tax_credit = std::cmp::min(foreign_tax_credit, tax);
+            foreign_tax_credit -= bracket_foreign_tax_credit;
+
+            brackets.push(BracketCalculation {
+                rate: bracket.rate,
+                income: bracket_income,
+                deductions: bracket_deductions,
+                tax_base: bracket_tax_base,
+                tax,
+                foreign_tax_credit: bracket_foreign_tax_credit,
+            });
+        }
+
+        Ok(TaxCalculation {brackets})
+    }
+}
+
+// Раздел 1: tax to pay
+fn write_tax_to_pay<W: Write>(
+    writer: &mut Writer<W>, taxpayer: &TaxPayerConfig, calculation: &TaxCalculation,
+) -> EmptyResult {
+    writer.start("НалПУ", &[])?;
+
+    for (bracket, classification_code) in calculation.brackets.iter().zip(TAX_CLASSIFICATION_CODES) {
+        let tax_to_pay = bracket.tax_to_pay();
+
+        writer.empty("СумНалПУ", &[
+            // 1 - уплата (доплата) в бюджет, 3 - отсутствие налога к уплате
+            ("ТипНал", s!(if tax_to_pay.is_zero() {"3"} else {"1"})),
+            ("КБК", classification_code.to_owned()),
+            ("ОКТМО", taxpayer.oktmo.clone()),
+            ("НалДоплБюдж", format_tax(tax_to_pay)?),
+            ("НалВозврБюдж", format_tax(dec!(0))?),
+        ])?;
+    }
+
+    writer.end()
+}
+
+fn write_tax_calculation<W: Write>(writer: &mut Writer<W>, calculation: &TaxCalculation) -> EmptyResult {
+    for bracket in &calculation.brackets {
+        writer.empty("НалБаза", &[
+            ("Ставка", bracket.rate.normalize().to_string()),
+            ("СумДохОбщ", format_amount(bracket.income)?),
+            ("СумДохОбл", format_amount(bracket.income)?),
+            ("СумВыч", format_amount(bracket.deductions)?),
+            ("НалБаза", format_amount(bracket.tax_base)?),
+            ("НалИсчисл", format_tax(bracket.tax)?),
+            ("НалУплИнГос", format_tax(bracket.foreign_tax_credit)?),
+            ("НалДоплБюдж", format_tax(bracket.tax_to_pay())?),
+            ("НалВозврБюдж", format_tax(dec!(0))?),
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_name<W: Write>(writer: &mut Writer<W>, taxpayer: &TaxPayerConfig) -> EmptyResult {
+    let mut attributes = vec![
+        ("Фамилия", taxpayer.last_name.clone()),
+        ("Имя", taxpayer.first_name.clone()),
+    ];
+
+    if let Some(ref middle_name) = taxpayer.middle_name {
+        attributes.push(("Отчество", middle_name.clone()));
+    }
+
+    writer.empty("ФИО", &attributes)
+}
+
+fn write_income<W: Write>(writer: &mut Writer<W>, income: &CurrencyIncome) -> EmptyResult {
+    if income.controlled_foreign_company != ControlledForeignCompanyInfo::new_none() {
+        return Err!("Controlled foreign company income is not supported");
+    }
+
+    let currency = &income.currency;
+    let get_rate = |rate: Decimal, units| -> GenericResult<String> {
+        if units == 0 {
+            return Err!("Got an invalid currency rate units: {}", units);
+        }
+        Ok((rate / Decimal::from(units)).normalize().to_string())
+    };
+
+    let mut attributes = vec![
+        ("КодДох", format!("{:04}", income.type_.to_generic().code)),
+        ("НаимИст", income.description.clone()),
+        ("ОКСМИст", format!("{:03}", income.source_from.to_code())),
+        ("ОКСМЗач", format!("{:03}", income.received_in.to_code())),
+        ("ДатаДох", format_date(income.date)),
+        ("ДатаУплНал", format_date(income.tax_payment_date)),
+        ("КодВал", format!("{:03}", currency.code)),
+        ("КурсДох", get_rate(currency.income_date_rate, currency.income_date_units)?),
+        ("КурсНал", get_rate(currency.tax_payment_date_rate, currency.tax_payment_date_units)?),
+        ("СумДохВал", format_amount(income.amount)?),
+        ("СумДохРуб", format_amount(income.local_amount)?),
+    ];
+
+    if !income.paid_tax.is_zero() || !income.local_paid_tax.is_zero() {
+        attributes.push(("СумНалВал", format_amount(income.paid_tax)?));
+        attributes.push(("СумНалРуб", format_amount(income.local_paid_tax)?));
+    }
+
+    // Zero deduction code means that the income has no deduction
+    if income.deduction.code == 0 {
+        if !income.deduction.amount.is_zero() {
+            return Err!("Got a deduction without deduction code");
+        }
+        writer.empty("ДохИстИн", &attributes)?;
+    } else {
+        writer.start("ДохИстИн", &attributes)?;
+        writer.empty("Вычет", &[
+            ("КодВыч", format!("{:03}", income.deduction.code)),
+            ("СумВыч", format_amount(income.deduction.amount)?),
+        ])?;
+        writer.end()?;
+    }
+
+    Ok(())
+}
+
+// Taxes are calculated with kopecks precision first and then rounded to roubles (see localities)
+fn calculate_tax(amount: Decimal, rate: Decimal) -> Decimal {
+    round_tax(amount * rate / dec!(100))
+}
+
+fn round_tax(amount: Decimal) -> Decimal {
+    currency::round_to(currency::round(amount), 0)
+}
+
+fn format_date(date: Date) -> String {
+    date.format("%d.%m.%Y").to_string()
+}
+
+fn format_amount(amount: Decimal) -> GenericResult<String> {
+    let rounded = currency::round(amount);
+    if rounded != amount {
+        return Err!("An attempt to write a non-rounded amount: {:?}", amount);
+    } else if rounded.is_sign_negative() {
+        return Err!("Got a negative amount: {}", amount);
+    }
+    Ok(format!("{:.2}", rounded))
+}
+
+fn format_tax(tax: Decimal) -> GenericResult<String> {
+    if tax.is_sign_negative() || !tax.fract().is_zero() {
+        return Err!("Got an invalid tax amount: {}", tax);
+    }
+    Ok(tax.normalize().to_string())
+}
+
+struct Writer<W: Write>(EventWriter<W>);
+
+impl<W: Write> Writer<W> {
+    fn start(&mut self, name: &str, attributes: &[(&str, String)]) -> EmptyResult {
+        let mut event = XmlEvent::start_element(name);
+        for (name, value) in attributes {
+            event = event.attr(*name, value);
+        }
+        Ok(self.0.write(event)?)
+    }
+
+    fn empty(&mut self, name: &str, attributes: &[(&str, String)]) -> EmptyResult {
+        self.start(name, attributes)?;
+        self.end()
+    }
+
+    fn end(&mut self) -> EmptyResult {
+        Ok(self.0.write(XmlEvent::end_element())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use indoc::formatdoc;
+    use itertools::Itertools;
+    use libxml::parser::Parser;
+    use libxml::schemas::{SchemaParserContext, SchemaValidationContext};
+
+    use crate::localities::{self, Country};
+
+    use super::*;
+    use super::super::countries::CountryCode;
+
+    #[test]
+    fn generation() {
+        let (_temp_dir, path, statement) = get_statement();
+        let taxpayer = get_taxpayer();
+        let country = get_country();
+
+        let data = generate(
+            StatementFormat::get(2021).unwrap(), &taxpayer, &country.tax_brackets(2021),
+            statement.get_foreign_incomes_ref().unwrap(), dec!(0), date!(2022, 3, 1),
+            "6F9619FF-8B86-D011-B42D-00C04FC964FF").unwrap();
+
+        assert_eq!(data, formatdoc!(r#"
+            <?xml version="1.0" encoding="windows-1251"?>
+            <Файл ИдФайл="NO_NDFL3_7701_7701_771234567890_20220301_6F9619FF-8B86-D011-B42D-00C04FC964FF" ВерсПрог="investments {version}" ВерсФорм="5.07">
+              <Документ КНД="1151020" ДатаДок="01.03.2022" Период="34" ОтчетГод="2021" КодНО="7701" НомКорр="0" ПоМесту="760">
+                <СвНП>
+                  <НПФЛ3 ИННФЛ="771234567890">
+                    <ФИО Фамилия="Иванов" Имя="Иван" Отчество="Иванович" />
+                  </НПФЛ3>
+                </СвНП>
+                <Подписант ПрПодп="1">
+                  <ФИО Фамилия="Иванов" Имя="Иван" Отчество="Иванович" />
+                </Подписант>
+                <НДФЛ3>
+                  <НалПУ>
+                    <СумНалПУ ТипНал="1" КБК="18210102030011000110" ОКТМО="45375000" НалДоплБюдж="1193" НалВозврБюдж="0" />
+                  </НалПУ>
+                  <НалБаза Ставка="13" СумДохОбщ="14875.14" СумДохОбл="14875.14" СумВыч="10.00" НалБаза="14865.14" НалИсчисл="1932" НалУплИнГос="739" НалДоплБюдж="1193" НалВозврБюдж="0" />
+                  <Прил2>
+                    <ДохИстИн КодДох="1010" НаимИст="Дивиденд" ОКСМИст="840" ОКСМЗач="643" ДатаДох="01.01.2021" ДатаУплНал="01.01.2021" КодВал="840" КурсДох="73.8757" КурсНал="73.8757" СумДохВал="100.00" СумДохРуб="7387.57" СумНалВал="10.00" СумНалРуб="738.76" />
+                    <ДохИстИн КодДох="1530" НаимИст="Акции" ОКСМИст="840" ОКСМЗач="840" ДатаДох="01.01.2021" ДатаУплНал="01.01.2021" КодВал="840" КурсДох="73.8757" КурсНал="73.8757" СумДохВал="100.00" СумДохРуб="7387.57">
+                      <Вычет КодВыч="201" СумВыч="10.00" />
+                    </ДохИстИн>
+                    <ДохИстИн КодДох="6013" НаимИст="Проценты в рублях" ОКСМИст="840" ОКСМЗач="840" ДатаДох="01.01.2021" ДатаУплНал="01.01.2021" КодВал="643" КурсДох="1" КурсНал="1" СумДохВал="100.00" СумДохРуб="100.00" />
+                  </Прил2>
+                </НДФЛ3>
+              </Документ>
+            </Файл>"#,
+            version=env!("CARGO_PKG_VERSION"),
+        ));
+
+        statement.save().unwrap();
+        Parser::default().parse_file(&path).unwrap();
+    }
+
+    // The official XSD schema of the format isn't redistributed with the sources: download it from
+    // nalog.ru (3-НДФЛ, format version 5.07) to testdata/NO_NDFL3.xsd to run the test.
+    #[test]
+    #[ignore = "requires the official FNS XSD schema in testdata/NO_NDFL3.xsd"]
+    fn schema_validation() {
+        let (_temp_dir, path, statement) = get_statement();
+        statement.save().unwrap();
+
+        let document = Parser::default().parse_file(&path).unwrap();
+        let schema_path = Path::new(file!()).parent().unwrap().join("testdata/NO_NDFL3.xsd");
+        let mut schema_parser = SchemaParserContext::from_file(schema_path.to_str().unwrap());
+
+        let mut validator = SchemaValidationContext::from_parser(&mut schema_parser).map_err(|errors| {
+            format_errors(&errors)
+        }).unwrap();
+
+        validator.validate_document(&document).map_err(|errors| {
+            format_errors(&errors)
+        }).unwrap();
+    }
+
+    #[test]
+    fn tax_calculation() {
+        let taxpayer = get_taxpayer();
+        let country = get_country();
+        let path = tempfile::NamedTempFile::new().unwrap();
+        let mut statement = TaxStatement::new_xml(
+            path.path().to_str().unwrap(), 2021, &taxpayer, &country).unwrap();
+
+        let date = date!(2021, 1, 1);
+        let brackets = country.tax_brackets(2021);
+        let calculate = |statement: &TaxStatement, loss_carryforward| -> GenericResult<Vec<_>> {
+            let incomes = statement.get_foreign_incomes_ref().unwrap();
+            let calculation = TaxCalculation::new(&brackets, incomes, loss_carryforward)?;
+            Ok(calculation.brackets.iter().map(|bracket| (
+                bracket.rate, bracket.income, bracket.deductions, bracket.tax_base, bracket.tax,
+                bracket.foreign_tax_credit,
+            )).collect())
+        };
+
+        // Stock loss doesn't reduce dividend income
+        statement.add_stock_income(
+            "Акции", date, CountryCode::Usa, "RUB", dec!(1), dec!(1000), dec!(1000), dec!(1500)).unwrap();
+
+        // Foreign tax credit is limited by the tax which is due in Russia
+        statement.add_dividend_income(
+            "Дивиденд", date, CountryCode::Usa, CountryCode::Usa, "RUB", dec!(1),
+            dec!(1000), dec!(300), dec!(1000), dec!(300)).unwrap();
+
+        assert_eq!(calculate(&statement, dec!(0)).unwrap(), vec![
+            (dec!(13), dec!(2000), dec!(1000), dec!(1000), dec!(130), dec!(130)),
+        ]);
+
+        // Previous years' losses reduce only the profit from securities
+        let error = calculate(&statement, dec!(100)).err().unwrap().to_string();
+        assert_eq!(error, "Previous years' losses (100) exceed the profit from securities (0)");
+
+        statement.add_stock_income(
+            "Акции", date, CountryCode::Usa, "RUB", dec!(1), dec!(2000), dec!(2000), dec!(1000)).unwrap();
+
+        assert_eq!(calculate(&statement, dec!(300)).unwrap(), vec![
+            (dec!(13), dec!(4000), dec!(2800), dec!(1200), dec!(156), dec!(130)),
+        ]);
+
+        // Tax base above the threshold is taxed with the progressive tax rate
+        statement.add_interest_income(
+            "Проценты", date, CountryCode::Usa, "RUB", dec!(1), dec!(5_000_000), dec!(5_000_000)).unwrap();
+
+        assert_eq!(calculate(&statement, dec!(0)).unwrap(), vec![
+            (dec!(13), dec!(5_002_500), dec!(2500), dec!(5_000_000), dec!(650_000), dec!(130)),
+            (dec!(15), dec!(1500), dec!(0), dec!(1500), dec!(225), dec!(0)),
+        ]);
+    }
+
+    fn get_statement() -> (tempfile::TempDir, String, TaxStatement) {
+        let taxpayer = get_taxpayer();
+        let country = get_country();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("statement.xml").to_str().unwrap().to_owned();
+
+        assert!(TaxStatement::new_xml(&path, 2020, &taxpayer, &country).is_err());
+        let mut statement = TaxStatement::new_xml(&path, 2021, &taxpayer, &country).unwrap();
+
+        let date = date!(2021, 1, 1);
+        let currency_rate = dec!(73.8757);
+
+        statement.add_dividend_income(
+            "Дивиденд", date, CountryCode::Usa, CountryCode::Russia, "USD", currency_rate,
+            dec!(100), dec!(10), dec!(7387.57), dec!(738.76)).unwrap();
+
+        statement.add_stock_income(
+            "Акции", date, CountryCode::Usa, "USD", currency_rate, dec!(100), dec!(7387.57),
+            dec!(10)).unwrap();
+
+        statement.add_interest_income(
+            "Проценты в рублях", date, CountryCode::Usa, "RUB", dec!(1), dec!(100), dec!(100)).unwrap();
+
+        (temp_dir, path, statement)
+    }
+
+    fn get_taxpayer() -> TaxPayerConfig {
+        TaxPayerConfig {
+            inn: s!("771234567890"),
+            last_name: s!("Иванов"),
+            first_name: s!("Иван"),
+            middle_name: Some(s!("Иванович")),
+            tax_authority: s!("7701"),
+            oktmo: s!("45375000"),
+        }
+    }
+
+    fn get_country() -> Country {
+        let no_rates = BTreeMap::new();
+        localities::russia(&no_rates, &no_rates, &no_rates, &btreemap!{
+            2021 => vec![TaxBracket {threshold: dec!(5_000_000), rate: dec!(15)}],
+        })
+    }
+
+    fn format_errors(errors: &[libxml::error::StructuredError]) -> String {
+        errors.iter().map(|error| error.message()).join("\n")
+    }
+}
@@ -0,0 +1,422 @@
+use std::ops::AddAssign;
+
+use easy_logging::GlobalContext;
+use itertools::Itertools;
+use log::{debug, warn};
+
+use static_table_derive::StaticTable;
+
+use crate::config::Config;
+use crate::core::GenericResult;
+use crate::currency::Cash;
+use crate::currency::converter::CurrencyConverter;
+use crate::db;
+use crate::localities::Jurisdiction;
+use crate::telemetry::TelemetryRecordBuilder;
+
+use super::{progressive, tax_agent};
+
+#[derive(Clone, Copy)]
+pub struct IncomeSummary {
+    // Taxable income in local currency
+    pub income: Cash,
+    // Tax which is calculated by us (withheld by tax agent for brokers with Russia jurisdiction)
+    pub tax_to_pay: Cash,
+    // Tax which is withheld by income issuer's tax agent
+    pub withheld_tax: Cash,
+
+    pub lto_deduction: Cash,
+    pub loss_offset: Cash,
+}
+
+impl IncomeSummary {
+    pub fn new(currency: &str) -> IncomeSummary {
+        IncomeSummary {
+            income: Cash::zero(currency),
+            tax_to_pay: Cash::zero(currency),
+            withheld_tax: Cash::zero(currency),
+
+            lto_deduction: Cash::zero(currency),
+            loss_offset: Cash::zero(currency),
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.income.is_zero() && self.tax_to_pay.is_zero() && self.withheld_tax.is_zero() &&
+            self.lto_deduction.is_zero() && self.loss_offset.is_zero()
+    }
+}
+
+impl AddAssign<&IncomeSummary> for IncomeSummary {
+    fn add_assign(&mut self, other: &IncomeSummary) {
+        self.income += other.income;
+        self.tax_to_pay += other.tax_to_pay;
+        self.withheld_tax += other.withheld_tax;
+
+        self.lto_deduction += other.lto_deduction;
+        self.loss_offset += other.loss_offset;
+    }
+}
+
+pub struct PortfolioIncome {
+    pub trades: IncomeSummary,
+    pub dividends: IncomeSummary,
+    pub interest: IncomeSummary,
+    pub bonds: IncomeSummary,
+    pub repo: IncomeSummary,
+    pub derivatives: IncomeSummary,
+}
+
+impl PortfolioIncome {
+    // Tax which is calculated by us for brokers with Russia jurisdiction to check it against the
+    // withholdings of their tax agents
+    pub fn tax_agent_tax(&self) -> Cash {
+        let mut total = Cash::zero(self.trades.tax_to_pay.currency);
+        for (_, income) in self.iter() {
+            total += income.tax_to_pay;
+        }
+
+        // Interest and bond income processors report the tax as withheld on their own
+        total += self.interest.withheld_tax;
+        total += self.bonds.withheld_tax;
+
+        total
+    }
+
+    fn iter(&self) -> [(&'static str, &IncomeSummary); 6] {
+        [
+            ("Торговля ценными бумагами", &self.trades),
+            ("Дивиденды", &self.dividends),
+            ("Проценты на остаток", &self.interest),
+            ("Облигации", &self.bonds),
+            ("РЕПО", &self.repo),
+            ("ПФИ", &self.derivatives),
+        ]
+    }
+}
+
+// Processes income of all portfolios for the specified tax year. All foreign income may be
+// declared in a single tax statement at once.
+pub fn generate_tax_summary(
+    config: &Config, year: i32, tax_statement_path: Option<&str>,
+) -> GenericResult<TelemetryRecordBuilder> {
+    let country = config.get_tax_country();
+
+    let mut tax_statement = tax_statement_path.map(|path| {
+        super::open_tax_statement(config, path, year)
+    }).transpose()?;
+
+    let database = db::connect(&config.db_path)?;
+    let converter = CurrencyConverter::new(database, None, true);
+
+    let (portfolios, read_errors) = super::read_portfolios_except(config, &[]);
+    if !read_errors.is_empty() && super::requires_all_portfolios(config) {
+        return Err(read_errors.into_iter().next().unwrap().1);
+    }
+    let statements = portfolios.iter()
+        .map(|(portfolio, statement)| (*portfolio, statement))
+        .collect::<Vec<_>>();
+
+    let loss_carryforward = super::calculate_loss_carryforward(config, &statements, &converter)?;
+    let progressive_tax = super::calculate_progressive_tax(
+        config, &statements, loss_carryforward.as_ref(), &converter,
+    )?.map(|calculator| calculator.calculate());
+
+    let mut telemetry = TelemetryRecordBuilder::new();
+    let mut summary = TaxSummary::new(country.currency);
+
+    for (portfolio, error) in read_errors {
+        warn!("{}. Its income won't be taken into account in the tax summary.", error);
+        summary.add_failed(&portfolio.name, portfolio.broker.name());
+    }
+
+    for (portfolio, statement) in &portfolios {
+        if let Err(e) = statement.check_period_against_tax_year(year) {
+            debug!("Skipping {:?} portfolio: {}.", portfolio.name, e);
+            continue;
+        }
+
+        let _logging_context = GlobalContext::new(&portfolio.name);
+        telemetry.add_broker(portfolio.broker);
+
+        let portfolio_income = match super::process_income(
+            &country, portfolio, statement, Some(year), tax_statement.as_mut(),
+            loss_carryforward.as_ref(), &converter,
+        ) {
+            Ok(income) => income,
+            Err(e) => {
+                warn!(
+                    "Failed to process {:?} portfolio: {}. Its income won't be taken into account in the tax summary.",
+                    portfolio.name, e);
+                summary.add_failed(&portfolio.name, statement.broker.name);
+                continue;
+            },
+        };
+
+        // Brokers with Russia jurisdiction act as tax agents and withhold the calculated tax
+        let tax_agent = statement.broker.jurisdiction == Jurisdiction::Russia;
+        if tax_agent {
+            tax_agent::process_tax_agent_withholdings(
+                statement, Some(year), portfolio_income.tax_agent_tax());
+        }
+
+        summary.add(&portfolio.name, statement.broker.name, &portfolio_income, tax_agent);
+    }
+
+    if summary.failed_portfolios.is_empty() && summary.types.iter().all(|(_, income)| income.is_zero()) {
+        return Err!("There is no income in {} year", year);
+    }
+    summary.print(year);
+
+    if let Some(ref progressive_tax) = progressive_tax {
+        progressive::print(&country, progressive_tax, Some(year));
+    }
+
+    if let Some(ref tax_statement) = tax_statement {
+        // The statement may contain a part of income of the failed portfolios and misses income of
+        // the unreadable ones.
+        if !summary.failed_portfolios.is_empty() {
+            return Err!(
+                "The tax statement hasn't been saved because income of the following portfolios hasn't been processed: {}",
+                summary.failed_portfolios.iter().map(|(name, _)| format!("{:?}", name)).join(", "));
+        }
+        tax_statement.save()?;
+    }
+
+    Ok(telemetry)
+}
+
+// Income totals by income types and portfolios
+struct TaxSummary {
+    types: Vec<(&'static str, IncomeSummary)>,
+    portfolios: Vec<PortfolioSummary>,
+    // Portfolios which can't be read or processed: name and broker
+    failed_portfolios: Vec<(String, String)>,
+    total: IncomeSummary,
+}
+
+struct PortfolioSummary {
+    name: String,
+    broker: String,
+    income: IncomeSummary,
+}
+
+impl TaxSummary {
+    fn new(currency: &str) -> TaxSummary {
+        TaxSummary {
+            types: Vec::new(),
+            portfolios: Vec::new(),
+            failed_portfolios: Vec::new(),
+            total: IncomeSummary::new(currency),
+        }
+    }
+
+    fn add(&mut self, name: &str, broker: &str, portfolio_income: &PortfolioIncome, tax_agent: bool) {
+        let currency = self.total.income.currency;
+        let mut portfolio_total = IncomeSummary::new(currency);
+
+        for (index, &(income_type, income)) in portfolio_income.iter().iter().enumerate() {
+            let mut income = *income;
+            if tax_agent {
+                income.withheld_tax += income.tax_to_pay;
+                income.tax_to_pay = Cash::zero(currency);
+            }
+
+            if index == self.types.len() {
+                self.types.push((income_type, IncomeSummary::new(currency)));
+            }
+            self.types[index].1 += &income;
+
+            portfolio_total += &income;
+        }
+
+        if !portfolio_total.is_zero() {
+            self.portfolios.push(PortfolioSummary {
+                name: name.to_owned(),
+                broker: broker.to_owned(),
+                income: portfolio_total,
+            });
+        }
+
+        self.total += &portfolio_total;
+    }
+
+    fn add_failed(&mut self, name: &str, broker: &str) {
+        self.failed_portfolios.push((name.to_owned(), broker.to_owned()));
+    }
+
+    fn print(&self, year: i32) {
+        let total = &self.total;
+
+        let mut types_table = IncomeTypesTable::new();
+        for (name, income) in &self.types {
+            if !income.is_zero() {
+                types_table.add_row(IncomeTypeRow {
+                    income_type: (*name).to_owned(),
+                    income: income.income,
+                    withheld_tax: income.withheld_tax,
+                    tax_to_pay: income.tax_to_pay,
+                    lto_deduction: income.lto_deduction,
+                    loss_offset: income.loss_offset,
+                });
+            }
+        }
+
+        let mut brokers_table = BrokersTable::new();
+        for portfolio in &self.portfolios {
+            brokers_table.add_row(BrokerRow {
+                portfolio: portfolio.name.clone(),
+                broker: portfolio.broker.clone(),
+                income: portfolio.income.income,
+                withheld_tax: portfolio.income.withheld_tax,
+                tax_to_pay: portfolio.income.tax_to_pay,
+                lto_deduction: portfolio.income.lto_deduction,
+                loss_offset: portfolio.income.loss_offset,
+            });
+        }
+
+        // Income of the failed portfolios isn't included into the summary
+        for (name, broker) in &self.failed_portfolios {
+            let mut row = brokers_table.add_empty_row();
+            row.set_portfolio(format!("{} (не обработан)", name));
+            row.set_broker(broker.clone());
+        }
+
+        if total.lto_deduction.is_zero() {
+            types_table.hide_lto_deduction();
+            brokers_table.hide_lto_deduction();
+        }
+        if total.loss_offset.is_zero() {
+            types_table.hide_loss_offset();
+            brokers_table.hide_loss_offset();
+        }
+
+        {
+            let mut totals = types_table.add_empty_row();
+            totals.set_income(total.income);
+            totals.set_withheld_tax(total.withheld_tax);
+            totals.set_tax_to_pay(total.tax_to_pay);
+            totals.set_lto_deduction(total.lto_deduction);
+            totals.set_loss_offset(total.loss_offset);
+        }
+        types_table.print(&format!("Сводка по видам дохода за {} год", year));
+
+        {
+            let mut totals = brokers_table.add_empty_row();
+            totals.set_income(total.income);
+            totals.set_withheld_tax(total.withheld_tax);
+            totals.set_tax_to_pay(total.tax_to_pay);
+            totals.set_lto_deduction(total.lto_deduction);
+            totals.set_loss_offset(total.loss_offset);
+        }
+        brokers_table.print(&format!("Сводка по брокерам за {} год", year));
+    }
+}
+
+#[derive(StaticTable)]
+#[table(name="IncomeTypesTable")]
+struct IncomeTypeRow {
+    #[column(name="Вид дохода")]
+    income_type: String,
+    #[column(name="Налоговая\nбаза")]
+    income: Cash,
+    #[column(name="Удержано\nналоговыми\nагентами")]
+    withheld_tax: Cash,
+    #[column(name="К уплате\nсамостоятельно")]
+    tax_to_pay: Cash,
+    #[column(name="Льгота\nЛДВ")]
+    lto_deduction: Cash,
+    #[column(name="Перенесено\nубытков")]
+    loss_offset: Cash,
+}
+
+#[derive(StaticTable)]
+#[table(name="BrokersTable")]
+struct BrokerRow {
+    #[column(name="Портфель")]
+    portfolio: String,
+    #[column(name="Брокер")]
+    broker: String,
+    #[column(name="Налоговая\nбаза")]
+    income: Cash,
+    #[column(name="Удержано\nналоговыми\nагентами")]
+    withheld_tax: Cash,
+    #[column(name="К уплате\nсамостоятельно")]
+    tax_to_pay: Cash,
+    #[column(name="Льгота\nЛДВ")]
+    lto_deduction: Cash,
+    #[column(name="Перенесено\nубытков")]
+    loss_offset: Cash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregation() {
+        let currency = "RUB";
+        let cash = |amount| Cash::new(currency, amount);
+
+        let income = |income, tax_to_pay, withheld_tax| IncomeSummary {
+            income: cash(income),
+            tax_to_pay: cash(tax_to_pay),
+            withheld_tax: cash(withheld_tax),
+            ..IncomeSummary::new(currency)
+        };
+        let no_income = IncomeSummary::new(currency);
+
+        let foreign_broker = PortfolioIncome {
+            trades: income(dec!(1000), dec!(130), dec!(0)),
+            dividends: income(dec!(200), dec!(6), dec!(20)),
+            interest: income(dec!(100), dec!(13), dec!(0)),
+            bonds: no_income,
+            repo: no_income,
+            derivatives: no_income,
+        };
+        assert_eq!(foreign_broker.tax_agent_tax(), cash(dec!(149)));
+
+        let tax_agent = PortfolioIncome {
+            trades: income(dec!(500), dec!(65), dec!(0)),
+            dividends: no_income,
+            interest: income(dec!(100), dec!(0), dec!(13)),
+            bonds: income(dec!(300), dec!(0), dec!(39)),
+            repo: no_income,
+            derivatives: no_income,
+        };
+        assert_eq!(tax_agent.tax_agent_tax(), cash(dec!(117)));
+
+        let mut summary = TaxSummary::new(currency);
+        summary.add("foreign", "Interactive Brokers", &foreign_broker, false);
+        summary.add("empty", "Open Broker", &PortfolioIncome {
+            trades: no_income, dividends: no_income, interest: no_income,
+            bonds: no_income, repo: no_income, derivatives: no_income,
+        }, true);
+        summary.add("tax-agent", "Open Broker", &tax_agent, true);
+        summary.add_failed("missing", "Manual ledger");
+
+        let totals = |income: &IncomeSummary| (income.income, income.tax_to_pay, income.withheld_tax);
+
+        assert_eq!(summary.types.iter().map(|(name, income)| (*name, totals(income))).collect::<Vec<_>>(), vec![
+            ("Торговля ценными бумагами", (cash(dec!(1500)), cash(dec!(130)), cash(dec!(65)))),
+            ("Дивиденды", (cash(dec!(200)), cash(dec!(6)), cash(dec!(20)))),
+            ("Проценты на остаток", (cash(dec!(200)), cash(dec!(13)), cash(dec!(13)))),
+            ("Облигации", (cash(dec!(300)), cash(dec!(0)), cash(dec!(39)))),
+            ("РЕПО", (cash(dec!(0)), cash(dec!(0)), cash(dec!(0)))),
+            ("ПФИ", (cash(dec!(0)), cash(dec!(0)), cash(dec!(0)))),
+        ]);
+
+        // Portfolios without income are omitted
+        assert_eq!(summary.portfolios.iter().map(|portfolio| (
+            portfolio.name.as_str(), portfolio.broker.as_str(), totals(&portfolio.income),
+        )).collect::<Vec<_>>(), vec![
+            ("foreign", "Interactive Brokers", (cash(dec!(1300)), cash(dec!(149)), cash(dec!(20)))),
+            ("tax-agent", "Open Broker", (cash(dec!(900)), cash(dec!(0)), cash(dec!(117)))),
+        ]);
+
+        assert_eq!(totals(&summary.total), (cash(dec!(2200)), cash(dec!(149)), cash(dec!(137))));
+
+        // Failed portfolios are listed separately and don't affect the totals
+        assert_eq!(summary.failed_portfolios, vec![(s!("missing"), s!("Manual ledger"))]);
+    }
+}
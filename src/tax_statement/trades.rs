@@ -14,40 +14,22 @@ use crate::currency::{Cash, MultiCurrencyCashAccount};
 use crate::currency::converter::CurrencyConverter;
 use crate::formatting::{self, table::Cell};
 use crate::localities::{Country, Jurisdiction};
-use crate::tax_statement::statement::CountryCode;
-use crate::taxes::{IncomeType, TaxPaymentDaySpec};
+use crate::taxes::{IncomeType, LossCarryforward, TaxPaymentDaySpec};
 use crate::taxes::long_term_ownership::LtoDeductionCalculator;
 use crate::time::{self, Date};
 use crate::trades::{self, RealProfit};
 use crate::types::Decimal;
 
 use super::statement::TaxStatement;
+use super::summary::IncomeSummary;
 
 pub fn process_income(
     country: &Country, portfolio: &PortfolioConfig, broker_statement: &BrokerStatement,
-    year: Option<i32>, tax_statement: Option<&mut TaxStatement>, converter: &CurrencyConverter,
-) -> GenericResult<Cash> {
-    let mut processor = TradesProcessor {
-        portfolio,
-        broker_statement,
-        tax_year: year,
-
-        country,
-        converter,
-
-        trades_table: TradesTable::new(),
-        fifo_table: FifoTable::new(),
-        lto_table: LtoTable::new(),
-
-        same_dates: true,
-        same_currency: true,
-        non_trade_sources: false,
-        stock_splits: false,
-        tax_exemptions: false,
-        long_term_ownership: false,
-
-        tax_year_stat: BTreeMap::new(),
-    };
+    year: Option<i32>, tax_statement: Option<&mut TaxStatement>,
+    loss_carryforward: Option<&LossCarryforward>, converter: &CurrencyConverter,
+) -> GenericResult<IncomeSummary> {
+    let mut processor = TradesProcessor::new(
+        country, portfolio, broker_statement, year, loss_carryforward, converter);
 
     processor.process_trades(tax_statement)?;
 
@@ -56,13 +38,36 @@ pub fn process_income(
         processor.print(&totals);
     }
 
-    Ok(totals.tax_to_pay)
+    Ok(IncomeSummary {
+        income: totals.taxable_local_profit,
+        tax_to_pay: totals.tax_to_pay,
+        withheld_tax: Cash::zero(country.currency),
+        lto_deduction: totals.lto_deduction,
+        loss_offset: totals.loss_offset,
+    })
+}
+
+// Calculates taxable profit from stock trading for each tax year without previous years' losses
+pub fn calculate_yearly_profit(
+    country: &Country, portfolio: &PortfolioConfig, broker_statement: &BrokerStatement,
+    converter: &CurrencyConverter,
+) -> GenericResult<BTreeMap<i32, Decimal>> {
+    let mut processor = TradesProcessor::new(
+        country, portfolio, broker_statement, None, None, converter);
+
+    processor.process_trades(None)?;
+    processor.apply_lto_deductions();
+
+    Ok(processor.tax_year_stat.iter().map(|(&year, stat)| {
+        (year, stat.taxable_local_profit.amount)
+    }).collect())
 }
 
 struct TradesProcessor<'a> {
     portfolio: &'a PortfolioConfig,
     broker_statement: &'a BrokerStatement,
     tax_year: Option<i32>,
+    loss_carryforward: Option<&'a LossCarryforward>,
 
     country: &'a Country,
     converter: &'a CurrencyConverter,
@@ -70,6 +75,7 @@ struct TradesProcessor<'a> {
     trades_table: TradesTable,
     fifo_table: FifoTable,
     lto_table: LtoTable,
+    loss_carryforward_table: LossCarryforwardTable,
 
     same_dates: bool,
     same_currency: bool,
@@ -82,6 +88,36 @@ struct TradesProcessor<'a> {
 }
 
 impl<'a> TradesProcessor<'a> {
+    fn new(
+        country: &'a Country, portfolio: &'a PortfolioConfig, broker_statement: &'a BrokerStatement,
+        tax_year: Option<i32>, loss_carryforward: Option<&'a LossCarryforward>,
+        converter: &'a CurrencyConverter,
+    ) -> TradesProcessor<'a> {
+        TradesProcessor {
+            portfolio,
+            broker_statement,
+            tax_year,
+            loss_carryforward,
+
+            country,
+            converter,
+
+            trades_table: TradesTable::new(),
+            fifo_table: FifoTable::new(),
+            lto_table: LtoTable::new(),
+            loss_carryforward_table: LossCarryforwardTable::new(),
+
+            same_dates: true,
+            same_currency: true,
+            non_trade_sources: false,
+            stock_splits: false,
+            tax_exemptions: false,
+            long_term_ownership: false,
+
+            tax_year_stat: BTreeMap::new(),
+        }
+    }
+
     fn pre_process_fees(&mut self) -> GenericResult<VecDeque<PreprocessedFee>> {
         let broker = self.broker_statement.broker.type_;
         let mut fees = VecDeque::new();
@@ -154,17 +190,22 @@ impl<'a> TradesProcessor<'a> {
 
     fn process_trades(&mut self, mut tax_statement: Option<&'a mut TaxStatement>) -> EmptyResult {
         let mut fees = self.pre_process_fees()?;
-        let broker_jurisdiction = self.broker_statement.broker.type_.jurisdiction();
+        let broker_jurisdiction = self.broker_statement.broker.jurisdiction;
 
         let mut trade_id = 0;
 
         for trade in &self.broker_statement.stock_sells {
             match trade.type_ {
                 StockSellType::Trade {..} => (),
-                StockSellType::CorporateAction => continue,
+                StockSellType::CorporateAction | StockSellType::Transfer => continue,
             };
 
-            let tax_year = self.get_tax_year(trade.execution_date);
+            // Open short positions and emulated trades have no realized profit yet
+            if !trade.is_processed() || trade.is_projected() {
+                continue;
+            }
+
+            let tax_year = self.get_tax_year(trade.realization_date());
             if !self.needs_processing(tax_year) {
                 continue;
             }
@@ -182,18 +223,21 @@ impl<'a> TradesProcessor<'a> {
 
             if let Some(ref mut statement) = tax_statement {
                 match broker_jurisdiction {
-                    Jurisdiction::Usa => {
-                        let tax_year_stat = self.tax_year_stat.get_mut(&tax_year).unwrap();
-                        let additional_fees = tax_year_stat.deductible_fees.take().unwrap_or_default();
-                        self.add_income(statement, trade, &details, additional_fees)?;
-                    },
-
                     Jurisdiction::Russia => {
                         warn!(concat!(
                             "Don't declare income from trading in the tax statement ",
                             "assuming that it will be declared by broker's tax agent.",
                         ));
                         tax_statement = None;
+                    },
+
+                    _ => {
+                        let tax_year_stat = self.tax_year_stat.get_mut(&tax_year).unwrap();
+                        let additional_cost = tax_year_stat.deductible_fees.take().unwrap_or_default();
+                        tax_year_stat.declared_local_profit +=
+                            details.local_revenue.amount - details.total_local_cost.amount - additional_cost;
+
+                        self.add_income(statement, trade, &details, additional_cost)?;
                     }
                 }
             }
@@ -203,6 +247,26 @@ impl<'a> TradesProcessor<'a> {
             self.post_process_fee(fee);
         }
 
+        // Previous years' losses are declared separately from the trades, but can't exceed the
+        // declared profit of the portfolio which they are applied to.
+        if let (Some(statement), Some(loss_carryforward)) = (tax_statement, self.loss_carryforward) {
+            for (&year, stat) in &self.tax_year_stat {
+                let applied = loss_carryforward.applied(year, &self.portfolio.name);
+                if applied.is_zero() {
+                    continue;
+                }
+
+                let declared_profit = std::cmp::max(stat.declared_local_profit, dec!(0));
+                if applied > declared_profit {
+                    return Err!(
+                        "Unable to declare {} of previous years' losses in {}: there is not enough profit",
+                        self.country.cash(applied - declared_profit), year);
+                }
+
+                statement.add_loss_carryforward(applied)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -227,7 +291,7 @@ impl<'a> TradesProcessor<'a> {
 
         let execution_currency_rate = if price.currency != self.country.currency {
             Some(self.converter.precise_currency_rate(
-                trade.execution_date, price.currency, self.country.currency)?)
+                trade.realization_date(), price.currency, self.country.currency)?)
         } else {
             None
         };
@@ -235,7 +299,7 @@ impl<'a> TradesProcessor<'a> {
         let real = details.real_profit(self.converter)?;
 
         {
-            let tax_year = self.tax_year_stat(trade.execution_date);
+            let tax_year = self.tax_year_stat(trade.realization_date());
 
             tax_year.purchase_cost.deposit(details.purchase_cost);
             tax_year.purchase_local_cost += details.purchase_local_cost;
@@ -277,14 +341,14 @@ impl<'a> TradesProcessor<'a> {
         });
 
         for (index, buy_trade) in details.fifo.iter().enumerate() {
-            self.process_fifo(trade_id, buy_trade, trade.execution_date, index == 0)?;
+            self.process_fifo(trade_id, buy_trade, trade.realization_date(), index == 0)?;
         }
 
         Ok(())
     }
 
     fn process_fifo(
-        &mut self, trade_id: usize, trade: &FifoDetails, sell_execution_date: Date, first: bool,
+        &mut self, trade_id: usize, trade: &FifoDetails, sell_realization_date: Date, first: bool,
     ) -> EmptyResult {
         let security = self.broker_statement.instrument_info.get_name(&trade.original_symbol);
         self.stock_splits |= trade.multiplier != dec!(1);
@@ -336,7 +400,7 @@ impl<'a> TradesProcessor<'a> {
         };
 
         if let Some(ref deductible) = trade.long_term_ownership_deductible {
-            let tax_year_stat = self.tax_year_stat(sell_execution_date);
+            let tax_year_stat = self.tax_year_stat(sell_realization_date);
             tax_year_stat.lto_calculator.as_mut().unwrap()
                 .add(deductible.profit, deductible.years, false);
             self.long_term_ownership = true;
@@ -376,21 +440,25 @@ impl<'a> TradesProcessor<'a> {
 
     fn add_income(
         &self, tax_statement: &mut TaxStatement, trade: &StockSell, details: &SellDetails,
-        additional_fees: Decimal,
+        additional_cost: Decimal,
     ) -> EmptyResult {
         assert_eq!(details.taxable_local_profit, details.local_profit);
         assert!(details.fifo.iter().all(|trade| trade.long_term_ownership_deductible.is_none()));
 
         let name = self.broker_statement.instrument_info.get_name(&trade.original_symbol);
         let description = format!("{}: Продажа {}", self.broker_statement.broker.name, name);
-        let country_code = CountryCode::new(self.broker_statement.broker.type_.jurisdiction().code())?;
+        let country_code = self.broker_statement.broker.jurisdiction.country_code();
+
+        // Income of short positions is realized on their close, so the income date may differ from
+        // the execution date. Currency rate must be taken at the same date.
+        let date = trade.realization_date();
 
-        let cost = details.total_local_cost.amount + additional_fees;
+        let cost = details.total_local_cost.amount + additional_cost;
         let precise_currency_rate = self.converter.precise_currency_rate(
-            trade.execution_date, details.revenue.currency, self.country.currency)?;
+            date, details.revenue.currency, self.country.currency)?;
 
         tax_statement.add_stock_income(
-            &description, trade.execution_date, country_code,
+            &description, date, country_code,
             details.revenue.currency, precise_currency_rate,
             details.revenue.amount, details.local_revenue.amount, cost,
         ).map_err(|e| format!(
@@ -401,14 +469,15 @@ impl<'a> TradesProcessor<'a> {
         Ok(())
     }
 
-    fn process_totals(&mut self) -> GenericResult<Totals> {
+    fn apply_lto_deductions(&mut self) -> Cash {
         let local_currency = self.country.currency;
-        let tax_payment_day = self.portfolio.tax_payment_day();
+        let mut total_deduction = Cash::zero(local_currency);
 
         for (&year, stat) in &mut self.tax_year_stat {
             let lto = stat.lto_calculator.take().unwrap().calculate();
             if !lto.deduction.is_zero() {
                 stat.taxable_local_profit.amount -= lto.deduction;
+                total_deduction.amount += lto.deduction;
                 self.lto_table.add_row(LtoRow {
                     year,
                     deduction: Cash::new(local_currency, lto.deduction),
@@ -417,6 +486,46 @@ impl<'a> TradesProcessor<'a> {
             }
         }
 
+        total_deduction
+    }
+
+    fn apply_loss_carryforward(&mut self) -> Cash {
+        let local_currency = self.country.currency;
+        let mut total_applied = Cash::zero(local_currency);
+
+        let loss_carryforward = match self.loss_carryforward {
+            Some(loss_carryforward) => loss_carryforward,
+            None => return total_applied,
+        };
+
+        for (&year, stat) in &mut self.tax_year_stat {
+            let applied = loss_carryforward.applied(year, &self.portfolio.name);
+            let balance = loss_carryforward.get(year).map(|stat| stat.balance).unwrap_or_default();
+
+            if !applied.is_zero() {
+                stat.taxable_local_profit.amount -= applied;
+                total_applied.amount += applied;
+            }
+
+            if !applied.is_zero() || !balance.is_zero() {
+                self.loss_carryforward_table.add_row(LossCarryforwardRow {
+                    year,
+                    applied: Cash::new(local_currency, applied),
+                    balance: Cash::new(local_currency, balance),
+                });
+            }
+        }
+
+        total_applied
+    }
+
+    fn process_totals(&mut self) -> GenericResult<Totals> {
+        let local_currency = self.country.currency;
+        let tax_payment_day = self.portfolio.tax_payment_day();
+
+        let lto_deduction = self.apply_lto_deductions();
+        let loss_offset = self.apply_loss_carryforward();
+
         let mut total_local_profit = Cash::zero(local_currency);
         let mut total_taxable_local_profit = Cash::zero(local_currency);
 
@@ -471,6 +580,9 @@ impl<'a> TradesProcessor<'a> {
             tax_to_pay: total_tax_to_pay,
             tax_deduction: total_tax_deduction,
 
+            lto_deduction,
+            loss_offset,
+
             real,
         })
     }
@@ -518,6 +630,7 @@ impl<'a> TradesProcessor<'a> {
         }
         if self.tax_year.is_some() {
             self.lto_table.hide_year();
+            self.loss_carryforward_table.hide_year();
         }
 
         let mut totals_row = self.trades_table.add_empty_row();
@@ -544,6 +657,10 @@ impl<'a> TradesProcessor<'a> {
         if !self.lto_table.is_empty() {
             self.lto_table.print("Льгота на долгосрочное владение ценными бумагами");
         }
+
+        if !self.loss_carryforward_table.is_empty() {
+            self.loss_carryforward_table.print("Перенос убытков прошлых лет");
+        }
     }
 
     fn tax_year_stat(&mut self, date: Date) -> &mut TaxYearStat {
@@ -564,6 +681,7 @@ impl<'a> TradesProcessor<'a> {
 
                 deductible_fees: None,
                 lto_calculator: Some(LtoDeductionCalculator::new()),
+                declared_local_profit: dec!(0),
             }
         })
     }
@@ -597,6 +715,7 @@ struct TaxYearStat {
 
     deductible_fees: Option<Decimal>,
     lto_calculator: Option<LtoDeductionCalculator>,
+    declared_local_profit: Decimal,
 }
 
 struct Totals {
@@ -606,6 +725,9 @@ struct Totals {
     tax_to_pay: Cash,
     tax_deduction: Cash,
 
+    lto_deduction: Cash,
+    loss_offset: Cash,
+
     real: Option<RealProfit>,
 }
 
@@ -704,4 +826,89 @@ struct LtoRow {
     deduction: Cash,
     #[column(name="Лимит")]
     limit: Cash,
-}
\ No newline at end of file
+}
+
+#[derive(StaticTable)]
+#[table(name="LossCarryforwardTable")]
+struct LossCarryforwardRow {
+    #[column(name="Год")]
+    year: i32,
+    #[column(name="Перенесено\nубытков")]
+    applied: Cash,
+    #[column(name="Остаток\nубытков")]
+    balance: Cash,
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::NamedTempFile;
+
+    use crate::config::Config;
+    use crate::taxes::{LossCarryforwardCalculator, LossCarryforwardConfig};
+
+    use super::*;
+    use super::super::{calculate_loss_carryforward, read_other_portfolios};
+
+    #[test]
+    fn loss_carryforward() {
+        let database = NamedTempFile::new().unwrap();
+        let mut config = Config::load("testdata/configs/loss-carryforward/config.yaml").unwrap();
+        config.db_path = database.path().to_str().unwrap().to_owned();
+
+        let country = config.get_tax_country();
+        let converter = CurrencyConverter::mock();
+
+        let error = read_other_portfolios(&config, &[]).err().unwrap().to_string();
+        assert!(error.starts_with(r#"Failed to read "missing" portfolio: "#), "{}", error);
+
+        config.portfolios.retain(|portfolio| portfolio.name != "missing");
+        let statements = read_other_portfolios(&config, &[]).unwrap();
+        let statements = statements.iter()
+            .map(|(portfolio, statement)| (*portfolio, statement))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statements.iter().map(|(portfolio, _)| portfolio.name.as_str()).collect::<Vec<_>>(),
+            vec!["self-declared", "tax-agent"]);
+
+        // Profit of the tax agent's portfolio is taken into account as well
+        let loss_carryforward = calculate_loss_carryforward(&config, &statements, &converter).unwrap().unwrap();
+        let stat = loss_carryforward.get(2021).unwrap();
+        assert_eq!((stat.profit, stat.applied, stat.balance), (dec!(300), dec!(200), dec!(0)));
+        assert_eq!(loss_carryforward.applied(2021, "self-declared"), dec!(150));
+        assert_eq!(loss_carryforward.applied(2021, "tax-agent"), dec!(50));
+
+        let (portfolio, broker_statement) = statements[0];
+        let process = |loss_carryforward| -> GenericResult<(Vec<(Decimal, Decimal)>, Decimal, Cash)> {
+            let path = NamedTempFile::new().unwrap();
+            let mut tax_statement = TaxStatement::new_xml(
+                path.path().to_str().unwrap(), 2021, config.taxpayer.as_ref().unwrap(), &country)?;
+
+            let mut processor = TradesProcessor::new(
+                &country, portfolio, broker_statement, Some(2021), Some(loss_carryforward), &converter);
+            processor.process_trades(Some(&mut tax_statement))?;
+            let totals = processor.process_totals()?;
+
+            let incomes = tax_statement.get_foreign_incomes_ref()?.iter()
+                .map(|income| (income.local_amount, income.deduction.amount))
+                .collect();
+
+            Ok((incomes, tax_statement.get_loss_carryforward(), totals.taxable_local_profit))
+        };
+
+        // The losses are declared separately from the trades
+        let (incomes, declared_loss_carryforward, taxable_profit) = process(&loss_carryforward).unwrap();
+        assert_eq!(incomes, vec![(dec!(600), dec!(500)), (dec!(550), dec!(500))]);
+        assert_eq!(declared_loss_carryforward, dec!(150));
+        assert_eq!(taxable_profit, Cash::new("RUB", dec!(0)));
+
+        // The applied losses exceed the portfolio profit
+        let mut calculator = LossCarryforwardCalculator::new("RUB", &LossCarryforwardConfig {
+            losses: btreemap!{2020 => dec!(500)},
+            ..Default::default()
+        });
+        calculator.add_profit(2021, &portfolio.name, dec!(1000));
+
+        let error = process(&calculator.calculate()).err().unwrap().to_string();
+        assert_eq!(error, "Unable to declare 350₽ of previous years' losses in 2021: there is not enough profit");
+    }
+}
@@ -0,0 +1,317 @@
+// Tax loss carryforward logic.
+//
+// Losses from trading securities can be carried forward to the next ten years and reduce the taxable
+// profit of those years. The losses are tracked across all portfolios, because the tax base is
+// calculated for the whole tax year, not for each broker separately.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::Deserialize;
+
+use static_table_derive::StaticTable;
+
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::types::Decimal;
+
+const CARRYFORWARD_YEARS: i32 = 10;
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct LossCarryforwardConfig {
+    #[serde(default)]
+    pub order: LossCarryforwardOrder,
+
+    // Losses of the years which aren't covered by broker statements
+    #[serde(default)]
+    pub losses: BTreeMap<i32, Decimal>,
+}
+
+impl LossCarryforwardConfig {
+    pub fn validate(&self) -> EmptyResult {
+        for (&year, &loss) in &self.losses {
+            if loss <= dec!(0) {
+                return Err!("Invalid {} year loss: {}", year, loss);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LossCarryforwardOrder {
+    // Use the oldest losses first, so less losses will expire
+    #[default]
+    OldestFirst,
+    NewestFirst,
+}
+
+pub struct LossCarryforwardCalculator {
+    currency: &'static str,
+    order: LossCarryforwardOrder,
+    years: BTreeMap<i32, TaxYearProfit>,
+}
+
+#[derive(Default)]
+struct TaxYearProfit {
+    declared_loss: Decimal,
+    portfolios: Vec<(String, Decimal)>,
+}
+
+impl LossCarryforwardCalculator {
+    pub fn new(currency: &'static str, config: &LossCarryforwardConfig) -> LossCarryforwardCalculator {
+        let mut calculator = LossCarryforwardCalculator {
+            currency,
+            order: config.order,
+            years: BTreeMap::new(),
+        };
+
+        for (&year, &loss) in &config.losses {
+            calculator.years.entry(year).or_default().declared_loss += loss;
+        }
+
+        calculator
+    }
+
+    // Portfolios must be added in the same order each time to get stable distribution of the
+    // applied losses between them.
+    pub fn add_profit(&mut self, year: i32, portfolio: &str, profit: Decimal) {
+        let portfolios = &mut self.years.entry(year).or_default().portfolios;
+
+        match portfolios.iter_mut().find(|(name, _)| name == portfolio) {
+            Some((_, total)) => *total += profit,
+            None => portfolios.push((portfolio.to_owned(), profit)),
+        }
+    }
+
+    pub fn calculate(self) -> LossCarryforward {
+        let mut losses: VecDeque<(i32, Decimal)> = VecDeque::new();
+        let mut years = BTreeMap::new();
+
+        for (year, stat) in self.years {
+            let mut expired = dec!(0);
+            while let Some(&(loss_year, loss)) = losses.front() {
+                if year - loss_year <= CARRYFORWARD_YEARS {
+                    break;
+                }
+                expired += loss;
+                losses.pop_front();
+            }
+
+            let profit: Decimal = stat.portfolios.iter().map(|(_, profit)| profit).sum();
+            let mut loss = stat.declared_loss;
+            let mut applied = dec!(0);
+            let mut portfolios = Vec::new();
+
+            if profit < dec!(0) {
+                loss -= profit;
+            } else {
+                let mut remaining_profit = profit;
+
+                while !remaining_profit.is_zero() {
+                    let carried_loss = match self.order {
+                        LossCarryforwardOrder::OldestFirst => losses.front_mut(),
+                        LossCarryforwardOrder::NewestFirst => losses.back_mut(),
+                    };
+
+                    let carried_loss = match carried_loss {
+                        Some((_, carried_loss)) => carried_loss,
+                        None => break,
+                    };
+
+                    let amount = std::cmp::min(*carried_loss, remaining_profit);
+                    *carried_loss -= amount;
+                    remaining_profit -= amount;
+                    applied += amount;
+
+                    if carried_loss.is_zero() {
+                        match self.order {
+                            LossCarryforwardOrder::OldestFirst => losses.pop_front(),
+                            LossCarryforwardOrder::NewestFirst => losses.pop_back(),
+                        };
+                    }
+                }
+
+                // Distribute the applied losses between portfolios with profit
+                let mut remaining = applied;
+                for (portfolio, profit) in stat.portfolios {
+                    if remaining.is_zero() {
+                        break;
+                    } else if profit <= dec!(0) {
+                        continue;
+                    }
+
+                    let amount = std::cmp::min(profit, remaining);
+                    remaining -= amount;
+                    portfolios.push((portfolio, amount));
+                }
+                assert!(remaining.is_zero());
+            }
+
+            if !loss.is_zero() {
+                losses.push_back((year, loss));
+            }
+
+            years.insert(year, LossCarryforwardYear {
+                profit, loss, applied, expired,
+                balance: losses.iter().map(|(_, loss)| loss).sum(),
+                losses: losses.iter().cloned().collect(),
+                portfolios,
+            });
+        }
+
+        LossCarryforward {
+            currency: self.currency,
+            years,
+        }
+    }
+}
+
+pub struct LossCarryforward {
+    currency: &'static str,
+    years: BTreeMap<i32, LossCarryforwardYear>,
+}
+
+#[cfg_attr(test, derive(PartialEq, Debug))]
+pub struct LossCarryforwardYear {
+    // Total profit of the year across all portfolios
+    pub profit: Decimal,
+    // Loss of the year which is carried forward
+    pub loss: Decimal,
+    // Previous years losses applied to the year profit
+    pub applied: Decimal,
+    // Losses which have expired by the year
+    pub expired: Decimal,
+    // Losses which can be carried forward to the next years
+    pub balance: Decimal,
+
+    losses: Vec<(i32, Decimal)>,
+    portfolios: Vec<(String, Decimal)>,
+}
+
+impl LossCarryforward {
+    pub fn get(&self, year: i32) -> Option<&LossCarryforwardYear> {
+        self.years.get(&year)
+    }
+
+    // Returns previous years losses which are applied to the specified portfolio's profit
+    pub fn applied(&self, year: i32, portfolio: &str) -> Decimal {
+        self.years.get(&year).and_then(|stat| {
+            stat.portfolios.iter().find(|(name, _)| name == portfolio).map(|&(_, amount)| amount)
+        }).unwrap_or_default()
+    }
+
+    // Returns losses which are still available for the specified year after applying them to the
+    // already realized profit.
+    pub fn available(&self, year: i32) -> Decimal {
+        let stat = match self.years.range(..=year).next_back() {
+            Some((_, stat)) => stat,
+            None => return dec!(0),
+        };
+
+        stat.losses.iter()
+            .filter(|&&(loss_year, _)| year - loss_year <= CARRYFORWARD_YEARS)
+            .map(|(_, loss)| loss)
+            .sum()
+    }
+
+    pub fn print(&self, title: &str) {
+        let mut table = LossCarryforwardTable::new();
+
+        for (&year, stat) in &self.years {
+            table.add_row(LossCarryforwardRow {
+                year,
+                profit: Cash::new(self.currency, stat.profit),
+                loss: Cash::new(self.currency, stat.loss),
+                applied: Cash::new(self.currency, stat.applied),
+                expired: Cash::new(self.currency, stat.expired),
+                balance: Cash::new(self.currency, stat.balance),
+            });
+        }
+
+        if !table.is_empty() {
+            table.print(title);
+        }
+    }
+}
+
+#[derive(StaticTable)]
+#[table(name="LossCarryforwardTable")]
+struct LossCarryforwardRow {
+    #[column(name="Year")]
+    year: i32,
+    #[column(name="Profit")]
+    profit: Cash,
+    #[column(name="Loss")]
+    loss: Cash,
+    #[column(name="Applied")]
+    applied: Cash,
+    #[column(name="Expired")]
+    expired: Cash,
+    #[column(name="Balance")]
+    balance: Cash,
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    #[rstest(order => [LossCarryforwardOrder::OldestFirst, LossCarryforwardOrder::NewestFirst])]
+    fn calculation(order: LossCarryforwardOrder) {
+        let config = LossCarryforwardConfig {
+            order,
+            losses: btreemap!{2009 => dec!(1000), 2018 => dec!(100)},
+        };
+
+        let mut calculator = LossCarryforwardCalculator::new("RUB", &config);
+        calculator.add_profit(2019, "first", dec!(-150));
+        calculator.add_profit(2019, "second", dec!(-50));
+        calculator.add_profit(2020, "first", dec!(50));
+        calculator.add_profit(2020, "second", dec!(-100));
+        calculator.add_profit(2021, "first", dec!(-30));
+        calculator.add_profit(2021, "second", dec!(200));
+        calculator.add_profit(2021, "first", dec!(80));
+        calculator.add_profit(2021, "third", dec!(0));
+
+        let result = calculator.calculate();
+
+        // 2009 year loss expires in 2020
+        assert_eq!(result.get(2019).unwrap().balance, dec!(1300));
+        assert_eq!(result.get(2020).unwrap().expired, dec!(1000));
+        assert_eq!(result.get(2020).unwrap().balance, dec!(350));
+
+        let stat = result.get(2021).unwrap();
+        assert_eq!(stat.profit, dec!(250));
+        assert_eq!(stat.applied, dec!(250));
+        assert_eq!(stat.balance, dec!(100));
+
+        assert_eq!(result.applied(2021, "first"), dec!(50));
+        assert_eq!(result.applied(2021, "second"), dec!(200));
+        assert_eq!(result.applied(2021, "third"), dec!(0));
+
+        assert_eq!(result.available(2018), dec!(1100));
+        assert_eq!(result.available(2021), dec!(100));
+        assert_eq!(result.available(2022), dec!(100));
+    }
+
+    #[rstest(order, balance_2031,
+        case(LossCarryforwardOrder::OldestFirst, dec!(100)),
+        case(LossCarryforwardOrder::NewestFirst, dec!(0)),
+    )]
+    fn application_order(order: LossCarryforwardOrder, balance_2031: Decimal) {
+        let config = LossCarryforwardConfig {
+            order,
+            losses: btreemap!{2020 => dec!(100), 2021 => dec!(100)},
+        };
+
+        let mut calculator = LossCarryforwardCalculator::new("RUB", &config);
+        calculator.add_profit(2022, "portfolio", dec!(100));
+        calculator.add_profit(2031, "portfolio", dec!(0));
+
+        // Oldest first order uses 2020 loss which allows to use 2021 loss in 2031
+        let result = calculator.calculate();
+        assert_eq!(result.get(2031).unwrap().balance, balance_2031);
+    }
+}
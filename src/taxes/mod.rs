@@ -1,25 +1,31 @@
 pub mod long_term_ownership;
+mod loss_carryforward;
 mod net_calculator;
 mod payment_day;
+mod progressive;
 mod remapping;
 
 use serde::Deserialize;
 use serde::de::{Deserializer, Error};
 
-use crate::brokers::Broker;
 use crate::core::EmptyResult;
 use crate::localities::Jurisdiction;
 
 pub use self::long_term_ownership::{
     LtoDeductibleProfit, LtoDeductionCalculator, LtoDeduction,
     NetLtoDeduction, NetLtoDeductionCalculator};
+pub use self::loss_carryforward::{
+    LossCarryforward, LossCarryforwardCalculator, LossCarryforwardConfig};
 pub use self::net_calculator::{NetTax, NetTaxCalculator};
 pub use self::payment_day::{TaxPaymentDay, TaxPaymentDaySpec};
+pub use self::progressive::{
+    ProgressiveTax, ProgressiveTaxCalculator, TaxBracket, validate_progressive_tax_rates};
 pub use self::remapping::TaxRemapping;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum IncomeType {
     Trading,
+    Derivatives,
     Dividends,
     Interest,
 }
@@ -41,7 +47,7 @@ impl<'de> Deserialize<'de> for TaxExemption {
     }
 }
 
-pub fn validate_tax_exemptions(broker: Broker, exemptions: &[TaxExemption]) -> EmptyResult {
+pub fn validate_tax_exemptions(jurisdiction: Jurisdiction, exemptions: &[TaxExemption]) -> EmptyResult {
     if exemptions.is_empty() {
         return Ok(());
     }
@@ -50,7 +56,7 @@ pub fn validate_tax_exemptions(broker: Broker, exemptions: &[TaxExemption]) -> E
         return Err!("Only one tax exemption can be specified per portfolio");
     }
 
-    if broker.jurisdiction() != Jurisdiction::Russia {
+    if jurisdiction != Jurisdiction::Russia {
         return Err!("Tax exemptions are only supported for brokers with Russia jurisdiction");
     }
 
@@ -0,0 +1,285 @@
+// Progressive tax rates logic.
+//
+// Starting from 2021 the tax rate depends on the combined income of the whole tax year: income above
+// the threshold is taxed with increased rate. Brokers (as tax agents) and tax statements calculate
+// taxes with the flat rate for each income separately, so the difference must be calculated
+// across all portfolios of the tax year and paid in addition. Tax agents apply the increased rate to
+// their own income above the threshold, so the tax which they withhold above the flat rate reduces
+// the surcharge.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use static_table_derive::StaticTable;
+
+use crate::core::EmptyResult;
+use crate::currency::Cash;
+use crate::localities::Country;
+use crate::types::Decimal;
+
+use super::IncomeType;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TaxBracket {
+    // Income above the threshold is taxed with the specified rate
+    pub threshold: Decimal,
+    pub rate: Decimal,
+}
+
+pub fn validate_progressive_tax_rates(tax_rates: &BTreeMap<i32, Vec<TaxBracket>>) -> EmptyResult {
+    for (&year, brackets) in tax_rates {
+        if year < 0 {
+            return Err!("Invalid progressive tax rate year: {}", year);
+        } else if brackets.is_empty() {
+            return Err!("Progressive tax rates for {} year are not specified", year);
+        }
+
+        let mut prev_threshold = None;
+
+        for bracket in brackets {
+            if bracket.threshold <= dec!(0) || prev_threshold.map_or(false, |prev| bracket.threshold <= prev) {
+                return Err!(
+                    "Invalid {} year progressive tax threshold: {}. Thresholds must be positive and ascending",
+                    year, bracket.threshold);
+            } else if bracket.rate < dec!(0) || bracket.rate > dec!(100) {
+                return Err!("Invalid tax rate: {}", bracket.rate);
+            }
+            prev_threshold = Some(bracket.threshold);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct ProgressiveTaxCalculator {
+    country: Country,
+    years: BTreeMap<i32, ProgressiveTaxYear>,
+}
+
+impl ProgressiveTaxCalculator {
+    pub fn new(country: &Country) -> ProgressiveTaxCalculator {
+        ProgressiveTaxCalculator {
+            country: country.clone(),
+            years: BTreeMap::new(),
+        }
+    }
+
+    // Trading income may be negative: losses of one portfolio reduce profit of the others
+    pub fn add_income(&mut self, year: i32, income_type: IncomeType, income: Decimal) {
+        let stat = self.years.entry(year).or_default();
+
+        match income_type {
+            // Derivatives form a separate tax base, but we don't split trading income of the year
+            // into tax bases here.
+            IncomeType::Trading | IncomeType::Derivatives => stat.trading += income,
+            IncomeType::Dividends => stat.dividends += income,
+            IncomeType::Interest => stat.interest += income,
+        }
+    }
+
+    // Income which is taxed by tax agents. It must be added via add_income() as well.
+    pub fn add_tax_agent_income(&mut self, year: i32, income: Decimal) {
+        self.years.entry(year).or_default().tax_agent_income += income;
+    }
+
+    // Tax which is withheld by tax agents
+    pub fn add_withheld_tax(&mut self, year: i32, tax: Decimal) {
+        self.years.entry(year).or_default().withheld_tax += tax;
+    }
+
+    pub fn calculate(self) -> ProgressiveTax {
+        let country = self.country;
+
+        let years = self.years.into_iter().map(|(year, mut stat)| {
+            stat.income = std::cmp::max(stat.trading, dec!(0)) + stat.dividends + stat.interest;
+
+            let flat_tax_rate = country.tax_brackets(year)[0].rate / dec!(100);
+            let withheld_surcharge = std::cmp::max(
+                stat.withheld_tax - stat.tax_agent_income * flat_tax_rate, dec!(0));
+
+            let surcharge = country.progressive_tax_surcharge(year, country.cash(stat.income)).amount;
+            stat.surcharge = country.round_tax(country.cash(
+                std::cmp::max(surcharge - withheld_surcharge, dec!(0)))).amount;
+
+            (year, stat)
+        }).collect();
+
+        ProgressiveTax {
+            currency: country.currency,
+            years,
+        }
+    }
+}
+
+pub struct ProgressiveTax {
+    currency: &'static str,
+    years: BTreeMap<i32, ProgressiveTaxYear>,
+}
+
+#[derive(Default, Clone)]
+#[cfg_attr(test, derive(PartialEq, Debug))]
+pub struct ProgressiveTaxYear {
+    pub trading: Decimal,
+    pub dividends: Decimal,
+    pub interest: Decimal,
+
+    // Income which is taxed by tax agents and the tax which they have withheld
+    pub tax_agent_income: Decimal,
+    pub withheld_tax: Decimal,
+
+    // Combined taxable income of the year
+    pub income: Decimal,
+    // Tax which must be paid in addition to the tax calculated with flat rate and the tax withheld by
+    // tax agents above the flat rate
+    pub surcharge: Decimal,
+}
+
+impl ProgressiveTax {
+    pub fn get(&self, year: i32) -> Option<&ProgressiveTaxYear> {
+        self.years.get(&year)
+    }
+
+    pub fn surcharge(&self, year: i32) -> Decimal {
+        self.get(year).map(|stat| stat.surcharge).unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(i32, &ProgressiveTaxYear)> {
+        self.years.iter().map(|(&year, stat)| (year, stat))
+    }
+
+    pub fn print(&self, title: &str) {
+        let mut table = ProgressiveTaxTable::new();
+
+        for (year, stat) in self.iter() {
+            if stat.surcharge.is_zero() {
+                continue;
+            }
+
+            table.add_row(ProgressiveTaxRow {
+                year,
+                income: Cash::new(self.currency, stat.income),
+                withheld_tax: Cash::new(self.currency, stat.withheld_tax),
+                surcharge: Cash::new(self.currency, stat.surcharge),
+            });
+        }
+
+        if !table.is_empty() {
+            table.print(title);
+        }
+    }
+}
+
+#[derive(StaticTable)]
+#[table(name="ProgressiveTaxTable")]
+struct ProgressiveTaxRow {
+    #[column(name="Year")]
+    year: i32,
+    #[column(name="Income")]
+    income: Cash,
+    #[column(name="Withheld tax")]
+    withheld_tax: Cash,
+    #[column(name="Surcharge")]
+    surcharge: Cash,
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::localities;
+
+    use super::*;
+
+    #[rstest(income, surcharge_2021, surcharge_2025,
+        case(dec!(4_000_000), dec!(0), dec!(0)),
+        case(dec!(5_000_000), dec!(0), dec!(0)),
+        case(dec!(6_000_000), dec!(20_000), dec!(20_000)),
+        case(dec!(12_000_000), dec!(140_000), dec!(240_000)),
+    )]
+    fn calculation(income: Decimal, surcharge_2021: Decimal, surcharge_2025: Decimal) {
+        let brackets = btreemap!{
+            2021 => vec![TaxBracket {threshold: dec!(5_000_000), rate: dec!(15)}],
+            2025 => vec![
+                TaxBracket {threshold: dec!(5_000_000), rate: dec!(15)},
+                TaxBracket {threshold: dec!(10_000_000), rate: dec!(20)},
+            ],
+        };
+        validate_progressive_tax_rates(&brackets).unwrap();
+
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &brackets);
+
+        let mut calculator = ProgressiveTaxCalculator::new(&country);
+        for &year in &[2020, 2021, 2025] {
+            calculator.add_income(year, IncomeType::Trading, income - dec!(1_000_000));
+            calculator.add_income(year, IncomeType::Trading, dec!(-500_000));
+            calculator.add_income(year, IncomeType::Dividends, dec!(1_000_000));
+            calculator.add_income(year, IncomeType::Interest, dec!(500_000));
+            calculator.add_tax_agent_income(year, dec!(1_000_000));
+            calculator.add_withheld_tax(year, dec!(130_000));
+        }
+        let result = calculator.calculate();
+
+        assert_eq!(result.surcharge(2020), dec!(0));
+        assert_eq!(result.surcharge(2021), surcharge_2021);
+        assert_eq!(result.surcharge(2025), surcharge_2025);
+
+        let stat = result.get(2025).unwrap();
+        assert_eq!(stat.income, income);
+        assert_eq!(stat.withheld_tax, dec!(130_000));
+    }
+
+    #[test]
+    fn trading_loss() {
+        let brackets = btreemap!{2021 => vec![TaxBracket {threshold: dec!(1_000_000), rate: dec!(15)}]};
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &brackets);
+
+        // Trading losses don't reduce other income
+        let mut calculator = ProgressiveTaxCalculator::new(&country);
+        calculator.add_income(2021, IncomeType::Trading, dec!(-1_000_000));
+        calculator.add_income(2021, IncomeType::Dividends, dec!(2_000_000));
+
+        let result = calculator.calculate();
+        assert_eq!(result.get(2021).unwrap().income, dec!(2_000_000));
+        assert_eq!(result.surcharge(2021), dec!(20_000));
+    }
+
+    #[rstest(withheld_tax, surcharge,
+        // The tax agent has applied the increased rate to its income above the threshold
+        case(dec!(800_000), dec!(20_000)),
+        // The tax agent has withheld the tax with the flat rate
+        case(dec!(780_000), dec!(40_000)),
+        // The tax agent hasn't withheld the tax yet
+        case(dec!(0), dec!(40_000)),
+        // The tax agent has applied the increased rate to all the income
+        case(dec!(1_050_000), dec!(0)),
+    )]
+    fn tax_agent_withholding(withheld_tax: Decimal, surcharge: Decimal) {
+        let brackets = btreemap!{2021 => vec![TaxBracket {threshold: dec!(5_000_000), rate: dec!(15)}]};
+        let no_rates = BTreeMap::new();
+        let country = localities::russia(&no_rates, &no_rates, &no_rates, &brackets);
+
+        let mut calculator = ProgressiveTaxCalculator::new(&country);
+        calculator.add_income(2021, IncomeType::Trading, dec!(6_000_000));
+        calculator.add_tax_agent_income(2021, dec!(6_000_000));
+        calculator.add_withheld_tax(2021, withheld_tax);
+        calculator.add_income(2021, IncomeType::Dividends, dec!(1_000_000));
+
+        let result = calculator.calculate();
+        assert_eq!(result.get(2021).unwrap().income, dec!(7_000_000));
+        assert_eq!(result.surcharge(2021), surcharge);
+    }
+
+    #[test]
+    fn validation() {
+        let brackets = btreemap!{2021 => vec![
+            TaxBracket {threshold: dec!(5_000_000), rate: dec!(15)},
+            TaxBracket {threshold: dec!(5_000_000), rate: dec!(20)},
+        ]};
+        assert!(validate_progressive_tax_rates(&brackets).is_err());
+    }
+}
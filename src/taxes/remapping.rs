@@ -1,11 +1,13 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::core::EmptyResult;
 use crate::formatting::format_date;
 use crate::types::Date;
 
+// Statements may be read in parallel, so the rules are marked as mapped via atomics
 pub struct TaxRemapping {
-    remapping: HashMap<(Date, String), (Date, bool)>
+    remapping: HashMap<(Date, String), (Date, AtomicBool)>
 }
 
 impl TaxRemapping {
@@ -15,8 +17,12 @@ impl TaxRemapping {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.remapping.is_empty()
+    }
+
     pub fn add(&mut self, date: Date, description: &str, to_date: Date) -> EmptyResult {
-        if self.remapping.insert((date, description.to_owned()), (to_date, false)).is_some() {
+        if self.remapping.insert((date, description.to_owned()), (to_date, AtomicBool::new(false))).is_some() {
             return Err!(
                 "Invalid tax remapping configuration: Duplicated match: {} - {:?}",
                 format_date(date), description);
@@ -24,9 +30,9 @@ impl TaxRemapping {
         Ok(())
     }
 
-    pub fn map(&mut self, date: Date, description: &str) -> Date {
-        if let Some((to_date, mapped)) = self.remapping.get_mut(&(date, description.to_owned())) {
-            *mapped = true;
+    pub fn map(&self, date: Date, description: &str) -> Date {
+        if let Some((to_date, mapped)) = self.remapping.get(&(date, description.to_owned())) {
+            mapped.store(true, Ordering::Relaxed);
             *to_date
         } else {
             date
@@ -35,7 +41,7 @@ impl TaxRemapping {
 
     pub fn ensure_all_mapped(&self) -> EmptyResult {
         for ((date, description), (_, mapped)) in self.remapping.iter() {
-            if !mapped {
+            if !mapped.load(Ordering::Relaxed) {
                 return Err!(
                     "The following tax remapping rule hasn't been mapped to any tax: {} - {:?}",
                     format_date(*date), description)
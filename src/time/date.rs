@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use super::{Date, Time, DateTime};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct DateOptTime {
     pub date: Date,
     pub time: Option<Time>,
@@ -1,9 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 use crate::core::GenericResult;
 use crate::formatting;
 
 use super::Date;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct Period {
     first: Date,
     last: Date,
@@ -94,6 +94,75 @@ pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
     *value == T::default()
 }
 
+// Serializes maps with non-string keys (which aren't supported by JSON) as a list of key-value pairs
+pub mod map_as_pairs {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+        where K: Serialize, V: Serialize, S: Serializer
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+        where K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>, D: Deserializer<'de>
+    {
+        let pairs: Vec<(K, V)> = Deserialize::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
+// Serializes values of third-party types via their string representation
+pub mod string_value {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+        where T: Display, S: Serializer
+    {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+        where T: FromStr, D: Deserializer<'de>
+    {
+        let value: String = Deserialize::deserialize(deserializer)?;
+        value.parse().map_err(|_| D::Error::custom(format!("Invalid value: {:?}", value)))
+    }
+}
+
+// The same as string_value, but for sets of values
+pub mod string_set {
+    use std::collections::HashSet;
+    use std::fmt::Display;
+    use std::hash::Hash;
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<T, S>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where T: Display, S: Serializer
+    {
+        serializer.collect_seq(set.iter().map(ToString::to_string))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<HashSet<T>, D::Error>
+        where T: FromStr + Eq + Hash, D: Deserializer<'de>
+    {
+        let values: Vec<String> = Deserialize::deserialize(deserializer)?;
+        values.into_iter().map(|value| {
+            value.parse().map_err(|_| D::Error::custom(format!("Invalid value: {:?}", value)))
+        }).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
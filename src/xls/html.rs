@@ -0,0 +1,212 @@
+use std::fs;
+
+use calamine::Range;
+use itertools::Itertools;
+use scraper::{ElementRef, Html};
+
+use crate::core::GenericResult;
+
+use super::Cell;
+
+// Converts HTML document to a sheet to be able to parse HTML statements the same way as XLS ones:
+// each text block becomes a single cell row and each table row becomes a row of cells with expanded
+// colspan/rowspan, so column titles and values are aligned as in spreadsheets.
+pub fn read_html(path: &str) -> GenericResult<Range<Cell>> {
+    let data = fs::read(path)?;
+
+    let html = match String::from_utf8(data) {
+        Ok(html) => html,
+        Err(e) => encoding_rs::WINDOWS_1251.decode(e.as_bytes()).0.into_owned(),
+    };
+
+    Ok(parse_html(&html))
+}
+
+fn parse_html(html: &str) -> Range<Cell> {
+    let document = Html::parse_document(html);
+
+    let mut converter = HtmlConverter::default();
+    converter.process_element(document.root_element());
+    converter.flush_text();
+
+    converter.into_sheet()
+}
+
+#[derive(Default)]
+struct HtmlConverter {
+    rows: Vec<Vec<Cell>>,
+    text: String,
+}
+
+impl HtmlConverter {
+    fn process_element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+
+        match name {
+            "head" | "script" | "style" => return,
+            "br" => return self.flush_text(),
+            "table" => {
+                self.flush_text();
+                return self.process_table(element);
+            },
+            _ => {},
+        }
+
+        let block = matches!(name,
+            "body" | "div" | "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "ul" | "ol" | "li");
+
+        if block {
+            self.flush_text();
+        }
+
+        for child in element.children() {
+            if let Some(child) = ElementRef::wrap(child) {
+                self.process_element(child);
+            } else if let Some(text) = child.value().as_text() {
+                self.text.push_str(text);
+            }
+        }
+
+        if block {
+            self.flush_text();
+        }
+    }
+
+    fn flush_text(&mut self) {
+        let text = normalize_text(&self.text);
+        self.text.clear();
+
+        if !text.is_empty() {
+            self.rows.push(vec![Cell::String(text)]);
+        }
+    }
+
+    fn process_table(&mut self, table: ElementRef) {
+        // Remaining number of rows occupied by cells with rowspan for each column
+        let mut occupied: Vec<usize> = Vec::new();
+
+        for row in get_table_rows(table) {
+            let mut cells = Vec::new();
+
+            for cell in child_elements(row).filter(|cell| matches!(cell.value().name(), "td" | "th")) {
+                while occupied.get(cells.len()).copied().unwrap_or_default() != 0 {
+                    occupied[cells.len()] -= 1;
+                    cells.push(Cell::Empty);
+                }
+
+                let value = normalize_text(&cell.text().collect::<String>());
+                let colspan = get_span(cell, "colspan");
+                let rowspan = get_span(cell, "rowspan");
+
+                for index in 0..colspan {
+                    let column = cells.len();
+                    if occupied.len() <= column {
+                        occupied.resize(column + 1, 0);
+                    }
+                    occupied[column] = rowspan - 1;
+
+                    cells.push(if index == 0 && !value.is_empty() {
+                        Cell::String(value.clone())
+                    } else {
+                        Cell::Empty
+                    });
+                }
+            }
+
+            for remaining in occupied.iter_mut().skip(cells.len()) {
+                if *remaining != 0 {
+                    *remaining -= 1;
+                }
+            }
+
+            self.rows.push(cells);
+        }
+
+        // Tables are always separated by an empty row as in spreadsheets
+        self.rows.push(Vec::new());
+    }
+
+    fn into_sheet(self) -> Range<Cell> {
+        let width = self.rows.iter().map(Vec::len).max().unwrap_or_default();
+        if width == 0 {
+            return Range::empty();
+        }
+
+        let mut sheet = Range::new((0, 0), (self.rows.len() as u32 - 1, width as u32 - 1));
+
+        for (row_id, row) in self.rows.into_iter().enumerate() {
+            for (column_id, cell) in row.into_iter().enumerate() {
+                sheet.set_value((row_id as u32, column_id as u32), cell);
+            }
+        }
+
+        sheet
+    }
+}
+
+fn get_table_rows<'a>(table: ElementRef<'a>) -> Vec<ElementRef<'a>> {
+    let mut rows = Vec::new();
+
+    for element in child_elements(table) {
+        match element.value().name() {
+            "tr" => rows.push(element),
+            "thead" | "tbody" | "tfoot" => {
+                rows.extend(child_elements(element).filter(|row| row.value().name() == "tr"));
+            },
+            _ => {},
+        }
+    }
+
+    rows
+}
+
+fn child_elements<'a>(element: ElementRef<'a>) -> impl Iterator<Item=ElementRef<'a>> {
+    element.children().filter_map(ElementRef::wrap)
+}
+
+fn get_span(cell: ElementRef, name: &str) -> usize {
+    cell.value().attr(name)
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|&span| span != 0)
+        .unwrap_or(1)
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use super::*;
+
+    #[test]
+    fn html_parsing() {
+        let sheet = parse_html(indoc!(r#"
+            <html>
+            <head><title>Report</title></head>
+            <body>
+                <h3>Отчет брокера</h3>
+                <p>за период с 01.01.2021 по 31.12.2021</p>
+                <table>
+                    <tr><th rowspan="2">Name</th><th colspan="2">Start</th><th>End</th></tr>
+                    <tr><th>Quantity</th><th>Price</th><th>Quantity</th></tr>
+                    <tr><td>SBER</td><td>1 000</td><td> 250,5 </td><td></td></tr>
+                </table>
+            </body>
+            </html>
+        "#));
+
+        let string = |value: &str| Cell::String(value.to_owned());
+        let rows: Vec<Vec<Cell>> = sheet.rows().map(|row| row.to_vec()).collect();
+
+        assert_eq!(rows, vec![
+            vec![string("Отчет брокера"), Cell::Empty, Cell::Empty, Cell::Empty],
+            vec![string("за период с 01.01.2021 по 31.12.2021"), Cell::Empty, Cell::Empty, Cell::Empty],
+            vec![string("Name"), string("Start"), Cell::Empty, string("End")],
+            vec![Cell::Empty, string("Quantity"), string("Price"), string("Quantity")],
+            vec![string("SBER"), string("1 000"), string("250,5"), Cell::Empty],
+            vec![Cell::Empty, Cell::Empty, Cell::Empty, Cell::Empty],
+        ]);
+    }
+}
@@ -1,10 +1,12 @@
 mod cell;
+mod html;
 mod parser;
 mod sheet;
 mod table;
 mod util;
 
 pub use self::cell::*;
+pub use self::html::*;
 pub use self::parser::*;
 pub use self::sheet::*;
 pub use self::table::*;
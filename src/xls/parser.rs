@@ -11,9 +11,16 @@ pub struct XlsStatementParser {
 
 impl XlsStatementParser {
     pub fn read(path: &str, parser: Box<dyn SheetParser>, sections: Vec<Section>) -> EmptyResult {
-        let mut parser = XlsStatementParser {
-            sheet: SheetReader::new(path, parser)?,
-        };
+        XlsStatementParser::read_sheet(SheetReader::new(path, parser)?, sections)
+    }
+
+    pub fn read_html(path: &str, parser: Box<dyn SheetParser>, sections: Vec<Section>) -> EmptyResult {
+        let sheet = xls::read_html(path)?;
+        XlsStatementParser::read_sheet(SheetReader::new_from(sheet, parser), sections)
+    }
+
+    fn read_sheet(sheet: SheetReader, sections: Vec<Section>) -> EmptyResult {
+        let mut parser = XlsStatementParser {sheet};
 
         if let Err(e) = parser.parse(sections) {
             return Err(parser.sheet.detalize_error(&e.to_string()).into());
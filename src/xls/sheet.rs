@@ -21,12 +21,17 @@ impl SheetReader {
         let sheet = open_sheet(path, sheet_name)?.ok_or_else(|| format!(
             "There is no {:?} sheet in the workbook", sheet_name))?;
 
-        Ok(SheetReader {
+        Ok(SheetReader::new_from(sheet, parser))
+    }
+
+    // Allows to read statements of other formats converted to a sheet (see read_html())
+    pub fn new_from(sheet: Range<Cell>, parser: Box<dyn SheetParser>) -> SheetReader {
+        SheetReader {
             sheet, parser,
             prev_row_id: None,
             next_row_id: 0,
             eof_reached: false,
-        })
+        }
     }
 
     pub fn repeatable_table_column_titles(&self) -> bool {
@@ -91,6 +96,10 @@ pub trait SheetParser {
     }
 }
 
+pub fn sheet_names(path: &str) -> GenericResult<Vec<String>> {
+    Ok(open_workbook_auto(path)?.sheet_names().to_vec())
+}
+
 pub fn open_sheet(path: &str, name: &str) -> GenericResult<Option<Range<Cell>>> {
     let mut workbook = open_workbook_auto(path)?;
     Ok(workbook.worksheet_range(name).transpose()?)